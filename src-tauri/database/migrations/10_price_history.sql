@@ -0,0 +1,42 @@
+-- Every change of a retail (`products.price`) or tier price, written by
+-- the Rust side together with its audit log entry.
+CREATE TABLE IF NOT EXISTS price_history (
+    id                INTEGER PRIMARY KEY AUTOINCREMENT,
+    sku               TEXT NOT NULL,
+    tier              TEXT,                      -- Optional: product_prices tier, NULL for the retail price
+    old_price         DECIMAL(10,2),             -- Optional: NULL when the price did not exist before
+    new_price         DECIMAL(10,2) NOT NULL,
+    source            TEXT NOT NULL,             -- "import", "manual" or "scheduled"
+    changed_by        INTEGER,                   -- Optional: user, NULL for scheduled changes
+    changed_at        DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (changed_by) REFERENCES users(id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_price_history_sku ON price_history(sku, changed_at);
+
+-- Price changes entered ahead of time, e.g. the monthly flour increase.
+-- A background task applies them once `effective_at` has passed.
+CREATE TABLE IF NOT EXISTS scheduled_price_changes (
+    id                INTEGER PRIMARY KEY AUTOINCREMENT,
+    sku               TEXT NOT NULL,
+    tier              TEXT,                      -- Optional: product_prices tier, NULL for the retail price
+    new_price         DECIMAL(10,2) NOT NULL CHECK (new_price >= 0),
+    effective_at      DATETIME NOT NULL,         -- UTC
+    created_by        INTEGER,
+    created_at        DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    applied_at        DATETIME,                  -- Set once applied
+    cancelled_at      DATETIME,                  -- Set if cancelled before being applied
+    FOREIGN KEY (sku) REFERENCES products(sku),
+    FOREIGN KEY (created_by) REFERENCES users(id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_scheduled_price_changes_due
+    ON scheduled_price_changes(effective_at) WHERE applied_at IS NULL AND cancelled_at IS NULL;
+
+-- Set when the price changes so the shelf label gets reprinted
+ALTER TABLE products ADD COLUMN needs_label INTEGER NOT NULL DEFAULT 0;
+
+CREATE TRIGGER IF NOT EXISTS scheduled_price_changes_after_product_delete AFTER DELETE ON products
+BEGIN
+    DELETE FROM scheduled_price_changes WHERE sku = OLD.sku;
+END;
@@ -0,0 +1,57 @@
+-- Stock is opt-in per product: packaged goods are counted, fresh
+-- tortillas are not.
+ALTER TABLE products ADD COLUMN track_stock INTEGER NOT NULL DEFAULT 0;
+
+-- Current quantity on hand, kept by the Rust side with each movement.
+CREATE TABLE IF NOT EXISTS stock_levels (
+    sku               TEXT PRIMARY KEY,
+    quantity          DECIMAL(10,3) NOT NULL DEFAULT 0, -- May go negative when selling uncounted stock
+    updated_at        DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (sku) REFERENCES products(sku)
+);
+
+-- Ledger of every change of stock. No foreign key to products, so the
+-- history outlives deleted products.
+CREATE TABLE IF NOT EXISTS stock_movements (
+    id                INTEGER PRIMARY KEY AUTOINCREMENT,
+    sku               TEXT NOT NULL,
+    kind              TEXT NOT NULL CHECK (kind IN (
+                          'sale', 'return', 'receipt', 'adjustment', 'waste', 'transfer'
+                      )),
+    quantity          DECIMAL(10,3) NOT NULL,    -- Signed: negative when stock goes out
+    balance           DECIMAL(10,3) NOT NULL,    -- Stock after the movement
+    reference         TEXT,                      -- Optional: ticket id, purchase order, ...
+    reason            TEXT,                      -- Optional: required for manual adjustments
+    user_id           INTEGER,
+    created_at        DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (user_id) REFERENCES users(id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_stock_movements_sku ON stock_movements(sku, created_at);
+CREATE INDEX IF NOT EXISTS idx_stock_movements_reference ON stock_movements(reference);
+
+CREATE TRIGGER IF NOT EXISTS stock_levels_after_product_delete AFTER DELETE ON products
+BEGIN
+    DELETE FROM stock_levels WHERE sku = OLD.sku;
+END;
+
+-- New `manage_inventory` permission. SQLite cannot alter a CHECK
+-- constraint, so role_permissions is rebuilt with the longer list.
+CREATE TABLE role_permissions_new (
+    role_id           INTEGER NOT NULL,
+    permission        TEXT NOT NULL CHECK (permission IN (
+                          'sell', 'void', 'refund', 'discount', 'import',
+                          'change_rate', 'open_drawer', 'view_reports', 'manage_users',
+                          'manage_inventory'
+                      )),
+    PRIMARY KEY (role_id, permission),
+    FOREIGN KEY (role_id) REFERENCES roles(id) ON DELETE CASCADE
+);
+INSERT INTO role_permissions_new (role_id, permission)
+    SELECT role_id, permission FROM role_permissions;
+DROP TABLE role_permissions;
+ALTER TABLE role_permissions_new RENAME TO role_permissions;
+
+INSERT OR IGNORE INTO role_permissions (role_id, permission) VALUES
+    (1, 'manage_inventory'),
+    (2, 'manage_inventory');
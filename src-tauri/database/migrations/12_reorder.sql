@@ -0,0 +1,21 @@
+-- Who a product is bought from, for purchase lists and orders.
+CREATE TABLE IF NOT EXISTS suppliers (
+    id                INTEGER PRIMARY KEY AUTOINCREMENT,
+    name              TEXT NOT NULL UNIQUE COLLATE NOCASE,
+    contact_name      TEXT,
+    phone             TEXT,
+    email             TEXT,
+    notes             TEXT,
+    is_active         INTEGER NOT NULL DEFAULT 1,
+    created_at        DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at        DATETIME
+);
+
+ALTER TABLE products ADD COLUMN supplier_id INTEGER REFERENCES suppliers(id);
+
+-- Optional stock thresholds of tracked products: below `min_stock` the
+-- product is low and gets reordered up to `max_stock`.
+ALTER TABLE products ADD COLUMN min_stock DECIMAL(10,3);
+ALTER TABLE products ADD COLUMN max_stock DECIMAL(10,3);
+
+CREATE INDEX IF NOT EXISTS idx_products_supplier_id ON products(supplier_id);
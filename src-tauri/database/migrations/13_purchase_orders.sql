@@ -0,0 +1,62 @@
+-- Currency the supplier invoices in by default.
+ALTER TABLE suppliers ADD COLUMN currency TEXT NOT NULL DEFAULT 'MXN' CHECK (currency IN ('MXN', 'USD'));
+
+-- Last cost paid for one unit of the product, in pesos.
+ALTER TABLE products ADD COLUMN cost DECIMAL(10,4);
+
+CREATE TABLE IF NOT EXISTS purchase_orders (
+    id                INTEGER PRIMARY KEY AUTOINCREMENT,
+    supplier_id       INTEGER NOT NULL,
+    status            TEXT NOT NULL DEFAULT 'open' CHECK (status IN (
+                          'open', 'partial', 'received', 'cancelled'
+                      )),
+    currency          TEXT NOT NULL DEFAULT 'MXN' CHECK (currency IN ('MXN', 'USD')),
+    notes             TEXT,
+    created_by        INTEGER,
+    created_at        DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at        DATETIME,
+    FOREIGN KEY (supplier_id) REFERENCES suppliers(id),
+    FOREIGN KEY (created_by) REFERENCES users(id)
+);
+
+-- No foreign key to products, so orders outlive deleted products.
+CREATE TABLE IF NOT EXISTS purchase_order_lines (
+    id                INTEGER PRIMARY KEY AUTOINCREMENT,
+    purchase_order_id INTEGER NOT NULL,
+    sku               TEXT NOT NULL,
+    product_name      TEXT NOT NULL,             -- Historical name when ordered
+    quantity_ordered  DECIMAL(10,3) NOT NULL CHECK (quantity_ordered > 0),
+    quantity_received DECIMAL(10,3) NOT NULL DEFAULT 0,
+    unit_cost         DECIMAL(10,4) NOT NULL,    -- In the currency of the order
+    FOREIGN KEY (purchase_order_id) REFERENCES purchase_orders(id) ON DELETE CASCADE
+);
+
+-- Each delivery against an order, with the supplier invoice it came with.
+-- `exchange_rate` is the pesos per unit of `currency` used to cost it
+-- (1 for MXN invoices).
+CREATE TABLE IF NOT EXISTS purchase_receipts (
+    id                INTEGER PRIMARY KEY AUTOINCREMENT,
+    purchase_order_id INTEGER NOT NULL,
+    supplier_invoice  TEXT,
+    currency          TEXT NOT NULL CHECK (currency IN ('MXN', 'USD')),
+    exchange_rate     DECIMAL(10,4) NOT NULL CHECK (exchange_rate > 0),
+    received_by       INTEGER,
+    received_at       DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (purchase_order_id) REFERENCES purchase_orders(id),
+    FOREIGN KEY (received_by) REFERENCES users(id)
+);
+
+CREATE TABLE IF NOT EXISTS purchase_receipt_lines (
+    receipt_id        INTEGER NOT NULL,
+    line_id           INTEGER NOT NULL,
+    quantity          DECIMAL(10,3) NOT NULL CHECK (quantity > 0),
+    unit_cost         DECIMAL(10,4) NOT NULL,    -- As invoiced, in the receipt currency
+    unit_cost_mxn     DECIMAL(10,4) NOT NULL,
+    PRIMARY KEY (receipt_id, line_id),
+    FOREIGN KEY (receipt_id) REFERENCES purchase_receipts(id),
+    FOREIGN KEY (line_id) REFERENCES purchase_order_lines(id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_purchase_orders_status ON purchase_orders(status, created_at);
+CREATE INDEX IF NOT EXISTS idx_purchase_order_lines_order ON purchase_order_lines(purchase_order_id);
+CREATE INDEX IF NOT EXISTS idx_purchase_receipts_order ON purchase_receipts(purchase_order_id);
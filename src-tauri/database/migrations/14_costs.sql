@@ -0,0 +1,24 @@
+-- Weighted average cost of one unit, in pesos, next to the last cost
+-- (`products.cost`). Margins are measured against the average.
+ALTER TABLE products ADD COLUMN average_cost DECIMAL(10,4);
+UPDATE products SET average_cost = cost WHERE cost IS NOT NULL;
+
+-- Average unit cost of the product when it was sold; NULL when the
+-- product had no cost yet.
+ALTER TABLE ticket_items ADD COLUMN line_item_cost DECIMAL(10,4);
+
+CREATE TABLE IF NOT EXISTS cost_history (
+    id                INTEGER PRIMARY KEY AUTOINCREMENT,
+    sku               TEXT NOT NULL,
+    old_cost          DECIMAL(10,4),
+    new_cost          DECIMAL(10,4) NOT NULL,
+    old_average_cost  DECIMAL(10,4),
+    new_average_cost  DECIMAL(10,4) NOT NULL,
+    source            TEXT NOT NULL,             -- "receipt" or "manual"
+    reference         TEXT,                      -- Optional: purchase order of a receipt
+    changed_by        INTEGER,
+    changed_at        DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (changed_by) REFERENCES users(id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_cost_history_sku ON cost_history(sku, changed_at);
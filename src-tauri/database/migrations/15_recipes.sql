@@ -0,0 +1,81 @@
+-- Bill of materials of produced goods: `yield_quantity` units of the
+-- product are made from the listed quantities of raw materials (e.g.
+-- 25 kg of flour, 2.5 kg of lard, ... make 38 kg of tortilla de harina).
+CREATE TABLE IF NOT EXISTS recipes (
+    sku               TEXT PRIMARY KEY,
+    yield_quantity    DECIMAL(10,3) NOT NULL CHECK (yield_quantity > 0),
+    notes             TEXT,
+    updated_at        DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    -- No ON DELETE CASCADE, same reason as product_barcodes.
+    FOREIGN KEY (sku) REFERENCES products(sku)
+);
+
+CREATE TABLE IF NOT EXISTS recipe_ingredients (
+    recipe_sku        TEXT NOT NULL,
+    ingredient_sku    TEXT NOT NULL,
+    quantity          DECIMAL(10,4) NOT NULL CHECK (quantity > 0), -- Per `yield_quantity` of the recipe
+    PRIMARY KEY (recipe_sku, ingredient_sku),
+    FOREIGN KEY (recipe_sku) REFERENCES recipes(sku),
+    FOREIGN KEY (ingredient_sku) REFERENCES products(sku)
+);
+
+-- Raw materials turned into finished goods. No foreign keys to products,
+-- so production history outlives deleted products.
+CREATE TABLE IF NOT EXISTS production_batches (
+    id                INTEGER PRIMARY KEY AUTOINCREMENT,
+    sku               TEXT NOT NULL,
+    product_name      TEXT NOT NULL,             -- Historical name when produced
+    quantity          DECIMAL(10,3) NOT NULL CHECK (quantity > 0),
+    unit_cost         DECIMAL(10,4),             -- Optional: NULL when an ingredient had no cost
+    notes             TEXT,
+    produced_by       INTEGER,
+    produced_at       DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (produced_by) REFERENCES users(id)
+);
+
+CREATE TABLE IF NOT EXISTS production_batch_ingredients (
+    batch_id             INTEGER NOT NULL,
+    ingredient_sku       TEXT NOT NULL,
+    product_name         TEXT NOT NULL,
+    theoretical_quantity DECIMAL(10,4) NOT NULL, -- What the recipe calls for
+    actual_quantity      DECIMAL(10,4) NOT NULL, -- What was really used
+    unit_cost            DECIMAL(10,4),
+    PRIMARY KEY (batch_id, ingredient_sku),
+    FOREIGN KEY (batch_id) REFERENCES production_batches(id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_recipe_ingredients_ingredient ON recipe_ingredients(ingredient_sku);
+CREATE INDEX IF NOT EXISTS idx_production_batches_produced_at ON production_batches(produced_at);
+
+CREATE TRIGGER IF NOT EXISTS recipes_after_product_delete AFTER DELETE ON products
+BEGIN
+    DELETE FROM recipe_ingredients WHERE recipe_sku = OLD.sku OR ingredient_sku = OLD.sku;
+    DELETE FROM recipes WHERE sku = OLD.sku;
+END;
+
+-- New `production` (finished goods in) and `consumption` (raw materials
+-- out) movements. SQLite cannot alter a CHECK constraint, so
+-- stock_movements is rebuilt with the longer list, keeping its ids.
+CREATE TABLE stock_movements_new (
+    id                INTEGER PRIMARY KEY AUTOINCREMENT,
+    sku               TEXT NOT NULL,
+    kind              TEXT NOT NULL CHECK (kind IN (
+                          'sale', 'return', 'receipt', 'adjustment', 'waste', 'transfer',
+                          'production', 'consumption'
+                      )),
+    quantity          DECIMAL(10,3) NOT NULL,    -- Signed: negative when stock goes out
+    balance           DECIMAL(10,3) NOT NULL,    -- Stock after the movement
+    reference         TEXT,                      -- Optional: ticket id, purchase order, batch, ...
+    reason            TEXT,                      -- Optional: required for manual adjustments
+    user_id           INTEGER,
+    created_at        DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (user_id) REFERENCES users(id)
+);
+INSERT INTO stock_movements_new (id, sku, kind, quantity, balance, reference, reason, user_id, created_at)
+    SELECT id, sku, kind, quantity, balance, reference, reason, user_id, created_at
+    FROM stock_movements;
+DROP TABLE stock_movements;
+ALTER TABLE stock_movements_new RENAME TO stock_movements;
+
+CREATE INDEX IF NOT EXISTS idx_stock_movements_sku ON stock_movements(sku, created_at);
+CREATE INDEX IF NOT EXISTS idx_stock_movements_reference ON stock_movements(reference);
@@ -0,0 +1,18 @@
+-- Goods thrown away or given out without a sale, valued at cost. No
+-- foreign key to products, so the record outlives deleted products.
+CREATE TABLE IF NOT EXISTS waste_records (
+    id                INTEGER PRIMARY KEY AUTOINCREMENT,
+    sku               TEXT NOT NULL,
+    product_name      TEXT NOT NULL,             -- Historical name when recorded
+    quantity          DECIMAL(10,3) NOT NULL CHECK (quantity > 0),
+    reason            TEXT NOT NULL CHECK (reason IN (
+                          'unsold', 'damaged', 'expired', 'sample', 'other'
+                      )),
+    notes             TEXT,                      -- Required for 'other'
+    unit_cost         DECIMAL(10,4),             -- Optional: NULL when the product had no cost
+    user_id           INTEGER,
+    created_at        DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (user_id) REFERENCES users(id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_waste_records_created_at ON waste_records(created_at);
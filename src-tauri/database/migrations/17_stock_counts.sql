@@ -0,0 +1,35 @@
+-- Physical inventory counts. A count takes a snapshot of the stock of
+-- every tracked product in its scope when it starts; variances are
+-- measured against that snapshot and posted as adjustments.
+CREATE TABLE IF NOT EXISTS stock_counts (
+    id                INTEGER PRIMARY KEY AUTOINCREMENT,
+    category_id       INTEGER,                   -- Optional: NULL for a full count; includes subcategories
+    category_name     TEXT,                      -- Historical name of the category
+    status            TEXT NOT NULL DEFAULT 'open' CHECK (status IN ('open', 'posted', 'cancelled')),
+    notes             TEXT,
+    created_by        INTEGER,
+    created_at        DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    posted_by         INTEGER,
+    posted_at         DATETIME,
+    FOREIGN KEY (created_by) REFERENCES users(id),
+    FOREIGN KEY (posted_by) REFERENCES users(id)
+);
+
+-- One line per product in the scope of the count. No foreign key to
+-- products, so posted counts outlive deleted products.
+CREATE TABLE IF NOT EXISTS stock_count_lines (
+    id                INTEGER PRIMARY KEY AUTOINCREMENT,
+    count_id          INTEGER NOT NULL,
+    sku               TEXT NOT NULL,
+    product_name      TEXT NOT NULL,             -- Historical name when the count started
+    expected_quantity DECIMAL(10,3) NOT NULL,    -- Stock when the count started
+    counted_quantity  DECIMAL(10,3),             -- Optional: NULL until counted
+    previous_quantity DECIMAL(10,3),             -- Optional: count replaced by a recount
+    recount           INTEGER NOT NULL DEFAULT 0, -- Flagged to be counted again
+    counted_by        INTEGER,
+    counted_at        DATETIME,
+    adjustment        DECIMAL(10,3),             -- Optional: quantity posted when approved
+    UNIQUE (count_id, sku),
+    FOREIGN KEY (count_id) REFERENCES stock_counts(id) ON DELETE CASCADE,
+    FOREIGN KEY (counted_by) REFERENCES users(id)
+);
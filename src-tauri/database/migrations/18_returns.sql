@@ -0,0 +1,41 @@
+-- Pesos per dollar when the ticket was paid; NULL on tickets recorded
+-- before it was stored.
+ALTER TABLE tickets ADD COLUMN exchange_rate DECIMAL(10,4);
+
+-- Returns are negative tickets linked to the sale they refund.
+ALTER TABLE tickets ADD COLUMN original_ticket_id TEXT REFERENCES tickets(id);
+
+CREATE INDEX IF NOT EXISTS idx_tickets_original_ticket_id ON tickets(original_ticket_id);
+
+-- Returned items have a negative quantity and point at the item of the
+-- sale they return. SQLite cannot alter a CHECK constraint, so
+-- ticket_items is rebuilt.
+CREATE TABLE ticket_items_new (
+    id                TEXT PRIMARY KEY,  -- e.g., sha256 hash of (sales_id + product_sku + quantity + product_price)
+    ticket_id          TEXT NOT NULL,     -- Foreign key to tickets table
+    line_item_sku       TEXT NOT NULL,     -- Historical SKU at sale time
+    line_item_plu_code TEXT,              -- Historical vendor SKU at sale time
+    line_item_barcode TEXT,
+    line_item_product_name      TEXT NOT NULL,     -- Historical name at sale time
+    line_item_price     DECIMAL(10,2) NOT NULL,  -- Historical price at sale time
+    line_item_quantity  INTEGER NOT NULL CHECK (line_item_quantity <> 0), -- Negative when returned
+    line_item_total        DECIMAL(10,2) NOT NULL,
+    snapshot_created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    line_item_cost      DECIMAL(10,4),
+    original_item_id    TEXT,              -- Optional: item of the sale a returned item refunds
+    FOREIGN KEY (ticket_id) REFERENCES tickets(id),
+    FOREIGN KEY (original_item_id) REFERENCES ticket_items(id)
+);
+INSERT INTO ticket_items_new (
+    id, ticket_id, line_item_sku, line_item_plu_code, line_item_barcode, line_item_product_name,
+    line_item_price, line_item_quantity, line_item_total, snapshot_created_at, line_item_cost
+)
+    SELECT id, ticket_id, line_item_sku, line_item_plu_code, line_item_barcode,
+           line_item_product_name, line_item_price, line_item_quantity, line_item_total,
+           snapshot_created_at, line_item_cost
+    FROM ticket_items;
+DROP TABLE ticket_items;
+ALTER TABLE ticket_items_new RENAME TO ticket_items;
+
+CREATE INDEX IF NOT EXISTS idx_ticket_items_ticket_id ON ticket_items(ticket_id);
+CREATE INDEX IF NOT EXISTS idx_ticket_items_original_item_id ON ticket_items(original_item_id);
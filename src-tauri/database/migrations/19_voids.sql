@@ -0,0 +1,7 @@
+-- Voided tickets are kept, marked with who voided them and why, and left
+-- out of sales reports.
+ALTER TABLE tickets ADD COLUMN voided_at DATETIME;
+ALTER TABLE tickets ADD COLUMN voided_by INTEGER REFERENCES users(id);
+ALTER TABLE tickets ADD COLUMN void_reason TEXT;
+
+ALTER TABLE ticket_items ADD COLUMN voided INTEGER NOT NULL DEFAULT 0;
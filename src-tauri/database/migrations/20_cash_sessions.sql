@@ -0,0 +1,32 @@
+-- Cash register sessions (shifts). A session belongs to one terminal and
+-- cashier and opens with the float counted in each currency. The drawer
+-- is expected to hold the float plus the cash of its tickets; at close
+-- the cashier counts blind and the difference is kept as over/short.
+CREATE TABLE IF NOT EXISTS cash_sessions (
+    id                INTEGER PRIMARY KEY AUTOINCREMENT,
+    pos_id            TEXT NOT NULL,
+    cashier_id        INTEGER NOT NULL,
+    cashier_name      TEXT NOT NULL,             -- Historical name when the session opened
+    opened_at         DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    opening_pesos     DECIMAL(10,2) NOT NULL CHECK (opening_pesos >= 0),
+    opening_dollars   DECIMAL(10,2) NOT NULL CHECK (opening_dollars >= 0),
+    closed_at         DATETIME,                  -- Optional: NULL while open
+    closed_by         INTEGER,
+    expected_pesos    DECIMAL(10,2),             -- Expected in the drawer when closed
+    expected_dollars  DECIMAL(10,2),
+    counted_pesos     DECIMAL(10,2),             -- Blind count when closed
+    counted_dollars   DECIMAL(10,2),
+    notes             TEXT,
+    FOREIGN KEY (cashier_id) REFERENCES users(id),
+    FOREIGN KEY (closed_by) REFERENCES users(id)
+);
+
+-- At most one open session per terminal
+CREATE UNIQUE INDEX IF NOT EXISTS idx_cash_sessions_open ON cash_sessions(pos_id)
+    WHERE closed_at IS NULL;
+
+-- Session a ticket was paid (or refunded) in; NULL on tickets recorded
+-- before sessions existed.
+ALTER TABLE tickets ADD COLUMN cash_session_id INTEGER REFERENCES cash_sessions(id);
+
+CREATE INDEX IF NOT EXISTS idx_tickets_cash_session_id ON tickets(cash_session_id);
@@ -0,0 +1,20 @@
+-- Cash put into or taken out of the drawer during a session, apart from
+-- tickets: change top-ups (paid in), expenses such as the gas delivery
+-- (paid out) and drops to the safe. Counted in the session's expected
+-- drawer.
+CREATE TABLE IF NOT EXISTS cash_movements (
+    id                INTEGER PRIMARY KEY AUTOINCREMENT,
+    cash_session_id   INTEGER NOT NULL,
+    kind              TEXT NOT NULL CHECK (kind IN ('paid_in', 'paid_out', 'safe_drop')),
+    currency          TEXT NOT NULL CHECK (currency IN ('MXN', 'USD')),
+    amount            DECIMAL(10,2) NOT NULL CHECK (amount > 0),
+    reason            TEXT NOT NULL,
+    created_by        INTEGER NOT NULL,
+    authorized_by     INTEGER NOT NULL,          -- The cashier, or the manager who approved
+    created_at        DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (cash_session_id) REFERENCES cash_sessions(id),
+    FOREIGN KEY (created_by) REFERENCES users(id),
+    FOREIGN KEY (authorized_by) REFERENCES users(id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_cash_movements_cash_session_id ON cash_movements(cash_session_id);
@@ -0,0 +1,59 @@
+-- Coins and bills cash is counted in, per currency. Retired ones are
+-- kept inactive so older counts still list them.
+CREATE TABLE IF NOT EXISTS denominations (
+    id                INTEGER PRIMARY KEY AUTOINCREMENT,
+    currency          TEXT NOT NULL CHECK (currency IN ('MXN', 'USD')),
+    kind              TEXT NOT NULL CHECK (kind IN ('coin', 'bill')),
+    value             DECIMAL(10,2) NOT NULL CHECK (value > 0),
+    active            INTEGER NOT NULL DEFAULT 1,
+    UNIQUE (currency, kind, value)
+);
+
+INSERT OR IGNORE INTO denominations (currency, kind, value) VALUES
+    ('MXN', 'coin', 0.5),
+    ('MXN', 'coin', 1),
+    ('MXN', 'coin', 2),
+    ('MXN', 'coin', 5),
+    ('MXN', 'coin', 10),
+    ('MXN', 'coin', 20),
+    ('MXN', 'bill', 20),
+    ('MXN', 'bill', 50),
+    ('MXN', 'bill', 100),
+    ('MXN', 'bill', 200),
+    ('MXN', 'bill', 500),
+    ('MXN', 'bill', 1000),
+    ('USD', 'bill', 1),
+    ('USD', 'bill', 2),
+    ('USD', 'bill', 5),
+    ('USD', 'bill', 10),
+    ('USD', 'bill', 20),
+    ('USD', 'bill', 50),
+    ('USD', 'bill', 100);
+
+-- Cash counted by denomination when a session opens or closes, or for a
+-- safe drop. The totals are what the session or the drop recorded.
+CREATE TABLE IF NOT EXISTS cash_counts (
+    id                INTEGER PRIMARY KEY AUTOINCREMENT,
+    cash_session_id   INTEGER NOT NULL,
+    purpose           TEXT NOT NULL CHECK (purpose IN ('opening', 'closing', 'safe_drop')),
+    cash_movement_id  INTEGER,                   -- Optional: the safe drop counted
+    total_pesos       DECIMAL(10,2) NOT NULL,
+    total_dollars     DECIMAL(10,2) NOT NULL,
+    counted_by        INTEGER NOT NULL,
+    created_at        DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (cash_session_id) REFERENCES cash_sessions(id),
+    FOREIGN KEY (cash_movement_id) REFERENCES cash_movements(id),
+    FOREIGN KEY (counted_by) REFERENCES users(id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_cash_counts_cash_session_id ON cash_counts(cash_session_id);
+
+CREATE TABLE IF NOT EXISTS cash_count_lines (
+    id                INTEGER PRIMARY KEY AUTOINCREMENT,
+    count_id          INTEGER NOT NULL,
+    denomination_id   INTEGER NOT NULL,
+    quantity          INTEGER NOT NULL CHECK (quantity >= 0),
+    UNIQUE (count_id, denomination_id),
+    FOREIGN KEY (count_id) REFERENCES cash_counts(id) ON DELETE CASCADE,
+    FOREIGN KEY (denomination_id) REFERENCES denominations(id)
+);
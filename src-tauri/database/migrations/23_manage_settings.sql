@@ -0,0 +1,21 @@
+-- New `manage_settings` permission for the printer and drawer limits.
+-- SQLite cannot alter a CHECK constraint, so role_permissions is rebuilt
+-- with the longer list.
+CREATE TABLE role_permissions_new (
+    role_id           INTEGER NOT NULL,
+    permission        TEXT NOT NULL CHECK (permission IN (
+                          'sell', 'void', 'refund', 'discount', 'import',
+                          'change_rate', 'open_drawer', 'view_reports', 'manage_users',
+                          'manage_inventory', 'manage_settings'
+                      )),
+    PRIMARY KEY (role_id, permission),
+    FOREIGN KEY (role_id) REFERENCES roles(id) ON DELETE CASCADE
+);
+INSERT INTO role_permissions_new (role_id, permission)
+    SELECT role_id, permission FROM role_permissions;
+DROP TABLE role_permissions;
+ALTER TABLE role_permissions_new RENAME TO role_permissions;
+
+INSERT OR IGNORE INTO role_permissions (role_id, permission) VALUES
+    (1, 'manage_settings'),
+    (2, 'manage_settings');
@@ -0,0 +1,46 @@
+-- Full text index over the product catalog, used by the `search_products` command.
+-- `remove_diacritics 2` makes "tortilla maiz" match "Tortilla Maíz", and the
+-- prefix indexes keep "tort*" style queries fast on large catalogs.
+CREATE VIRTUAL TABLE IF NOT EXISTS products_fts USING fts5(
+    sku,
+    plu_code,
+    barcode,
+    product_name,
+    tokenize = 'unicode61 remove_diacritics 2',
+    prefix = '1 2 3'
+);
+
+-- FTS rows share the rowid of their product.
+INSERT INTO products_fts (rowid, sku, plu_code, barcode, product_name)
+SELECT rowid, sku, plu_code, barcode, product_name FROM products;
+
+-- INSERT OR REPLACE deletes the old row without firing DELETE triggers
+-- (recursive_triggers is off), so drop the stale FTS row before the insert.
+CREATE TRIGGER IF NOT EXISTS products_fts_before_insert BEFORE INSERT ON products
+BEGIN
+    DELETE FROM products_fts WHERE rowid = (SELECT rowid FROM products WHERE sku = NEW.sku);
+END;
+
+CREATE TRIGGER IF NOT EXISTS products_fts_after_insert AFTER INSERT ON products
+BEGIN
+    INSERT INTO products_fts (rowid, sku, plu_code, barcode, product_name)
+    VALUES (NEW.rowid, NEW.sku, NEW.plu_code, NEW.barcode, NEW.product_name);
+END;
+
+CREATE TRIGGER IF NOT EXISTS products_fts_after_update AFTER UPDATE ON products
+BEGIN
+    DELETE FROM products_fts WHERE rowid = OLD.rowid;
+    INSERT INTO products_fts (rowid, sku, plu_code, barcode, product_name)
+    VALUES (NEW.rowid, NEW.sku, NEW.plu_code, NEW.barcode, NEW.product_name);
+END;
+
+CREATE TRIGGER IF NOT EXISTS products_fts_after_delete AFTER DELETE ON products
+BEGIN
+    DELETE FROM products_fts WHERE rowid = OLD.rowid;
+END;
+
+-- `sku` is the primary key, so this index only duplicated the implicit one.
+DROP INDEX IF EXISTS idx_products_sku;
+
+-- Matches on codes outrank matches on the product name.
+INSERT INTO products_fts (products_fts, rank) VALUES ('rank', 'bm25(10.0, 10.0, 10.0, 1.0)');
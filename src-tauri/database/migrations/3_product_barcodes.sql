@@ -0,0 +1,98 @@
+-- A product can be scanned with several barcodes: one per supplier plus
+-- case/pack barcodes. `quantity` is how many units of `sku` one scan adds
+-- (1 for the unit barcode, 12 for a case of 12, ...).
+CREATE TABLE IF NOT EXISTS product_barcodes (
+    barcode           TEXT PRIMARY KEY,
+    sku               TEXT NOT NULL,
+    quantity          DECIMAL(10,3) NOT NULL DEFAULT 1 CHECK (quantity > 0),
+    description       TEXT, -- Optional: e.g. "Caja 12 pzas", supplier name
+    created_at        DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    -- No ON DELETE CASCADE: INSERT OR REPLACE on products deletes the row
+    -- internally and would take every alias with it.
+    FOREIGN KEY (sku) REFERENCES products(sku)
+);
+
+CREATE INDEX IF NOT EXISTS idx_product_barcodes_sku ON product_barcodes(sku);
+
+INSERT OR IGNORE INTO product_barcodes (barcode, sku)
+SELECT TRIM(barcode), sku FROM products
+WHERE barcode IS NOT NULL AND TRIM(barcode) <> '';
+
+-- The FTS triggers reference products.barcode, drop them before the column.
+DROP TRIGGER IF EXISTS products_fts_before_insert;
+DROP TRIGGER IF EXISTS products_fts_after_insert;
+DROP TRIGGER IF EXISTS products_fts_after_update;
+DROP TRIGGER IF EXISTS products_fts_after_delete;
+
+ALTER TABLE products DROP COLUMN barcode;
+
+-- The FTS `barcode` column now holds every alias of the product, space separated.
+DELETE FROM products_fts;
+INSERT INTO products_fts (rowid, sku, plu_code, barcode, product_name)
+SELECT p.rowid, p.sku, p.plu_code,
+       (SELECT group_concat(b.barcode, ' ') FROM product_barcodes b WHERE b.sku = p.sku),
+       p.product_name
+FROM products p;
+
+CREATE TRIGGER IF NOT EXISTS products_fts_before_insert BEFORE INSERT ON products
+BEGIN
+    DELETE FROM products_fts WHERE rowid = (SELECT rowid FROM products WHERE sku = NEW.sku);
+END;
+
+CREATE TRIGGER IF NOT EXISTS products_fts_after_insert AFTER INSERT ON products
+BEGIN
+    INSERT INTO products_fts (rowid, sku, plu_code, barcode, product_name)
+    VALUES (
+        NEW.rowid, NEW.sku, NEW.plu_code,
+        (SELECT group_concat(barcode, ' ') FROM product_barcodes WHERE sku = NEW.sku),
+        NEW.product_name
+    );
+END;
+
+CREATE TRIGGER IF NOT EXISTS products_fts_after_update AFTER UPDATE ON products
+BEGIN
+    DELETE FROM products_fts WHERE rowid = OLD.rowid;
+    INSERT INTO products_fts (rowid, sku, plu_code, barcode, product_name)
+    VALUES (
+        NEW.rowid, NEW.sku, NEW.plu_code,
+        (SELECT group_concat(barcode, ' ') FROM product_barcodes WHERE sku = NEW.sku),
+        NEW.product_name
+    );
+END;
+
+CREATE TRIGGER IF NOT EXISTS products_fts_after_delete AFTER DELETE ON products
+BEGIN
+    DELETE FROM products_fts WHERE rowid = OLD.rowid;
+    DELETE FROM product_barcodes WHERE sku = OLD.sku;
+END;
+
+-- Keep the aggregated barcodes of a product in sync with its aliases.
+CREATE TRIGGER IF NOT EXISTS product_barcodes_fts_after_insert AFTER INSERT ON product_barcodes
+BEGIN
+    DELETE FROM products_fts WHERE rowid = (SELECT rowid FROM products WHERE sku = NEW.sku);
+    INSERT INTO products_fts (rowid, sku, plu_code, barcode, product_name)
+    SELECT p.rowid, p.sku, p.plu_code,
+           (SELECT group_concat(b.barcode, ' ') FROM product_barcodes b WHERE b.sku = p.sku),
+           p.product_name
+    FROM products p WHERE p.sku = NEW.sku;
+END;
+
+CREATE TRIGGER IF NOT EXISTS product_barcodes_fts_after_update AFTER UPDATE ON product_barcodes
+BEGIN
+    DELETE FROM products_fts WHERE rowid IN (SELECT rowid FROM products WHERE sku IN (OLD.sku, NEW.sku));
+    INSERT INTO products_fts (rowid, sku, plu_code, barcode, product_name)
+    SELECT p.rowid, p.sku, p.plu_code,
+           (SELECT group_concat(b.barcode, ' ') FROM product_barcodes b WHERE b.sku = p.sku),
+           p.product_name
+    FROM products p WHERE p.sku IN (OLD.sku, NEW.sku);
+END;
+
+CREATE TRIGGER IF NOT EXISTS product_barcodes_fts_after_delete AFTER DELETE ON product_barcodes
+BEGIN
+    DELETE FROM products_fts WHERE rowid = (SELECT rowid FROM products WHERE sku = OLD.sku);
+    INSERT INTO products_fts (rowid, sku, plu_code, barcode, product_name)
+    SELECT p.rowid, p.sku, p.plu_code,
+           (SELECT group_concat(b.barcode, ' ') FROM product_barcodes b WHERE b.sku = p.sku),
+           p.product_name
+    FROM products p WHERE p.sku = OLD.sku;
+END;
@@ -0,0 +1,17 @@
+CREATE TABLE IF NOT EXISTS categories (
+    id                INTEGER PRIMARY KEY AUTOINCREMENT,
+    code              TEXT UNIQUE, -- Optional: code from the source catalog, e.g. "10" in "Tortilla (10)"
+    name              TEXT NOT NULL,
+    parent_id         INTEGER,     -- Optional: NULL for top level categories
+    sort_order        INTEGER NOT NULL DEFAULT 0, -- Position of the category in the quick-key grid
+    created_at        DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (parent_id) REFERENCES categories(id)
+);
+
+-- Names are unique among siblings, so imports can find a category by name.
+CREATE UNIQUE INDEX IF NOT EXISTS idx_categories_parent_name
+    ON categories(IFNULL(parent_id, 0), name COLLATE NOCASE);
+
+ALTER TABLE products ADD COLUMN category_id INTEGER REFERENCES categories(id);
+
+CREATE INDEX IF NOT EXISTS idx_products_category_id ON products(category_id);
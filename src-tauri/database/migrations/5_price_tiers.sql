@@ -0,0 +1,18 @@
+-- Extra prices of a product besides `products.price` (the retail price),
+-- e.g. "mayoreo" for wholesale. Catalogs from other POS systems bring
+-- these as "Precio Mayoreo", "Precio 2", ...
+CREATE TABLE IF NOT EXISTS product_prices (
+    sku               TEXT NOT NULL,
+    tier              TEXT NOT NULL,             -- e.g. "mayoreo"
+    price             DECIMAL(10,2) NOT NULL CHECK (price >= 0),
+    min_quantity      DECIMAL(10,3),             -- Optional: units from which the tier applies, NULL when applied by hand
+    updated_at        DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (sku, tier),
+    -- No ON DELETE CASCADE, same reason as product_barcodes.
+    FOREIGN KEY (sku) REFERENCES products(sku)
+);
+
+CREATE TRIGGER IF NOT EXISTS product_prices_after_product_delete AFTER DELETE ON products
+BEGIN
+    DELETE FROM product_prices WHERE sku = OLD.sku;
+END;
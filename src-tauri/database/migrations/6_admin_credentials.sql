@@ -0,0 +1,10 @@
+-- Secrets guarding admin-only actions (imports, settings, ...).
+-- The plaintext `admin_password` setting is hashed into this table by the
+-- app on first use and then deleted, since SQL cannot compute Argon2.
+CREATE TABLE IF NOT EXISTS admin_credentials (
+    name              TEXT PRIMARY KEY,          -- e.g. "admin"
+    password_hash     TEXT NOT NULL,             -- Argon2id PHC string
+    failed_attempts   INTEGER NOT NULL DEFAULT 0, -- Consecutive failures since the last success or lockout
+    locked_until      DATETIME,                  -- Optional: UTC time until which every attempt is refused
+    updated_at        DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
@@ -0,0 +1,48 @@
+-- Cashier accounts. Each user signs in with a PIN or password and gets
+-- the permissions of their role; commands check them in Rust.
+CREATE TABLE IF NOT EXISTS roles (
+    id                INTEGER PRIMARY KEY AUTOINCREMENT,
+    name              TEXT NOT NULL UNIQUE COLLATE NOCASE,
+    created_at        DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE IF NOT EXISTS role_permissions (
+    role_id           INTEGER NOT NULL,
+    permission        TEXT NOT NULL CHECK (permission IN (
+                          'sell', 'void', 'refund', 'discount', 'import',
+                          'change_rate', 'open_drawer', 'view_reports', 'manage_users'
+                      )),
+    PRIMARY KEY (role_id, permission),
+    FOREIGN KEY (role_id) REFERENCES roles(id) ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS users (
+    id                INTEGER PRIMARY KEY AUTOINCREMENT,
+    username          TEXT NOT NULL UNIQUE COLLATE NOCASE,
+    display_name      TEXT NOT NULL,              -- Printed on tickets, e.g. "María"
+    role_id           INTEGER NOT NULL,
+    secret_hash       TEXT NOT NULL,              -- Argon2id PHC string of the PIN or password
+    is_active         INTEGER NOT NULL DEFAULT 1, -- Inactive users cannot sign in but keep their tickets
+    failed_attempts   INTEGER NOT NULL DEFAULT 0,
+    locked_until      DATETIME,                   -- Optional: UTC time until which sign-in is refused
+    created_at        DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at        DATETIME,
+    FOREIGN KEY (role_id) REFERENCES roles(id)
+);
+
+INSERT OR IGNORE INTO roles (id, name) VALUES
+    (1, 'Administrador'),
+    (2, 'Encargado'),
+    (3, 'Cajero');
+
+INSERT OR IGNORE INTO role_permissions (role_id, permission) VALUES
+    (1, 'sell'), (1, 'void'), (1, 'refund'), (1, 'discount'), (1, 'import'),
+    (1, 'change_rate'), (1, 'open_drawer'), (1, 'view_reports'), (1, 'manage_users'),
+    (2, 'sell'), (2, 'void'), (2, 'refund'), (2, 'discount'),
+    (2, 'change_rate'), (2, 'open_drawer'), (2, 'view_reports'),
+    (3, 'sell'), (3, 'open_drawer');
+
+-- `cashier_name` stays as the name printed at sale time, like the line item snapshots.
+ALTER TABLE tickets ADD COLUMN cashier_id INTEGER REFERENCES users(id);
+
+CREATE INDEX IF NOT EXISTS idx_tickets_cashier_id ON tickets(cashier_id);
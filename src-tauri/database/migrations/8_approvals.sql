@@ -0,0 +1,22 @@
+-- Largest discount (percent off the catalog price) a role may give
+-- without holding the `discount` permission or a manager approval.
+ALTER TABLE roles ADD COLUMN max_discount_percent DECIMAL(5,2) NOT NULL DEFAULT 0;
+
+-- Restricted actions a manager approved on a cashier's terminal.
+CREATE TABLE IF NOT EXISTS approvals (
+    id                INTEGER PRIMARY KEY AUTOINCREMENT,
+    action            TEXT NOT NULL,             -- Permission that was lacking, e.g. "discount", "void"
+    ticket_id         TEXT,                      -- Optional: ticket the action applies to
+    requested_by      INTEGER NOT NULL,          -- Signed in user who needed the approval
+    approved_by       INTEGER NOT NULL,          -- Manager who entered their PIN
+    reason            TEXT NOT NULL,
+    amount            DECIMAL(10,2),             -- Optional: money involved, e.g. the discount given
+    details           TEXT,                      -- Optional: what was approved, e.g. "Tortilla 48.00 -> 40.00"
+    created_at        DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (ticket_id) REFERENCES tickets(id),
+    FOREIGN KEY (requested_by) REFERENCES users(id),
+    FOREIGN KEY (approved_by) REFERENCES users(id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_approvals_ticket_id ON approvals(ticket_id);
+CREATE INDEX IF NOT EXISTS idx_approvals_created_at ON approvals(created_at);
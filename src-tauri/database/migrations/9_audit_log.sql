@@ -0,0 +1,32 @@
+-- Append-only record of sensitive actions, written by the Rust commands.
+-- Each entry carries the hash of the previous one, so an edited or deleted
+-- entry breaks the chain (see `verify_audit_log`).
+CREATE TABLE IF NOT EXISTS audit_log (
+    id                INTEGER PRIMARY KEY AUTOINCREMENT,
+    created_at        TEXT NOT NULL,              -- UTC, part of the hashed data
+    user_id           INTEGER,                    -- Optional: signed in user, NULL for admin menu actions
+    user_name         TEXT,
+    action            TEXT NOT NULL,              -- e.g. "change_price", "import_catalog", "open_drawer"
+    entity            TEXT NOT NULL,              -- e.g. "product", "settings", "user"
+    entity_id         TEXT,                       -- Optional: e.g. the product SKU
+    before_value      TEXT,                       -- Optional: JSON of the values before the change
+    after_value       TEXT,                       -- Optional: JSON of the values after the change
+    prev_hash         TEXT NOT NULL UNIQUE,       -- Hash of the previous entry; zeros for the first one
+    hash              TEXT NOT NULL UNIQUE        -- sha256 of prev_hash and this entry's values
+);
+
+CREATE INDEX IF NOT EXISTS idx_audit_log_created_at ON audit_log(created_at);
+CREATE INDEX IF NOT EXISTS idx_audit_log_action ON audit_log(action);
+CREATE INDEX IF NOT EXISTS idx_audit_log_entity ON audit_log(entity, entity_id);
+
+CREATE TRIGGER IF NOT EXISTS audit_log_no_update
+BEFORE UPDATE ON audit_log
+BEGIN
+    SELECT RAISE(ABORT, 'audit_log is append-only');
+END;
+
+CREATE TRIGGER IF NOT EXISTS audit_log_no_delete
+BEFORE DELETE ON audit_log
+BEGIN
+    SELECT RAISE(ABORT, 'audit_log is append-only');
+END;
@@ -0,0 +1,229 @@
+use crate::audit;
+use crate::db;
+use crate::users::{self, Permission, SessionUser};
+use argon2::password_hash::rand_core::OsRng;
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::Argon2;
+use serde::Serialize;
+use sqlx::{Pool, Sqlite};
+use tauri::AppHandle;
+
+/// Credential guarding imports and other admin-only actions.
+const ADMIN: &str = "admin";
+/// Failed attempts allowed before a credential (admin or user) is locked.
+pub(crate) const MAX_FAILED_ATTEMPTS: i64 = 5;
+pub(crate) const LOCKOUT_MINUTES: i64 = 5;
+const MIN_PASSWORD_LENGTH: usize = 6;
+
+#[derive(Debug, Serialize)]
+pub struct AdminStatus {
+    pub is_set: bool,
+    /// UTC time until which attempts are refused, while locked.
+    pub locked_until: Option<String>,
+}
+
+#[derive(sqlx::FromRow)]
+struct Credential {
+    password_hash: String,
+    locked_until: Option<String>,
+}
+
+/// Argon2id hash (PHC string) of a password or PIN.
+pub(crate) fn hash_password(password: &str) -> Result<String, String> {
+    let salt = SaltString::generate(&mut OsRng);
+    Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+        .map_err(|e| e.to_string())
+}
+
+pub(crate) fn password_matches(password_hash: &str, password: &str) -> Result<bool, String> {
+    let hash = PasswordHash::new(password_hash).map_err(|e| e.to_string())?;
+    Ok(Argon2::default()
+        .verify_password(password.as_bytes(), &hash)
+        .is_ok())
+}
+
+async fn store_hash(pool: &Pool<Sqlite>, password_hash: &str) -> Result<(), String> {
+    sqlx::query(
+        "INSERT INTO admin_credentials (name, password_hash) VALUES (?, ?)
+         ON CONFLICT(name) DO UPDATE SET
+             password_hash = excluded.password_hash,
+             failed_attempts = 0,
+             locked_until = NULL,
+             updated_at = CURRENT_TIMESTAMP",
+    )
+    .bind(ADMIN)
+    .bind(password_hash)
+    .execute(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Returns the admin credential, first hashing the plaintext
+/// `admin_password` setting older versions stored, if any.
+async fn load_credential(pool: &Pool<Sqlite>) -> Result<Option<Credential>, String> {
+    let query = "SELECT password_hash,
+                        CASE WHEN locked_until > CURRENT_TIMESTAMP THEN locked_until END AS locked_until
+                 FROM admin_credentials WHERE name = ?";
+    let credential = sqlx::query_as::<_, Credential>(query)
+        .bind(ADMIN)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    if credential.is_some() {
+        return Ok(credential);
+    }
+
+    let legacy: Option<String> =
+        sqlx::query_scalar("SELECT CAST(value AS TEXT) FROM settings WHERE key = 'admin_password'")
+            .fetch_optional(pool)
+            .await
+            .map_err(|e| e.to_string())?;
+    // The value was stored as JSON by some versions and raw by others
+    let legacy = legacy
+        .map(|value| serde_json::from_str::<String>(&value).unwrap_or(value))
+        .filter(|value| !value.is_empty());
+    let Some(legacy) = legacy else {
+        return Ok(None);
+    };
+
+    store_hash(pool, &hash_password(&legacy)?).await?;
+    sqlx::query("DELETE FROM settings WHERE key = 'admin_password'")
+        .execute(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    sqlx::query_as::<_, Credential>(query)
+        .bind(ADMIN)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Checks the admin password. Every failure is counted in the database, so
+/// restarting the app does not reset the limit; after `MAX_FAILED_ATTEMPTS`
+/// failures the credential refuses every attempt for `LOCKOUT_MINUTES`.
+pub async fn verify(pool: &Pool<Sqlite>, password: &str) -> Result<(), String> {
+    let credential = load_credential(pool)
+        .await?
+        .ok_or("No admin password has been set")?;
+    if let Some(locked_until) = credential.locked_until {
+        return Err(format!(
+            "Too many failed attempts; try again after {} UTC",
+            locked_until
+        ));
+    }
+
+    if password_matches(&credential.password_hash, password)? {
+        sqlx::query(
+            "UPDATE admin_credentials SET failed_attempts = 0, locked_until = NULL WHERE name = ?",
+        )
+        .bind(ADMIN)
+        .execute(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+        return Ok(());
+    }
+
+    // Counted in one statement, so concurrent attempts cannot overwrite
+    // each other's count
+    let (failed_attempts, locked): (i64, bool) = sqlx::query_as(
+        "UPDATE admin_credentials
+         SET failed_attempts = CASE WHEN failed_attempts + 1 >= ?1 THEN 0
+                                    ELSE failed_attempts + 1 END,
+             locked_until = CASE WHEN failed_attempts + 1 >= ?1 THEN datetime('now', ?2)
+                                 ELSE locked_until END
+         WHERE name = ?3
+         RETURNING failed_attempts, IFNULL(locked_until > CURRENT_TIMESTAMP, 0)",
+    )
+    .bind(MAX_FAILED_ATTEMPTS)
+    .bind(format!("+{} minutes", LOCKOUT_MINUTES))
+    .bind(ADMIN)
+    .fetch_one(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+    if locked {
+        return Err(format!(
+            "Incorrect password. Too many failed attempts; locked for {} minutes",
+            LOCKOUT_MINUTES
+        ));
+    }
+    Err(format!(
+        "Incorrect password ({} attempts left)",
+        MAX_FAILED_ATTEMPTS - failed_attempts
+    ))
+}
+
+pub async fn is_set(pool: &Pool<Sqlite>) -> Result<bool, String> {
+    Ok(load_credential(pool).await?.is_some())
+}
+
+/// Sets the admin password. Changing an existing one requires the current
+/// password, checked with the same attempt limits as `verify`; setting the
+/// first one requires `user` to hold `manage_users`.
+pub async fn set_password(
+    pool: &Pool<Sqlite>,
+    user: Option<&SessionUser>,
+    current_password: Option<&str>,
+    new_password: &str,
+) -> Result<(), String> {
+    if new_password.chars().count() < MIN_PASSWORD_LENGTH {
+        return Err(format!(
+            "The password must have at least {} characters",
+            MIN_PASSWORD_LENGTH
+        ));
+    }
+    let was_set = is_set(pool).await?;
+    if !was_set && !user.is_some_and(|user| user.can(Permission::ManageUsers)) {
+        return Err("Sign in as a user with manage_users to set the admin password".to_string());
+    }
+    if was_set {
+        verify(
+            pool,
+            current_password.ok_or("The current password is required")?,
+        )
+        .await?;
+    }
+    store_hash(pool, &hash_password(new_password)?).await?;
+    let action = if was_set {
+        "change_admin_password"
+    } else {
+        "set_admin_password"
+    };
+    audit::log(pool, user, action, "admin", Some(ADMIN), None, None).await
+}
+
+#[tauri::command]
+pub async fn get_admin_status(app_handle: AppHandle) -> Result<AdminStatus, String> {
+    let pool = db::pool(&app_handle).await?;
+    let credential = load_credential(&pool).await?;
+    Ok(AdminStatus {
+        is_set: credential.is_some(),
+        locked_until: credential.and_then(|credential| credential.locked_until),
+    })
+}
+
+#[tauri::command]
+pub async fn set_admin_password(
+    app_handle: AppHandle,
+    current_password: Option<String>,
+    new_password: String,
+) -> Result<(), String> {
+    let pool = db::pool(&app_handle).await?;
+    let user = users::current_user(&app_handle);
+    set_password(
+        &pool,
+        user.as_ref(),
+        current_password.as_deref(),
+        &new_password,
+    )
+    .await
+}
+
+#[tauri::command]
+pub async fn verify_admin_password(app_handle: AppHandle, password: String) -> Result<(), String> {
+    let pool = db::pool(&app_handle).await?;
+    verify(&pool, &password).await
+}
@@ -0,0 +1,109 @@
+use crate::audit;
+use crate::users::{self, Permission, SessionUser};
+use serde::Deserialize;
+use serde_json::json;
+use sqlx::{Pool, Sqlite, SqliteConnection};
+
+/// Prefix of the error a restricted command returns when the signed in user
+/// lacks the permission: `needs_approval:<permission>:<message>`. The webview
+/// then asks a manager for their PIN and calls the command again with it.
+pub const NEEDS_APPROVAL: &str = "needs_approval";
+
+/// A manager's PIN entered on the cashier's terminal.
+#[derive(Debug, Deserialize)]
+pub struct Approval {
+    pub manager_username: String,
+    pub manager_secret: String,
+    pub reason: String,
+}
+
+/// Manager who approved an action, and why.
+pub struct Approver {
+    pub user: SessionUser,
+    pub reason: String,
+}
+
+pub fn needs_approval(permission: Permission, message: &str) -> String {
+    format!("{}:{}:{}", NEEDS_APPROVAL, permission.as_str(), message)
+}
+
+/// Lets `user` perform an action that takes `permission`. Returns `None` if
+/// they hold it themselves, or the manager who approved it. Without an
+/// approval the error is a `needs_approval` one.
+pub async fn authorize(
+    pool: &Pool<Sqlite>,
+    user: &SessionUser,
+    permission: Permission,
+    message: &str,
+    approval: Option<&Approval>,
+) -> Result<Option<Approver>, String> {
+    if user.can(permission) {
+        return Ok(None);
+    }
+    let approval = approval.ok_or_else(|| needs_approval(permission, message))?;
+    let reason = approval.reason.trim();
+    if reason.is_empty() {
+        return Err("The approval needs a reason".to_string());
+    }
+
+    let manager =
+        users::authenticate(pool, &approval.manager_username, &approval.manager_secret).await?;
+    if manager.id == user.id {
+        return Err("A user cannot approve their own action".to_string());
+    }
+    if !manager.can(permission) {
+        return Err(format!(
+            "{} ({}) is not allowed to approve {}",
+            manager.display_name,
+            manager.role_name,
+            permission.as_str()
+        ));
+    }
+    Ok(Some(Approver {
+        user: manager,
+        reason: reason.to_string(),
+    }))
+}
+
+/// Stores an approval, usually in the transaction of the approved action,
+/// and writes it to the audit log.
+pub async fn record(
+    conn: &mut SqliteConnection,
+    permission: Permission,
+    ticket_id: Option<&str>,
+    requested_by: &SessionUser,
+    approver: &Approver,
+    amount: Option<f64>,
+    details: &str,
+) -> Result<(), String> {
+    sqlx::query(
+        "INSERT INTO approvals (action, ticket_id, requested_by, approved_by, reason, amount, details)
+         VALUES (?, ?, ?, ?, ?, ?, ?)",
+    )
+    .bind(permission.as_str())
+    .bind(ticket_id)
+    .bind(requested_by.id)
+    .bind(approver.user.id)
+    .bind(&approver.reason)
+    .bind(amount)
+    .bind(details)
+    .execute(&mut *conn)
+    .await
+    .map_err(|e| e.to_string())?;
+    audit::record(
+        conn,
+        Some(requested_by),
+        "approval",
+        "ticket",
+        ticket_id,
+        None,
+        Some(json!({
+            "permission": permission,
+            "approved_by": approver.user.display_name,
+            "reason": approver.reason,
+            "amount": amount,
+            "details": details,
+        })),
+    )
+    .await
+}
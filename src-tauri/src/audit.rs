@@ -0,0 +1,272 @@
+use crate::db;
+use crate::users::{self, Permission, SessionUser};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use sqlx::{Pool, Sqlite, SqliteConnection};
+use tauri::AppHandle;
+
+/// `prev_hash` of the first entry.
+const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+const DEFAULT_LIMIT: i64 = 500;
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct AuditEntry {
+    pub id: i64,
+    pub created_at: String,
+    pub user_id: Option<i64>,
+    pub user_name: Option<String>,
+    pub action: String,
+    pub entity: String,
+    pub entity_id: Option<String>,
+    pub before_value: Option<String>,
+    pub after_value: Option<String>,
+    pub prev_hash: String,
+    pub hash: String,
+}
+
+impl AuditEntry {
+    fn compute_hash(&self) -> String {
+        entry_hash(
+            &self.prev_hash,
+            &self.created_at,
+            self.user_id,
+            self.user_name.as_deref(),
+            &self.action,
+            &self.entity,
+            self.entity_id.as_deref(),
+            self.before_value.as_deref(),
+            self.after_value.as_deref(),
+        )
+    }
+}
+
+/// Filters of the audit log viewer; dates are local `YYYY-MM-DD`, both inclusive.
+#[derive(Debug, Default, Deserialize)]
+pub struct AuditFilter {
+    pub from_date: Option<String>,
+    pub to_date: Option<String>,
+    pub user_id: Option<i64>,
+    pub action: Option<String>,
+    pub entity: Option<String>,
+    pub entity_id: Option<String>,
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AuditVerification {
+    pub entries: i64,
+    pub is_valid: bool,
+    /// First entry that does not match the chain, if any.
+    pub broken_at: Option<i64>,
+    pub problem: Option<String>,
+}
+
+#[allow(clippy::too_many_arguments)]
+fn entry_hash(
+    prev_hash: &str,
+    created_at: &str,
+    user_id: Option<i64>,
+    user_name: Option<&str>,
+    action: &str,
+    entity: &str,
+    entity_id: Option<&str>,
+    before_value: Option<&str>,
+    after_value: Option<&str>,
+) -> String {
+    // A JSON array keeps the fields apart, e.g. "ab" + "c" vs "a" + "bc"
+    let data = serde_json::json!([
+        prev_hash,
+        created_at,
+        user_id,
+        user_name,
+        action,
+        entity,
+        entity_id,
+        before_value,
+        after_value
+    ]);
+    hex::encode(Sha256::digest(data.to_string().as_bytes()))
+}
+
+/// Appends an entry to the audit log. Call it in the transaction of the
+/// change it records, so both are written or neither is.
+///
+/// The transaction takes the database write lock before it reads the last
+/// hash, so concurrent appends wait for each other (up to the busy timeout)
+/// instead of chaining onto the same entry.
+pub async fn record(
+    conn: &mut SqliteConnection,
+    user: Option<&SessionUser>,
+    action: &str,
+    entity: &str,
+    entity_id: Option<&str>,
+    before: Option<Value>,
+    after: Option<Value>,
+) -> Result<(), String> {
+    // Deletes nothing (and fires no trigger) but starts a write, which a
+    // deferred transaction otherwise only does at the INSERT below
+    sqlx::query("DELETE FROM audit_log WHERE 0")
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| e.to_string())?;
+    let prev_hash: String =
+        sqlx::query_scalar("SELECT hash FROM audit_log ORDER BY id DESC LIMIT 1")
+            .fetch_optional(&mut *conn)
+            .await
+            .map_err(|e| e.to_string())?
+            .unwrap_or_else(|| GENESIS_HASH.to_string());
+    let created_at: String = sqlx::query_scalar("SELECT strftime('%Y-%m-%d %H:%M:%f', 'now')")
+        .fetch_one(&mut *conn)
+        .await
+        .map_err(|e| e.to_string())?;
+    let user_id = user.map(|user| user.id);
+    let user_name = user.map(|user| user.display_name.as_str());
+    let before_value = before.map(|value| value.to_string());
+    let after_value = after.map(|value| value.to_string());
+    let hash = entry_hash(
+        &prev_hash,
+        &created_at,
+        user_id,
+        user_name,
+        action,
+        entity,
+        entity_id,
+        before_value.as_deref(),
+        after_value.as_deref(),
+    );
+
+    sqlx::query(
+        "INSERT INTO audit_log (
+             created_at, user_id, user_name, action, entity, entity_id,
+             before_value, after_value, prev_hash, hash
+         ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+    )
+    .bind(&created_at)
+    .bind(user_id)
+    .bind(user_name)
+    .bind(action)
+    .bind(entity)
+    .bind(entity_id)
+    .bind(&before_value)
+    .bind(&after_value)
+    .bind(&prev_hash)
+    .bind(&hash)
+    .execute(&mut *conn)
+    .await
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// `record` in a transaction of its own, for changes made outside one.
+pub async fn log(
+    pool: &Pool<Sqlite>,
+    user: Option<&SessionUser>,
+    action: &str,
+    entity: &str,
+    entity_id: Option<&str>,
+    before: Option<Value>,
+    after: Option<Value>,
+) -> Result<(), String> {
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    record(&mut tx, user, action, entity, entity_id, before, after).await?;
+    tx.commit().await.map_err(|e| e.to_string())
+}
+
+/// Audit log entries, newest first. Requires `manage_users`.
+#[tauri::command]
+pub async fn get_audit_log(
+    app_handle: AppHandle,
+    filter: Option<AuditFilter>,
+) -> Result<Vec<AuditEntry>, String> {
+    users::require(&app_handle, Permission::ManageUsers)?;
+    let filter = filter.unwrap_or_default();
+    let pool = db::pool(&app_handle).await?;
+    sqlx::query_as::<_, AuditEntry>(
+        "SELECT * FROM audit_log
+         WHERE (?1 IS NULL OR date(created_at, 'localtime') >= ?1)
+           AND (?2 IS NULL OR date(created_at, 'localtime') <= ?2)
+           AND (?3 IS NULL OR user_id = ?3)
+           AND (?4 IS NULL OR action = ?4)
+           AND (?5 IS NULL OR entity = ?5)
+           AND (?6 IS NULL OR entity_id = ?6)
+         ORDER BY id DESC
+         LIMIT ?7",
+    )
+    .bind(filter.from_date)
+    .bind(filter.to_date)
+    .bind(filter.user_id)
+    .bind(filter.action)
+    .bind(filter.entity)
+    .bind(filter.entity_id)
+    .bind(filter.limit.unwrap_or(DEFAULT_LIMIT))
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| e.to_string())
+}
+
+/// Walks the whole chain. An edited entry no longer matches its hash, a
+/// deleted one leaves a gap in the ids or a `prev_hash` pointing nowhere,
+/// and deleting the newest entries leaves the autoincrement counter ahead
+/// of the last id.
+#[tauri::command]
+pub async fn verify_audit_log(app_handle: AppHandle) -> Result<AuditVerification, String> {
+    users::require(&app_handle, Permission::ManageUsers)?;
+    let pool = db::pool(&app_handle).await?;
+    let entries = sqlx::query_as::<_, AuditEntry>("SELECT * FROM audit_log ORDER BY id")
+        .fetch_all(&pool)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let broken = |id: i64, problem: String| AuditVerification {
+        entries: entries.len() as i64,
+        is_valid: false,
+        broken_at: Some(id),
+        problem: Some(problem),
+    };
+
+    let mut prev_hash = GENESIS_HASH.to_string();
+    let mut prev_id = 0;
+    for entry in &entries {
+        if entry.id != prev_id + 1 {
+            return Ok(broken(
+                entry.id,
+                format!("Entries {} to {} are missing", prev_id + 1, entry.id - 1),
+            ));
+        }
+        if entry.prev_hash != prev_hash {
+            return Ok(broken(
+                entry.id,
+                "Does not follow the previous entry".to_string(),
+            ));
+        }
+        if entry.compute_hash() != entry.hash {
+            return Ok(broken(
+                entry.id,
+                "Was modified after it was written".to_string(),
+            ));
+        }
+        prev_hash = entry.hash.clone();
+        prev_id = entry.id;
+    }
+
+    let last_assigned: i64 = sqlx::query_scalar(
+        "SELECT IFNULL(MAX(seq), 0) FROM sqlite_sequence WHERE name = 'audit_log'",
+    )
+    .fetch_one(&pool)
+    .await
+    .map_err(|e| e.to_string())?;
+    if last_assigned > prev_id {
+        return Ok(broken(
+            prev_id + 1,
+            format!("Entries {} to {} are missing", prev_id + 1, last_assigned),
+        ));
+    }
+
+    Ok(AuditVerification {
+        entries: entries.len() as i64,
+        is_valid: true,
+        broken_at: None,
+        problem: None,
+    })
+}
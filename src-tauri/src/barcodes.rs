@@ -0,0 +1,206 @@
+use crate::audit;
+use crate::db;
+use crate::types::product;
+use crate::users::{self, Permission};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sqlx::{Executor, Sqlite, SqliteConnection};
+use tauri::AppHandle;
+
+/// Selects a product row in the shape of `types::product`; `barcode` is the
+/// unit barcode (smallest pack) of the product, if any.
+pub const PRODUCT_COLUMNS: &str = "p.sku, p.plu_code,
+    (SELECT b.barcode FROM product_barcodes b WHERE b.sku = p.sku ORDER BY b.quantity, b.rowid LIMIT 1) AS barcode,
+    p.product_name, CAST(p.price AS REAL) AS price, p.category_id";
+
+#[derive(Debug, Serialize, Deserialize, sqlx::FromRow)]
+pub struct ProductBarcode {
+    pub barcode: String,
+    pub sku: String,
+    pub quantity: f64,
+    pub description: Option<String>,
+}
+
+/// What a scanned or typed code resolves to: the product and how many
+/// units of it the code stands for.
+#[derive(Debug, Serialize)]
+pub struct ResolvedCode {
+    pub product: product,
+    pub barcode: Option<String>,
+    pub quantity: f64,
+}
+
+/// Adds `barcode` to `sku` unless the barcode is already known.
+/// Imports use this so existing aliases (and their pack quantities) are kept
+/// instead of being overwritten. Returns true when a new alias was added.
+pub async fn merge_barcode<'e, E>(
+    executor: E,
+    sku: &str,
+    barcode: &str,
+    quantity: f64,
+) -> Result<bool, sqlx::Error>
+where
+    E: Executor<'e, Database = Sqlite>,
+{
+    let result = sqlx::query(
+        "INSERT INTO product_barcodes (barcode, sku, quantity) VALUES (?, ?, ?)
+         ON CONFLICT(barcode) DO NOTHING",
+    )
+    .bind(barcode.trim())
+    .bind(sku)
+    .bind(quantity)
+    .execute(executor)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Resolves a barcode alias, SKU or PLU code to its product.
+/// Barcode aliases carry their pack quantity, SKU and PLU codes count as one unit.
+#[tauri::command]
+pub async fn resolve_product_code(
+    app_handle: AppHandle,
+    code: String,
+) -> Result<Option<ResolvedCode>, String> {
+    let code = code.trim();
+    if code.is_empty() {
+        return Ok(None);
+    }
+    let pool = db::pool(&app_handle).await?;
+
+    let alias = sqlx::query_as::<_, ProductBarcode>(
+        "SELECT barcode, sku, CAST(quantity AS REAL) AS quantity, description
+         FROM product_barcodes WHERE barcode = ?",
+    )
+    .bind(code)
+    .fetch_optional(&pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let (sku_or_plu, barcode, quantity) = match alias {
+        Some(alias) => (alias.sku, Some(alias.barcode), alias.quantity),
+        None => (code.to_string(), None, 1.0),
+    };
+
+    let product = sqlx::query_as::<_, product>(&format!(
+        "SELECT {PRODUCT_COLUMNS} FROM products p WHERE p.sku = ?1 OR p.plu_code = ?1
+         ORDER BY p.sku = ?1 DESC LIMIT 1"
+    ))
+    .bind(&sku_or_plu)
+    .fetch_optional(&pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    Ok(product.map(|product| ResolvedCode {
+        product,
+        barcode,
+        quantity,
+    }))
+}
+
+#[tauri::command]
+pub async fn get_product_barcodes(
+    app_handle: AppHandle,
+    sku: String,
+) -> Result<Vec<ProductBarcode>, String> {
+    let pool = db::pool(&app_handle).await?;
+    sqlx::query_as::<_, ProductBarcode>(
+        "SELECT barcode, sku, CAST(quantity AS REAL) AS quantity, description
+         FROM product_barcodes WHERE sku = ? ORDER BY quantity, rowid",
+    )
+    .bind(sku)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| e.to_string())
+}
+
+/// Assigns a barcode to a product. Unlike `merge_barcode`, an existing alias
+/// is moved to this product and takes the new quantity and description.
+/// Requires `import`.
+#[tauri::command]
+pub async fn set_product_barcode(
+    app_handle: AppHandle,
+    product_barcode: ProductBarcode,
+) -> Result<(), String> {
+    if product_barcode.barcode.trim().is_empty() {
+        return Err("Barcode cannot be empty".to_string());
+    }
+    if product_barcode.quantity <= 0.0 {
+        return Err("Barcode quantity must be greater than zero".to_string());
+    }
+    let user = users::require(&app_handle, Permission::Import)?;
+    let barcode = product_barcode.barcode.trim();
+    let pool = db::pool(&app_handle).await?;
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    let before = load_barcode(&mut tx, barcode).await?;
+    sqlx::query(
+        "INSERT INTO product_barcodes (barcode, sku, quantity, description) VALUES (?, ?, ?, ?)
+         ON CONFLICT(barcode) DO UPDATE SET
+             sku = excluded.sku,
+             quantity = excluded.quantity,
+             description = excluded.description",
+    )
+    .bind(barcode)
+    .bind(&product_barcode.sku)
+    .bind(product_barcode.quantity)
+    .bind(&product_barcode.description)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| e.to_string())?;
+    audit::record(
+        &mut tx,
+        Some(&user),
+        "set_product_barcode",
+        "barcode",
+        Some(barcode),
+        before.map(|before| json!(before)),
+        Some(json!({
+            "sku": product_barcode.sku,
+            "quantity": product_barcode.quantity,
+            "description": product_barcode.description,
+        })),
+    )
+    .await?;
+    tx.commit().await.map_err(|e| e.to_string())
+}
+
+/// Removes a barcode alias. Requires `import`.
+#[tauri::command]
+pub async fn remove_product_barcode(app_handle: AppHandle, barcode: String) -> Result<(), String> {
+    let user = users::require(&app_handle, Permission::Import)?;
+    let barcode = barcode.trim();
+    let pool = db::pool(&app_handle).await?;
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    let Some(before) = load_barcode(&mut tx, barcode).await? else {
+        return Ok(());
+    };
+    sqlx::query("DELETE FROM product_barcodes WHERE barcode = ?")
+        .bind(barcode)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| e.to_string())?;
+    audit::record(
+        &mut tx,
+        Some(&user),
+        "remove_product_barcode",
+        "barcode",
+        Some(barcode),
+        Some(json!(before)),
+        None,
+    )
+    .await?;
+    tx.commit().await.map_err(|e| e.to_string())
+}
+
+async fn load_barcode(
+    conn: &mut SqliteConnection,
+    barcode: &str,
+) -> Result<Option<ProductBarcode>, String> {
+    sqlx::query_as::<_, ProductBarcode>(
+        "SELECT barcode, sku, CAST(quantity AS REAL) AS quantity, description
+         FROM product_barcodes WHERE barcode = ?",
+    )
+    .bind(barcode)
+    .fetch_optional(&mut *conn)
+    .await
+    .map_err(|e| e.to_string())
+}
@@ -0,0 +1,213 @@
+use crate::db;
+use crate::users::{self, Permission, SessionUser};
+use serde::{Deserialize, Serialize};
+use sqlx::{Pool, Sqlite, SqliteConnection};
+use std::collections::HashSet;
+use tauri::AppHandle;
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct Denomination {
+    pub id: i64,
+    pub currency: String,
+    /// `coin` or `bill`.
+    pub kind: String,
+    pub value: f64,
+}
+
+/// Pieces of one denomination, as sent by the count form.
+#[derive(Debug, Deserialize)]
+pub struct DenominationCount {
+    pub denomination_id: i64,
+    pub quantity: i64,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct CashCount {
+    pub id: i64,
+    pub cash_session_id: i64,
+    /// `opening`, `closing` or `safe_drop`.
+    pub purpose: String,
+    pub cash_movement_id: Option<i64>,
+    pub total_pesos: f64,
+    pub total_dollars: f64,
+    pub counted_by_name: String,
+    pub created_at: String,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct CashCountLine {
+    pub denomination_id: i64,
+    pub currency: String,
+    pub kind: String,
+    pub value: f64,
+    pub quantity: i64,
+    pub amount: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CashCountDetail {
+    pub count: CashCount,
+    pub lines: Vec<CashCountLine>,
+}
+
+const COUNT_QUERY: &str = "SELECT c.id, c.cash_session_id, c.purpose, c.cash_movement_id,
+        CAST(c.total_pesos AS REAL) AS total_pesos,
+        CAST(c.total_dollars AS REAL) AS total_dollars,
+        u.display_name AS counted_by_name,
+        datetime(c.created_at, 'localtime') AS created_at
+    FROM cash_counts c
+    JOIN users u ON u.id = c.counted_by";
+
+/// Label of a count's purpose, for its slip.
+pub fn purpose_label(purpose: &str) -> &str {
+    match purpose {
+        "opening" => "Apertura",
+        "closing" => "Cierre",
+        "safe_drop" => "Retiro a caja fuerte",
+        _ => purpose,
+    }
+}
+
+/// Active denominations, per currency, largest first.
+#[tauri::command]
+pub async fn get_denominations(app_handle: AppHandle) -> Result<Vec<Denomination>, String> {
+    users::require(&app_handle, Permission::Sell)?;
+    let pool = db::pool(&app_handle).await?;
+    sqlx::query_as::<_, Denomination>(
+        "SELECT id, currency, kind, CAST(value AS REAL) AS value
+         FROM denominations
+         WHERE active
+         ORDER BY currency, value DESC, kind",
+    )
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| e.to_string())
+}
+
+/// Pesos and dollars a count adds up to. Every line must be a known
+/// denomination, listed once, with a quantity of zero or more.
+pub async fn count_totals(
+    conn: &mut SqliteConnection,
+    lines: &[DenominationCount],
+) -> Result<(f64, f64), String> {
+    let mut seen = HashSet::new();
+    let (mut pesos, mut dollars) = (0.0, 0.0);
+    for line in lines {
+        if line.quantity < 0 {
+            return Err("Counted quantities cannot be negative".to_string());
+        }
+        if !seen.insert(line.denomination_id) {
+            return Err(format!(
+                "Denomination {} is counted twice",
+                line.denomination_id
+            ));
+        }
+        let (currency, value): (String, f64) =
+            sqlx::query_as("SELECT currency, CAST(value AS REAL) FROM denominations WHERE id = ?")
+                .bind(line.denomination_id)
+                .fetch_optional(&mut *conn)
+                .await
+                .map_err(|e| e.to_string())?
+                .ok_or_else(|| format!("Unknown denomination {}", line.denomination_id))?;
+        let amount = value * line.quantity as f64;
+        if currency == "MXN" {
+            pesos += amount;
+        } else {
+            dollars += amount;
+        }
+    }
+    Ok((
+        (pesos * 100.0).round() / 100.0,
+        (dollars * 100.0).round() / 100.0,
+    ))
+}
+
+/// Stores a count of a session with its totals, as counted by `user`.
+/// Returns its id.
+pub async fn save_count(
+    conn: &mut SqliteConnection,
+    cash_session_id: i64,
+    purpose: &str,
+    cash_movement_id: Option<i64>,
+    lines: &[DenominationCount],
+    user: &SessionUser,
+) -> Result<i64, String> {
+    let (total_pesos, total_dollars) = count_totals(conn, lines).await?;
+    let id = sqlx::query(
+        "INSERT INTO cash_counts (
+             cash_session_id, purpose, cash_movement_id, total_pesos, total_dollars, counted_by
+         ) VALUES (?, ?, ?, ?, ?, ?)",
+    )
+    .bind(cash_session_id)
+    .bind(purpose)
+    .bind(cash_movement_id)
+    .bind(total_pesos)
+    .bind(total_dollars)
+    .bind(user.id)
+    .execute(&mut *conn)
+    .await
+    .map_err(|e| e.to_string())?
+    .last_insert_rowid();
+    for line in lines.iter().filter(|line| line.quantity > 0) {
+        sqlx::query(
+            "INSERT INTO cash_count_lines (count_id, denomination_id, quantity) VALUES (?, ?, ?)",
+        )
+        .bind(id)
+        .bind(line.denomination_id)
+        .bind(line.quantity)
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| e.to_string())?;
+    }
+    Ok(id)
+}
+
+/// Counts of a session, oldest first.
+#[tauri::command]
+pub async fn get_cash_counts(
+    app_handle: AppHandle,
+    cash_session_id: i64,
+) -> Result<Vec<CashCount>, String> {
+    users::require(&app_handle, Permission::Sell)?;
+    let pool = db::pool(&app_handle).await?;
+    sqlx::query_as::<_, CashCount>(&format!(
+        "{} WHERE c.cash_session_id = ? ORDER BY c.id",
+        COUNT_QUERY
+    ))
+    .bind(cash_session_id)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| e.to_string())
+}
+
+/// A count with a line for every active denomination (and any retired
+/// one it counted), uncounted ones at zero.
+pub async fn load_count(pool: &Pool<Sqlite>, id: i64) -> Result<CashCountDetail, String> {
+    let count = sqlx::query_as::<_, CashCount>(&format!("{} WHERE c.id = ?", COUNT_QUERY))
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Unknown cash count {}", id))?;
+    let lines = sqlx::query_as::<_, CashCountLine>(
+        "SELECT d.id AS denomination_id, d.currency, d.kind, CAST(d.value AS REAL) AS value,
+                IFNULL(l.quantity, 0) AS quantity,
+                CAST(IFNULL(l.quantity, 0) * d.value AS REAL) AS amount
+         FROM denominations d
+         LEFT JOIN cash_count_lines l ON l.denomination_id = d.id AND l.count_id = ?
+         WHERE d.active OR l.id IS NOT NULL
+         ORDER BY d.currency, d.value DESC, d.kind",
+    )
+    .bind(id)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+    Ok(CashCountDetail { count, lines })
+}
+
+#[tauri::command]
+pub async fn get_cash_count(app_handle: AppHandle, id: i64) -> Result<CashCountDetail, String> {
+    users::require(&app_handle, Permission::Sell)?;
+    let pool = db::pool(&app_handle).await?;
+    load_count(&pool, id).await
+}
@@ -0,0 +1,230 @@
+use crate::approvals::{self, Approval};
+use crate::audit;
+use crate::cash_counts::{self, DenominationCount};
+use crate::cash_sessions;
+use crate::db;
+use crate::users::{self, Permission};
+use serde::Serialize;
+use serde_json::json;
+use sqlx::{Pool, Sqlite};
+use tauri::AppHandle;
+
+/// Cash movement kinds with their printed label.
+pub const KINDS: [(&str, &str); 3] = [
+    ("paid_in", "Entrada de efectivo"),
+    ("paid_out", "Salida de efectivo"),
+    ("safe_drop", "Retiro a caja fuerte"),
+];
+
+pub const CURRENCIES: [&str; 2] = ["MXN", "USD"];
+
+pub fn kind_label(kind: &str) -> &str {
+    KINDS
+        .iter()
+        .find(|(code, _)| *code == kind)
+        .map(|(_, label)| *label)
+        .unwrap_or(kind)
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct CashMovement {
+    pub id: i64,
+    pub cash_session_id: i64,
+    pub kind: String,
+    pub currency: String,
+    pub amount: f64,
+    pub reason: String,
+    pub created_by_name: String,
+    pub authorized_by_name: String,
+    pub created_at: String,
+}
+
+const MOVEMENT_QUERY: &str = "SELECT m.id, m.cash_session_id, m.kind, m.currency,
+        CAST(m.amount AS REAL) AS amount, m.reason,
+        cu.display_name AS created_by_name, au.display_name AS authorized_by_name,
+        datetime(m.created_at, 'localtime') AS created_at
+    FROM cash_movements m
+    JOIN users cu ON cu.id = m.created_by
+    JOIN users au ON au.id = m.authorized_by";
+
+/// Movements of a session, oldest first.
+#[tauri::command]
+pub async fn get_cash_movements(
+    app_handle: AppHandle,
+    cash_session_id: i64,
+) -> Result<Vec<CashMovement>, String> {
+    users::require(&app_handle, Permission::Sell)?;
+    let pool = db::pool(&app_handle).await?;
+    sqlx::query_as::<_, CashMovement>(&format!(
+        "{} WHERE m.cash_session_id = ? ORDER BY m.id",
+        MOVEMENT_QUERY
+    ))
+    .bind(cash_session_id)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| e.to_string())
+}
+
+/// Records cash paid into or out of the drawer of the cashier's open
+/// session, or dropped to the safe. Takes the `open_drawer` permission or
+/// a manager's `approval`; whoever authorized it is kept with the
+/// movement. Users with `view_reports` cannot take cash out beyond what
+/// the drawer should hold; for everyone else the shortfall shows at the
+/// close, as refusing would reveal the expected drawer the blind count
+/// hides. A safe drop counted by denomination keeps its `count`, whose
+/// total in the drop's currency is the amount. Returns the id of the
+/// movement, for its slip.
+#[tauri::command]
+pub async fn record_cash_movement(
+    app_handle: AppHandle,
+    kind: String,
+    currency: String,
+    amount: f64,
+    reason: String,
+    approval: Option<Approval>,
+    count: Option<Vec<DenominationCount>>,
+) -> Result<i64, String> {
+    let cashier = users::require(&app_handle, Permission::Sell)?;
+    if !KINDS.iter().any(|(code, _)| *code == kind) {
+        return Err(format!("Unknown cash movement {}", kind));
+    }
+    if !CURRENCIES.contains(&currency.as_str()) {
+        return Err(format!("Unknown currency {}", currency));
+    }
+    if count.is_some() && kind != "safe_drop" {
+        return Err("Only safe drops are counted by denomination".to_string());
+    }
+    let pool = db::pool(&app_handle).await?;
+    let amount = match &count {
+        Some(lines) => {
+            let mut conn = pool.acquire().await.map_err(|e| e.to_string())?;
+            let (pesos, dollars) = cash_counts::count_totals(&mut conn, lines).await?;
+            let (total, other) = if currency == "MXN" {
+                (pesos, dollars)
+            } else {
+                (dollars, pesos)
+            };
+            if other > 0.0 {
+                return Err(format!(
+                    "A safe drop in {} can only count {}",
+                    currency, currency
+                ));
+            }
+            total
+        }
+        None => amount,
+    };
+    if amount <= 0.0 {
+        return Err("The amount must be positive".to_string());
+    }
+    let reason = reason.trim();
+    if reason.is_empty() {
+        return Err("A cash movement needs a reason".to_string());
+    }
+    let cash_session_id = {
+        let mut conn = pool.acquire().await.map_err(|e| e.to_string())?;
+        cash_sessions::current_id(&mut conn, &cashier).await?
+    };
+
+    let message = format!(
+        "{} needs approval for {} of {:.2} {}",
+        cashier.display_name,
+        kind_label(&kind).to_lowercase(),
+        amount,
+        currency
+    );
+    let approver = approvals::authorize(
+        &pool,
+        &cashier,
+        Permission::OpenDrawer,
+        &message,
+        approval.as_ref(),
+    )
+    .await?;
+    let authorized_by = approver
+        .as_ref()
+        .map(|approver| approver.user.id)
+        .unwrap_or(cashier.id);
+
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    if kind != "paid_in" && cashier.can(Permission::ViewReports) {
+        let (pesos, dollars) = cash_sessions::expected_drawer(&mut tx, cash_session_id).await?;
+        let available = if currency == "MXN" { pesos } else { dollars };
+        if amount > available + 0.005 {
+            return Err("The amount exceeds what the drawer can give".to_string());
+        }
+    }
+    let id = sqlx::query(
+        "INSERT INTO cash_movements (
+             cash_session_id, kind, currency, amount, reason, created_by, authorized_by
+         ) VALUES (?, ?, ?, ?, ?, ?, ?)",
+    )
+    .bind(cash_session_id)
+    .bind(&kind)
+    .bind(&currency)
+    .bind(amount)
+    .bind(reason)
+    .bind(cashier.id)
+    .bind(authorized_by)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| e.to_string())?
+    .last_insert_rowid();
+    if let Some(lines) = &count {
+        cash_counts::save_count(
+            &mut tx,
+            cash_session_id,
+            "safe_drop",
+            Some(id),
+            lines,
+            &cashier,
+        )
+        .await?;
+    }
+
+    if let Some(approver) = &approver {
+        approvals::record(
+            &mut tx,
+            Permission::OpenDrawer,
+            None,
+            &cashier,
+            approver,
+            Some(amount),
+            &format!("{} {}: {}", kind_label(&kind), currency, reason),
+        )
+        .await?;
+    }
+    audit::record(
+        &mut tx,
+        Some(&cashier),
+        "record_cash_movement",
+        "cash_movement",
+        Some(&id.to_string()),
+        None,
+        Some(json!({
+            "cash_session_id": cash_session_id,
+            "kind": kind,
+            "currency": currency,
+            "amount": amount,
+            "reason": reason,
+            "authorized_by": authorized_by,
+        })),
+    )
+    .await?;
+    tx.commit().await.map_err(|e| e.to_string())?;
+
+    if kind == "paid_in" {
+        cash_sessions::notify_drawer_limit(&app_handle, &pool, cash_session_id).await;
+    }
+    Ok(id)
+}
+
+/// A cash movement, for its slip.
+pub async fn load_cash_movement(pool: &Pool<Sqlite>, id: i64) -> Result<CashMovement, String> {
+    sqlx::query_as::<_, CashMovement>(&format!("{} WHERE m.id = ?", MOVEMENT_QUERY))
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Unknown cash movement {}", id))
+}
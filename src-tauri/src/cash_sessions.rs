@@ -0,0 +1,459 @@
+use crate::audit;
+use crate::cash_counts::{self, DenominationCount};
+use crate::db;
+use crate::sales::POS_ID;
+use crate::settings;
+use crate::users::{self, Permission, SessionUser};
+use serde::Serialize;
+use serde_json::json;
+use sqlx::{Pool, Sqlite, SqliteConnection};
+use tauri::{AppHandle, Emitter};
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct CashSession {
+    pub id: i64,
+    pub pos_id: String,
+    pub cashier_id: i64,
+    pub cashier_name: String,
+    pub opened_at: String,
+    pub opening_pesos: f64,
+    pub opening_dollars: f64,
+    pub closed_at: Option<String>,
+    pub closed_by_name: Option<String>,
+    /// Expected in the drawer when closed; None while open, so the close
+    /// count is blind.
+    pub expected_pesos: Option<f64>,
+    pub expected_dollars: Option<f64>,
+    pub counted_pesos: Option<f64>,
+    pub counted_dollars: Option<f64>,
+    pub notes: Option<String>,
+}
+
+/// Tickets of a session, voided ones left out. Returns are negative and
+/// net out of the amounts.
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct SessionTotals {
+    pub tickets: i64,
+    pub returns: i64,
+    pub total_due: f64,
+    pub pesos_paid: f64,
+    pub dollars_paid: f64,
+    pub cards_paid: f64,
+    pub others_paid: f64,
+    /// Given in pesos.
+    pub change: f64,
+}
+
+/// Cash paid in, paid out and dropped to the safe during a session, per
+/// currency. See cash_movements.rs.
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct MovementTotals {
+    pub paid_in_pesos: f64,
+    pub paid_out_pesos: f64,
+    pub safe_drops_pesos: f64,
+    pub paid_in_dollars: f64,
+    pub paid_out_dollars: f64,
+    pub safe_drops_dollars: f64,
+}
+
+/// Session close report: the drawer expected from the float, the tickets
+/// and the cash movements, against the blind count.
+#[derive(Debug, Serialize)]
+pub struct CashSessionReport {
+    pub session: CashSession,
+    pub totals: SessionTotals,
+    pub movements: MovementTotals,
+    pub expected_pesos: f64,
+    pub expected_dollars: f64,
+    /// Counted minus expected; None while open.
+    pub over_short_pesos: Option<f64>,
+    pub over_short_dollars: Option<f64>,
+}
+
+const SESSION_QUERY: &str = "SELECT s.id, s.pos_id, s.cashier_id, s.cashier_name,
+        datetime(s.opened_at, 'localtime') AS opened_at,
+        CAST(s.opening_pesos AS REAL) AS opening_pesos,
+        CAST(s.opening_dollars AS REAL) AS opening_dollars,
+        datetime(s.closed_at, 'localtime') AS closed_at,
+        u.display_name AS closed_by_name,
+        CAST(s.expected_pesos AS REAL) AS expected_pesos,
+        CAST(s.expected_dollars AS REAL) AS expected_dollars,
+        CAST(s.counted_pesos AS REAL) AS counted_pesos,
+        CAST(s.counted_dollars AS REAL) AS counted_dollars,
+        s.notes
+    FROM cash_sessions s
+    LEFT JOIN users u ON u.id = s.closed_by";
+
+async fn load_session(conn: &mut SqliteConnection, id: i64) -> Result<CashSession, String> {
+    sqlx::query_as::<_, CashSession>(&format!("{} WHERE s.id = ?", SESSION_QUERY))
+        .bind(id)
+        .fetch_optional(&mut *conn)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Unknown cash session {}", id))
+}
+
+async fn open_session(conn: &mut SqliteConnection) -> Result<Option<CashSession>, String> {
+    sqlx::query_as::<_, CashSession>(&format!(
+        "{} WHERE s.pos_id = ? AND s.closed_at IS NULL",
+        SESSION_QUERY
+    ))
+    .bind(POS_ID)
+    .fetch_optional(&mut *conn)
+    .await
+    .map_err(|e| e.to_string())
+}
+
+async fn session_totals(conn: &mut SqliteConnection, id: i64) -> Result<SessionTotals, String> {
+    sqlx::query_as::<_, SessionTotals>(
+        "SELECT COUNT(*) - COUNT(original_ticket_id) AS tickets,
+                COUNT(original_ticket_id) AS returns,
+                CAST(IFNULL(SUM(total_due), 0) AS REAL) AS total_due,
+                CAST(IFNULL(SUM(pesos_paid), 0) AS REAL) AS pesos_paid,
+                CAST(IFNULL(SUM(dollars_paid), 0) AS REAL) AS dollars_paid,
+                CAST(IFNULL(SUM(cards_paid), 0) AS REAL) AS cards_paid,
+                CAST(IFNULL(SUM(others_paid), 0) AS REAL) AS others_paid,
+                CAST(IFNULL(SUM(change), 0) AS REAL) AS change
+         FROM tickets
+         WHERE cash_session_id = ? AND voided_at IS NULL",
+    )
+    .bind(id)
+    .fetch_one(&mut *conn)
+    .await
+    .map_err(|e| e.to_string())
+}
+
+async fn movement_totals(conn: &mut SqliteConnection, id: i64) -> Result<MovementTotals, String> {
+    sqlx::query_as::<_, MovementTotals>(
+        "SELECT CAST(IFNULL(SUM(CASE WHEN kind = 'paid_in' AND currency = 'MXN'
+                                     THEN amount END), 0) AS REAL) AS paid_in_pesos,
+                CAST(IFNULL(SUM(CASE WHEN kind = 'paid_out' AND currency = 'MXN'
+                                     THEN amount END), 0) AS REAL) AS paid_out_pesos,
+                CAST(IFNULL(SUM(CASE WHEN kind = 'safe_drop' AND currency = 'MXN'
+                                     THEN amount END), 0) AS REAL) AS safe_drops_pesos,
+                CAST(IFNULL(SUM(CASE WHEN kind = 'paid_in' AND currency = 'USD'
+                                     THEN amount END), 0) AS REAL) AS paid_in_dollars,
+                CAST(IFNULL(SUM(CASE WHEN kind = 'paid_out' AND currency = 'USD'
+                                     THEN amount END), 0) AS REAL) AS paid_out_dollars,
+                CAST(IFNULL(SUM(CASE WHEN kind = 'safe_drop' AND currency = 'USD'
+                                     THEN amount END), 0) AS REAL) AS safe_drops_dollars
+         FROM cash_movements
+         WHERE cash_session_id = ?",
+    )
+    .bind(id)
+    .fetch_one(&mut *conn)
+    .await
+    .map_err(|e| e.to_string())
+}
+
+/// Pesos and dollars the drawer should hold: the float plus the cash
+/// taken and paid in, minus the change given, the cash refunded, paid
+/// out and dropped to the safe.
+fn expected(
+    session: &CashSession,
+    totals: &SessionTotals,
+    movements: &MovementTotals,
+) -> (f64, f64) {
+    (
+        session.opening_pesos + totals.pesos_paid - totals.change + movements.paid_in_pesos
+            - movements.paid_out_pesos
+            - movements.safe_drops_pesos,
+        session.opening_dollars + totals.dollars_paid + movements.paid_in_dollars
+            - movements.paid_out_dollars
+            - movements.safe_drops_dollars,
+    )
+}
+
+/// Pesos and dollars the drawer of an open session should hold now.
+pub async fn expected_drawer(conn: &mut SqliteConnection, id: i64) -> Result<(f64, f64), String> {
+    let session = load_session(conn, id).await?;
+    let totals = session_totals(conn, id).await?;
+    let movements = movement_totals(conn, id).await?;
+    Ok(expected(&session, &totals, &movements))
+}
+
+/// Cash over the drawer limit of its currency.
+#[derive(Debug, Clone, Serialize)]
+pub struct DrawerLimitAlert {
+    pub currency: String,
+    pub limit: f64,
+}
+
+/// Emits a `drawer-limit` event when the drawer of a session holds more
+/// pesos or dollars than the limits in the settings, to suggest a safe
+/// drop. Only the limits are sent, not the amounts, so the close count
+/// stays blind. Called after cash comes in; failures are only logged.
+pub async fn notify_drawer_limit(app_handle: &AppHandle, pool: &Pool<Sqlite>, id: i64) {
+    let check = async {
+        let settings = settings::load(pool).await?;
+        if settings.drawer_limit_pesos.is_none() && settings.drawer_limit_dollars.is_none() {
+            return Ok(Vec::new());
+        }
+        let mut conn = pool.acquire().await.map_err(|e| e.to_string())?;
+        let (pesos, dollars) = expected_drawer(&mut conn, id).await?;
+        let mut alerts = Vec::new();
+        for (currency, amount, limit) in [
+            ("MXN", pesos, settings.drawer_limit_pesos),
+            ("USD", dollars, settings.drawer_limit_dollars),
+        ] {
+            if let Some(limit) = limit.filter(|limit| amount > *limit) {
+                alerts.push(DrawerLimitAlert {
+                    currency: currency.to_string(),
+                    limit,
+                });
+            }
+        }
+        Ok::<_, String>(alerts)
+    };
+    match check.await {
+        Ok(alerts) if alerts.is_empty() => {}
+        Ok(alerts) => {
+            if let Err(e) = app_handle.emit("drawer-limit", &alerts) {
+                eprintln!("Failed to emit drawer-limit: {}", e);
+            }
+        }
+        Err(e) => eprintln!("Failed to check the drawer limit: {}", e),
+    }
+}
+
+/// Id of the session `cashier` has open on this terminal, which tickets
+/// are recorded in. Selling and refunding need one.
+pub async fn current_id(conn: &mut SqliteConnection, cashier: &SessionUser) -> Result<i64, String> {
+    match open_session(conn).await? {
+        Some(session) if session.cashier_id == cashier.id => Ok(session.id),
+        Some(session) => Err(format!(
+            "The cash session of {} is open on this terminal",
+            session.cashier_name
+        )),
+        None => Err("Open a cash session before selling".to_string()),
+    }
+}
+
+/// The session open on this terminal, if any.
+#[tauri::command]
+pub async fn get_current_cash_session(
+    app_handle: AppHandle,
+) -> Result<Option<CashSession>, String> {
+    users::require(&app_handle, Permission::Sell)?;
+    let pool = db::pool(&app_handle).await?;
+    let mut conn = pool.acquire().await.map_err(|e| e.to_string())?;
+    open_session(&mut conn).await
+}
+
+/// Sessions opened on a local date (`YYYY-MM-DD`), latest first.
+#[tauri::command]
+pub async fn get_cash_sessions(
+    app_handle: AppHandle,
+    date: String,
+) -> Result<Vec<CashSession>, String> {
+    users::require(&app_handle, Permission::ViewReports)?;
+    let pool = db::pool(&app_handle).await?;
+    sqlx::query_as::<_, CashSession>(&format!(
+        "{} WHERE date(s.opened_at, 'localtime') = ? ORDER BY s.opened_at DESC",
+        SESSION_QUERY
+    ))
+    .bind(date)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| e.to_string())
+}
+
+/// Opens a session on this terminal for the signed in cashier with the
+/// float counted in each currency. When the float is counted by
+/// denomination, the `count` is kept and its totals are the float.
+/// Returns its id.
+#[tauri::command]
+pub async fn open_cash_session(
+    app_handle: AppHandle,
+    opening_pesos: f64,
+    opening_dollars: f64,
+    count: Option<Vec<DenominationCount>>,
+) -> Result<i64, String> {
+    let cashier = users::require(&app_handle, Permission::Sell)?;
+    let pool = db::pool(&app_handle).await?;
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    let (opening_pesos, opening_dollars) = match &count {
+        Some(lines) => cash_counts::count_totals(&mut tx, lines).await?,
+        None => (opening_pesos, opening_dollars),
+    };
+    if opening_pesos < 0.0 || opening_dollars < 0.0 {
+        return Err("The opening float cannot be negative".to_string());
+    }
+    if let Some(session) = open_session(&mut tx).await? {
+        return Err(format!(
+            "The cash session of {} is already open on this terminal",
+            session.cashier_name
+        ));
+    }
+    let id = sqlx::query(
+        "INSERT INTO cash_sessions (pos_id, cashier_id, cashier_name, opening_pesos, opening_dollars)
+         VALUES (?, ?, ?, ?, ?)",
+    )
+    .bind(POS_ID)
+    .bind(cashier.id)
+    .bind(&cashier.display_name)
+    .bind(opening_pesos)
+    .bind(opening_dollars)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| e.to_string())?
+    .last_insert_rowid();
+    if let Some(lines) = &count {
+        cash_counts::save_count(&mut tx, id, "opening", None, lines, &cashier).await?;
+    }
+    audit::record(
+        &mut tx,
+        Some(&cashier),
+        "open_cash_session",
+        "cash_session",
+        Some(&id.to_string()),
+        None,
+        Some(json!({
+            "pos_id": POS_ID,
+            "opening_pesos": opening_pesos,
+            "opening_dollars": opening_dollars,
+        })),
+    )
+    .await?;
+    tx.commit().await.map_err(|e| e.to_string())?;
+    Ok(id)
+}
+
+/// Closes a session with the blind count of the drawer. The expected
+/// amounts are kept with the count, and the report with the over/short
+/// per currency is returned. Only the session's cashier, or a user with
+/// `view_reports`, can close it. When the drawer is counted by
+/// denomination, the `count` is kept and its totals are the counted
+/// amounts.
+#[tauri::command]
+pub async fn close_cash_session(
+    app_handle: AppHandle,
+    id: i64,
+    counted_pesos: f64,
+    counted_dollars: f64,
+    notes: Option<String>,
+    count: Option<Vec<DenominationCount>>,
+) -> Result<CashSessionReport, String> {
+    let user = users::require(&app_handle, Permission::Sell)?;
+    let pool = db::pool(&app_handle).await?;
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    let (counted_pesos, counted_dollars) = match &count {
+        Some(lines) => cash_counts::count_totals(&mut tx, lines).await?,
+        None => (counted_pesos, counted_dollars),
+    };
+    if counted_pesos < 0.0 || counted_dollars < 0.0 {
+        return Err("The counted amounts cannot be negative".to_string());
+    }
+    let session = load_session(&mut tx, id).await?;
+    if session.closed_at.is_some() {
+        return Err(format!("Cash session {} is already closed", id));
+    }
+    if session.cashier_id != user.id && !user.can(Permission::ViewReports) {
+        return Err(format!(
+            "Cash session {} belongs to {}",
+            id, session.cashier_name
+        ));
+    }
+    let (expected_pesos, expected_dollars) = expected_drawer(&mut tx, id).await?;
+    let notes = notes
+        .map(|notes| notes.trim().to_string())
+        .filter(|notes| !notes.is_empty());
+    sqlx::query(
+        "UPDATE cash_sessions
+         SET closed_at = CURRENT_TIMESTAMP, closed_by = ?, expected_pesos = ?,
+             expected_dollars = ?, counted_pesos = ?, counted_dollars = ?, notes = ?
+         WHERE id = ?",
+    )
+    .bind(user.id)
+    .bind(expected_pesos)
+    .bind(expected_dollars)
+    .bind(counted_pesos)
+    .bind(counted_dollars)
+    .bind(&notes)
+    .bind(id)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| e.to_string())?;
+    if let Some(lines) = &count {
+        cash_counts::save_count(&mut tx, id, "closing", None, lines, &user).await?;
+    }
+    audit::record(
+        &mut tx,
+        Some(&user),
+        "close_cash_session",
+        "cash_session",
+        Some(&id.to_string()),
+        None,
+        Some(json!({
+            "expected_pesos": expected_pesos,
+            "expected_dollars": expected_dollars,
+            "counted_pesos": counted_pesos,
+            "counted_dollars": counted_dollars,
+            "over_short_pesos": counted_pesos - expected_pesos,
+            "over_short_dollars": counted_dollars - expected_dollars,
+        })),
+    )
+    .await?;
+    let report = session_report(&mut tx, id).await?;
+    tx.commit().await.map_err(|e| e.to_string())?;
+    Ok(report)
+}
+
+async fn session_report(conn: &mut SqliteConnection, id: i64) -> Result<CashSessionReport, String> {
+    let session = load_session(conn, id).await?;
+    let totals = session_totals(conn, id).await?;
+    let movements = movement_totals(conn, id).await?;
+    let (expected_pesos, expected_dollars) =
+        match (session.expected_pesos, session.expected_dollars) {
+            (Some(pesos), Some(dollars)) => (pesos, dollars),
+            _ => expected(&session, &totals, &movements),
+        };
+    Ok(CashSessionReport {
+        over_short_pesos: session
+            .counted_pesos
+            .map(|counted| counted - expected_pesos),
+        over_short_dollars: session
+            .counted_dollars
+            .map(|counted| counted - expected_dollars),
+        session,
+        totals,
+        movements,
+        expected_pesos,
+        expected_dollars,
+    })
+}
+
+/// Close report of a session for `user`. Open sessions are only shown to
+/// users with `view_reports`, so cashiers count blind; closed ones also
+/// to their own cashier.
+pub async fn load_report(
+    pool: &Pool<Sqlite>,
+    user: &SessionUser,
+    id: i64,
+) -> Result<CashSessionReport, String> {
+    let mut conn = pool.acquire().await.map_err(|e| e.to_string())?;
+    let report = session_report(&mut conn, id).await?;
+    let session = &report.session;
+    if !user.can(Permission::ViewReports) {
+        if session.cashier_id != user.id {
+            return Err(format!(
+                "Cash session {} belongs to {}",
+                id, session.cashier_name
+            ));
+        }
+        if session.closed_at.is_none() {
+            return Err(format!("Cash session {} is still open", id));
+        }
+    }
+    Ok(report)
+}
+
+#[tauri::command]
+pub async fn get_cash_session_report(
+    app_handle: AppHandle,
+    id: i64,
+) -> Result<CashSessionReport, String> {
+    let user = users::require(&app_handle, Permission::Sell)?;
+    let pool = db::pool(&app_handle).await?;
+    load_report(&pool, &user, id).await
+}
@@ -0,0 +1,212 @@
+use crate::barcodes::PRODUCT_COLUMNS;
+use crate::db;
+use crate::types::product;
+use crate::users::{self, Permission};
+use serde::{Deserialize, Serialize};
+use sqlx::SqliteConnection;
+use tauri::AppHandle;
+
+#[derive(Debug, Serialize, Deserialize, sqlx::FromRow)]
+pub struct Category {
+    pub id: i64,
+    pub code: Option<String>,
+    pub name: String,
+    pub parent_id: Option<i64>,
+    pub sort_order: i64,
+    #[serde(default)]
+    pub product_count: i64,
+}
+
+/// Splits a catalog label like "Tortilla (10)" into its name and code.
+/// Labels without a trailing "(code)" are returned as a name only.
+pub fn parse_category_label(label: &str) -> (String, Option<String>) {
+    let label = label.trim();
+    if let Some(open) = label.rfind('(') {
+        if let Some(code) = label[open + 1..].strip_suffix(')') {
+            let name = label[..open].trim();
+            let code = code.trim();
+            if !name.is_empty() && !code.is_empty() {
+                return (name.to_string(), Some(code.to_string()));
+            }
+        }
+    }
+    (label.to_string(), None)
+}
+
+/// Finds the category for a catalog label under `parent_id` (top level when
+/// None), creating it if needed. A known code wins over the name, so renaming
+/// a category in the source catalog does not create a duplicate.
+/// Returns None for a blank label.
+pub async fn get_or_create_category(
+    conn: &mut SqliteConnection,
+    label: &str,
+    parent_id: Option<i64>,
+) -> Result<Option<i64>, sqlx::Error> {
+    let (name, code) = parse_category_label(label);
+    if name.is_empty() {
+        return Ok(None);
+    }
+
+    let existing: Option<i64> = sqlx::query_scalar(
+        "SELECT id FROM categories
+         WHERE (?1 IS NOT NULL AND code = ?1)
+            OR (parent_id IS ?3 AND name = ?2 COLLATE NOCASE)
+         ORDER BY code = ?1 DESC
+         LIMIT 1",
+    )
+    .bind(&code)
+    .bind(&name)
+    .bind(parent_id)
+    .fetch_optional(&mut *conn)
+    .await?;
+    if existing.is_some() {
+        return Ok(existing);
+    }
+
+    let result = sqlx::query("INSERT INTO categories (code, name, parent_id) VALUES (?, ?, ?)")
+        .bind(&code)
+        .bind(&name)
+        .bind(parent_id)
+        .execute(&mut *conn)
+        .await?;
+    Ok(Some(result.last_insert_rowid()))
+}
+
+/// Resolves a catalog label to a category id, creating the category on the fly.
+#[tauri::command]
+pub async fn resolve_category_label(
+    app_handle: AppHandle,
+    label: String,
+) -> Result<Option<i64>, String> {
+    users::require(&app_handle, Permission::Import)?;
+    let pool = db::pool(&app_handle).await?;
+    let mut conn = pool.acquire().await.map_err(|e| e.to_string())?;
+    get_or_create_category(&mut conn, &label, None)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_categories(app_handle: AppHandle) -> Result<Vec<Category>, String> {
+    let pool = db::pool(&app_handle).await?;
+    sqlx::query_as::<_, Category>(
+        "SELECT c.id, c.code, c.name, c.parent_id, c.sort_order,
+                (SELECT COUNT(*) FROM products p WHERE p.category_id = c.id) AS product_count
+         FROM categories c
+         ORDER BY c.sort_order, c.name",
+    )
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn create_category(
+    app_handle: AppHandle,
+    name: String,
+    code: Option<String>,
+    parent_id: Option<i64>,
+) -> Result<i64, String> {
+    users::require(&app_handle, Permission::Import)?;
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Category name cannot be empty".to_string());
+    }
+    let pool = db::pool(&app_handle).await?;
+    let result = sqlx::query("INSERT INTO categories (code, name, parent_id) VALUES (?, ?, ?)")
+        .bind(code.as_deref().map(str::trim).filter(|c| !c.is_empty()))
+        .bind(name)
+        .bind(parent_id)
+        .execute(&pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(result.last_insert_rowid())
+}
+
+/// Updates a category. Its new parent cannot be the category itself or
+/// one of its subcategories, which would make the tree loop.
+#[tauri::command]
+pub async fn update_category(app_handle: AppHandle, category: Category) -> Result<(), String> {
+    users::require(&app_handle, Permission::Import)?;
+    if category.name.trim().is_empty() {
+        return Err("Category name cannot be empty".to_string());
+    }
+    let pool = db::pool(&app_handle).await?;
+    if let Some(parent_id) = category.parent_id {
+        // UNION stops on a loop already in the table instead of recursing
+        let in_subtree: bool = sqlx::query_scalar(
+            "WITH RECURSIVE ancestors(id) AS (
+                 SELECT ?1
+                 UNION
+                 SELECT c.parent_id FROM categories c
+                 JOIN ancestors a ON c.id = a.id
+                 WHERE c.parent_id IS NOT NULL
+             )
+             SELECT EXISTS (SELECT 1 FROM ancestors WHERE id = ?2)",
+        )
+        .bind(parent_id)
+        .bind(category.id)
+        .fetch_one(&pool)
+        .await
+        .map_err(|e| e.to_string())?;
+        if in_subtree {
+            return Err(
+                "A category cannot be moved under itself or one of its subcategories".to_string(),
+            );
+        }
+    }
+    sqlx::query(
+        "UPDATE categories SET code = ?, name = ?, parent_id = ?, sort_order = ? WHERE id = ?",
+    )
+    .bind(&category.code)
+    .bind(category.name.trim())
+    .bind(category.parent_id)
+    .bind(category.sort_order)
+    .bind(category.id)
+    .execute(&pool)
+    .await
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn set_product_category(
+    app_handle: AppHandle,
+    sku: String,
+    category_id: Option<i64>,
+) -> Result<(), String> {
+    users::require(&app_handle, Permission::Import)?;
+    let pool = db::pool(&app_handle).await?;
+    sqlx::query("UPDATE products SET category_id = ? WHERE sku = ?")
+        .bind(category_id)
+        .bind(sku)
+        .execute(&pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Products for a quick-key grid: everything in the category and its
+/// subcategories, or the uncategorized products when no category is given.
+#[tauri::command]
+pub async fn get_quick_keys(
+    app_handle: AppHandle,
+    category_id: Option<i64>,
+) -> Result<Vec<product>, String> {
+    let pool = db::pool(&app_handle).await?;
+    sqlx::query_as::<_, product>(&format!(
+        "WITH RECURSIVE tree(id) AS (
+             SELECT id FROM categories WHERE id = ?1
+             UNION
+             SELECT c.id FROM categories c JOIN tree ON c.parent_id = tree.id
+         )
+         SELECT {PRODUCT_COLUMNS} FROM products p
+         WHERE (?1 IS NULL AND p.category_id IS NULL)
+            OR p.category_id IN (SELECT id FROM tree)
+         ORDER BY p.product_name"
+    ))
+    .bind(category_id)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| e.to_string())
+}
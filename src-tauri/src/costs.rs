@@ -0,0 +1,270 @@
+use crate::audit;
+use crate::db;
+use crate::users::{self, Permission, SessionUser};
+use serde::Serialize;
+use serde_json::json;
+use sqlx::{Pool, Sqlite, SqliteConnection};
+use tauri::{AppHandle, Emitter};
+
+/// Days of receipts averaged for products that do not track stock, which
+/// have no quantity on hand to weigh the old average against.
+const UNTRACKED_AVERAGE_DAYS: i64 = 90;
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct CostChange {
+    pub id: i64,
+    pub sku: String,
+    pub old_cost: Option<f64>,
+    pub new_cost: f64,
+    pub old_average_cost: Option<f64>,
+    pub new_average_cost: f64,
+    pub source: String,
+    pub reference: Option<String>,
+    pub changed_by: Option<String>,
+    pub changed_at: String,
+}
+
+/// A retail (`tier` None) or tier price below the last cost of the product.
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct BelowCostItem {
+    pub sku: String,
+    pub product_name: String,
+    pub tier: Option<String>,
+    pub price: f64,
+    pub cost: f64,
+}
+
+#[derive(sqlx::FromRow)]
+struct ProductCost {
+    cost: Option<f64>,
+    average_cost: Option<f64>,
+    track_stock: bool,
+    on_hand: f64,
+}
+
+async fn product_cost(conn: &mut SqliteConnection, sku: &str) -> Result<ProductCost, String> {
+    sqlx::query_as::<_, ProductCost>(
+        "SELECT CAST(p.cost AS REAL) AS cost, CAST(p.average_cost AS REAL) AS average_cost,
+                p.track_stock, CAST(IFNULL(l.quantity, 0) AS REAL) AS on_hand
+         FROM products p
+         LEFT JOIN stock_levels l ON l.sku = p.sku
+         WHERE p.sku = ?",
+    )
+    .bind(sku)
+    .fetch_optional(conn)
+    .await
+    .map_err(|e| e.to_string())?
+    .ok_or_else(|| format!("Unknown product {}", sku))
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn write_cost(
+    conn: &mut SqliteConnection,
+    sku: &str,
+    old: &ProductCost,
+    new_cost: f64,
+    new_average_cost: f64,
+    source: &str,
+    reference: Option<&str>,
+    user: Option<&SessionUser>,
+) -> Result<(), String> {
+    sqlx::query("UPDATE products SET cost = ?, average_cost = ? WHERE sku = ?")
+        .bind(new_cost)
+        .bind(new_average_cost)
+        .bind(sku)
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| e.to_string())?;
+    sqlx::query(
+        "INSERT INTO cost_history (
+             sku, old_cost, new_cost, old_average_cost, new_average_cost, source, reference,
+             changed_by
+         ) VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+    )
+    .bind(sku)
+    .bind(old.cost)
+    .bind(new_cost)
+    .bind(old.average_cost)
+    .bind(new_average_cost)
+    .bind(source)
+    .bind(reference)
+    .bind(user.map(|user| user.id))
+    .execute(&mut *conn)
+    .await
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Weighted average of the old average cost over the stock on hand
+/// (negative stock counts as none) and `quantity` more units at `unit_cost`.
+fn weighted_average(old: &ProductCost, quantity: f64, unit_cost: f64) -> f64 {
+    let on_hand = old.on_hand.max(0.0);
+    match old.average_cost {
+        Some(average) if on_hand + quantity > 0.0 => {
+            (on_hand * average + quantity * unit_cost) / (on_hand + quantity)
+        }
+        _ => unit_cost,
+    }
+}
+
+/// Costs `quantity` units received at `unit_cost` pesos each. Call it
+/// before the receipt movement is added to the stock: the old average is
+/// weighed by the stock on hand. Products that do not track stock average
+/// their receipts of the last `UNTRACKED_AVERAGE_DAYS` days instead, this
+/// one included.
+pub async fn record_receipt(
+    conn: &mut SqliteConnection,
+    sku: &str,
+    quantity: f64,
+    unit_cost: f64,
+    reference: Option<&str>,
+    user: Option<&SessionUser>,
+) -> Result<(), String> {
+    let old = product_cost(conn, sku).await?;
+    let average_cost = if old.track_stock {
+        weighted_average(&old, quantity, unit_cost)
+    } else {
+        sqlx::query_scalar::<_, Option<f64>>(
+            "SELECT CAST(SUM(rl.quantity * rl.unit_cost_mxn) AS REAL) / SUM(rl.quantity)
+             FROM purchase_receipt_lines rl
+             JOIN purchase_receipts r ON r.id = rl.receipt_id
+             JOIN purchase_order_lines l ON l.id = rl.line_id
+             WHERE l.sku = ? AND r.received_at >= datetime('now', ?)",
+        )
+        .bind(sku)
+        .bind(format!("-{} days", UNTRACKED_AVERAGE_DAYS))
+        .fetch_one(&mut *conn)
+        .await
+        .map_err(|e| e.to_string())?
+        .unwrap_or(unit_cost)
+    };
+    write_cost(
+        conn,
+        sku,
+        &old,
+        unit_cost,
+        average_cost,
+        "receipt",
+        reference,
+        user,
+    )
+    .await
+}
+
+/// Costs `quantity` units made in a production batch at `unit_cost` pesos
+/// each. Call it before the production movement is added to the stock.
+pub async fn record_production(
+    conn: &mut SqliteConnection,
+    sku: &str,
+    quantity: f64,
+    unit_cost: f64,
+    reference: Option<&str>,
+    user: Option<&SessionUser>,
+) -> Result<(), String> {
+    let old = product_cost(conn, sku).await?;
+    let average_cost = weighted_average(&old, quantity, unit_cost);
+    write_cost(
+        conn,
+        sku,
+        &old,
+        unit_cost,
+        average_cost,
+        "production",
+        reference,
+        user,
+    )
+    .await
+}
+
+/// Retail and tier prices below the last cost of their product.
+pub async fn below_cost_items(pool: &Pool<Sqlite>) -> Result<Vec<BelowCostItem>, String> {
+    sqlx::query_as::<_, BelowCostItem>(
+        "SELECT p.sku, p.product_name, NULL AS tier, CAST(p.price AS REAL) AS price,
+                CAST(p.cost AS REAL) AS cost
+         FROM products p
+         WHERE p.cost IS NOT NULL AND p.price < p.cost
+         UNION ALL
+         SELECT p.sku, p.product_name, pp.tier, CAST(pp.price AS REAL) AS price,
+                CAST(p.cost AS REAL) AS cost
+         FROM product_prices pp
+         JOIN products p ON p.sku = pp.sku
+         WHERE p.cost IS NOT NULL AND pp.price < p.cost
+         ORDER BY product_name, tier",
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())
+}
+
+/// Emits a `below-cost` event with the prices below cost, if there are
+/// any. Called after prices or costs change; failures are only logged so
+/// they never undo the change.
+pub async fn notify_below_cost(app_handle: &AppHandle, pool: &Pool<Sqlite>) {
+    match below_cost_items(pool).await {
+        Ok(items) if items.is_empty() => {}
+        Ok(items) => {
+            if let Err(e) = app_handle.emit("below-cost", &items) {
+                eprintln!("Failed to emit below-cost: {}", e);
+            }
+        }
+        Err(e) => eprintln!("Failed to check prices below cost: {}", e),
+    }
+}
+
+/// Sets the cost of a product by hand, e.g. for goods bought without a
+/// purchase order. Both the last and the average cost take the new value.
+/// Catalog changes take the `import` permission.
+#[tauri::command]
+pub async fn set_product_cost(app_handle: AppHandle, sku: String, cost: f64) -> Result<(), String> {
+    let user = users::require(&app_handle, Permission::Import)?;
+    if cost < 0.0 {
+        return Err("The cost cannot be negative".to_string());
+    }
+    let pool = db::pool(&app_handle).await?;
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    let old = product_cost(&mut tx, &sku).await?;
+    write_cost(&mut tx, &sku, &old, cost, cost, "manual", None, Some(&user)).await?;
+    audit::record(
+        &mut tx,
+        Some(&user),
+        "change_cost",
+        "product",
+        Some(&sku),
+        Some(json!({ "cost": old.cost, "average_cost": old.average_cost })),
+        Some(json!({ "cost": cost, "average_cost": cost })),
+    )
+    .await?;
+    tx.commit().await.map_err(|e| e.to_string())?;
+    notify_below_cost(&app_handle, &pool).await;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_cost_history(
+    app_handle: AppHandle,
+    sku: String,
+) -> Result<Vec<CostChange>, String> {
+    let pool = db::pool(&app_handle).await?;
+    sqlx::query_as::<_, CostChange>(
+        "SELECT h.id, h.sku, CAST(h.old_cost AS REAL) AS old_cost,
+                CAST(h.new_cost AS REAL) AS new_cost,
+                CAST(h.old_average_cost AS REAL) AS old_average_cost,
+                CAST(h.new_average_cost AS REAL) AS new_average_cost,
+                h.source, h.reference, u.display_name AS changed_by,
+                datetime(h.changed_at, 'localtime') AS changed_at
+         FROM cost_history h
+         LEFT JOIN users u ON u.id = h.changed_by
+         WHERE h.sku = ?
+         ORDER BY h.id DESC",
+    )
+    .bind(sku)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_below_cost_products(app_handle: AppHandle) -> Result<Vec<BelowCostItem>, String> {
+    let pool = db::pool(&app_handle).await?;
+    below_cost_items(&pool).await
+}
@@ -0,0 +1,286 @@
+use sqlx::sqlite::SqliteConnectOptions;
+use sqlx::{ConnectOptions, Pool, Sqlite};
+use std::path::PathBuf;
+use std::sync::OnceLock;
+use tauri::plugin::{Builder as PluginBuilder, TauriPlugin};
+use tauri::{AppHandle, Context, Manager, Runtime};
+use tauri_plugin_sql::{DbInstances, DbPool, Migration, MigrationKind};
+
+/// Database file used when `BORDERPOS_DATABASE` is not set.
+const DEFAULT_DATABASE: &str = "pos_demo.db";
+
+/// Connection string of the POS database, e.g. "sqlite:pos_demo.db".
+/// `BORDERPOS_DATABASE` picks another file (a name relative to the app
+/// config dir, or an absolute path), which is handy for tests and training
+/// stations. The SQL plugin preloads and migrates this database, Rust
+/// commands use it through `pool`, and the webview asks for it through
+/// `get_database_url`.
+pub fn database_url() -> &'static str {
+    static DATABASE_URL: OnceLock<String> = OnceLock::new();
+    DATABASE_URL.get_or_init(|| {
+        let database = std::env::var("BORDERPOS_DATABASE")
+            .ok()
+            .filter(|database| !database.trim().is_empty())
+            .unwrap_or_else(|| DEFAULT_DATABASE.to_string());
+        if database.starts_with("sqlite:") {
+            database
+        } else {
+            format!("sqlite:{}", database)
+        }
+    })
+}
+
+/// Schema migrations, in order. Add new ones at the end with the next
+/// version; never edit one that has shipped.
+pub fn migrations() -> Vec<Migration> {
+    vec![
+        Migration {
+            version: 1,
+            description: "schema migration",
+            sql: include_str!("../database/migrations/1_schema.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 2,
+            description: "product search",
+            sql: include_str!("../database/migrations/2_product_search.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 3,
+            description: "product barcodes",
+            sql: include_str!("../database/migrations/3_product_barcodes.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 4,
+            description: "categories",
+            sql: include_str!("../database/migrations/4_categories.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 5,
+            description: "price tiers",
+            sql: include_str!("../database/migrations/5_price_tiers.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 6,
+            description: "admin credentials",
+            sql: include_str!("../database/migrations/6_admin_credentials.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 7,
+            description: "users",
+            sql: include_str!("../database/migrations/7_users.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 8,
+            description: "approvals",
+            sql: include_str!("../database/migrations/8_approvals.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 9,
+            description: "audit_log",
+            sql: include_str!("../database/migrations/9_audit_log.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 10,
+            description: "price_history",
+            sql: include_str!("../database/migrations/10_price_history.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 11,
+            description: "inventory",
+            sql: include_str!("../database/migrations/11_inventory.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 12,
+            description: "reorder",
+            sql: include_str!("../database/migrations/12_reorder.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 13,
+            description: "purchase_orders",
+            sql: include_str!("../database/migrations/13_purchase_orders.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 14,
+            description: "costs",
+            sql: include_str!("../database/migrations/14_costs.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 15,
+            description: "recipes",
+            sql: include_str!("../database/migrations/15_recipes.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 16,
+            description: "waste",
+            sql: include_str!("../database/migrations/16_waste.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 17,
+            description: "stock_counts",
+            sql: include_str!("../database/migrations/17_stock_counts.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 18,
+            description: "returns",
+            sql: include_str!("../database/migrations/18_returns.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 19,
+            description: "voids",
+            sql: include_str!("../database/migrations/19_voids.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 20,
+            description: "cash_sessions",
+            sql: include_str!("../database/migrations/20_cash_sessions.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 21,
+            description: "cash_movements",
+            sql: include_str!("../database/migrations/21_cash_movements.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 22,
+            description: "cash_counts",
+            sql: include_str!("../database/migrations/22_cash_counts.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 23,
+            description: "manage_settings",
+            sql: include_str!("../database/migrations/23_manage_settings.sql"),
+            kind: MigrationKind::Up,
+        },
+    ]
+}
+
+/// Newest schema version this build understands.
+fn latest_schema_version() -> i64 {
+    migrations()
+        .iter()
+        .map(|migration| migration.version)
+        .max()
+        .unwrap_or(0)
+}
+
+/// Makes the SQL plugin preload `database_url()` instead of whatever
+/// `tauri.conf.json` lists, so migrations always run on the database in use.
+pub fn preload_database<R: Runtime>(context: &mut Context<R>) {
+    context.config_mut().plugins.0.insert(
+        "sql".to_string(),
+        serde_json::json!({ "preload": [database_url()] }),
+    );
+}
+
+/// The SQL plugin with the migrations registered for the POS database.
+pub fn sql_plugin<R: Runtime>() -> TauriPlugin<R, Option<tauri_plugin_sql::PluginConfig>> {
+    tauri_plugin_sql::Builder::default()
+        .add_migrations(database_url(), migrations())
+        .build()
+}
+
+/// Path of the database file, resolved the way the SQL plugin does it
+/// (relative to the app config dir).
+pub fn database_path<R: Runtime>(app_handle: &AppHandle<R>) -> Result<PathBuf, String> {
+    let file = database_url().trim_start_matches("sqlite:");
+    let file = file.split('?').next().unwrap_or(file);
+    let config_dir = app_handle
+        .path()
+        .app_config_dir()
+        .map_err(|e| e.to_string())?;
+    Ok(config_dir.join(file))
+}
+
+/// Schema version recorded in the database, 0 for a new or unmigrated file.
+async fn schema_version(path: &PathBuf) -> Result<i64, sqlx::Error> {
+    let mut conn = SqliteConnectOptions::new()
+        .filename(path)
+        .read_only(true)
+        .connect()
+        .await?;
+    let has_migrations: bool = sqlx::query_scalar(
+        "SELECT EXISTS (SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = '_sqlx_migrations')",
+    )
+    .fetch_one(&mut conn)
+    .await?;
+    if !has_migrations {
+        return Ok(0);
+    }
+    sqlx::query_scalar("SELECT IFNULL(MAX(version), 0) FROM _sqlx_migrations WHERE success")
+        .fetch_one(&mut conn)
+        .await
+}
+
+/// Refuses to start against a database migrated by a newer BorderPOS, which
+/// this build would misread or, worse, write to with an old schema in mind.
+/// Must be registered before `sql_plugin` so it runs first.
+pub fn schema_guard<R: Runtime>() -> TauriPlugin<R> {
+    PluginBuilder::new("schema-guard")
+        .setup(|app, _api| {
+            let path = database_path(app)?;
+            if !path.exists() {
+                return Ok(());
+            }
+            let version = tauri::async_runtime::block_on(schema_version(&path))?;
+            let latest = latest_schema_version();
+            if version > latest {
+                let message = format!(
+                    "La base de datos {} tiene la versión de esquema {}, pero esta versión de BorderPOS solo conoce hasta la {}. Actualice BorderPOS para abrirla.",
+                    path.display(),
+                    version,
+                    latest
+                );
+                tfd::MessageBox::new("BorderPOS", &message)
+                    .with_icon(tfd::MessageBoxIcon::Error)
+                    .run_modal();
+                return Err(format!(
+                    "Database schema version {} is newer than the supported version {}",
+                    version, latest
+                )
+                .into());
+            }
+            Ok(())
+        })
+        .build()
+}
+
+/// Returns the pool the SQL plugin opened for the POS database, so Rust
+/// commands share the same connections as the webview.
+pub async fn pool(app_handle: &AppHandle) -> Result<Pool<Sqlite>, String> {
+    let instances = app_handle.state::<DbInstances>();
+    let instances = instances.0.read().await;
+
+    let db_pool = instances
+        .get(database_url())
+        .ok_or_else(|| format!("Database instance '{}' not found.", database_url()))?;
+
+    let DbPool::Sqlite(pool) = db_pool;
+    Ok(pool.clone())
+}
+
+/// Connection string the webview passes to `Database.get`.
+#[tauri::command]
+pub fn get_database_url() -> String {
+    database_url().to_string()
+}
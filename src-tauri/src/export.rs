@@ -0,0 +1,401 @@
+use crate::db;
+use crate::import::WHOLESALE_TIER;
+use crate::reorder::{self, ReorderSuggestion};
+use crate::users::{self, Permission};
+use rust_xlsxwriter::{Format, Workbook};
+use sqlx::{Pool, Sqlite};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use tauri::AppHandle;
+use tauri_plugin_dialog::DialogExt;
+
+/// Column headers of the catalog export. They are names the product import
+/// detects on its own, so an edited export can be imported back as is.
+/// "Otros códigos de barras" is informational and ignored by the import.
+const HEADERS: [&str; 9] = [
+    "SKU",
+    "PLU",
+    "Código de barras",
+    "Producto",
+    "Precio",
+    "Precio mayoreo",
+    "Departamento",
+    "Categoría",
+    "Otros códigos de barras",
+];
+const PRICE_COLUMN: usize = 4;
+const WHOLESALE_PRICE_COLUMN: usize = 5;
+
+#[derive(sqlx::FromRow)]
+struct CatalogRow {
+    sku: String,
+    plu_code: Option<String>,
+    product_name: String,
+    price: f64,
+    wholesale_price: Option<f64>,
+    /// Parent of the category, when it is a subcategory.
+    department: Option<String>,
+    /// "Tortilla (10)" when the category has a code, so the import resolves it by code.
+    category: Option<String>,
+}
+
+#[derive(sqlx::FromRow)]
+struct BarcodeRow {
+    sku: String,
+    barcode: String,
+    quantity: f64,
+}
+
+struct ExportRow {
+    catalog: CatalogRow,
+    barcode: String,
+    other_barcodes: String,
+}
+
+async fn load_catalog(pool: &Pool<Sqlite>) -> Result<Vec<ExportRow>, sqlx::Error> {
+    let products = sqlx::query_as::<_, CatalogRow>(
+        "SELECT p.sku, p.plu_code, p.product_name, CAST(p.price AS REAL) AS price,
+                CAST(pp.price AS REAL) AS wholesale_price,
+                CASE WHEN d.code IS NULL THEN d.name ELSE d.name || ' (' || d.code || ')' END AS department,
+                CASE WHEN c.code IS NULL THEN c.name ELSE c.name || ' (' || c.code || ')' END AS category
+         FROM products p
+         LEFT JOIN categories c ON c.id = p.category_id
+         LEFT JOIN categories d ON d.id = c.parent_id
+         LEFT JOIN product_prices pp ON pp.sku = p.sku AND pp.tier = ?
+         ORDER BY d.sort_order, d.name, c.sort_order, c.name, p.product_name",
+    )
+    .bind(WHOLESALE_TIER)
+    .fetch_all(pool)
+    .await?;
+
+    let mut barcodes: HashMap<String, Vec<BarcodeRow>> = HashMap::new();
+    for row in sqlx::query_as::<_, BarcodeRow>(
+        "SELECT sku, barcode, CAST(quantity AS REAL) AS quantity
+         FROM product_barcodes ORDER BY quantity, rowid",
+    )
+    .fetch_all(pool)
+    .await?
+    {
+        barcodes.entry(row.sku.clone()).or_default().push(row);
+    }
+
+    Ok(products
+        .into_iter()
+        .map(|catalog| {
+            let aliases = barcodes.remove(&catalog.sku).unwrap_or_default();
+            let mut aliases = aliases.into_iter();
+            let barcode = aliases
+                .next()
+                .map(|alias| alias.barcode)
+                .unwrap_or_default();
+            let other_barcodes = aliases
+                .map(|alias| {
+                    if alias.quantity == 1.0 {
+                        alias.barcode
+                    } else {
+                        format!("{} (x{})", alias.barcode, alias.quantity)
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join(" ");
+            ExportRow {
+                catalog,
+                barcode,
+                other_barcodes,
+            }
+        })
+        .collect())
+}
+
+fn text_columns(row: &ExportRow) -> [String; 9] {
+    [
+        row.catalog.sku.clone(),
+        row.catalog.plu_code.clone().unwrap_or_default(),
+        row.barcode.clone(),
+        row.catalog.product_name.clone(),
+        format!("{:.2}", row.catalog.price),
+        row.catalog
+            .wholesale_price
+            .map(|price| format!("{:.2}", price))
+            .unwrap_or_default(),
+        row.catalog.department.clone().unwrap_or_default(),
+        row.catalog.category.clone().unwrap_or_default(),
+        row.other_barcodes.clone(),
+    ]
+}
+
+fn write_xlsx(path: &Path, rows: &[ExportRow]) -> Result<(), String> {
+    let mut workbook = Workbook::new();
+    let worksheet = workbook.add_worksheet();
+    let header_format = Format::new().set_bold();
+    let price_format = Format::new().set_num_format("0.00");
+
+    worksheet.set_name("Productos").map_err(|e| e.to_string())?;
+    for (column, header) in HEADERS.iter().enumerate() {
+        worksheet
+            .write_string_with_format(0, column as u16, *header, &header_format)
+            .map_err(|e| e.to_string())?;
+    }
+    for (index, row) in rows.iter().enumerate() {
+        let excel_row = index as u32 + 1;
+        for (column, value) in text_columns(row).into_iter().enumerate() {
+            let number = match column {
+                PRICE_COLUMN => Some(row.catalog.price),
+                WHOLESALE_PRICE_COLUMN => row.catalog.wholesale_price,
+                _ => None,
+            };
+            if let Some(number) = number {
+                worksheet
+                    .write_number_with_format(excel_row, column as u16, number, &price_format)
+                    .map_err(|e| e.to_string())?;
+            } else if !value.is_empty() {
+                // Codes are written as text so Excel keeps leading zeros
+                worksheet
+                    .write_string(excel_row, column as u16, value)
+                    .map_err(|e| e.to_string())?;
+            }
+        }
+    }
+    worksheet
+        .set_freeze_panes(1, 0)
+        .map_err(|e| e.to_string())?;
+    worksheet.autofit();
+
+    workbook.save(path).map_err(|e| e.to_string())
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Writes UTF-8 with a BOM so Excel opens accents correctly.
+fn write_csv(path: &Path, rows: &[ExportRow]) -> Result<(), String> {
+    let mut content = String::from("\u{feff}");
+    content.push_str(&HEADERS.map(csv_field).join(","));
+    content.push_str("\r\n");
+    for row in rows {
+        content.push_str(&text_columns(row).map(|value| csv_field(&value)).join(","));
+        content.push_str("\r\n");
+    }
+    std::fs::write(path, content).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+/// Asks where to save an export, suggesting `file_name`. The path comes
+/// from the dialog rather than the webview, so exports only write where
+/// the user chose. None when the user cancels.
+async fn pick_export_path(
+    app_handle: &AppHandle,
+    file_name: &str,
+) -> Result<Option<PathBuf>, String> {
+    let dialog = app_handle
+        .dialog()
+        .file()
+        .add_filter("Excel", &["xlsx"])
+        .add_filter("CSV Files", &["csv"])
+        .set_file_name(file_name);
+    let file_path = tauri::async_runtime::spawn_blocking(move || dialog.blocking_save_file())
+        .await
+        .map_err(|e| e.to_string())?;
+    file_path
+        .map(|path| path.into_path().map_err(|e| e.to_string()))
+        .transpose()
+}
+
+/// Exports the whole catalog to `path`; the extension picks the format
+/// (`.xlsx`, anything else is CSV). Takes the `import` permission.
+/// Returns the number of products written.
+pub async fn export_catalog(app_handle: &AppHandle, path: &Path) -> Result<usize, String> {
+    users::require(app_handle, Permission::Import)?;
+    let pool = db::pool(app_handle).await?;
+    let rows = load_catalog(&pool).await.map_err(|e| e.to_string())?;
+
+    let is_xlsx = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(|extension| extension.eq_ignore_ascii_case("xlsx"))
+        .unwrap_or(false);
+    if is_xlsx {
+        write_xlsx(path, &rows)?;
+    } else {
+        write_csv(path, &rows)?;
+    }
+    Ok(rows.len())
+}
+
+/// Exports the catalog where the user chooses. Returns the number of
+/// products written, or None when the user cancels.
+#[tauri::command]
+pub async fn export_products(app_handle: AppHandle) -> Result<Option<usize>, String> {
+    users::require(&app_handle, Permission::Import)?;
+    let Some(path) = pick_export_path(&app_handle, "productos.xlsx").await? else {
+        return Ok(None);
+    };
+    export_catalog(&app_handle, &path).await.map(Some)
+}
+
+const PURCHASE_LIST_HEADERS: [&str; 8] = [
+    "Proveedor",
+    "SKU",
+    "Producto",
+    "Existencia",
+    "Mínimo",
+    "Máximo",
+    "Venta diaria",
+    "Pedir",
+];
+const NO_SUPPLIER: &str = "Sin proveedor";
+
+fn purchase_list_numbers(row: &ReorderSuggestion) -> [Option<f64>; 5] {
+    [
+        Some(row.quantity),
+        row.min_stock,
+        row.max_stock,
+        Some(row.avg_daily_sales),
+        Some(row.suggested_quantity),
+    ]
+}
+
+/// Excel sheet names are up to 31 characters, without `[]:*?/\`, and
+/// unique regardless of case. Suppliers whose names only differ past the
+/// 31st character get a " (2)", " (3)"... suffix.
+fn sheet_name(supplier: &str, taken: &mut HashSet<String>) -> String {
+    let name: String = supplier
+        .chars()
+        .map(|c| if "[]:*?/\\".contains(c) { '-' } else { c })
+        .collect();
+    let mut candidate: String = name.chars().take(31).collect();
+    let mut number = 1;
+    while !taken.insert(candidate.to_lowercase()) {
+        number += 1;
+        let suffix = format!(" ({})", number);
+        candidate = name.chars().take(31 - suffix.len()).collect::<String>() + &suffix;
+    }
+    candidate
+}
+
+/// One sheet per supplier, without the supplier column.
+fn write_purchase_list_xlsx(
+    path: &Path,
+    by_supplier: &BTreeMap<String, Vec<&ReorderSuggestion>>,
+) -> Result<(), String> {
+    let mut workbook = Workbook::new();
+    let header_format = Format::new().set_bold();
+    let number_format = Format::new().set_num_format("0.###");
+    let mut sheet_names = HashSet::new();
+
+    for (supplier, rows) in by_supplier {
+        let worksheet = workbook.add_worksheet();
+        worksheet
+            .set_name(sheet_name(supplier, &mut sheet_names))
+            .map_err(|e| e.to_string())?;
+        for (column, header) in PURCHASE_LIST_HEADERS[1..].iter().enumerate() {
+            worksheet
+                .write_string_with_format(0, column as u16, *header, &header_format)
+                .map_err(|e| e.to_string())?;
+        }
+        for (index, row) in rows.iter().enumerate() {
+            let excel_row = index as u32 + 1;
+            worksheet
+                .write_string(excel_row, 0, &row.sku)
+                .map_err(|e| e.to_string())?;
+            worksheet
+                .write_string(excel_row, 1, &row.product_name)
+                .map_err(|e| e.to_string())?;
+            for (offset, number) in purchase_list_numbers(row).into_iter().enumerate() {
+                if let Some(number) = number {
+                    worksheet
+                        .write_number_with_format(
+                            excel_row,
+                            2 + offset as u16,
+                            number,
+                            &number_format,
+                        )
+                        .map_err(|e| e.to_string())?;
+                }
+            }
+        }
+        worksheet
+            .set_freeze_panes(1, 0)
+            .map_err(|e| e.to_string())?;
+        worksheet.autofit();
+    }
+    if by_supplier.is_empty() {
+        workbook.add_worksheet();
+    }
+
+    workbook.save(path).map_err(|e| e.to_string())
+}
+
+fn write_purchase_list_csv(
+    path: &Path,
+    by_supplier: &BTreeMap<String, Vec<&ReorderSuggestion>>,
+) -> Result<(), String> {
+    let mut content = String::from("\u{feff}");
+    content.push_str(&PURCHASE_LIST_HEADERS.map(csv_field).join(","));
+    content.push_str("\r\n");
+    for (supplier, rows) in by_supplier {
+        for row in rows {
+            let mut fields = vec![
+                csv_field(supplier),
+                csv_field(&row.sku),
+                csv_field(&row.product_name),
+            ];
+            fields.extend(
+                purchase_list_numbers(row)
+                    .map(|number| number.map(|number| number.to_string()).unwrap_or_default()),
+            );
+            content.push_str(&fields.join(","));
+            content.push_str("\r\n");
+        }
+    }
+    std::fs::write(path, content).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+/// Exports the reorder suggestions as a purchase list per supplier
+/// (`.xlsx`: a sheet each; anything else: CSV) where the user chooses.
+/// Takes the `manage_inventory` permission. Returns the number of
+/// products written, or None when the user cancels.
+#[tauri::command]
+pub async fn export_purchase_list(
+    app_handle: AppHandle,
+    days: Option<i64>,
+    cover_days: Option<f64>,
+) -> Result<Option<usize>, String> {
+    users::require(&app_handle, Permission::ManageInventory)?;
+    let Some(path) = pick_export_path(&app_handle, "pedido.xlsx").await? else {
+        return Ok(None);
+    };
+    let path = path.as_path();
+    let pool = db::pool(&app_handle).await?;
+    let suggestions = reorder::reorder_suggestions(
+        &pool,
+        days.unwrap_or(reorder::DEFAULT_SALES_DAYS),
+        cover_days.unwrap_or(reorder::DEFAULT_COVER_DAYS),
+    )
+    .await?;
+
+    let mut by_supplier: BTreeMap<String, Vec<&ReorderSuggestion>> = BTreeMap::new();
+    for suggestion in &suggestions {
+        let supplier = suggestion.supplier_name.as_deref().unwrap_or(NO_SUPPLIER);
+        by_supplier
+            .entry(supplier.to_string())
+            .or_default()
+            .push(suggestion);
+    }
+
+    let is_xlsx = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(|extension| extension.eq_ignore_ascii_case("xlsx"))
+        .unwrap_or(false);
+    if is_xlsx {
+        write_purchase_list_xlsx(path, &by_supplier)?;
+    } else {
+        write_purchase_list_csv(path, &by_supplier)?;
+    }
+    Ok(Some(suggestions.len()))
+}
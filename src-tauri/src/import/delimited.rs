@@ -0,0 +1,155 @@
+use encoding_rs::{Encoding, UTF_8, WINDOWS_1252};
+
+use super::Table;
+
+const CANDIDATE_DELIMITERS: [char; 4] = [',', ';', '\t', '|'];
+
+/// Decodes a delimited text file as exported by Excel or another POS.
+/// A BOM (UTF-8 or UTF-16) decides the encoding; without one the bytes are
+/// read as UTF-8 and fall back to Windows-1252, which is what Excel uses
+/// for "CSV (delimitado por comas)" on Spanish Windows.
+/// Returns the text and the name of the encoding that was used.
+pub fn decode(bytes: &[u8]) -> (String, &'static str) {
+    if let Some((encoding, bom_length)) = Encoding::for_bom(bytes) {
+        let (text, _) = encoding.decode_without_bom_handling(&bytes[bom_length..]);
+        return (text.into_owned(), encoding.name());
+    }
+    match std::str::from_utf8(bytes) {
+        Ok(text) => (text.to_string(), UTF_8.name()),
+        Err(_) => {
+            let (text, _) = WINDOWS_1252.decode_without_bom_handling(bytes);
+            (text.into_owned(), WINDOWS_1252.name())
+        }
+    }
+}
+
+/// Picks the delimiter that appears most often (outside quotes) in the header line.
+pub fn detect_delimiter(text: &str) -> char {
+    let header = text
+        .lines()
+        .find(|line| !line.trim().is_empty())
+        .unwrap_or("");
+    let mut best = (',', 0);
+    for delimiter in CANDIDATE_DELIMITERS {
+        let mut in_quotes = false;
+        let count = header
+            .chars()
+            .filter(|c| {
+                if *c == '"' {
+                    in_quotes = !in_quotes;
+                }
+                !in_quotes && *c == delimiter
+            })
+            .count();
+        if count > best.1 {
+            best = (delimiter, count);
+        }
+    }
+    best.0
+}
+
+/// Splits delimited text into records.
+/// Handles quoted fields with embedded delimiters, doubled quotes and line
+/// breaks, CRLF line endings and blank lines. Unlike a strict CSV reader it
+/// also accepts whitespace around quoted fields (`1, "Tortillas", 48`), which
+/// hand-edited files like `sample_products.csv` contain. Every record keeps
+/// the 1-based line number it starts on.
+pub fn parse(text: &str, delimiter: char) -> Vec<(usize, Vec<String>)> {
+    let mut records = Vec::new();
+    let mut record: Vec<String> = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut was_quoted = false;
+    let mut line = 1;
+    let mut record_line = 1;
+    let mut chars = text.chars().peekable();
+
+    let is_padding = |c: char| c != delimiter && (c == ' ' || c == '\t');
+
+    while let Some(c) = chars.next() {
+        if in_quotes {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    field.push('"');
+                    chars.next();
+                }
+                '"' => in_quotes = false,
+                '\n' => {
+                    line += 1;
+                    field.push(c);
+                }
+                _ => field.push(c),
+            }
+            continue;
+        }
+
+        match c {
+            '"' if !was_quoted && field.trim().is_empty() => {
+                field.clear();
+                in_quotes = true;
+                was_quoted = true;
+            }
+            c if c == delimiter => {
+                record.push(finish_field(&mut field, &mut was_quoted));
+            }
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\n' => {
+                record.push(finish_field(&mut field, &mut was_quoted));
+                if record.iter().any(|value| !value.is_empty()) {
+                    records.push((record_line, std::mem::take(&mut record)));
+                } else {
+                    record.clear();
+                }
+                line += 1;
+                record_line = line;
+            }
+            // Text after a closing quote is kept, except padding before the delimiter
+            c if was_quoted && is_padding(c) => {}
+            _ => field.push(c),
+        }
+    }
+
+    if !field.is_empty() || was_quoted || !record.is_empty() {
+        record.push(finish_field(&mut field, &mut was_quoted));
+    }
+    if record.iter().any(|value| !value.is_empty()) {
+        records.push((record_line, record));
+    }
+    records
+}
+
+fn finish_field(field: &mut String, was_quoted: &mut bool) -> String {
+    let value = if *was_quoted {
+        std::mem::take(field)
+    } else {
+        let value = field.trim().to_string();
+        field.clear();
+        value
+    };
+    *was_quoted = false;
+    value
+}
+
+/// Reads a delimited file into a table; the first record is the header.
+pub fn read_table(bytes: &[u8]) -> Table {
+    let (text, encoding) = decode(bytes);
+    let delimiter = detect_delimiter(&text);
+    let mut records = parse(&text, delimiter).into_iter();
+    let headers = records
+        .next()
+        .map(|(_, headers)| headers)
+        .unwrap_or_default();
+
+    Table {
+        headers,
+        rows: records.collect(),
+        source: format!(
+            "{} ({})",
+            encoding,
+            match delimiter {
+                '\t' => "tab".to_string(),
+                other => format!("'{}'", other),
+            }
+        ),
+    }
+}
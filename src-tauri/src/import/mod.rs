@@ -0,0 +1,769 @@
+use crate::audit;
+use crate::barcodes;
+use crate::categories;
+use crate::costs;
+use crate::db;
+use crate::prices;
+use crate::users::{self, Permission, SessionUser};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sqlx::{Pool, Sqlite};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+
+mod delimited;
+mod presets;
+mod spreadsheet;
+
+pub use presets::ImportPreset;
+pub use spreadsheet::EXTENSIONS as SPREADSHEET_EXTENSIONS;
+
+/// `product_prices` tier the import fills from wholesale price columns.
+pub const WHOLESALE_TIER: &str = "mayoreo";
+
+/// Raw rows read from an import file, before any column mapping.
+pub struct Table {
+    pub headers: Vec<String>,
+    /// Data rows with the line (or sheet row) number they come from.
+    pub rows: Vec<(usize, Vec<String>)>,
+    /// Human readable description of how the file was read, e.g. "UTF-8 (',')".
+    pub source: String,
+}
+
+/// Which header feeds each product field. Unset fields are detected from
+/// the header names; `sku`, `product_name` and `price` must end up mapped.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ColumnMapping {
+    pub sku: Option<String>,
+    pub plu_code: Option<String>,
+    pub barcode: Option<String>,
+    pub product_name: Option<String>,
+    pub price: Option<String>,
+    pub wholesale_price: Option<String>,
+    pub wholesale_min_quantity: Option<String>,
+    /// Top level category; `category`, when also mapped, is created under it.
+    pub department: Option<String>,
+    pub category: Option<String>,
+}
+
+/// Header names (normalized, see `normalize_header`) recognized per field.
+const SKU_HEADERS: &[&str] = &["sku", "codigo", "clave", "code", "id"];
+const PLU_CODE_HEADERS: &[&str] = &["plu", "plu_code", "vendor_sku", "codigo proveedor"];
+const BARCODE_HEADERS: &[&str] = &["barcode", "codigo de barras", "codigo barras", "ean", "upc"];
+const PRODUCT_NAME_HEADERS: &[&str] = &[
+    "product_name",
+    "producto",
+    "nombre",
+    "descripcion",
+    "name",
+    "product",
+];
+const PRICE_HEADERS: &[&str] = &[
+    "price",
+    "precio",
+    "precio venta",
+    "precio de venta",
+    "precio publico",
+];
+const WHOLESALE_PRICE_HEADERS: &[&str] = &["wholesale_price", "precio mayoreo", "mayoreo"];
+const WHOLESALE_MIN_QUANTITY_HEADERS: &[&str] = &["wholesale_min_quantity", "cantidad mayoreo"];
+const DEPARTMENT_HEADERS: &[&str] = &["departamento", "department"];
+const CATEGORY_HEADERS: &[&str] = &["category", "categoria"];
+
+/// Lowercases a header and strips accents and surrounding spaces,
+/// so "Categoría " and "categoria" compare equal.
+fn normalize_header(header: &str) -> String {
+    header
+        .trim()
+        .to_lowercase()
+        .chars()
+        .map(|c| match c {
+            'á' | 'à' | 'ä' => 'a',
+            'é' | 'è' | 'ë' => 'e',
+            'í' | 'ì' | 'ï' => 'i',
+            'ó' | 'ò' | 'ö' => 'o',
+            'ú' | 'ù' | 'ü' => 'u',
+            'ñ' => 'n',
+            other => other,
+        })
+        .collect()
+}
+
+/// First header, in order of `known`, that matches one of the known names.
+fn find_header(headers: &[String], known: &[&str]) -> Option<String> {
+    known.iter().find_map(|name| {
+        headers
+            .iter()
+            .find(|header| normalize_header(header) == *name)
+            .cloned()
+    })
+}
+
+impl ColumnMapping {
+    fn fields_mut(&mut self) -> [(&mut Option<String>, &'static [&'static str]); 9] {
+        [
+            (&mut self.sku, SKU_HEADERS),
+            (&mut self.plu_code, PLU_CODE_HEADERS),
+            (&mut self.barcode, BARCODE_HEADERS),
+            (&mut self.product_name, PRODUCT_NAME_HEADERS),
+            (&mut self.price, PRICE_HEADERS),
+            (&mut self.wholesale_price, WHOLESALE_PRICE_HEADERS),
+            (
+                &mut self.wholesale_min_quantity,
+                WHOLESALE_MIN_QUANTITY_HEADERS,
+            ),
+            (&mut self.department, DEPARTMENT_HEADERS),
+            (&mut self.category, CATEGORY_HEADERS),
+        ]
+    }
+
+    /// Fills every unset field with the first header that matches one of its
+    /// known names. A header already mapped to a field is not used twice.
+    pub fn detect(mut self, headers: &[String]) -> Self {
+        let mut used: HashSet<String> = self
+            .fields_mut()
+            .into_iter()
+            .filter_map(|(header, _)| header.as_deref().map(normalize_header))
+            .collect();
+        for (header, known) in self.fields_mut() {
+            if header.is_some() {
+                continue;
+            }
+            let available: Vec<String> = headers
+                .iter()
+                .filter(|h| !used.contains(&normalize_header(h)))
+                .cloned()
+                .collect();
+            *header = find_header(&available, known);
+            if let Some(found) = header.as_deref() {
+                used.insert(normalize_header(found));
+            }
+        }
+        self
+    }
+}
+
+/// Column indexes for a mapping resolved against a header row.
+struct MappedColumns {
+    sku: usize,
+    plu_code: Option<usize>,
+    barcode: Option<usize>,
+    product_name: usize,
+    price: usize,
+    wholesale_price: Option<usize>,
+    wholesale_min_quantity: Option<usize>,
+    department: Option<usize>,
+    category: Option<usize>,
+}
+
+impl MappedColumns {
+    fn resolve(mapping: &ColumnMapping, headers: &[String]) -> Result<Self, String> {
+        let index_of = |field: &str, header: &Option<String>| -> Result<Option<usize>, String> {
+            match header {
+                None => Ok(None),
+                Some(header) => headers
+                    .iter()
+                    .position(|h| normalize_header(h) == normalize_header(header))
+                    .map(Some)
+                    .ok_or_else(|| {
+                        format!("Column '{}' for {} not found in the file", header, field)
+                    }),
+            }
+        };
+        let required = |field: &str, header: &Option<String>| -> Result<usize, String> {
+            index_of(field, header)?.ok_or_else(|| format!("No column is mapped to {}", field))
+        };
+
+        Ok(Self {
+            sku: required("sku", &mapping.sku)?,
+            plu_code: index_of("plu_code", &mapping.plu_code)?,
+            barcode: index_of("barcode", &mapping.barcode)?,
+            product_name: required("product_name", &mapping.product_name)?,
+            price: required("price", &mapping.price)?,
+            wholesale_price: index_of("wholesale_price", &mapping.wholesale_price)?,
+            wholesale_min_quantity: index_of(
+                "wholesale_min_quantity",
+                &mapping.wholesale_min_quantity,
+            )?,
+            department: index_of("department", &mapping.department)?,
+            category: index_of("category", &mapping.category)?,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportedProduct {
+    pub line: usize,
+    pub sku: String,
+    pub plu_code: Option<String>,
+    pub barcode: Option<String>,
+    pub product_name: String,
+    pub price: f64,
+    pub wholesale_price: Option<f64>,
+    pub wholesale_min_quantity: Option<f64>,
+    /// Category labels as written in the file, e.g. "Tortilla (10)".
+    pub department: Option<String>,
+    pub category: Option<String>,
+}
+
+impl ImportedProduct {
+    /// Name of the category the product ends up in: the category when the
+    /// file has one, otherwise the department.
+    fn category_name(&self) -> Option<String> {
+        self.category
+            .as_ref()
+            .or(self.department.as_ref())
+            .map(|label| categories::parse_category_label(label).0)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FieldChange {
+    pub field: &'static str,
+    pub before: String,
+    pub after: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProductChange {
+    pub product: ImportedProduct,
+    pub changes: Vec<FieldChange>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LineError {
+    pub line: usize,
+    pub message: String,
+}
+
+/// Dry run of an import: what would be inserted or changed, and which lines fail.
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportPreview {
+    /// Identifies the preview to `commit_product_import`.
+    pub id: u64,
+    pub source: String,
+    pub headers: Vec<String>,
+    pub mapping: ColumnMapping,
+    pub new_products: Vec<ImportedProduct>,
+    pub changed_products: Vec<ProductChange>,
+    pub unchanged_count: usize,
+    pub errors: Vec<LineError>,
+    /// Lines that import fine but not entirely, e.g. a barcode owned by another product.
+    pub warnings: Vec<LineError>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ImportSummary {
+    pub inserted: usize,
+    pub updated: usize,
+    pub unchanged: usize,
+    pub skipped: usize,
+    pub barcodes_added: usize,
+}
+
+/// Values other systems write for "no value".
+fn non_empty(value: &str) -> Option<String> {
+    let value = value.trim();
+    match value.to_lowercase().as_str() {
+        "" | "null" | "n/a" | "-" => None,
+        _ => Some(value.to_string()),
+    }
+}
+
+/// Parses prices like "48", "$1,234.50" or "12,50" (decimal comma).
+fn parse_price(value: &str) -> Option<f64> {
+    let cleaned: String = value
+        .chars()
+        .filter(|c| !matches!(c, '$' | ' ' | '\u{a0}'))
+        .collect();
+    let normalized = match (cleaned.rfind(','), cleaned.contains('.')) {
+        (Some(comma), false) if cleaned.len() - comma - 1 <= 2 => cleaned.replace(',', "."),
+        _ => cleaned.replace(',', ""),
+    };
+    normalized
+        .parse::<f64>()
+        .ok()
+        .filter(|price| price.is_finite() && *price >= 0.0)
+}
+
+fn parse_row(
+    columns: &MappedColumns,
+    line: usize,
+    values: &[String],
+) -> Result<ImportedProduct, String> {
+    let cell = |index: usize| values.get(index).and_then(|value| non_empty(value));
+    let optional = |index: Option<usize>| index.and_then(cell);
+
+    let sku = cell(columns.sku).ok_or("Missing SKU")?;
+    let product_name = cell(columns.product_name).ok_or("Missing product name")?;
+    let raw_price = cell(columns.price).ok_or("Missing price")?;
+    let price = parse_price(&raw_price).ok_or_else(|| format!("Invalid price '{}'", raw_price))?;
+    // Other POS systems export 0 for products without a wholesale price
+    let wholesale_price = match optional(columns.wholesale_price) {
+        Some(raw) => parse_price(&raw)
+            .ok_or_else(|| format!("Invalid wholesale price '{}'", raw))
+            .map(|price| Some(price).filter(|price| *price > 0.0))?,
+        None => None,
+    };
+    let wholesale_min_quantity = match optional(columns.wholesale_min_quantity) {
+        Some(raw) => parse_price(&raw)
+            .ok_or_else(|| format!("Invalid wholesale quantity '{}'", raw))
+            .map(|quantity| Some(quantity).filter(|quantity| *quantity > 0.0))?,
+        None => None,
+    };
+
+    Ok(ImportedProduct {
+        line,
+        // Catalogs with a single code column use it for both, like the original import did
+        plu_code: match columns.plu_code {
+            Some(index) => cell(index),
+            None => Some(sku.clone()),
+        },
+        sku,
+        barcode: optional(columns.barcode),
+        product_name,
+        price,
+        wholesale_price,
+        wholesale_min_quantity: wholesale_price.and(wholesale_min_quantity),
+        department: optional(columns.department),
+        category: optional(columns.category),
+    })
+}
+
+/// Current state of a product, for diffing against the file.
+#[derive(sqlx::FromRow)]
+struct ExistingProduct {
+    sku: String,
+    plu_code: Option<String>,
+    product_name: String,
+    price: f64,
+    category_name: Option<String>,
+    wholesale_price: Option<f64>,
+    wholesale_min_quantity: Option<f64>,
+}
+
+async fn plan_import(
+    pool: &Pool<Sqlite>,
+    table: &Table,
+    mapping: ColumnMapping,
+) -> Result<ImportPreview, String> {
+    let mapping = mapping.detect(&table.headers);
+    let columns = MappedColumns::resolve(&mapping, &table.headers)?;
+
+    let existing: HashMap<String, ExistingProduct> = sqlx::query_as::<_, ExistingProduct>(
+        "SELECT p.sku, p.plu_code, p.product_name, CAST(p.price AS REAL) AS price,
+                c.name AS category_name,
+                CAST(pp.price AS REAL) AS wholesale_price,
+                CAST(pp.min_quantity AS REAL) AS wholesale_min_quantity
+         FROM products p
+         LEFT JOIN categories c ON c.id = p.category_id
+         LEFT JOIN product_prices pp ON pp.sku = p.sku AND pp.tier = ?",
+    )
+    .bind(WHOLESALE_TIER)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())?
+    .into_iter()
+    .map(|product| (product.sku.clone(), product))
+    .collect();
+
+    let barcode_owners: HashMap<String, String> =
+        sqlx::query_as::<_, (String, String)>("SELECT barcode, sku FROM product_barcodes")
+            .fetch_all(pool)
+            .await
+            .map_err(|e| e.to_string())?
+            .into_iter()
+            .collect();
+
+    let mut preview = ImportPreview {
+        id: 0,
+        source: table.source.clone(),
+        headers: table.headers.clone(),
+        mapping,
+        new_products: Vec::new(),
+        changed_products: Vec::new(),
+        unchanged_count: 0,
+        errors: Vec::new(),
+        warnings: Vec::new(),
+    };
+    let mut seen_skus = HashSet::new();
+
+    for (line, values) in &table.rows {
+        let product = match parse_row(&columns, *line, values) {
+            Ok(product) => product,
+            Err(message) => {
+                preview.errors.push(LineError {
+                    line: *line,
+                    message,
+                });
+                continue;
+            }
+        };
+        if !seen_skus.insert(product.sku.clone()) {
+            preview.errors.push(LineError {
+                line: *line,
+                message: format!("Duplicate SKU '{}' in the file", product.sku),
+            });
+            continue;
+        }
+
+        let mut new_barcode = false;
+        if let Some(barcode) = &product.barcode {
+            match barcode_owners.get(barcode) {
+                None => new_barcode = true,
+                Some(owner) if owner != &product.sku => preview.warnings.push(LineError {
+                    line: *line,
+                    message: format!(
+                        "Barcode '{}' already belongs to SKU '{}' and will not be added",
+                        barcode, owner
+                    ),
+                }),
+                Some(_) => {}
+            }
+        }
+
+        let Some(current) = existing.get(&product.sku) else {
+            preview.new_products.push(product);
+            continue;
+        };
+
+        let mut changes = Vec::new();
+        if current.product_name != product.product_name {
+            changes.push(FieldChange {
+                field: "product_name",
+                before: current.product_name.clone(),
+                after: product.product_name.clone(),
+            });
+        }
+        if (current.price - product.price).abs() >= 0.005 {
+            changes.push(FieldChange {
+                field: "price",
+                before: format!("{:.2}", current.price),
+                after: format!("{:.2}", product.price),
+            });
+        }
+        if current.plu_code != product.plu_code {
+            changes.push(FieldChange {
+                field: "plu_code",
+                before: current.plu_code.clone().unwrap_or_default(),
+                after: product.plu_code.clone().unwrap_or_default(),
+            });
+        }
+        if let Some(wholesale_price) = product.wholesale_price {
+            let differs = match current.wholesale_price {
+                Some(current_price) => (current_price - wholesale_price).abs() >= 0.005,
+                None => true,
+            };
+            if differs || current.wholesale_min_quantity != product.wholesale_min_quantity {
+                changes.push(FieldChange {
+                    field: "wholesale_price",
+                    before: current
+                        .wholesale_price
+                        .map(|price| format!("{:.2}", price))
+                        .unwrap_or_default(),
+                    after: format!("{:.2}", wholesale_price),
+                });
+            }
+        }
+        if let Some(name) = product.category_name() {
+            let current_name = current.category_name.clone().unwrap_or_default();
+            if normalize_header(&current_name) != normalize_header(&name) {
+                changes.push(FieldChange {
+                    field: "category",
+                    before: current_name,
+                    after: name,
+                });
+            }
+        }
+        if new_barcode {
+            changes.push(FieldChange {
+                field: "barcode",
+                before: String::new(),
+                after: product.barcode.clone().unwrap_or_default(),
+            });
+        }
+
+        if changes.is_empty() {
+            preview.unchanged_count += 1;
+        } else {
+            preview
+                .changed_products
+                .push(ProductChange { product, changes });
+        }
+    }
+
+    Ok(preview)
+}
+
+/// Applies a planned import in a single transaction.
+/// Existing products keep their `created_at` and, when the file has no
+/// category for them, their category. Barcodes are merged, never replaced.
+/// Price changes go to the price history, and the import itself to the
+/// audit log.
+async fn apply_import(
+    pool: &Pool<Sqlite>,
+    preview: &ImportPreview,
+    user: &SessionUser,
+    source: &str,
+) -> Result<ImportSummary, String> {
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    let mut barcodes_added = 0;
+
+    let products = preview.new_products.iter().chain(
+        preview
+            .changed_products
+            .iter()
+            .map(|change| &change.product),
+    );
+    for product in products {
+        let department_id = match &product.department {
+            Some(label) => categories::get_or_create_category(&mut tx, label, None)
+                .await
+                .map_err(|e| format!("Line {}: {}", product.line, e))?,
+            None => None,
+        };
+        let category_id = match &product.category {
+            Some(label) => categories::get_or_create_category(&mut tx, label, department_id)
+                .await
+                .map_err(|e| format!("Line {}: {}", product.line, e))?,
+            None => department_id,
+        };
+
+        let old_price = prices::current_price(&mut tx, &product.sku, None).await?;
+        let old_wholesale_price =
+            prices::current_price(&mut tx, &product.sku, Some(WHOLESALE_TIER)).await?;
+
+        sqlx::query(
+            "INSERT INTO products (sku, plu_code, product_name, price, category_id)
+             VALUES (?, ?, ?, ?, ?)
+             ON CONFLICT(sku) DO UPDATE SET
+                 plu_code = excluded.plu_code,
+                 product_name = excluded.product_name,
+                 price = excluded.price,
+                 category_id = IFNULL(excluded.category_id, products.category_id),
+                 updated_at = CURRENT_TIMESTAMP",
+        )
+        .bind(&product.sku)
+        .bind(&product.plu_code)
+        .bind(&product.product_name)
+        .bind(product.price)
+        .bind(category_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Line {}: {}", product.line, e))?;
+
+        if let Some(wholesale_price) = product.wholesale_price {
+            sqlx::query(
+                "INSERT INTO product_prices (sku, tier, price, min_quantity)
+                 VALUES (?, ?, ?, ?)
+                 ON CONFLICT(sku, tier) DO UPDATE SET
+                     price = excluded.price,
+                     min_quantity = excluded.min_quantity,
+                     updated_at = CURRENT_TIMESTAMP",
+            )
+            .bind(&product.sku)
+            .bind(WHOLESALE_TIER)
+            .bind(wholesale_price)
+            .bind(product.wholesale_min_quantity)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| format!("Line {}: {}", product.line, e))?;
+        }
+
+        // New products have no price to change from
+        if old_price.is_some() {
+            let changes = [
+                (None, old_price, Some(product.price)),
+                (
+                    Some(WHOLESALE_TIER),
+                    old_wholesale_price,
+                    product.wholesale_price,
+                ),
+            ];
+            for (tier, old, new) in changes {
+                let Some(new) = new else { continue };
+                if old.is_some_and(|old| (old - new).abs() < 0.005) {
+                    continue;
+                }
+                prices::record_change(&mut tx, &product.sku, tier, old, new, Some(user), "import")
+                    .await?;
+            }
+        }
+
+        if let Some(barcode) = &product.barcode {
+            if barcodes::merge_barcode(&mut *tx, &product.sku, barcode, 1.0)
+                .await
+                .map_err(|e| format!("Line {}: {}", product.line, e))?
+            {
+                barcodes_added += 1;
+            }
+        }
+    }
+
+    let summary = ImportSummary {
+        inserted: preview.new_products.len(),
+        updated: preview.changed_products.len(),
+        unchanged: preview.unchanged_count,
+        skipped: preview.errors.len(),
+        barcodes_added,
+    };
+    audit::record(
+        &mut tx,
+        Some(user),
+        "import_catalog",
+        "catalog",
+        Some(source),
+        None,
+        Some(json!(summary)),
+    )
+    .await?;
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+    Ok(summary)
+}
+
+/// Reads an import file; workbooks read `sheet` (or their first sheet),
+/// anything else is treated as delimited text.
+fn load_table(path: &Path, sheet: Option<&str>) -> Result<Table, String> {
+    if spreadsheet::is_spreadsheet(path) {
+        return spreadsheet::read_table(path, sheet);
+    }
+    let bytes =
+        std::fs::read(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    Ok(delimited::read_table(&bytes))
+}
+
+/// Builds the mapping for an import: the preset's columns first, then any
+/// explicit overrides from `mapping`; `plan_import` detects the rest.
+fn import_mapping(
+    table: &Table,
+    preset: Option<&str>,
+    mapping: Option<ColumnMapping>,
+) -> Result<ColumnMapping, String> {
+    let mut mapping = mapping.unwrap_or_default();
+    let Some(preset) = preset else {
+        return Ok(mapping);
+    };
+    let preset = presets::find_preset(preset)?;
+    let mut merged = preset.mapping(&table.headers);
+    for ((merged, _), (explicit, _)) in merged.fields_mut().into_iter().zip(mapping.fields_mut()) {
+        if explicit.is_some() {
+            *merged = explicit.take();
+        }
+    }
+    if merged.sku.is_none() || merged.product_name.is_none() {
+        return Err(format!(
+            "The file does not have the columns of a {} export ({})",
+            preset.name, preset.description
+        ));
+    }
+    Ok(merged)
+}
+
+/// Import state of the station: the file picked from the Import menu once
+/// the admin password was given, which is the only one the import
+/// commands read, and its last preview, kept so an import writes exactly
+/// what the user reviewed even if the file changed since.
+#[derive(Default)]
+pub struct PendingImport(Mutex<PendingState>);
+
+#[derive(Default)]
+struct PendingState {
+    file: Option<String>,
+    preview: Option<ImportPreview>,
+    last_preview_id: u64,
+}
+
+/// Allows the import commands to read `path`, replacing any earlier file
+/// and its preview. Call it only after the admin password was verified.
+pub fn allow_file(app_handle: &AppHandle, path: String) -> Result<(), String> {
+    let pending = app_handle.state::<PendingImport>();
+    let mut pending = pending.0.lock().map_err(|e| e.to_string())?;
+    pending.file = Some(path);
+    pending.preview = None;
+    Ok(())
+}
+
+fn check_file(app_handle: &AppHandle, path: &str) -> Result<(), String> {
+    let pending = app_handle.state::<PendingImport>();
+    let pending = pending.0.lock().map_err(|e| e.to_string())?;
+    if pending.file.as_deref() != Some(path) {
+        return Err("Choose the file to import from the Import menu".to_string());
+    }
+    Ok(())
+}
+
+/// Catalog formats of other POS systems the import understands.
+#[tauri::command]
+pub fn get_import_presets() -> Vec<&'static ImportPreset> {
+    presets::PRESETS.iter().collect()
+}
+
+/// Sheets of a workbook to choose from; empty for CSV and other text files.
+#[tauri::command]
+pub fn get_import_sheets(app_handle: AppHandle, path: String) -> Result<Vec<String>, String> {
+    users::require(&app_handle, Permission::Import)?;
+    check_file(&app_handle, &path)?;
+    let path = Path::new(&path);
+    if spreadsheet::is_spreadsheet(path) {
+        spreadsheet::sheet_names(path)
+    } else {
+        Ok(Vec::new())
+    }
+}
+
+/// Parses the picked import file and returns the dry-run diff without
+/// touching the database.
+#[tauri::command]
+pub async fn preview_product_import(
+    app_handle: AppHandle,
+    path: String,
+    sheet: Option<String>,
+    preset: Option<String>,
+    mapping: Option<ColumnMapping>,
+) -> Result<ImportPreview, String> {
+    users::require(&app_handle, Permission::Import)?;
+    check_file(&app_handle, &path)?;
+    let table = load_table(Path::new(&path), sheet.as_deref())?;
+    let mapping = import_mapping(&table, preset.as_deref(), mapping)?;
+    let pool = db::pool(&app_handle).await?;
+    let mut preview = plan_import(&pool, &table, mapping).await?;
+
+    let pending = app_handle.state::<PendingImport>();
+    let mut pending = pending.0.lock().map_err(|e| e.to_string())?;
+    if pending.file.as_deref() != Some(path.as_str()) {
+        return Err("Another file was chosen while this one was read".to_string());
+    }
+    pending.last_preview_id += 1;
+    preview.id = pending.last_preview_id;
+    pending.preview = Some(preview.clone());
+    Ok(preview)
+}
+
+/// Imports the preview `preview_id` as it was shown, without reading the
+/// file again. Lines with errors are skipped; everything else is written
+/// in one transaction. Fails when a newer preview replaced it.
+#[tauri::command]
+pub async fn commit_product_import(
+    app_handle: AppHandle,
+    preview_id: u64,
+) -> Result<ImportSummary, String> {
+    let user = users::require(&app_handle, Permission::Import)?;
+    let (path, preview) = {
+        let pending = app_handle.state::<PendingImport>();
+        let mut pending = pending.0.lock().map_err(|e| e.to_string())?;
+        match (pending.file.clone(), pending.preview.take()) {
+            (Some(path), Some(preview)) if preview.id == preview_id => (path, preview),
+            (_, preview) => {
+                pending.preview = preview;
+                return Err("The import preview is out of date, preview the file again".to_string());
+            }
+        }
+    };
+    let pool = db::pool(&app_handle).await?;
+    let summary = apply_import(&pool, &preview, &user, &path).await?;
+    costs::notify_below_cost(&app_handle, &pool).await;
+    Ok(summary)
+}
@@ -0,0 +1,110 @@
+use serde::Serialize;
+
+use super::{find_header, ColumnMapping};
+
+/// Header names a preset looks for, per field, in order of preference.
+/// Names are compared normalized, so accents and case do not matter.
+#[derive(Debug)]
+struct PresetColumns {
+    sku: &'static [&'static str],
+    plu_code: &'static [&'static str],
+    barcode: &'static [&'static str],
+    product_name: &'static [&'static str],
+    price: &'static [&'static str],
+    wholesale_price: &'static [&'static str],
+    wholesale_min_quantity: &'static [&'static str],
+    department: &'static [&'static str],
+    category: &'static [&'static str],
+}
+
+/// Column layout of a catalog exported by another POS system.
+#[derive(Debug, Serialize)]
+pub struct ImportPreset {
+    pub id: &'static str,
+    pub name: &'static str,
+    pub description: &'static str,
+    #[serde(skip)]
+    columns: PresetColumns,
+}
+
+impl ImportPreset {
+    /// Mapping for a file in this format. Fields the file does not have stay
+    /// unset and are left to the regular header detection.
+    pub fn mapping(&self, headers: &[String]) -> ColumnMapping {
+        let columns = &self.columns;
+        ColumnMapping {
+            sku: find_header(headers, columns.sku),
+            plu_code: find_header(headers, columns.plu_code),
+            barcode: find_header(headers, columns.barcode),
+            product_name: find_header(headers, columns.product_name),
+            price: find_header(headers, columns.price),
+            wholesale_price: find_header(headers, columns.wholesale_price),
+            wholesale_min_quantity: find_header(headers, columns.wholesale_min_quantity),
+            department: find_header(headers, columns.department),
+            category: find_header(headers, columns.category),
+        }
+    }
+}
+
+pub const PRESETS: &[ImportPreset] = &[
+    ImportPreset {
+        id: "eleventa",
+        name: "eleventa",
+        description: "Productos > Exportar (Excel o CSV)",
+        columns: PresetColumns {
+            sku: &["codigo", "codigo de barras"],
+            plu_code: &[],
+            barcode: &[],
+            product_name: &["descripcion", "producto"],
+            price: &[
+                "precio venta",
+                "p. venta",
+                "precio de venta",
+                "precio publico",
+            ],
+            wholesale_price: &["precio mayoreo", "p. mayoreo", "mayoreo"],
+            wholesale_min_quantity: &[],
+            department: &["departamento", "depto", "depto."],
+            category: &[],
+        },
+    },
+    ImportPreset {
+        id: "sicar",
+        name: "SICAR",
+        description: "Catálogo de artículos exportado a Excel",
+        columns: PresetColumns {
+            sku: &["clave"],
+            plu_code: &[],
+            barcode: &["clave alterna", "codigo de barras"],
+            product_name: &["descripcion"],
+            price: &["precio 1", "precio1"],
+            wholesale_price: &["precio 2", "precio2"],
+            wholesale_min_quantity: &["mayoreo 2", "cantidad 2", "cant. mayoreo 2"],
+            department: &["departamento"],
+            category: &["categoria"],
+        },
+    },
+    ImportPreset {
+        id: "mybusiness",
+        name: "MyBusiness POS",
+        description: "Exportación de la tabla de artículos (prods)",
+        columns: PresetColumns {
+            sku: &["articulo"],
+            plu_code: &[],
+            barcode: &["codigo de barras", "codigobarras"],
+            product_name: &["descrip", "descripcion"],
+            price: &["precio1", "precio 1"],
+            wholesale_price: &["precio2", "precio 2"],
+            wholesale_min_quantity: &["cantidad2", "cantidad 2"],
+            department: &["linea"],
+            category: &[],
+        },
+    },
+];
+
+pub fn find_preset(id: &str) -> Result<&'static ImportPreset, String> {
+    PRESETS
+        .iter()
+        .find(|preset| preset.id == id)
+        .ok_or_else(|| format!("Unknown import preset '{}'", id))
+}
@@ -0,0 +1,73 @@
+use calamine::{open_workbook_auto, Data, Reader};
+use std::path::Path;
+
+use super::Table;
+
+/// File extensions read as workbooks instead of delimited text.
+pub const EXTENSIONS: &[&str] = &["xlsx", "xlsm", "xlsb", "xls", "ods"];
+
+pub fn is_spreadsheet(path: &Path) -> bool {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .map(|extension| EXTENSIONS.contains(&extension.to_lowercase().as_str()))
+        .unwrap_or(false)
+}
+
+pub fn sheet_names(path: &Path) -> Result<Vec<String>, String> {
+    let workbook = open_workbook_auto(path).map_err(|e| e.to_string())?;
+    Ok(workbook.sheet_names())
+}
+
+/// Spreadsheet cells as the text an import would see in a CSV export.
+/// Whole-number floats drop the ".0", so codes typed as numbers
+/// (e.g. barcode 7501234567890) stay intact.
+fn cell_to_string(cell: &Data) -> String {
+    match cell {
+        Data::Float(value) if value.fract() == 0.0 && value.abs() < 1e15 => {
+            format!("{}", *value as i64)
+        }
+        other => other.to_string().trim().to_string(),
+    }
+}
+
+/// Reads one sheet (the first one when `sheet` is None) into a table.
+/// The first non-empty row is the header; row numbers match the ones
+/// shown by Excel/LibreOffice.
+pub fn read_table(path: &Path, sheet: Option<&str>) -> Result<Table, String> {
+    let mut workbook = open_workbook_auto(path).map_err(|e| e.to_string())?;
+    let sheet = match sheet {
+        Some(sheet) => sheet.to_string(),
+        None => workbook
+            .sheet_names()
+            .first()
+            .cloned()
+            .ok_or("The workbook has no sheets")?,
+    };
+    let range = workbook
+        .worksheet_range(&sheet)
+        .map_err(|e| format!("Failed to read sheet '{}': {}", sheet, e))?;
+    let first_row = range.start().map(|(row, _)| row as usize).unwrap_or(0);
+
+    let mut rows = range
+        .rows()
+        .enumerate()
+        .map(|(index, cells)| {
+            (
+                first_row + index + 1,
+                cells.iter().map(cell_to_string).collect::<Vec<_>>(),
+            )
+        })
+        .filter(|(_, values)| values.iter().any(|value| !value.is_empty()));
+
+    let headers = rows.next().map(|(_, headers)| headers).unwrap_or_default();
+
+    Ok(Table {
+        headers,
+        rows: rows.collect(),
+        source: format!(
+            "{} ({})",
+            sheet,
+            path.extension().and_then(|e| e.to_str()).unwrap_or("")
+        ),
+    })
+}
@@ -0,0 +1,283 @@
+use crate::audit;
+use crate::db;
+use crate::users::{self, Permission, SessionUser};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sqlx::SqliteConnection;
+use tauri::AppHandle;
+
+const DEFAULT_MOVEMENT_LIMIT: i64 = 200;
+
+/// Why stock changed. Stored in `stock_movements.kind`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MovementKind {
+    Sale,
+    Return,
+    Receipt,
+    Adjustment,
+    Waste,
+    Transfer,
+    /// Finished goods made in a production batch.
+    Production,
+    /// Raw materials used by a production batch or a sale of a recipe.
+    Consumption,
+}
+
+impl MovementKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            MovementKind::Sale => "sale",
+            MovementKind::Return => "return",
+            MovementKind::Receipt => "receipt",
+            MovementKind::Adjustment => "adjustment",
+            MovementKind::Waste => "waste",
+            MovementKind::Transfer => "transfer",
+            MovementKind::Production => "production",
+            MovementKind::Consumption => "consumption",
+        }
+    }
+}
+
+impl std::str::FromStr for MovementKind {
+    type Err = String;
+
+    fn from_str(kind: &str) -> Result<Self, Self::Err> {
+        match kind {
+            "sale" => Ok(MovementKind::Sale),
+            "return" => Ok(MovementKind::Return),
+            "receipt" => Ok(MovementKind::Receipt),
+            "adjustment" => Ok(MovementKind::Adjustment),
+            "waste" => Ok(MovementKind::Waste),
+            "transfer" => Ok(MovementKind::Transfer),
+            "production" => Ok(MovementKind::Production),
+            "consumption" => Ok(MovementKind::Consumption),
+            other => Err(format!("Unknown stock movement kind {}", other)),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct StockLevel {
+    pub sku: String,
+    pub product_name: String,
+    pub track_stock: bool,
+    pub quantity: f64,
+    pub min_stock: Option<f64>,
+    pub max_stock: Option<f64>,
+    pub supplier_id: Option<i64>,
+    pub updated_at: Option<String>,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct StockMovement {
+    pub id: i64,
+    pub sku: String,
+    pub kind: String,
+    pub quantity: f64,
+    pub balance: f64,
+    pub reference: Option<String>,
+    pub reason: Option<String>,
+    pub user_name: Option<String>,
+    pub created_at: String,
+}
+
+async fn is_tracked(conn: &mut SqliteConnection, sku: &str) -> Result<bool, String> {
+    sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM products WHERE sku = ? AND track_stock)")
+        .bind(sku)
+        .fetch_one(conn)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Adds a movement of `quantity` (negative when stock goes out) to the
+/// ledger and the stock level. Products that do not track stock are left
+/// alone; returns the new stock, or None for those.
+pub async fn record_movement(
+    conn: &mut SqliteConnection,
+    sku: &str,
+    kind: MovementKind,
+    quantity: f64,
+    reference: Option<&str>,
+    reason: Option<&str>,
+    user: Option<&SessionUser>,
+) -> Result<Option<f64>, String> {
+    if !is_tracked(conn, sku).await? {
+        return Ok(None);
+    }
+    let balance: f64 = sqlx::query_scalar(
+        "INSERT INTO stock_levels (sku, quantity) VALUES (?1, ?2)
+         ON CONFLICT(sku) DO UPDATE SET
+             quantity = stock_levels.quantity + excluded.quantity,
+             updated_at = CURRENT_TIMESTAMP
+         RETURNING CAST(quantity AS REAL)",
+    )
+    .bind(sku)
+    .bind(quantity)
+    .fetch_one(&mut *conn)
+    .await
+    .map_err(|e| e.to_string())?;
+    sqlx::query(
+        "INSERT INTO stock_movements (sku, kind, quantity, balance, reference, reason, user_id)
+         VALUES (?, ?, ?, ?, ?, ?, ?)",
+    )
+    .bind(sku)
+    .bind(kind.as_str())
+    .bind(quantity)
+    .bind(balance)
+    .bind(reference)
+    .bind(reason)
+    .bind(user.map(|user| user.id))
+    .execute(&mut *conn)
+    .await
+    .map_err(|e| e.to_string())?;
+    Ok(Some(balance))
+}
+
+/// Stock of the products that track it, or of every product with `all`.
+#[tauri::command]
+pub async fn get_stock_levels(
+    app_handle: AppHandle,
+    all: Option<bool>,
+) -> Result<Vec<StockLevel>, String> {
+    let pool = db::pool(&app_handle).await?;
+    sqlx::query_as::<_, StockLevel>(
+        "SELECT p.sku, p.product_name, p.track_stock,
+                CAST(IFNULL(s.quantity, 0) AS REAL) AS quantity,
+                CAST(p.min_stock AS REAL) AS min_stock,
+                CAST(p.max_stock AS REAL) AS max_stock,
+                p.supplier_id,
+                datetime(s.updated_at, 'localtime') AS updated_at
+         FROM products p
+         LEFT JOIN stock_levels s ON s.sku = p.sku
+         WHERE p.track_stock OR ?
+         ORDER BY p.product_name",
+    )
+    .bind(all.unwrap_or(false))
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_stock(app_handle: AppHandle, sku: String) -> Result<StockLevel, String> {
+    let pool = db::pool(&app_handle).await?;
+    sqlx::query_as::<_, StockLevel>(
+        "SELECT p.sku, p.product_name, p.track_stock,
+                CAST(IFNULL(s.quantity, 0) AS REAL) AS quantity,
+                CAST(p.min_stock AS REAL) AS min_stock,
+                CAST(p.max_stock AS REAL) AS max_stock,
+                p.supplier_id,
+                datetime(s.updated_at, 'localtime') AS updated_at
+         FROM products p
+         LEFT JOIN stock_levels s ON s.sku = p.sku
+         WHERE p.sku = ?",
+    )
+    .bind(&sku)
+    .fetch_optional(&pool)
+    .await
+    .map_err(|e| e.to_string())?
+    .ok_or_else(|| format!("Unknown product {}", sku))
+}
+
+/// Movements of a product, newest first.
+#[tauri::command]
+pub async fn get_stock_movements(
+    app_handle: AppHandle,
+    sku: String,
+    limit: Option<i64>,
+) -> Result<Vec<StockMovement>, String> {
+    let pool = db::pool(&app_handle).await?;
+    sqlx::query_as::<_, StockMovement>(
+        "SELECT m.id, m.sku, m.kind, CAST(m.quantity AS REAL) AS quantity,
+                CAST(m.balance AS REAL) AS balance, m.reference, m.reason,
+                u.display_name AS user_name,
+                datetime(m.created_at, 'localtime') AS created_at
+         FROM stock_movements m
+         LEFT JOIN users u ON u.id = m.user_id
+         WHERE m.sku = ?
+         ORDER BY m.id DESC
+         LIMIT ?",
+    )
+    .bind(sku)
+    .bind(limit.unwrap_or(DEFAULT_MOVEMENT_LIMIT))
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| e.to_string())
+}
+
+/// Turns stock tracking on or off for a product.
+#[tauri::command]
+pub async fn set_stock_tracking(
+    app_handle: AppHandle,
+    sku: String,
+    track_stock: bool,
+) -> Result<(), String> {
+    let user = users::require(&app_handle, Permission::ManageInventory)?;
+    let pool = db::pool(&app_handle).await?;
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    let updated = sqlx::query("UPDATE products SET track_stock = ? WHERE sku = ?")
+        .bind(track_stock)
+        .bind(&sku)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| e.to_string())?;
+    if updated.rows_affected() == 0 {
+        return Err(format!("Unknown product {}", sku));
+    }
+    audit::record(
+        &mut tx,
+        Some(&user),
+        "set_stock_tracking",
+        "product",
+        Some(&sku),
+        None,
+        Some(json!({ "track_stock": track_stock })),
+    )
+    .await?;
+    tx.commit().await.map_err(|e| e.to_string())
+}
+
+/// Corrects the stock of a product by `quantity` (negative to take out).
+/// Returns the new stock.
+#[tauri::command]
+pub async fn adjust_stock(
+    app_handle: AppHandle,
+    sku: String,
+    quantity: f64,
+    reason: String,
+) -> Result<f64, String> {
+    let user = users::require(&app_handle, Permission::ManageInventory)?;
+    let reason = reason.trim();
+    if reason.is_empty() {
+        return Err("Stock adjustments need a reason".to_string());
+    }
+    if quantity == 0.0 {
+        return Err("The adjustment quantity cannot be zero".to_string());
+    }
+    let pool = db::pool(&app_handle).await?;
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    let balance = record_movement(
+        &mut tx,
+        &sku,
+        MovementKind::Adjustment,
+        quantity,
+        None,
+        Some(reason),
+        Some(&user),
+    )
+    .await?
+    .ok_or_else(|| format!("Product {} does not track stock", sku))?;
+    audit::record(
+        &mut tx,
+        Some(&user),
+        "adjust_stock",
+        "product",
+        Some(&sku),
+        Some(json!({ "quantity": balance - quantity })),
+        Some(json!({ "quantity": balance, "reason": reason })),
+    )
+    .await?;
+    tx.commit().await.map_err(|e| e.to_string())?;
+    Ok(balance)
+}
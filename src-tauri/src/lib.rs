@@ -7,19 +7,42 @@ use tauri::{
     menu::{AboutMetadata, MenuBuilder, MenuItemBuilder, SubmenuBuilder},
     AppHandle, Emitter, Manager,
 };
-use tauri_plugin_dialog::DialogExt;
-use tauri_plugin_fs::FsExt;
+use tauri_plugin_dialog::{DialogExt, MessageDialogKind};
 use tauri_plugin_opener::OpenerExt;
-use tauri_plugin_sql::{Builder, DbInstances, DbPool, Migration, MigrationKind};
 //use tinyfiledialogs as tfd;
-use sqlx::{pool, Pool, Row, Sqlite};
+use sqlx::{pool, Pool, Sqlite};
 use std::hash::{Hash, Hasher, SipHasher};
 use tfd;
 
-const CORRECT_IMPORT_PASSWORD: &str = "harina123"; // CHANGE THIS!
-
+pub mod admin;
+pub mod approvals;
+pub mod audit;
+pub mod barcodes;
+pub mod cash_counts;
+pub mod cash_movements;
+pub mod cash_sessions;
+pub mod categories;
+pub mod costs;
+pub mod db;
+pub mod export;
+pub mod import;
+pub mod inventory;
+pub mod prices;
+pub mod purchases;
+pub mod recipes;
+pub mod reorder;
+pub mod reports;
+pub mod returns;
+pub mod sales;
+pub mod search;
+pub mod settings;
+pub mod stock_counts;
+pub mod suppliers;
 pub mod ticket_printer;
 pub mod types;
+pub mod users;
+pub mod voids;
+pub mod waste;
 use escpos::driver::*;
 
 use types::ticket;
@@ -58,6 +81,133 @@ fn print_ticket(ticket_data: ticket, vid: u16, pid: u16, printer_name: String) -
     "Printed successfully".to_string()
 }
 
+/// Opens the cash drawer wired to the configured thermal printer and
+/// records who opened it.
+#[tauri::command]
+async fn open_cash_drawer(app_handle: AppHandle) -> Result<(), String> {
+    let user = users::require(&app_handle, users::Permission::OpenDrawer)?;
+    let pool = db::pool(&app_handle).await?;
+    ticket_printer::with_thermal_printer(&pool, ticket_printer::open_cash_drawer).await?;
+    audit::log(
+        &pool,
+        Some(&user),
+        "open_drawer",
+        "drawer",
+        None,
+        None,
+        None,
+    )
+    .await
+}
+
+/// Prints a purchase order on the configured thermal printer.
+#[tauri::command]
+async fn print_purchase_order(app_handle: AppHandle, id: i64) -> Result<(), String> {
+    users::require(&app_handle, users::Permission::ManageInventory)?;
+    let pool = db::pool(&app_handle).await?;
+    let detail = purchases::load_order(&pool, id).await?;
+    ticket_printer::with_thermal_printer(&pool, |printer| {
+        ticket_printer::print_purchase_order(printer, &detail)
+    })
+    .await
+}
+
+/// Prints the waste report of a day on the configured thermal printer.
+#[tauri::command]
+async fn print_waste_report(app_handle: AppHandle, date: String) -> Result<(), String> {
+    users::require(&app_handle, users::Permission::ViewReports)?;
+    let pool = db::pool(&app_handle).await?;
+    let report = waste::waste_report(&pool, &date).await?;
+    ticket_printer::with_thermal_printer(&pool, |printer| {
+        ticket_printer::print_waste_report(printer, &report)
+    })
+    .await
+}
+
+/// Prints the blind sheet of a stock count on the configured thermal printer.
+#[tauri::command]
+async fn print_count_sheet(app_handle: AppHandle, id: i64) -> Result<(), String> {
+    users::require(&app_handle, users::Permission::ManageInventory)?;
+    let pool = db::pool(&app_handle).await?;
+    let detail = stock_counts::load_count(&pool, id).await?;
+    ticket_printer::with_thermal_printer(&pool, |printer| {
+        ticket_printer::print_count_sheet(printer, &detail)
+    })
+    .await
+}
+
+/// Prints the variances of a stock count on the configured thermal printer.
+#[tauri::command]
+async fn print_count_variances(app_handle: AppHandle, id: i64) -> Result<(), String> {
+    users::require(&app_handle, users::Permission::ManageInventory)?;
+    let pool = db::pool(&app_handle).await?;
+    let detail = stock_counts::load_count(&pool, id).await?;
+    ticket_printer::with_thermal_printer(&pool, |printer| {
+        ticket_printer::print_count_variances(printer, &detail)
+    })
+    .await
+}
+
+/// Prints the receipt of a return on the configured thermal printer.
+#[tauri::command]
+async fn print_return(app_handle: AppHandle, id: String) -> Result<(), String> {
+    users::require(&app_handle, users::Permission::Sell)?;
+    let pool = db::pool(&app_handle).await?;
+    let receipt = returns::load_return(&pool, &id).await?;
+    ticket_printer::with_thermal_printer(&pool, |printer| {
+        ticket_printer::print_return(printer, &receipt)
+    })
+    .await
+}
+
+/// Prints the slip of a voided ticket on the configured thermal printer.
+#[tauri::command]
+async fn print_void_slip(app_handle: AppHandle, id: String) -> Result<(), String> {
+    users::require(&app_handle, users::Permission::Sell)?;
+    let pool = db::pool(&app_handle).await?;
+    let slip = voids::load_void_slip(&pool, &id).await?;
+    ticket_printer::with_thermal_printer(&pool, |printer| {
+        ticket_printer::print_void_slip(printer, &slip)
+    })
+    .await
+}
+
+/// Prints the close report of a cash session on the configured thermal printer.
+#[tauri::command]
+async fn print_cash_session_report(app_handle: AppHandle, id: i64) -> Result<(), String> {
+    let user = users::require(&app_handle, users::Permission::Sell)?;
+    let pool = db::pool(&app_handle).await?;
+    let report = cash_sessions::load_report(&pool, &user, id).await?;
+    ticket_printer::with_thermal_printer(&pool, |printer| {
+        ticket_printer::print_cash_session_report(printer, &report)
+    })
+    .await
+}
+
+/// Prints the slip of a cash movement on the configured thermal printer.
+#[tauri::command]
+async fn print_cash_movement(app_handle: AppHandle, id: i64) -> Result<(), String> {
+    users::require(&app_handle, users::Permission::Sell)?;
+    let pool = db::pool(&app_handle).await?;
+    let movement = cash_movements::load_cash_movement(&pool, id).await?;
+    ticket_printer::with_thermal_printer(&pool, |printer| {
+        ticket_printer::print_cash_movement(printer, &movement)
+    })
+    .await
+}
+
+/// Prints the slip of a count by denomination on the configured thermal printer.
+#[tauri::command]
+async fn print_cash_count(app_handle: AppHandle, id: i64) -> Result<(), String> {
+    users::require(&app_handle, users::Permission::Sell)?;
+    let pool = db::pool(&app_handle).await?;
+    let count = cash_counts::load_count(&pool, id).await?;
+    ticket_printer::with_thermal_printer(&pool, |printer| {
+        ticket_printer::print_cash_count(printer, &count)
+    })
+    .await
+}
+
 #[cfg(windows)]
 fn generate_vid_pid(printer: &WindowsPrinter) -> (u16, u16) {
     let mut hasher = SipHasher::new();
@@ -182,31 +332,181 @@ fn get_printers() -> Result<Vec<UsbDevice>, String> {
         Ok(devices_info)
     }
 }
-async fn get_database_settings_table_password_key_value(
-    app_handle: &AppHandle,
-) -> Result<String, String> {
-    let instances = app_handle.state::<DbInstances>();
-    let instances = instances.0.read().await;
-
-    let target_db = "sqlite:pos_demo.db";
-    let db_pool = instances
-        .get(target_db)
-        .ok_or_else(|| format!("Database instance '{}' not found.", target_db))?;
-
-    let DbPool::Sqlite(pool) = db_pool;
-    let query = "SELECT value FROM settings WHERE key = 'admin_password'";
-    let result = sqlx::query(query)
-        .fetch_one(pool)
-        .await
-        .map_err(|e| e.to_string())?;
-
-    let password: String = result.try_get("value").map_err(|e| e.to_string())?;
-    Ok(password)
+/// Asks for the admin password (through native dialogs, as menu events
+/// have no webview to prompt in) before an admin-only menu action.
+/// When no password has been set yet, the user is asked to create one.
+fn require_admin_password(app_handle: &AppHandle, action: &str) -> bool {
+    let show_error = |message: &str| {
+        tfd::MessageBox::new("Admin Password", message)
+            .with_icon(tfd::MessageBoxIcon::Warning)
+            .run_modal();
+    };
+    let pool = match tauri::async_runtime::block_on(db::pool(app_handle)) {
+        Ok(pool) => pool,
+        Err(e) => {
+            eprintln!("Error opening the database: {}", e);
+            show_error(&e);
+            return false;
+        }
+    };
+
+    match tauri::async_runtime::block_on(admin::is_set(&pool)) {
+        Ok(true) => {
+            let Some(password) = tfd::InputBox::new(
+                "Password Required",
+                &format!("Please enter the admin password to {}:", action),
+            )
+            .password(true)
+            .run_modal() else {
+                return false;
+            };
+            match tauri::async_runtime::block_on(admin::verify(&pool, &password)) {
+                Ok(()) => true,
+                Err(e) => {
+                    show_error(&e);
+                    false
+                }
+            }
+        }
+        Ok(false) => {
+            let Some(password) = tfd::InputBox::new(
+                "Set Admin Password",
+                &format!(
+                    "No admin password is set yet. Enter a new one to {}:",
+                    action
+                ),
+            )
+            .password(true)
+            .run_modal() else {
+                return false;
+            };
+            let Some(repeated) =
+                tfd::InputBox::new("Set Admin Password", "Repeat the new password:")
+                    .password(true)
+                    .run_modal()
+            else {
+                return false;
+            };
+            if password != repeated {
+                show_error("The passwords do not match");
+                return false;
+            }
+            match tauri::async_runtime::block_on(admin::set_password(
+                &pool,
+                users::current_user(app_handle).as_ref(),
+                None,
+                &password,
+            )) {
+                Ok(()) => true,
+                Err(e) => {
+                    show_error(&e);
+                    false
+                }
+            }
+        }
+        Err(e) => {
+            eprintln!("Error reading the admin password: {}", e);
+            show_error(&e);
+            false
+        }
+    }
+}
+
+/// Changes the admin password from the App menu.
+fn change_admin_password(app_handle: &AppHandle) {
+    let Ok(pool) = tauri::async_runtime::block_on(db::pool(app_handle)) else {
+        return;
+    };
+    let current = match tauri::async_runtime::block_on(admin::is_set(&pool)) {
+        Ok(true) => {
+            match tfd::InputBox::new("Change Admin Password", "Current password:")
+                .password(true)
+                .run_modal()
+            {
+                Some(current) => Some(current),
+                None => return,
+            }
+        }
+        _ => None,
+    };
+    let Some(password) = tfd::InputBox::new("Change Admin Password", "New password:")
+        .password(true)
+        .run_modal()
+    else {
+        return;
+    };
+    let Some(repeated) = tfd::InputBox::new("Change Admin Password", "Repeat the new password:")
+        .password(true)
+        .run_modal()
+    else {
+        return;
+    };
+
+    let (message, icon) = if password != repeated {
+        (
+            "The passwords do not match".to_string(),
+            tfd::MessageBoxIcon::Warning,
+        )
+    } else {
+        match tauri::async_runtime::block_on(admin::set_password(
+            &pool,
+            users::current_user(app_handle).as_ref(),
+            current.as_deref(),
+            &password,
+        )) {
+            Ok(()) => (
+                "Admin password changed".to_string(),
+                tfd::MessageBoxIcon::Info,
+            ),
+            Err(e) => (e, tfd::MessageBoxIcon::Warning),
+        }
+    };
+    tfd::MessageBox::new("Change Admin Password", &message)
+        .with_icon(icon)
+        .run_modal();
+}
+
+/// Lets the user pick a product file and hands its path to the frontend,
+/// which previews and commits the import through the `import` commands.
+/// Those only read the file picked here, after the admin password.
+fn pick_import_file(app_handle: &AppHandle, filter_name: &str, extensions: &[&str]) {
+    let app_handle_clone = app_handle.clone();
+    app_handle
+        .dialog()
+        .file()
+        .add_filter(filter_name, extensions)
+        .pick_file(move |file_path| {
+            let Some(path) = file_path else {
+                return;
+            };
+            let allowed = path
+                .into_path()
+                .map_err(|e| e.to_string())
+                .and_then(|path| {
+                    let path = path.to_string_lossy().to_string();
+                    import::allow_file(&app_handle_clone, path.clone())?;
+                    Ok(path)
+                });
+            match allowed {
+                Ok(path) => {
+                    let _ = app_handle_clone.emit("import-csv-selected", path);
+                }
+                Err(e) => {
+                    eprintln!("Failed to resolve import file path: {}", e);
+                    let _ = app_handle_clone.emit("import-csv-error", "Failed to read file");
+                }
+            }
+        });
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+    let mut context = tauri::generate_context!();
+    db::preload_database(&mut context);
+
     tauri::Builder::default()
+        .manage(users::Session::default())
+        .manage(import::PendingImport::default())
         .plugin(tauri_plugin_fs::init())
         .setup(|app| {
             // 0) Print app_dir to console
@@ -231,6 +531,18 @@ pub fn run() {
                 .id("import-csv")
                 .build(app)?;
 
+            let import_spreadsheet = MenuItemBuilder::new("Import Excel/ODS Products")
+                .id("import-spreadsheet")
+                .build(app)?;
+
+            let export_products = MenuItemBuilder::new("Export Products")
+                .id("export-products")
+                .build(app)?;
+
+            let change_password = MenuItemBuilder::new("Change Admin Password")
+                .id("change-admin-password")
+                .build(app)?;
+
             let app_submenu = SubmenuBuilder::new(app, "App")
                 .about(Some(AboutMetadata {
                     ..Default::default()
@@ -245,6 +557,9 @@ pub fn run() {
                 .item(&show_db_folder)
                 .item(&reload_app) // New "Reload" item
                 .item(&import_csv) // New menu item
+                .item(&import_spreadsheet)
+                .item(&export_products)
+                .item(&change_password)
                 .item(&custom_quit)
                 .build()?;
 
@@ -267,7 +582,8 @@ pub fn run() {
                 }
 
                 if event.id() == show_db_folder.id() {
-                    let db_folder = app_handle.path().app_data_dir();
+                    let db_folder = db::database_path(app_handle)
+                        .map(|path| path.parent().map(|dir| dir.to_path_buf()).unwrap_or(path));
                     if let Ok(path) = db_folder {
                         let path_str = path.to_string_lossy().to_string();
                         let _ = app_handle.opener().open_path(path_str, None::<&str>);
@@ -282,106 +598,183 @@ pub fn run() {
                     }
                 }
 
-                if event.id() == import_csv.id() {
-                    println!("'Import CSV' clicked. Requesting password via tfd.");
-
-                    /*
-                    // let import_Password =get_database_settings_table_password_key_value(&app_handle);
-                    let app_handle_clone = app_handle.clone();
-                    let password_result = tauri::async_runtime::block_on(
-                        get_database_settings_table_password_key_value(&app_handle_clone),
-                    );
+                if event.id() == import_csv.id()
+                    && require_admin_password(app_handle, "import products")
+                {
+                    pick_import_file(app_handle, "CSV Files", &["csv", "txt"]);
+                }
 
-                    let db_password = match password_result {
-                        Ok(pwd) => pwd,
-                        Err(e) => {
-                            eprintln!("Error retrieving password: {}", e);
-                            tfd::MessageBox::new("Import Error", "Failed to retrieve password")
-                                .with_icon(tfd::MessageBoxIcon::Error)
-                                .run_modal();
-                            return;
-                        }
-                    };
-                    println!("Password: {}", db_password);
+                if event.id() == import_spreadsheet.id()
+                    && require_admin_password(app_handle, "import products")
+                {
+                    pick_import_file(app_handle, "Spreadsheets", import::SPREADSHEET_EXTENSIONS);
+                }
 
-                    let password_input = tfd::InputBox::new(
-                        "Password Required",
-                        "Please enter the password for CSV import:",
-                    )
-                    .password(true)
-                    .run_modal(); // Corrected to use run_modal()
-
-                    // Guard Clause 1: User cancelled password input
-                    let entered_password = match password_input {
-                        Some(p) => p, // Continues at Indent Level 2
-                        None => {
-                            // Indent Level 3 for this block
-                            println!("Password input cancelled by user.");
-                            return; // Exit this event handling
-                        }
-                    }; // `entered_password` is now available, code flow continues at Indent Level 2
-
-                    // Guard Clause 2: Incorrect password
-                    if entered_password != db_password {
-                        // Indent Level 2
-                        println!("Incorrect password entered.");
-                        println!("Password: {}", db_password);
-                        println!("Entered Password: {}", entered_password);
-                        tfd::MessageBox::new("Import Error", "Incorrect Password")
-                            .with_icon(tfd::MessageBoxIcon::Warning)
-                            .run_modal();
-                        return; // Exit this event handling
-                    }
-                    */
+                if event.id() == change_password.id() {
+                    change_admin_password(app_handle);
+                }
 
+                if event.id() == export_products.id() {
                     let app_handle_clone = app_handle.clone();
                     app_handle
                         .dialog()
                         .file()
+                        .add_filter("Excel", &["xlsx"])
                         .add_filter("CSV Files", &["csv"])
-                        .pick_file(move |file_path| {
-                            if let Some(path) = file_path {
-                                // Read the file content
-                                match app_handle_clone.fs().read_to_string(path.clone()) {
-                                    Ok(csv_content) => {
-                                        // Emit the CSV content to the frontend
-                                        let _ = app_handle_clone
-                                            .emit("import-csv-selected", csv_content);
-                                        println!("CSV file selected: {:?}", path);
-                                    }
-                                    Err(e) => {
-                                        eprintln!("Failed to read CSV file: {}", e);
-                                        let _ = app_handle_clone
-                                            .emit("import-csv-error", "Failed to read CSV file");
-                                    }
-                                }
-                            } else {
-                                println!("No CSV file selected");
-                            }
+                        .set_file_name("productos.xlsx")
+                        .save_file(move |file_path| {
+                            let Some(path) = file_path.and_then(|path| path.into_path().ok())
+                            else {
+                                println!("No export file selected");
+                                return;
+                            };
+                            tauri::async_runtime::spawn(async move {
+                                let (message, kind) =
+                                    match export::export_catalog(&app_handle_clone, &path).await {
+                                        Ok(count) => (
+                                            format!("{} productos exportados", count),
+                                            MessageDialogKind::Info,
+                                        ),
+                                        Err(e) => {
+                                            eprintln!("Failed to export products: {}", e);
+                                            (
+                                                format!("No se pudo exportar: {}", e),
+                                                MessageDialogKind::Error,
+                                            )
+                                        }
+                                    };
+                                app_handle_clone
+                                    .dialog()
+                                    .message(message)
+                                    .title("Exportar productos")
+                                    .kind(kind)
+                                    .show(|_| {});
+                            });
                         });
                 }
             });
 
+            prices::spawn_scheduler(app.handle().clone());
+            reorder::spawn_low_stock_check(app.handle().clone());
+
             Ok(())
         })
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_log::Builder::new().build())
         .plugin(tauri_plugin_opener::init())
-        .plugin(
-            tauri_plugin_sql::Builder::default()
-                .add_migrations(
-                    "sqlite:pos_demo3.db",
-                    vec![Migration {
-                        version: 1,
-                        description: "schema migration",
-                        sql: include_str!("../database/migrations/1_schema.sql"),
-                        kind: MigrationKind::Up,
-                    }],
-                )
-                .build(),
-        )
+        .plugin(db::schema_guard())
+        .plugin(db::sql_plugin())
         .plugin(tauri_plugin_opener::init())
-        .invoke_handler(tauri::generate_handler![get_printers, print_ticket])
-        .run(tauri::generate_context!())
+        .invoke_handler(tauri::generate_handler![
+            get_printers,
+            print_ticket,
+            open_cash_drawer,
+            print_purchase_order,
+            print_waste_report,
+            print_count_sheet,
+            print_count_variances,
+            print_return,
+            print_void_slip,
+            print_cash_session_report,
+            print_cash_movement,
+            print_cash_count,
+            db::get_database_url,
+            settings::get_settings,
+            settings::update_settings,
+            admin::get_admin_status,
+            admin::set_admin_password,
+            admin::verify_admin_password,
+            users::get_login_users,
+            users::get_session,
+            users::login,
+            users::logout,
+            users::get_users,
+            users::get_roles,
+            users::set_role_permissions,
+            users::create_user,
+            users::update_user,
+            sales::get_sale_items,
+            sales::get_sales,
+            sales::record_sale,
+            returns::get_returnable_ticket,
+            returns::create_return,
+            voids::void_ticket,
+            cash_sessions::get_current_cash_session,
+            cash_sessions::get_cash_sessions,
+            cash_sessions::open_cash_session,
+            cash_sessions::close_cash_session,
+            cash_sessions::get_cash_session_report,
+            cash_movements::get_cash_movements,
+            cash_movements::record_cash_movement,
+            cash_counts::get_denominations,
+            cash_counts::get_cash_counts,
+            cash_counts::get_cash_count,
+            search::get_products,
+            search::search_products,
+            barcodes::resolve_product_code,
+            barcodes::get_product_barcodes,
+            barcodes::set_product_barcode,
+            barcodes::remove_product_barcode,
+            categories::resolve_category_label,
+            categories::get_categories,
+            categories::create_category,
+            categories::update_category,
+            categories::set_product_category,
+            categories::get_quick_keys,
+            reports::get_category_sales_report,
+            reports::get_z_report,
+            reports::get_margin_report,
+            audit::get_audit_log,
+            audit::verify_audit_log,
+            prices::set_product_price,
+            prices::schedule_price_change,
+            prices::cancel_scheduled_price_change,
+            prices::get_scheduled_price_changes,
+            prices::get_price_history,
+            prices::get_label_queue,
+            prices::clear_label_flags,
+            costs::set_product_cost,
+            costs::get_cost_history,
+            costs::get_below_cost_products,
+            inventory::get_stock_levels,
+            inventory::get_stock,
+            inventory::get_stock_movements,
+            inventory::set_stock_tracking,
+            inventory::adjust_stock,
+            reorder::set_stock_thresholds,
+            reorder::get_low_stock,
+            reorder::get_reorder_suggestions,
+            suppliers::get_suppliers,
+            suppliers::create_supplier,
+            suppliers::update_supplier,
+            suppliers::set_product_supplier,
+            purchases::get_purchase_orders,
+            purchases::get_purchase_order,
+            purchases::create_purchase_order,
+            purchases::cancel_purchase_order,
+            purchases::receive_purchase_order,
+            recipes::get_recipes,
+            recipes::save_recipe,
+            recipes::delete_recipe,
+            recipes::record_production,
+            recipes::get_production_batches,
+            recipes::get_ingredient_usage_report,
+            waste::record_waste,
+            waste::get_waste_report,
+            stock_counts::get_stock_counts,
+            stock_counts::get_stock_count,
+            stock_counts::create_stock_count,
+            stock_counts::record_count,
+            stock_counts::request_recount,
+            stock_counts::post_stock_count,
+            stock_counts::cancel_stock_count,
+            import::get_import_presets,
+            import::get_import_sheets,
+            import::preview_product_import,
+            import::commit_product_import,
+            export::export_products,
+            export::export_purchase_list
+        ])
+        .run(context)
         .expect("error while running tauri application");
 }
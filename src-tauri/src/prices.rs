@@ -0,0 +1,415 @@
+use crate::audit;
+use crate::costs;
+use crate::db;
+use crate::users::{self, Permission, SessionUser};
+use serde::Serialize;
+use serde_json::json;
+use sqlx::{Pool, Sqlite, SqliteConnection};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+/// How often the background task looks for due scheduled changes.
+const SCHEDULER_INTERVAL: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct PriceChange {
+    pub id: i64,
+    pub sku: String,
+    pub tier: Option<String>,
+    pub old_price: Option<f64>,
+    pub new_price: f64,
+    pub source: String,
+    pub changed_by: Option<String>,
+    pub changed_at: String,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct ScheduledPriceChange {
+    pub id: i64,
+    pub sku: String,
+    pub product_name: String,
+    pub tier: Option<String>,
+    pub current_price: Option<f64>,
+    pub new_price: f64,
+    /// Local time
+    pub effective_at: String,
+    pub created_by: Option<String>,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct LabelItem {
+    pub sku: String,
+    pub plu_code: Option<String>,
+    pub product_name: String,
+    pub price: f64,
+    pub updated_at: Option<String>,
+}
+
+#[derive(sqlx::FromRow)]
+struct DueChange {
+    id: i64,
+    sku: String,
+    tier: Option<String>,
+    new_price: f64,
+}
+
+async fn product_exists(conn: &mut SqliteConnection, sku: &str) -> Result<bool, String> {
+    sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM products WHERE sku = ?)")
+        .bind(sku)
+        .fetch_one(conn)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Retail price (`tier` None) or tier price of a product, if it has one.
+pub async fn current_price(
+    conn: &mut SqliteConnection,
+    sku: &str,
+    tier: Option<&str>,
+) -> Result<Option<f64>, String> {
+    let query = match tier {
+        None => "SELECT CAST(price AS REAL) FROM products WHERE sku = ?1",
+        Some(_) => "SELECT CAST(price AS REAL) FROM product_prices WHERE sku = ?1 AND tier = ?2",
+    };
+    sqlx::query_scalar(query)
+        .bind(sku)
+        .bind(tier)
+        .fetch_optional(conn)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Records a price change already written to `products` or
+/// `product_prices`: adds it to the price history and the audit log and
+/// flags the product for a new shelf label.
+pub async fn record_change(
+    conn: &mut SqliteConnection,
+    sku: &str,
+    tier: Option<&str>,
+    old_price: Option<f64>,
+    new_price: f64,
+    user: Option<&SessionUser>,
+    source: &str,
+) -> Result<(), String> {
+    sqlx::query(
+        "INSERT INTO price_history (sku, tier, old_price, new_price, source, changed_by)
+         VALUES (?, ?, ?, ?, ?, ?)",
+    )
+    .bind(sku)
+    .bind(tier)
+    .bind(old_price)
+    .bind(new_price)
+    .bind(source)
+    .bind(user.map(|user| user.id))
+    .execute(&mut *conn)
+    .await
+    .map_err(|e| e.to_string())?;
+    sqlx::query(
+        "UPDATE products SET needs_label = 1, updated_at = CURRENT_TIMESTAMP WHERE sku = ?",
+    )
+    .bind(sku)
+    .execute(&mut *conn)
+    .await
+    .map_err(|e| e.to_string())?;
+    audit::record(
+        conn,
+        user,
+        "change_price",
+        "product",
+        Some(sku),
+        Some(json!({ "tier": tier, "price": old_price })),
+        Some(json!({ "tier": tier, "price": new_price, "source": source })),
+    )
+    .await
+}
+
+/// Sets the retail price (`tier` None) or a tier price of a product.
+/// Returns whether the price changed.
+pub async fn set_price(
+    conn: &mut SqliteConnection,
+    sku: &str,
+    tier: Option<&str>,
+    new_price: f64,
+    user: Option<&SessionUser>,
+    source: &str,
+) -> Result<bool, String> {
+    if new_price < 0.0 {
+        return Err("The price cannot be negative".to_string());
+    }
+    if !product_exists(conn, sku).await? {
+        return Err(format!("Unknown product {}", sku));
+    }
+    let old_price = current_price(conn, sku, tier).await?;
+    if old_price.is_some_and(|old_price| (old_price - new_price).abs() < 0.005) {
+        return Ok(false);
+    }
+
+    let query = match tier {
+        None => "UPDATE products SET price = ?3 WHERE sku = ?1",
+        Some(_) => {
+            "INSERT INTO product_prices (sku, tier, price) VALUES (?1, ?2, ?3)
+             ON CONFLICT(sku, tier) DO UPDATE SET
+                 price = excluded.price,
+                 updated_at = CURRENT_TIMESTAMP"
+        }
+    };
+    sqlx::query(query)
+        .bind(sku)
+        .bind(tier)
+        .bind(new_price)
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| e.to_string())?;
+    record_change(conn, sku, tier, old_price, new_price, user, source).await?;
+    Ok(true)
+}
+
+/// Applies the scheduled changes whose time has come, oldest first, in one
+/// transaction. Returns how many were applied.
+pub async fn apply_due_changes(pool: &Pool<Sqlite>) -> Result<usize, String> {
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    let due = sqlx::query_as::<_, DueChange>(
+        "SELECT id, sku, tier, CAST(new_price AS REAL) AS new_price
+         FROM scheduled_price_changes
+         WHERE applied_at IS NULL AND cancelled_at IS NULL AND effective_at <= CURRENT_TIMESTAMP
+         ORDER BY effective_at, id",
+    )
+    .fetch_all(&mut *tx)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    for change in &due {
+        set_price(
+            &mut tx,
+            &change.sku,
+            change.tier.as_deref(),
+            change.new_price,
+            None,
+            "scheduled",
+        )
+        .await
+        .map_err(|e| format!("Scheduled price change {}: {}", change.id, e))?;
+        sqlx::query(
+            "UPDATE scheduled_price_changes SET applied_at = CURRENT_TIMESTAMP WHERE id = ?",
+        )
+        .bind(change.id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| e.to_string())?;
+    }
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+    Ok(due.len())
+}
+
+/// Starts the background task applying scheduled price changes. Windows
+/// get a `prices-changed` event with the number of changes applied.
+pub fn spawn_scheduler(app_handle: AppHandle) {
+    std::thread::spawn(move || loop {
+        let applied = tauri::async_runtime::block_on(async {
+            let pool = db::pool(&app_handle).await?;
+            apply_due_changes(&pool).await
+        });
+        match applied {
+            Ok(0) => {}
+            Ok(count) => {
+                if let Err(e) = app_handle.emit("prices-changed", count) {
+                    eprintln!("Failed to emit prices-changed: {}", e);
+                }
+                tauri::async_runtime::block_on(async {
+                    if let Ok(pool) = db::pool(&app_handle).await {
+                        costs::notify_below_cost(&app_handle, &pool).await;
+                    }
+                });
+            }
+            Err(e) => eprintln!("Failed to apply scheduled price changes: {}", e),
+        }
+        std::thread::sleep(SCHEDULER_INTERVAL);
+    });
+}
+
+/// Changes a price right away. Catalog changes take the `import` permission.
+#[tauri::command]
+pub async fn set_product_price(
+    app_handle: AppHandle,
+    sku: String,
+    tier: Option<String>,
+    price: f64,
+) -> Result<bool, String> {
+    let user = users::require(&app_handle, Permission::Import)?;
+    let pool = db::pool(&app_handle).await?;
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    let changed = set_price(&mut tx, &sku, tier.as_deref(), price, Some(&user), "manual").await?;
+    tx.commit().await.map_err(|e| e.to_string())?;
+    if changed {
+        costs::notify_below_cost(&app_handle, &pool).await;
+    }
+    Ok(changed)
+}
+
+/// Schedules a price change for a future local date and time
+/// (`YYYY-MM-DD HH:MM`). Returns the id of the scheduled change.
+#[tauri::command]
+pub async fn schedule_price_change(
+    app_handle: AppHandle,
+    sku: String,
+    tier: Option<String>,
+    new_price: f64,
+    effective_at: String,
+) -> Result<i64, String> {
+    let user = users::require(&app_handle, Permission::Import)?;
+    if new_price < 0.0 {
+        return Err("The price cannot be negative".to_string());
+    }
+    let pool = db::pool(&app_handle).await?;
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    if !product_exists(&mut tx, &sku).await? {
+        return Err(format!("Unknown product {}", sku));
+    }
+
+    let (effective_at_utc, is_future): (Option<String>, bool) = sqlx::query_as(
+        "SELECT datetime(?1, 'utc'), IFNULL(datetime(?1, 'utc') > CURRENT_TIMESTAMP, 0)",
+    )
+    .bind(effective_at.replace('T', " "))
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| e.to_string())?;
+    let effective_at_utc =
+        effective_at_utc.ok_or_else(|| format!("Invalid date and time {}", effective_at))?;
+    if !is_future {
+        return Err("Scheduled price changes must be in the future".to_string());
+    }
+
+    let id = sqlx::query(
+        "INSERT INTO scheduled_price_changes (sku, tier, new_price, effective_at, created_by)
+         VALUES (?, ?, ?, ?, ?)",
+    )
+    .bind(&sku)
+    .bind(&tier)
+    .bind(new_price)
+    .bind(&effective_at_utc)
+    .bind(user.id)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| e.to_string())?
+    .last_insert_rowid();
+    audit::record(
+        &mut tx,
+        Some(&user),
+        "schedule_price_change",
+        "product",
+        Some(&sku),
+        None,
+        Some(json!({ "tier": tier, "price": new_price, "effective_at": effective_at_utc })),
+    )
+    .await?;
+    tx.commit().await.map_err(|e| e.to_string())?;
+    Ok(id)
+}
+
+#[tauri::command]
+pub async fn cancel_scheduled_price_change(app_handle: AppHandle, id: i64) -> Result<(), String> {
+    let user = users::require(&app_handle, Permission::Import)?;
+    let pool = db::pool(&app_handle).await?;
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    let sku: String = sqlx::query_scalar(
+        "UPDATE scheduled_price_changes SET cancelled_at = CURRENT_TIMESTAMP
+         WHERE id = ? AND applied_at IS NULL AND cancelled_at IS NULL
+         RETURNING sku",
+    )
+    .bind(id)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|e| e.to_string())?
+    .ok_or("The price change was already applied or cancelled")?;
+    audit::record(
+        &mut tx,
+        Some(&user),
+        "cancel_price_change",
+        "product",
+        Some(&sku),
+        Some(json!({ "scheduled_price_change": id })),
+        None,
+    )
+    .await?;
+    tx.commit().await.map_err(|e| e.to_string())
+}
+
+/// Scheduled changes not applied or cancelled yet, soonest first.
+#[tauri::command]
+pub async fn get_scheduled_price_changes(
+    app_handle: AppHandle,
+) -> Result<Vec<ScheduledPriceChange>, String> {
+    let pool = db::pool(&app_handle).await?;
+    sqlx::query_as::<_, ScheduledPriceChange>(
+        "SELECT s.id, s.sku, p.product_name, s.tier,
+                CAST(CASE WHEN s.tier IS NULL THEN p.price ELSE pp.price END AS REAL) AS current_price,
+                CAST(s.new_price AS REAL) AS new_price,
+                datetime(s.effective_at, 'localtime') AS effective_at,
+                u.display_name AS created_by
+         FROM scheduled_price_changes s
+         JOIN products p ON p.sku = s.sku
+         LEFT JOIN product_prices pp ON pp.sku = s.sku AND pp.tier = s.tier
+         LEFT JOIN users u ON u.id = s.created_by
+         WHERE s.applied_at IS NULL AND s.cancelled_at IS NULL
+         ORDER BY s.effective_at, s.id",
+    )
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| e.to_string())
+}
+
+/// Price changes of a product, newest first.
+#[tauri::command]
+pub async fn get_price_history(
+    app_handle: AppHandle,
+    sku: String,
+) -> Result<Vec<PriceChange>, String> {
+    let pool = db::pool(&app_handle).await?;
+    sqlx::query_as::<_, PriceChange>(
+        "SELECT h.id, h.sku, h.tier, CAST(h.old_price AS REAL) AS old_price,
+                CAST(h.new_price AS REAL) AS new_price, h.source,
+                u.display_name AS changed_by,
+                datetime(h.changed_at, 'localtime') AS changed_at
+         FROM price_history h
+         LEFT JOIN users u ON u.id = h.changed_by
+         WHERE h.sku = ?
+         ORDER BY h.id DESC",
+    )
+    .bind(sku)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| e.to_string())
+}
+
+/// Products whose price changed since their shelf label was last printed.
+#[tauri::command]
+pub async fn get_label_queue(app_handle: AppHandle) -> Result<Vec<LabelItem>, String> {
+    let pool = db::pool(&app_handle).await?;
+    sqlx::query_as::<_, LabelItem>(
+        "SELECT sku, plu_code, product_name, CAST(price AS REAL) AS price,
+                datetime(updated_at, 'localtime') AS updated_at
+         FROM products
+         WHERE needs_label
+         ORDER BY product_name",
+    )
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| e.to_string())
+}
+
+/// Marks the labels of `skus` as reprinted.
+#[tauri::command]
+pub async fn clear_label_flags(app_handle: AppHandle, skus: Vec<String>) -> Result<(), String> {
+    users::require(&app_handle, Permission::Import)?;
+    let pool = db::pool(&app_handle).await?;
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    for sku in skus {
+        sqlx::query("UPDATE products SET needs_label = 0 WHERE sku = ?")
+            .bind(sku)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+    tx.commit().await.map_err(|e| e.to_string())
+}
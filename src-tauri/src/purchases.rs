@@ -0,0 +1,477 @@
+use crate::audit;
+use crate::costs;
+use crate::db;
+use crate::inventory::{self, MovementKind};
+use crate::settings;
+use crate::users::{self, Permission};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sqlx::{Pool, Sqlite, SqliteConnection};
+use tauri::AppHandle;
+
+/// Quantities below this are rounding noise, not stock still to receive.
+const QUANTITY_EPSILON: f64 = 0.0005;
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct PurchaseOrder {
+    pub id: i64,
+    pub supplier_id: i64,
+    pub supplier_name: String,
+    pub status: String,
+    pub currency: String,
+    pub notes: Option<String>,
+    pub created_by_name: Option<String>,
+    pub created_at: String,
+    /// Ordered quantity times cost, in the currency of the order.
+    pub total: f64,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct PurchaseOrderLine {
+    pub id: i64,
+    pub sku: String,
+    pub product_name: String,
+    pub quantity_ordered: f64,
+    pub quantity_received: f64,
+    pub unit_cost: f64,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct PurchaseReceipt {
+    pub id: i64,
+    pub supplier_invoice: Option<String>,
+    pub currency: String,
+    pub exchange_rate: f64,
+    pub received_by_name: Option<String>,
+    pub received_at: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PurchaseOrderDetail {
+    pub order: PurchaseOrder,
+    pub lines: Vec<PurchaseOrderLine>,
+    pub receipts: Vec<PurchaseReceipt>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct NewPurchaseOrderLine {
+    pub sku: String,
+    pub quantity: f64,
+    pub unit_cost: f64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct NewPurchaseOrder {
+    pub supplier_id: i64,
+    /// Defaults to the currency of the supplier.
+    pub currency: Option<String>,
+    pub notes: Option<String>,
+    pub lines: Vec<NewPurchaseOrderLine>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ReceivedLine {
+    pub line_id: i64,
+    pub quantity: f64,
+    /// Cost on the supplier invoice; defaults to the ordered cost.
+    pub unit_cost: Option<f64>,
+}
+
+/// A delivery against a purchase order.
+#[derive(Debug, Deserialize)]
+pub struct GoodsReceipt {
+    pub supplier_invoice: Option<String>,
+    /// Pesos per dollar of a USD invoice; defaults to the station rate.
+    /// MXN invoices always use 1.
+    pub exchange_rate: Option<f64>,
+    pub lines: Vec<ReceivedLine>,
+}
+
+const ORDER_QUERY: &str =
+    "SELECT o.id, o.supplier_id, s.name AS supplier_name, o.status, o.currency,
+        o.notes, u.display_name AS created_by_name, o.created_at,
+        CAST(IFNULL((
+            SELECT SUM(l.quantity_ordered * l.unit_cost)
+            FROM purchase_order_lines l WHERE l.purchase_order_id = o.id
+        ), 0) AS REAL) AS total
+    FROM purchase_orders o
+    JOIN suppliers s ON s.id = o.supplier_id
+    LEFT JOIN users u ON u.id = o.created_by";
+
+/// Reference of the stock movements of an order, e.g. `OC-12`.
+fn reference(id: i64) -> String {
+    format!("OC-{}", id)
+}
+
+async fn order_lines(
+    conn: &mut SqliteConnection,
+    id: i64,
+) -> Result<Vec<PurchaseOrderLine>, String> {
+    sqlx::query_as::<_, PurchaseOrderLine>(
+        "SELECT id, sku, product_name,
+                CAST(quantity_ordered AS REAL) AS quantity_ordered,
+                CAST(quantity_received AS REAL) AS quantity_received,
+                CAST(unit_cost AS REAL) AS unit_cost
+         FROM purchase_order_lines
+         WHERE purchase_order_id = ?
+         ORDER BY id",
+    )
+    .bind(id)
+    .fetch_all(conn)
+    .await
+    .map_err(|e| e.to_string())
+}
+
+/// An order with its lines and deliveries.
+pub async fn load_order(pool: &Pool<Sqlite>, id: i64) -> Result<PurchaseOrderDetail, String> {
+    let order = sqlx::query_as::<_, PurchaseOrder>(&format!("{ORDER_QUERY} WHERE o.id = ?"))
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Unknown purchase order {}", id))?;
+    let mut conn = pool.acquire().await.map_err(|e| e.to_string())?;
+    let lines = order_lines(&mut conn, id).await?;
+    let receipts = sqlx::query_as::<_, PurchaseReceipt>(
+        "SELECT r.id, r.supplier_invoice, r.currency,
+                CAST(r.exchange_rate AS REAL) AS exchange_rate,
+                u.display_name AS received_by_name, r.received_at
+         FROM purchase_receipts r
+         LEFT JOIN users u ON u.id = r.received_by
+         WHERE r.purchase_order_id = ?
+         ORDER BY r.id",
+    )
+    .bind(id)
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(|e| e.to_string())?;
+    Ok(PurchaseOrderDetail {
+        order,
+        lines,
+        receipts,
+    })
+}
+
+/// Purchase orders, newest first, optionally only those in `status`.
+#[tauri::command]
+pub async fn get_purchase_orders(
+    app_handle: AppHandle,
+    status: Option<String>,
+) -> Result<Vec<PurchaseOrder>, String> {
+    let pool = db::pool(&app_handle).await?;
+    sqlx::query_as::<_, PurchaseOrder>(&format!(
+        "{ORDER_QUERY} WHERE ?1 IS NULL OR o.status = ?1 ORDER BY o.id DESC"
+    ))
+    .bind(status)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_purchase_order(
+    app_handle: AppHandle,
+    id: i64,
+) -> Result<PurchaseOrderDetail, String> {
+    let pool = db::pool(&app_handle).await?;
+    load_order(&pool, id).await
+}
+
+/// Creates an open purchase order and returns its id.
+#[tauri::command]
+pub async fn create_purchase_order(
+    app_handle: AppHandle,
+    order: NewPurchaseOrder,
+) -> Result<i64, String> {
+    let user = users::require(&app_handle, Permission::ManageInventory)?;
+    if order.lines.is_empty() {
+        return Err("The purchase order has no products".to_string());
+    }
+    if let Some(line) = order
+        .lines
+        .iter()
+        .find(|line| line.quantity <= 0.0 || line.unit_cost < 0.0)
+    {
+        return Err(format!("Invalid quantity or cost for product {}", line.sku));
+    }
+    let pool = db::pool(&app_handle).await?;
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+
+    let supplier_currency: String =
+        sqlx::query_scalar("SELECT currency FROM suppliers WHERE id = ? AND is_active")
+            .bind(order.supplier_id)
+            .fetch_optional(&mut *tx)
+            .await
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| format!("Unknown or inactive supplier {}", order.supplier_id))?;
+    let currency = order.currency.unwrap_or(supplier_currency);
+    if !matches!(currency.as_str(), "MXN" | "USD") {
+        return Err(format!("Unknown currency {}", currency));
+    }
+
+    let id = sqlx::query(
+        "INSERT INTO purchase_orders (supplier_id, currency, notes, created_by) VALUES (?, ?, ?, ?)",
+    )
+    .bind(order.supplier_id)
+    .bind(&currency)
+    .bind(&order.notes)
+    .bind(user.id)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| e.to_string())?
+    .last_insert_rowid();
+
+    for line in &order.lines {
+        let product_name: String =
+            sqlx::query_scalar("SELECT product_name FROM products WHERE sku = ?")
+                .bind(&line.sku)
+                .fetch_optional(&mut *tx)
+                .await
+                .map_err(|e| e.to_string())?
+                .ok_or_else(|| format!("Unknown product {}", line.sku))?;
+        sqlx::query(
+            "INSERT INTO purchase_order_lines
+                 (purchase_order_id, sku, product_name, quantity_ordered, unit_cost)
+             VALUES (?, ?, ?, ?, ?)",
+        )
+        .bind(id)
+        .bind(&line.sku)
+        .bind(&product_name)
+        .bind(line.quantity)
+        .bind(line.unit_cost)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| e.to_string())?;
+    }
+
+    audit::record(
+        &mut tx,
+        Some(&user),
+        "create_purchase_order",
+        "purchase_order",
+        Some(&id.to_string()),
+        None,
+        Some(json!({
+            "supplier_id": order.supplier_id,
+            "currency": currency,
+            "lines": order.lines.len(),
+        })),
+    )
+    .await?;
+    tx.commit().await.map_err(|e| e.to_string())?;
+    Ok(id)
+}
+
+/// Cancels an order; what was already received stays in stock.
+#[tauri::command]
+pub async fn cancel_purchase_order(app_handle: AppHandle, id: i64) -> Result<(), String> {
+    let user = users::require(&app_handle, Permission::ManageInventory)?;
+    let pool = db::pool(&app_handle).await?;
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    let status: String = sqlx::query_scalar("SELECT status FROM purchase_orders WHERE id = ?")
+        .bind(id)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Unknown purchase order {}", id))?;
+    if !matches!(status.as_str(), "open" | "partial") {
+        return Err(format!("Purchase order {} is already {}", id, status));
+    }
+    sqlx::query(
+        "UPDATE purchase_orders SET status = 'cancelled', updated_at = CURRENT_TIMESTAMP
+         WHERE id = ?",
+    )
+    .bind(id)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| e.to_string())?;
+    audit::record(
+        &mut tx,
+        Some(&user),
+        "cancel_purchase_order",
+        "purchase_order",
+        Some(&id.to_string()),
+        Some(json!({ "status": status })),
+        Some(json!({ "status": "cancelled" })),
+    )
+    .await?;
+    tx.commit().await.map_err(|e| e.to_string())
+}
+
+/// Receives goods against an order, in full or in part. Each line adds a
+/// receipt movement to the stock and costs the product at the invoiced
+/// cost in pesos. Returns the id of the receipt.
+#[tauri::command]
+pub async fn receive_purchase_order(
+    app_handle: AppHandle,
+    id: i64,
+    receipt: GoodsReceipt,
+) -> Result<i64, String> {
+    let user = users::require(&app_handle, Permission::ManageInventory)?;
+    let lines: Vec<&ReceivedLine> = receipt
+        .lines
+        .iter()
+        .filter(|line| line.quantity != 0.0)
+        .collect();
+    if lines.is_empty() {
+        return Err("Nothing was received".to_string());
+    }
+    if lines
+        .iter()
+        .any(|line| line.quantity < 0.0 || line.unit_cost.is_some_and(|cost| cost < 0.0))
+    {
+        return Err("Received quantities and costs cannot be negative".to_string());
+    }
+    let pool = db::pool(&app_handle).await?;
+
+    let (status, currency): (String, String) =
+        sqlx::query_as("SELECT status, currency FROM purchase_orders WHERE id = ?")
+            .bind(id)
+            .fetch_optional(&pool)
+            .await
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| format!("Unknown purchase order {}", id))?;
+    if !matches!(status.as_str(), "open" | "partial") {
+        return Err(format!("Purchase order {} is already {}", id, status));
+    }
+    let exchange_rate = match currency.as_str() {
+        "USD" => match receipt.exchange_rate {
+            Some(rate) => rate,
+            None => settings::load(&pool).await?.exchange_rate_usd_to_mxn,
+        },
+        _ => 1.0,
+    };
+    if exchange_rate <= 0.0 {
+        return Err("The exchange rate must be positive".to_string());
+    }
+    let supplier_invoice = receipt
+        .supplier_invoice
+        .as_deref()
+        .map(str::trim)
+        .filter(|invoice| !invoice.is_empty());
+
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    let order_lines = order_lines(&mut tx, id).await?;
+    let receipt_id = sqlx::query(
+        "INSERT INTO purchase_receipts
+             (purchase_order_id, supplier_invoice, currency, exchange_rate, received_by)
+         VALUES (?, ?, ?, ?, ?)",
+    )
+    .bind(id)
+    .bind(supplier_invoice)
+    .bind(&currency)
+    .bind(exchange_rate)
+    .bind(user.id)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| e.to_string())?
+    .last_insert_rowid();
+
+    let reference = reference(id);
+    for received in &lines {
+        let line = order_lines
+            .iter()
+            .find(|line| line.id == received.line_id)
+            .ok_or_else(|| {
+                format!(
+                    "Line {} is not part of purchase order {}",
+                    received.line_id, id
+                )
+            })?;
+        let pending = line.quantity_ordered - line.quantity_received;
+        if received.quantity > pending + QUANTITY_EPSILON {
+            return Err(format!(
+                "{}: receiving {} but only {} are pending",
+                line.product_name, received.quantity, pending
+            ));
+        }
+        let unit_cost = received.unit_cost.unwrap_or(line.unit_cost);
+        let unit_cost_mxn = unit_cost * exchange_rate;
+
+        sqlx::query(
+            "INSERT INTO purchase_receipt_lines (receipt_id, line_id, quantity, unit_cost, unit_cost_mxn)
+             VALUES (?, ?, ?, ?, ?)",
+        )
+        .bind(receipt_id)
+        .bind(line.id)
+        .bind(received.quantity)
+        .bind(unit_cost)
+        .bind(unit_cost_mxn)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| e.to_string())?;
+        sqlx::query(
+            "UPDATE purchase_order_lines SET quantity_received = quantity_received + ? WHERE id = ?",
+        )
+        .bind(received.quantity)
+        .bind(line.id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| e.to_string())?;
+        costs::record_receipt(
+            &mut tx,
+            &line.sku,
+            received.quantity,
+            unit_cost_mxn,
+            Some(&reference),
+            Some(&user),
+        )
+        .await?;
+        inventory::record_movement(
+            &mut tx,
+            &line.sku,
+            MovementKind::Receipt,
+            received.quantity,
+            Some(&reference),
+            supplier_invoice,
+            Some(&user),
+        )
+        .await?;
+    }
+
+    let fully_received: bool = sqlx::query_scalar(
+        "SELECT NOT EXISTS (
+             SELECT 1 FROM purchase_order_lines
+             WHERE purchase_order_id = ? AND quantity_received < quantity_ordered - ?
+         )",
+    )
+    .bind(id)
+    .bind(QUANTITY_EPSILON)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| e.to_string())?;
+    let new_status = if fully_received {
+        "received"
+    } else {
+        "partial"
+    };
+    sqlx::query(
+        "UPDATE purchase_orders SET status = ?, updated_at = CURRENT_TIMESTAMP WHERE id = ?",
+    )
+    .bind(new_status)
+    .bind(id)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    audit::record(
+        &mut tx,
+        Some(&user),
+        "receive_purchase_order",
+        "purchase_order",
+        Some(&id.to_string()),
+        Some(json!({ "status": status })),
+        Some(json!({
+            "status": new_status,
+            "receipt_id": receipt_id,
+            "supplier_invoice": supplier_invoice,
+            "currency": currency,
+            "exchange_rate": exchange_rate,
+            "lines": lines.len(),
+        })),
+    )
+    .await?;
+    tx.commit().await.map_err(|e| e.to_string())?;
+    costs::notify_below_cost(&app_handle, &pool).await;
+    Ok(receipt_id)
+}
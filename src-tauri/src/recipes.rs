@@ -0,0 +1,528 @@
+use crate::audit;
+use crate::costs;
+use crate::db;
+use crate::inventory::{self, MovementKind};
+use crate::users::{self, Permission, SessionUser};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sqlx::SqliteConnection;
+use tauri::AppHandle;
+
+#[derive(Debug, Serialize, Deserialize, sqlx::FromRow)]
+pub struct RecipeIngredient {
+    pub ingredient_sku: String,
+    #[serde(default)]
+    #[sqlx(default)]
+    pub product_name: String,
+    /// Per `yield_quantity` of the recipe.
+    pub quantity: f64,
+    #[serde(default)]
+    #[sqlx(default)]
+    pub average_cost: Option<f64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, sqlx::FromRow)]
+pub struct Recipe {
+    pub sku: String,
+    #[serde(default)]
+    #[sqlx(default)]
+    pub product_name: String,
+    pub yield_quantity: f64,
+    pub notes: Option<String>,
+    /// Products that do not track stock (fresh tortillas) consume their
+    /// ingredients when sold; the others through production batches.
+    #[serde(default)]
+    #[sqlx(default)]
+    pub consumed_on_sale: bool,
+    #[serde(default)]
+    #[sqlx(skip)]
+    pub ingredients: Vec<RecipeIngredient>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UsedIngredient {
+    pub ingredient_sku: String,
+    pub quantity: f64,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct ProductionBatch {
+    pub id: i64,
+    pub sku: String,
+    pub product_name: String,
+    pub quantity: f64,
+    pub unit_cost: Option<f64>,
+    pub notes: Option<String>,
+    pub produced_by: Option<String>,
+    pub produced_at: String,
+}
+
+/// Theoretical versus actual use of a raw material over a period.
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct IngredientUsage {
+    pub sku: String,
+    pub product_name: String,
+    /// What the recipes call for, for the batches produced and the
+    /// recipes sold.
+    pub theoretical: f64,
+    /// What batches really used, plus what was sold through recipes and
+    /// what waste and stock adjustments took out.
+    pub actual: f64,
+    pub variance: f64,
+    pub variance_percent: Option<f64>,
+}
+
+/// Reference of the stock movements of a batch, e.g. `LOTE-7`.
+fn reference(id: i64) -> String {
+    format!("LOTE-{}", id)
+}
+
+async fn recipe_ingredients(
+    conn: &mut SqliteConnection,
+    sku: &str,
+) -> Result<Vec<RecipeIngredient>, String> {
+    sqlx::query_as::<_, RecipeIngredient>(
+        "SELECT ri.ingredient_sku, p.product_name, CAST(ri.quantity AS REAL) AS quantity,
+                CAST(IFNULL(p.average_cost, p.cost) AS REAL) AS average_cost
+         FROM recipe_ingredients ri
+         JOIN products p ON p.sku = ri.ingredient_sku
+         WHERE ri.recipe_sku = ?
+         ORDER BY p.product_name",
+    )
+    .bind(sku)
+    .fetch_all(conn)
+    .await
+    .map_err(|e| e.to_string())
+}
+
+async fn load_recipe(conn: &mut SqliteConnection, sku: &str) -> Result<Option<Recipe>, String> {
+    let recipe = sqlx::query_as::<_, Recipe>(
+        "SELECT r.sku, p.product_name, CAST(r.yield_quantity AS REAL) AS yield_quantity,
+                r.notes, NOT p.track_stock AS consumed_on_sale
+         FROM recipes r
+         JOIN products p ON p.sku = r.sku
+         WHERE r.sku = ?",
+    )
+    .bind(sku)
+    .fetch_optional(&mut *conn)
+    .await
+    .map_err(|e| e.to_string())?;
+    match recipe {
+        Some(mut recipe) => {
+            recipe.ingredients = recipe_ingredients(conn, sku).await?;
+            Ok(Some(recipe))
+        }
+        None => Ok(None),
+    }
+}
+
+/// Cost of one unit made by the recipe, from the average cost of its
+/// ingredients; None while an ingredient has no cost.
+fn unit_cost(recipe: &Recipe) -> Option<f64> {
+    recipe
+        .ingredients
+        .iter()
+        .map(|ingredient| {
+            ingredient
+                .average_cost
+                .map(|cost| cost * ingredient.quantity)
+        })
+        .sum::<Option<f64>>()
+        .map(|total| total / recipe.yield_quantity)
+}
+
+/// Recipe of a product sold fresh (one that does not track stock itself),
+/// whose ingredients go out of stock in its place.
+async fn fresh_recipe(conn: &mut SqliteConnection, sku: &str) -> Result<Option<Recipe>, String> {
+    Ok(load_recipe(conn, sku)
+        .await?
+        .filter(|recipe| recipe.consumed_on_sale))
+}
+
+/// Takes the ingredients of `quantity` units of a product sold fresh out
+/// of stock, as `kind` movements. Returns the recipe cost of one unit, or
+/// None when the product is not sold fresh or an ingredient has no cost.
+pub async fn consume_ingredients(
+    conn: &mut SqliteConnection,
+    sku: &str,
+    quantity: f64,
+    kind: MovementKind,
+    reference: &str,
+    user: &SessionUser,
+) -> Result<Option<f64>, String> {
+    let Some(recipe) = fresh_recipe(conn, sku).await? else {
+        return Ok(None);
+    };
+    for ingredient in &recipe.ingredients {
+        inventory::record_movement(
+            conn,
+            &ingredient.ingredient_sku,
+            kind,
+            -ingredient.quantity * quantity / recipe.yield_quantity,
+            Some(reference),
+            None,
+            Some(user),
+        )
+        .await?;
+    }
+    Ok(unit_cost(&recipe))
+}
+
+#[tauri::command]
+pub async fn get_recipes(app_handle: AppHandle) -> Result<Vec<Recipe>, String> {
+    let pool = db::pool(&app_handle).await?;
+    let mut conn = pool.acquire().await.map_err(|e| e.to_string())?;
+    let skus: Vec<String> = sqlx::query_scalar(
+        "SELECT r.sku FROM recipes r JOIN products p ON p.sku = r.sku ORDER BY p.product_name",
+    )
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(|e| e.to_string())?;
+    let mut recipes = Vec::with_capacity(skus.len());
+    for sku in skus {
+        if let Some(recipe) = load_recipe(&mut conn, &sku).await? {
+            recipes.push(recipe);
+        }
+    }
+    Ok(recipes)
+}
+
+/// Creates or replaces the recipe of a product.
+#[tauri::command]
+pub async fn save_recipe(app_handle: AppHandle, recipe: Recipe) -> Result<(), String> {
+    let user = users::require(&app_handle, Permission::ManageInventory)?;
+    if recipe.yield_quantity <= 0.0 {
+        return Err("The recipe yield must be positive".to_string());
+    }
+    if recipe.ingredients.is_empty() {
+        return Err("The recipe has no ingredients".to_string());
+    }
+    if let Some(ingredient) = recipe
+        .ingredients
+        .iter()
+        .find(|ingredient| ingredient.quantity <= 0.0 || ingredient.ingredient_sku == recipe.sku)
+    {
+        return Err(format!("Invalid ingredient {}", ingredient.ingredient_sku));
+    }
+    let pool = db::pool(&app_handle).await?;
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    let before = load_recipe(&mut tx, &recipe.sku).await?;
+
+    sqlx::query(
+        "INSERT INTO recipes (sku, yield_quantity, notes) VALUES (?1, ?2, ?3)
+         ON CONFLICT(sku) DO UPDATE SET
+             yield_quantity = excluded.yield_quantity,
+             notes = excluded.notes,
+             updated_at = CURRENT_TIMESTAMP",
+    )
+    .bind(&recipe.sku)
+    .bind(recipe.yield_quantity)
+    .bind(&recipe.notes)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| e.to_string())?;
+    sqlx::query("DELETE FROM recipe_ingredients WHERE recipe_sku = ?")
+        .bind(&recipe.sku)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| e.to_string())?;
+    for ingredient in &recipe.ingredients {
+        sqlx::query(
+            "INSERT INTO recipe_ingredients (recipe_sku, ingredient_sku, quantity)
+             VALUES (?, ?, ?)",
+        )
+        .bind(&recipe.sku)
+        .bind(&ingredient.ingredient_sku)
+        .bind(ingredient.quantity)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Ingredient {}: {}", ingredient.ingredient_sku, e))?;
+    }
+
+    let ingredients_json = |ingredients: &[RecipeIngredient]| {
+        ingredients
+            .iter()
+            .map(|ingredient| json!([ingredient.ingredient_sku, ingredient.quantity]))
+            .collect::<Vec<_>>()
+    };
+    audit::record(
+        &mut tx,
+        Some(&user),
+        "save_recipe",
+        "product",
+        Some(&recipe.sku),
+        before.map(|before| {
+            json!({
+                "yield_quantity": before.yield_quantity,
+                "ingredients": ingredients_json(&before.ingredients),
+            })
+        }),
+        Some(json!({
+            "yield_quantity": recipe.yield_quantity,
+            "ingredients": ingredients_json(&recipe.ingredients),
+        })),
+    )
+    .await?;
+    tx.commit().await.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn delete_recipe(app_handle: AppHandle, sku: String) -> Result<(), String> {
+    let user = users::require(&app_handle, Permission::ManageInventory)?;
+    let pool = db::pool(&app_handle).await?;
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    sqlx::query("DELETE FROM recipe_ingredients WHERE recipe_sku = ?")
+        .bind(&sku)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| e.to_string())?;
+    let deleted = sqlx::query("DELETE FROM recipes WHERE sku = ?")
+        .bind(&sku)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| e.to_string())?;
+    if deleted.rows_affected() == 0 {
+        return Err(format!("Product {} has no recipe", sku));
+    }
+    audit::record(
+        &mut tx,
+        Some(&user),
+        "delete_recipe",
+        "product",
+        Some(&sku),
+        None,
+        None,
+    )
+    .await?;
+    tx.commit().await.map_err(|e| e.to_string())
+}
+
+/// Records a production batch of `quantity` units of a product that
+/// tracks stock: its ingredients go out of stock and the product comes in,
+/// costed from the ingredients. `used` lists what was really used where it
+/// differs from the recipe. Returns the id of the batch.
+#[tauri::command]
+pub async fn record_production(
+    app_handle: AppHandle,
+    sku: String,
+    quantity: f64,
+    used: Option<Vec<UsedIngredient>>,
+    notes: Option<String>,
+) -> Result<i64, String> {
+    let user = users::require(&app_handle, Permission::ManageInventory)?;
+    if quantity <= 0.0 {
+        return Err("The produced quantity must be positive".to_string());
+    }
+    let used = used.unwrap_or_default();
+    if used.iter().any(|ingredient| ingredient.quantity < 0.0) {
+        return Err("Used quantities cannot be negative".to_string());
+    }
+    let pool = db::pool(&app_handle).await?;
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    let recipe = load_recipe(&mut tx, &sku)
+        .await?
+        .ok_or_else(|| format!("Product {} has no recipe", sku))?;
+    if recipe.consumed_on_sale {
+        return Err(format!(
+            "{} does not track stock; its ingredients are consumed when it is sold",
+            recipe.product_name
+        ));
+    }
+    if let Some(extra) = used.iter().find(|used| {
+        !recipe
+            .ingredients
+            .iter()
+            .any(|ingredient| ingredient.ingredient_sku == used.ingredient_sku)
+    }) {
+        return Err(format!(
+            "{} is not an ingredient of {}",
+            extra.ingredient_sku, recipe.product_name
+        ));
+    }
+
+    // (ingredient, theoretical, actual)
+    let lines: Vec<(&RecipeIngredient, f64, f64)> = recipe
+        .ingredients
+        .iter()
+        .map(|ingredient| {
+            let theoretical = ingredient.quantity * quantity / recipe.yield_quantity;
+            let actual = used
+                .iter()
+                .find(|used| used.ingredient_sku == ingredient.ingredient_sku)
+                .map_or(theoretical, |used| used.quantity);
+            (ingredient, theoretical, actual)
+        })
+        .collect();
+    let unit_cost = lines
+        .iter()
+        .map(|(ingredient, _, actual)| ingredient.average_cost.map(|cost| cost * actual))
+        .sum::<Option<f64>>()
+        .map(|total| total / quantity);
+
+    let id = sqlx::query(
+        "INSERT INTO production_batches (sku, product_name, quantity, unit_cost, notes, produced_by)
+         VALUES (?, ?, ?, ?, ?, ?)",
+    )
+    .bind(&sku)
+    .bind(&recipe.product_name)
+    .bind(quantity)
+    .bind(unit_cost)
+    .bind(&notes)
+    .bind(user.id)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| e.to_string())?
+    .last_insert_rowid();
+    let reference = reference(id);
+
+    for (ingredient, theoretical, actual) in &lines {
+        sqlx::query(
+            "INSERT INTO production_batch_ingredients (
+                 batch_id, ingredient_sku, product_name, theoretical_quantity, actual_quantity,
+                 unit_cost
+             ) VALUES (?, ?, ?, ?, ?, ?)",
+        )
+        .bind(id)
+        .bind(&ingredient.ingredient_sku)
+        .bind(&ingredient.product_name)
+        .bind(theoretical)
+        .bind(actual)
+        .bind(ingredient.average_cost)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| e.to_string())?;
+        if *actual > 0.0 {
+            inventory::record_movement(
+                &mut tx,
+                &ingredient.ingredient_sku,
+                MovementKind::Consumption,
+                -actual,
+                Some(&reference),
+                None,
+                Some(&user),
+            )
+            .await?;
+        }
+    }
+
+    if let Some(unit_cost) = unit_cost {
+        costs::record_production(
+            &mut tx,
+            &sku,
+            quantity,
+            unit_cost,
+            Some(&reference),
+            Some(&user),
+        )
+        .await?;
+    }
+    inventory::record_movement(
+        &mut tx,
+        &sku,
+        MovementKind::Production,
+        quantity,
+        Some(&reference),
+        notes.as_deref(),
+        Some(&user),
+    )
+    .await?;
+
+    audit::record(
+        &mut tx,
+        Some(&user),
+        "record_production",
+        "production_batch",
+        Some(&id.to_string()),
+        None,
+        Some(json!({ "sku": sku, "quantity": quantity, "unit_cost": unit_cost })),
+    )
+    .await?;
+    tx.commit().await.map_err(|e| e.to_string())?;
+    costs::notify_below_cost(&app_handle, &pool).await;
+    Ok(id)
+}
+
+/// Production batches between two local dates (`YYYY-MM-DD`, both inclusive).
+#[tauri::command]
+pub async fn get_production_batches(
+    app_handle: AppHandle,
+    from_date: String,
+    to_date: String,
+) -> Result<Vec<ProductionBatch>, String> {
+    let pool = db::pool(&app_handle).await?;
+    sqlx::query_as::<_, ProductionBatch>(
+        "SELECT b.id, b.sku, b.product_name, CAST(b.quantity AS REAL) AS quantity,
+                CAST(b.unit_cost AS REAL) AS unit_cost, b.notes,
+                u.display_name AS produced_by,
+                datetime(b.produced_at, 'localtime') AS produced_at
+         FROM production_batches b
+         LEFT JOIN users u ON u.id = b.produced_by
+         WHERE date(b.produced_at, 'localtime') BETWEEN ?1 AND ?2
+         ORDER BY b.id DESC",
+    )
+    .bind(from_date)
+    .bind(to_date)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| e.to_string())
+}
+
+/// Theoretical versus actual use of every raw material between two local
+/// dates (`YYYY-MM-DD`, both inclusive). Requires `view_reports`.
+#[tauri::command]
+pub async fn get_ingredient_usage_report(
+    app_handle: AppHandle,
+    from_date: String,
+    to_date: String,
+) -> Result<Vec<IngredientUsage>, String> {
+    users::require(&app_handle, Permission::ViewReports)?;
+    let pool = db::pool(&app_handle).await?;
+    sqlx::query_as::<_, IngredientUsage>(
+        "WITH batches AS (
+             SELECT bi.ingredient_sku AS sku,
+                    SUM(bi.theoretical_quantity) AS theoretical,
+                    SUM(bi.actual_quantity) AS actual
+             FROM production_batch_ingredients bi
+             JOIN production_batches b ON b.id = bi.batch_id
+             WHERE date(b.produced_at, 'localtime') BETWEEN ?1 AND ?2
+             GROUP BY bi.ingredient_sku
+         ),
+         sold AS (
+             SELECT m.sku, -SUM(m.quantity) AS quantity
+             FROM stock_movements m
+             JOIN tickets t ON t.id = m.reference
+             WHERE m.kind = 'consumption'
+               AND date(m.created_at, 'localtime') BETWEEN ?1 AND ?2
+             GROUP BY m.sku
+         ),
+         lost AS (
+             SELECT m.sku, -SUM(m.quantity) AS quantity
+             FROM stock_movements m
+             WHERE m.kind IN ('waste', 'adjustment')
+               AND date(m.created_at, 'localtime') BETWEEN ?1 AND ?2
+             GROUP BY m.sku
+         ),
+         usage AS (
+             SELECT p.sku, p.product_name,
+                    CAST(IFNULL(b.theoretical, 0) + IFNULL(s.quantity, 0) AS REAL) AS theoretical,
+                    CAST(IFNULL(b.actual, 0) + IFNULL(s.quantity, 0) + IFNULL(l.quantity, 0)
+                         AS REAL) AS actual
+             FROM products p
+             LEFT JOIN batches b ON b.sku = p.sku
+             LEFT JOIN sold s ON s.sku = p.sku
+             LEFT JOIN lost l ON l.sku = p.sku
+             WHERE b.sku IS NOT NULL OR s.sku IS NOT NULL
+                OR p.sku IN (SELECT ingredient_sku FROM recipe_ingredients)
+         )
+         SELECT sku, product_name, theoretical, actual, actual - theoretical AS variance,
+                CASE WHEN theoretical > 0
+                     THEN (actual - theoretical) / theoretical * 100 END AS variance_percent
+         FROM usage
+         ORDER BY ABS(actual - theoretical) DESC, product_name",
+    )
+    .bind(from_date)
+    .bind(to_date)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| e.to_string())
+}
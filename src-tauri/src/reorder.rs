@@ -0,0 +1,198 @@
+use crate::audit;
+use crate::db;
+use crate::users::{self, Permission};
+use serde::Serialize;
+use serde_json::json;
+use sqlx::{Pool, Sqlite};
+use std::collections::BTreeSet;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+/// How often the background task looks for products below their minimum.
+const LOW_STOCK_CHECK_INTERVAL: Duration = Duration::from_secs(5 * 60);
+/// Days of sales averaged for reorder suggestions.
+pub const DEFAULT_SALES_DAYS: i64 = 30;
+/// Days of sales a reorder should cover when the product has no maximum.
+pub const DEFAULT_COVER_DAYS: f64 = 7.0;
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct LowStockItem {
+    pub sku: String,
+    pub product_name: String,
+    pub quantity: f64,
+    pub min_stock: f64,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct ReorderSuggestion {
+    pub sku: String,
+    pub product_name: String,
+    pub supplier_id: Option<i64>,
+    pub supplier_name: Option<String>,
+    pub quantity: f64,
+    pub min_stock: Option<f64>,
+    pub max_stock: Option<f64>,
+    pub avg_daily_sales: f64,
+    #[sqlx(default)]
+    pub suggested_quantity: f64,
+}
+
+/// Tracked products at or below their minimum stock.
+pub async fn low_stock_items(pool: &Pool<Sqlite>) -> Result<Vec<LowStockItem>, String> {
+    sqlx::query_as::<_, LowStockItem>(
+        "SELECT p.sku, p.product_name,
+                CAST(IFNULL(l.quantity, 0) AS REAL) AS quantity,
+                CAST(p.min_stock AS REAL) AS min_stock
+         FROM products p
+         LEFT JOIN stock_levels l ON l.sku = p.sku
+         WHERE p.track_stock AND p.min_stock IS NOT NULL AND IFNULL(l.quantity, 0) <= p.min_stock
+         ORDER BY p.product_name",
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())
+}
+
+/// What to buy of each tracked product, from the average daily sales of
+/// the last `days` days. Products reorder when at or below `min_stock`
+/// (or, without one, when the stock covers less than `cover_days` of
+/// sales), up to `max_stock` or `min_stock` plus `cover_days` of sales.
+pub async fn reorder_suggestions(
+    pool: &Pool<Sqlite>,
+    days: i64,
+    cover_days: f64,
+) -> Result<Vec<ReorderSuggestion>, String> {
+    if days <= 0 || cover_days <= 0.0 {
+        return Err("The number of days must be positive".to_string());
+    }
+    let rows = sqlx::query_as::<_, ReorderSuggestion>(
+        "SELECT p.sku, p.product_name, p.supplier_id, s.name AS supplier_name,
+                CAST(IFNULL(l.quantity, 0) AS REAL) AS quantity,
+                CAST(p.min_stock AS REAL) AS min_stock,
+                CAST(p.max_stock AS REAL) AS max_stock,
+                CAST(IFNULL((
+                    SELECT SUM(ti.line_item_quantity)
+                    FROM ticket_items ti
+                    JOIN tickets t ON t.id = ti.ticket_id
+                    WHERE ti.line_item_sku = p.sku AND NOT ti.voided
+                      AND t.created_at >= datetime('now', ?1)
+                ), 0) AS REAL) / ?2 AS avg_daily_sales
+         FROM products p
+         LEFT JOIN stock_levels l ON l.sku = p.sku
+         LEFT JOIN suppliers s ON s.id = p.supplier_id
+         WHERE p.track_stock
+         ORDER BY s.name, p.product_name",
+    )
+    .bind(format!("-{} days", days))
+    .bind(days as f64)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    Ok(rows
+        .into_iter()
+        .filter_map(|mut row| {
+            let cover = row.avg_daily_sales * cover_days;
+            let is_low = match row.min_stock {
+                Some(min_stock) => row.quantity <= min_stock,
+                None => row.quantity < cover,
+            };
+            let target = row
+                .max_stock
+                .unwrap_or(row.min_stock.unwrap_or(0.0) + cover);
+            row.suggested_quantity = (target - row.quantity).ceil();
+            (is_low && row.suggested_quantity > 0.0).then_some(row)
+        })
+        .collect())
+}
+
+/// Starts the background task that emits a `low-stock` event with the
+/// products below their minimum whenever that list changes.
+pub fn spawn_low_stock_check(app_handle: AppHandle) {
+    std::thread::spawn(move || {
+        let mut last_skus = BTreeSet::new();
+        loop {
+            let items = tauri::async_runtime::block_on(async {
+                let pool = db::pool(&app_handle).await?;
+                low_stock_items(&pool).await
+            });
+            match items {
+                Ok(items) => {
+                    let skus: BTreeSet<String> =
+                        items.iter().map(|item| item.sku.clone()).collect();
+                    if skus != last_skus {
+                        if let Err(e) = app_handle.emit("low-stock", &items) {
+                            eprintln!("Failed to emit low-stock: {}", e);
+                        }
+                        last_skus = skus;
+                    }
+                }
+                Err(e) => eprintln!("Failed to check low stock: {}", e),
+            }
+            std::thread::sleep(LOW_STOCK_CHECK_INTERVAL);
+        }
+    });
+}
+
+/// Sets the minimum and maximum stock of a product; None clears them.
+#[tauri::command]
+pub async fn set_stock_thresholds(
+    app_handle: AppHandle,
+    sku: String,
+    min_stock: Option<f64>,
+    max_stock: Option<f64>,
+) -> Result<(), String> {
+    let user = users::require(&app_handle, Permission::ManageInventory)?;
+    if min_stock.is_some_and(|min| min < 0.0) || max_stock.is_some_and(|max| max < 0.0) {
+        return Err("Stock thresholds cannot be negative".to_string());
+    }
+    if let (Some(min), Some(max)) = (min_stock, max_stock) {
+        if min > max {
+            return Err("The minimum stock cannot be above the maximum".to_string());
+        }
+    }
+    let pool = db::pool(&app_handle).await?;
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    let updated = sqlx::query("UPDATE products SET min_stock = ?, max_stock = ? WHERE sku = ?")
+        .bind(min_stock)
+        .bind(max_stock)
+        .bind(&sku)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| e.to_string())?;
+    if updated.rows_affected() == 0 {
+        return Err(format!("Unknown product {}", sku));
+    }
+    audit::record(
+        &mut tx,
+        Some(&user),
+        "set_stock_thresholds",
+        "product",
+        Some(&sku),
+        None,
+        Some(json!({ "min_stock": min_stock, "max_stock": max_stock })),
+    )
+    .await?;
+    tx.commit().await.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_low_stock(app_handle: AppHandle) -> Result<Vec<LowStockItem>, String> {
+    let pool = db::pool(&app_handle).await?;
+    low_stock_items(&pool).await
+}
+
+#[tauri::command]
+pub async fn get_reorder_suggestions(
+    app_handle: AppHandle,
+    days: Option<i64>,
+    cover_days: Option<f64>,
+) -> Result<Vec<ReorderSuggestion>, String> {
+    let pool = db::pool(&app_handle).await?;
+    reorder_suggestions(
+        &pool,
+        days.unwrap_or(DEFAULT_SALES_DAYS),
+        cover_days.unwrap_or(DEFAULT_COVER_DAYS),
+    )
+    .await
+}
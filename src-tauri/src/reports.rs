@@ -0,0 +1,243 @@
+use crate::db;
+use crate::users::{self, Permission};
+use serde::Serialize;
+use tauri::AppHandle;
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct CategorySales {
+    pub category_id: Option<i64>,
+    pub category_name: String,
+    pub parent_id: Option<i64>,
+    pub tickets: i64,
+    pub quantity: f64,
+    pub total: f64,
+}
+
+/// Sales per category between two local dates (`YYYY-MM-DD`, both inclusive).
+/// Items are grouped by the product's current category; products that no
+/// longer exist or have no category are reported as "Sin categoría".
+/// Voided tickets are left out.
+#[tauri::command]
+pub async fn get_category_sales_report(
+    app_handle: AppHandle,
+    from_date: String,
+    to_date: String,
+) -> Result<Vec<CategorySales>, String> {
+    users::require(&app_handle, Permission::ViewReports)?;
+    let pool = db::pool(&app_handle).await?;
+    sqlx::query_as::<_, CategorySales>(
+        "SELECT c.id AS category_id,
+                IFNULL(c.name, 'Sin categoría') AS category_name,
+                c.parent_id,
+                COUNT(DISTINCT t.id) AS tickets,
+                CAST(IFNULL(SUM(ti.line_item_quantity), 0) AS REAL) AS quantity,
+                CAST(IFNULL(SUM(ti.line_item_total), 0) AS REAL) AS total
+         FROM ticket_items ti
+         JOIN tickets t ON t.id = ti.ticket_id
+         LEFT JOIN products p ON p.sku = ti.line_item_sku
+         LEFT JOIN categories c ON c.id = p.category_id
+         WHERE date(t.created_at, 'localtime') BETWEEN ?1 AND ?2 AND t.voided_at IS NULL
+         GROUP BY c.id
+         ORDER BY total DESC",
+    )
+    .bind(from_date)
+    .bind(to_date)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| e.to_string())
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct ZTotals {
+    /// Sales; returns are counted apart and net out of the amounts.
+    pub tickets: i64,
+    pub returns: i64,
+    /// Refunded, as a positive amount.
+    pub returns_total: f64,
+    /// Tickets voided that day, whatever day they were sold; voided
+    /// tickets are left out of every other total.
+    pub voids: i64,
+    pub voids_total: f64,
+    pub subtotal: f64,
+    pub taxes: f64,
+    pub total_due: f64,
+    pub pesos_paid: f64,
+    pub dollars_paid: f64,
+    pub cards_paid: f64,
+    pub others_paid: f64,
+    pub change: f64,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct CashierSales {
+    pub cashier_name: String,
+    pub tickets: i64,
+    pub total_due: f64,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct ApprovalEntry {
+    pub id: i64,
+    pub created_at: String,
+    pub action: String,
+    pub ticket_id: Option<String>,
+    pub requested_by: String,
+    pub approved_by: String,
+    pub reason: String,
+    pub amount: Option<f64>,
+    pub details: Option<String>,
+}
+
+/// End of day (Z) report for a local date (`YYYY-MM-DD`).
+#[derive(Debug, Serialize)]
+pub struct ZReport {
+    pub date: String,
+    pub totals: ZTotals,
+    pub cashiers: Vec<CashierSales>,
+    pub approvals: Vec<ApprovalEntry>,
+}
+
+#[tauri::command]
+pub async fn get_z_report(app_handle: AppHandle, date: String) -> Result<ZReport, String> {
+    users::require(&app_handle, Permission::ViewReports)?;
+    let pool = db::pool(&app_handle).await?;
+
+    let totals = sqlx::query_as::<_, ZTotals>(
+        "SELECT COUNT(*) - COUNT(original_ticket_id) AS tickets,
+                COUNT(original_ticket_id) AS returns,
+                CAST(-IFNULL(SUM(CASE WHEN original_ticket_id IS NOT NULL THEN total_due END), 0)
+                     AS REAL) AS returns_total,
+                (SELECT COUNT(*) FROM tickets v
+                 WHERE date(v.voided_at, 'localtime') = ?1)
+                    AS voids,
+                (SELECT CAST(IFNULL(SUM(v.total_due), 0) AS REAL) FROM tickets v
+                 WHERE date(v.voided_at, 'localtime') = ?1)
+                    AS voids_total,
+                CAST(IFNULL(SUM(subtotal), 0) AS REAL) AS subtotal,
+                CAST(IFNULL(SUM(taxes), 0) AS REAL) AS taxes,
+                CAST(IFNULL(SUM(total_due), 0) AS REAL) AS total_due,
+                CAST(IFNULL(SUM(pesos_paid), 0) AS REAL) AS pesos_paid,
+                CAST(IFNULL(SUM(dollars_paid), 0) AS REAL) AS dollars_paid,
+                CAST(IFNULL(SUM(cards_paid), 0) AS REAL) AS cards_paid,
+                CAST(IFNULL(SUM(others_paid), 0) AS REAL) AS others_paid,
+                CAST(IFNULL(SUM(change), 0) AS REAL) AS change
+         FROM tickets
+         WHERE date(created_at, 'localtime') = ?1 AND voided_at IS NULL",
+    )
+    .bind(&date)
+    .fetch_one(&pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let cashiers = sqlx::query_as::<_, CashierSales>(
+        "SELECT IFNULL(cashier_name, 'Sin cajero') AS cashier_name,
+                COUNT(*) - COUNT(original_ticket_id) AS tickets,
+                CAST(IFNULL(SUM(total_due), 0) AS REAL) AS total_due
+         FROM tickets
+         WHERE date(created_at, 'localtime') = ? AND voided_at IS NULL
+         GROUP BY cashier_id, cashier_name
+         ORDER BY total_due DESC",
+    )
+    .bind(&date)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let approvals = sqlx::query_as::<_, ApprovalEntry>(
+        "SELECT a.id, datetime(a.created_at, 'localtime') AS created_at, a.action, a.ticket_id,
+                r.display_name AS requested_by, m.display_name AS approved_by,
+                a.reason, CAST(a.amount AS REAL) AS amount, a.details
+         FROM approvals a
+         JOIN users r ON r.id = a.requested_by
+         JOIN users m ON m.id = a.approved_by
+         WHERE date(a.created_at, 'localtime') = ?
+         ORDER BY a.created_at",
+    )
+    .bind(&date)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    Ok(ZReport {
+        date,
+        totals,
+        cashiers,
+        approvals,
+    })
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct MarginRow {
+    /// SKU, category id or local date, depending on the grouping.
+    pub key: Option<String>,
+    pub label: String,
+    pub quantity: f64,
+    pub revenue: f64,
+    /// Cost of the items sold, at their average cost when sold.
+    pub cost: f64,
+    /// Revenue minus cost, of the items with a known cost only.
+    pub margin: f64,
+    pub margin_percent: Option<f64>,
+    /// Revenue of items sold before their product had a cost, left out
+    /// of the margin.
+    pub uncosted_revenue: f64,
+}
+
+/// Gross margin between two local dates (`YYYY-MM-DD`, both inclusive),
+/// per `product`, `category` (the product's current one) or `day`.
+/// Voided tickets are left out.
+#[tauri::command]
+pub async fn get_margin_report(
+    app_handle: AppHandle,
+    from_date: String,
+    to_date: String,
+    group_by: String,
+) -> Result<Vec<MarginRow>, String> {
+    users::require(&app_handle, Permission::ViewReports)?;
+    let (key, label, order) = match group_by.as_str() {
+        "product" => (
+            "ti.line_item_sku",
+            "MAX(ti.line_item_product_name)",
+            "margin DESC",
+        ),
+        "category" => (
+            "CAST(c.id AS TEXT)",
+            "IFNULL(MAX(c.name), 'Sin categoría')",
+            "margin DESC",
+        ),
+        "day" => (
+            "date(t.created_at, 'localtime')",
+            "date(t.created_at, 'localtime')",
+            "key",
+        ),
+        _ => return Err(format!("Unknown margin grouping {}", group_by)),
+    };
+    let pool = db::pool(&app_handle).await?;
+    sqlx::query_as::<_, MarginRow>(&format!(
+        "SELECT key, label, quantity, revenue, cost, costed_revenue - cost AS margin,
+                CASE WHEN costed_revenue > 0
+                     THEN (costed_revenue - cost) / costed_revenue * 100 END AS margin_percent,
+                revenue - costed_revenue AS uncosted_revenue
+         FROM (
+             SELECT {key} AS key, {label} AS label,
+                    CAST(IFNULL(SUM(ti.line_item_quantity), 0) AS REAL) AS quantity,
+                    CAST(IFNULL(SUM(ti.line_item_total), 0) AS REAL) AS revenue,
+                    CAST(IFNULL(SUM(ti.line_item_quantity * ti.line_item_cost), 0) AS REAL) AS cost,
+                    CAST(IFNULL(SUM(CASE WHEN ti.line_item_cost IS NOT NULL
+                                         THEN ti.line_item_total END), 0) AS REAL)
+                        AS costed_revenue
+             FROM ticket_items ti
+             JOIN tickets t ON t.id = ti.ticket_id
+             LEFT JOIN products p ON p.sku = ti.line_item_sku
+             LEFT JOIN categories c ON c.id = p.category_id
+             WHERE date(t.created_at, 'localtime') BETWEEN ?1 AND ?2 AND t.voided_at IS NULL
+             GROUP BY {key}
+         )
+         ORDER BY {order}"
+    ))
+    .bind(from_date)
+    .bind(to_date)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| e.to_string())
+}
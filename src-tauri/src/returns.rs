@@ -0,0 +1,476 @@
+use crate::approvals::{self, Approval};
+use crate::audit;
+use crate::cash_sessions;
+use crate::db;
+use crate::inventory::{self, MovementKind};
+use crate::sales::POS_ID;
+use crate::settings::{self, RefundRate};
+use crate::users::{self, Permission};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use sqlx::{Pool, Sqlite, SqliteConnection};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::AppHandle;
+
+/// Quantities below this are rounding noise, not items still to return.
+const QUANTITY_EPSILON: f64 = 0.0005;
+
+/// An item of a sale and how much of it was already returned.
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct ReturnableItem {
+    pub id: String,
+    pub sku: String,
+    #[serde(skip)]
+    pub plu_code: Option<String>,
+    #[serde(skip)]
+    pub barcode: Option<String>,
+    pub product_name: String,
+    pub price: f64,
+    pub quantity: f64,
+    pub returned: f64,
+    #[serde(skip)]
+    pub cost: Option<f64>,
+}
+
+/// A sale as the return screen needs it.
+#[derive(Debug, Serialize)]
+pub struct ReturnableTicket {
+    pub id: String,
+    pub created_at: String,
+    pub subtotal: f64,
+    pub taxes: f64,
+    pub total_due: f64,
+    /// Pesos per dollar refunds of this sale are paid at.
+    pub refund_rate: f64,
+    pub items: Vec<ReturnableItem>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ReturnLine {
+    /// Item of the original ticket.
+    pub item_id: String,
+    pub quantity: f64,
+    /// Puts the item back in stock; false for goods that cannot be sold
+    /// again. Defaults to true.
+    pub restock: Option<bool>,
+}
+
+/// Money given back, per tender. Dollars are converted at the refund rate.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct RefundTenders {
+    pub pesos: f64,
+    pub dollars: f64,
+    pub cards: f64,
+    pub others: f64,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct ReturnedItem {
+    pub product_name: String,
+    pub quantity: f64,
+    pub price: f64,
+    pub total: f64,
+}
+
+/// A return as printed on its receipt. Amounts are positive.
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct ReturnReceipt {
+    pub id: String,
+    pub original_ticket_id: String,
+    pub created_at: String,
+    pub cashier_name: Option<String>,
+    pub exchange_rate: f64,
+    pub total: f64,
+    pub pesos: f64,
+    pub dollars: f64,
+    pub cards: f64,
+    pub others: f64,
+    #[sqlx(skip)]
+    pub items: Vec<ReturnedItem>,
+}
+
+#[derive(sqlx::FromRow)]
+struct OriginalTicket {
+    id: String,
+    created_at: String,
+    subtotal: f64,
+    taxes: f64,
+    total_due: f64,
+    exchange_rate: Option<f64>,
+    original_ticket_id: Option<String>,
+    voided_at: Option<String>,
+}
+
+fn return_id(original_ticket_id: &str) -> String {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_nanos())
+        .unwrap_or_default();
+    let data = format!("{}return{}", original_ticket_id, nanos);
+    hex::encode(Sha256::digest(data.as_bytes()))
+}
+
+/// Id of a returned item: sha256 of return id and original item id.
+fn return_item_id(return_id: &str, item_id: &str) -> String {
+    hex::encode(Sha256::digest(
+        format!("{}{}", return_id, item_id).as_bytes(),
+    ))
+}
+
+/// Finds a sale by its id or by the folio printed on the ticket (the
+/// start of the id). The folio is compared as is, so `%` or `_` in it
+/// match nothing, and a folio shared by several tickets is refused.
+async fn original_ticket(
+    conn: &mut SqliteConnection,
+    folio: &str,
+) -> Result<OriginalTicket, String> {
+    let folio = folio.trim();
+    if folio.is_empty() {
+        return Err("Enter the folio of the ticket".to_string());
+    }
+    let mut tickets = sqlx::query_as::<_, OriginalTicket>(
+        "SELECT id, datetime(created_at, 'localtime') AS created_at,
+                CAST(IFNULL(subtotal, total_due) AS REAL) AS subtotal,
+                CAST(IFNULL(taxes, 0) AS REAL) AS taxes,
+                CAST(total_due AS REAL) AS total_due,
+                CAST(exchange_rate AS REAL) AS exchange_rate, original_ticket_id, voided_at
+         FROM tickets
+         WHERE substr(id, 1, length(?1)) = ?1
+         LIMIT 2",
+    )
+    .bind(folio.to_lowercase())
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(|e| e.to_string())?;
+    if tickets.len() > 1 {
+        return Err(format!("More than one ticket starts with {}", folio));
+    }
+    let ticket = tickets
+        .pop()
+        .ok_or_else(|| format!("Unknown ticket {}", folio))?;
+    if ticket.original_ticket_id.is_some() {
+        return Err(format!("Ticket {} is a return", folio));
+    }
+    if ticket.voided_at.is_some() {
+        return Err(format!("Ticket {} is void", folio));
+    }
+    Ok(ticket)
+}
+
+async fn returnable_items(
+    conn: &mut SqliteConnection,
+    ticket_id: &str,
+) -> Result<Vec<ReturnableItem>, String> {
+    sqlx::query_as::<_, ReturnableItem>(
+        "SELECT ti.id, ti.line_item_sku AS sku, ti.line_item_plu_code AS plu_code,
+                ti.line_item_barcode AS barcode, ti.line_item_product_name AS product_name,
+                CAST(ti.line_item_price AS REAL) AS price,
+                CAST(ti.line_item_quantity AS REAL) AS quantity,
+                CAST(IFNULL((
+                    SELECT -SUM(r.line_item_quantity) FROM ticket_items r
+                    WHERE r.original_item_id = ti.id AND NOT r.voided
+                ), 0) AS REAL) AS returned,
+                CAST(ti.line_item_cost AS REAL) AS cost
+         FROM ticket_items ti
+         WHERE ti.ticket_id = ?
+         ORDER BY ti.rowid",
+    )
+    .bind(ticket_id)
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(|e| e.to_string())
+}
+
+/// Pairs each line with the item of the sale it returns, refusing lines
+/// for more than is left to return.
+fn returned_lines<'a>(
+    items: &'a [ReturnableItem],
+    lines: &'a [ReturnLine],
+) -> Result<Vec<(&'a ReturnableItem, &'a ReturnLine)>, String> {
+    let mut returned: Vec<(&ReturnableItem, &ReturnLine)> = Vec::with_capacity(lines.len());
+    for line in lines {
+        let item = items
+            .iter()
+            .find(|item| item.id == line.item_id)
+            .ok_or_else(|| format!("Item {} is not part of the ticket", line.item_id))?;
+        if returned.iter().any(|(other, _)| other.id == item.id) {
+            return Err(format!("{} is listed twice", item.product_name));
+        }
+        let returnable = item.quantity - item.returned;
+        if line.quantity > returnable + QUANTITY_EPSILON {
+            return Err(format!(
+                "{}: returning {} but only {} can be returned",
+                item.product_name, line.quantity, returnable
+            ));
+        }
+        returned.push((item, line));
+    }
+    Ok(returned)
+}
+
+/// Pesos per dollar refunds of `ticket` are paid at, per the refund rate
+/// setting.
+async fn refund_rate(pool: &Pool<Sqlite>, ticket: &OriginalTicket) -> Result<f64, String> {
+    let settings = settings::load(pool).await?;
+    Ok(match settings.refund_rate {
+        RefundRate::Original => ticket
+            .exchange_rate
+            .unwrap_or(settings.exchange_rate_usd_to_mxn),
+        RefundRate::Current => settings.exchange_rate_usd_to_mxn,
+    })
+}
+
+/// A sale to return items from, by id or printed folio.
+#[tauri::command]
+pub async fn get_returnable_ticket(
+    app_handle: AppHandle,
+    folio: String,
+) -> Result<ReturnableTicket, String> {
+    users::require(&app_handle, Permission::Sell)?;
+    let pool = db::pool(&app_handle).await?;
+    let mut conn = pool.acquire().await.map_err(|e| e.to_string())?;
+    let ticket = original_ticket(&mut conn, &folio).await?;
+    let items = returnable_items(&mut conn, &ticket.id).await?;
+    let refund_rate = refund_rate(&pool, &ticket).await?;
+    Ok(ReturnableTicket {
+        id: ticket.id,
+        created_at: ticket.created_at,
+        subtotal: ticket.subtotal,
+        taxes: ticket.taxes,
+        total_due: ticket.total_due,
+        refund_rate,
+        items,
+    })
+}
+
+/// Returns items of a sale. Records a negative ticket linked to the
+/// original one, with the items at their sold price and cost, puts them
+/// back in stock and pays the refund in the given tenders, which must add
+/// up to the refunded amount, out of the cashier's open cash session. Taxes are refunded in proportion. Takes the
+/// `refund` permission or a manager's `approval`. Returns the id of the
+/// return ticket.
+#[tauri::command]
+pub async fn create_return(
+    app_handle: AppHandle,
+    original_ticket_id: String,
+    lines: Vec<ReturnLine>,
+    refund_tenders: RefundTenders,
+    approval: Option<Approval>,
+) -> Result<String, String> {
+    let cashier = users::require(&app_handle, Permission::Sell)?;
+    if lines.is_empty() {
+        return Err("Nothing is being returned".to_string());
+    }
+    if lines.iter().any(|line| line.quantity <= 0.0) {
+        return Err("Returned quantities must be positive".to_string());
+    }
+    let tenders = &refund_tenders;
+    if [
+        tenders.pesos,
+        tenders.dollars,
+        tenders.cards,
+        tenders.others,
+    ]
+    .iter()
+    .any(|amount| *amount < 0.0)
+    {
+        return Err("Refunded amounts cannot be negative".to_string());
+    }
+    let pool = db::pool(&app_handle).await?;
+    let (cash_session_id, original, items) = {
+        let mut conn = pool.acquire().await.map_err(|e| e.to_string())?;
+        let cash_session_id = cash_sessions::current_id(&mut conn, &cashier).await?;
+        let original = original_ticket(&mut conn, &original_ticket_id).await?;
+        let items = returnable_items(&mut conn, &original.id).await?;
+        (cash_session_id, original, items)
+    };
+    let returned = returned_lines(&items, &lines)?;
+
+    let subtotal: f64 = returned
+        .iter()
+        .map(|(item, line)| item.price * line.quantity)
+        .sum();
+    let taxes = if original.subtotal > 0.0 {
+        original.taxes * subtotal / original.subtotal
+    } else {
+        0.0
+    };
+    let total = ((subtotal + taxes) * 100.0).round() / 100.0;
+    let exchange_rate = refund_rate(&pool, &original).await?;
+    let refunded = tenders.pesos + tenders.dollars * exchange_rate + tenders.cards + tenders.others;
+    if (refunded - total).abs() >= 0.01 {
+        return Err(format!(
+            "The refund {:.2} does not match the returned amount {:.2}",
+            refunded, total
+        ));
+    }
+
+    let message = format!(
+        "{} needs approval to refund {:.2} of ticket {}",
+        cashier.display_name,
+        total,
+        &original.id[..7.min(original.id.len())]
+    );
+    let approver = approvals::authorize(
+        &pool,
+        &cashier,
+        Permission::Refund,
+        &message,
+        approval.as_ref(),
+    )
+    .await?;
+
+    let id = return_id(&original.id);
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    sqlx::query(
+        "INSERT INTO tickets (
+             id, subtotal, taxes, total_due, dollars_paid, pesos_paid, cards_paid, others_paid,
+             total_paid, change, cashier_id, cashier_name, pos_id, exchange_rate,
+             original_ticket_id, cash_session_id
+         ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, 0, ?, ?, ?, ?, ?, ?)",
+    )
+    .bind(&id)
+    .bind(-subtotal)
+    .bind(-taxes)
+    .bind(-total)
+    .bind(-tenders.dollars)
+    .bind(-tenders.pesos)
+    .bind(-tenders.cards)
+    .bind(-tenders.others)
+    .bind(-refunded)
+    .bind(cashier.id)
+    .bind(&cashier.display_name)
+    .bind(POS_ID)
+    .bind(exchange_rate)
+    .bind(&original.id)
+    .bind(cash_session_id)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    // Checked again now that the insert holds the write lock: another
+    // return or a void of the sale may have been written meanwhile
+    let original = original_ticket(&mut tx, &original.id).await?;
+    let items = returnable_items(&mut tx, &original.id).await?;
+    let returned = returned_lines(&items, &lines)?;
+
+    for (item, line) in &returned {
+        sqlx::query(
+            "INSERT INTO ticket_items (
+                 id, ticket_id, line_item_sku, line_item_plu_code, line_item_barcode,
+                 line_item_product_name, line_item_price, line_item_quantity, line_item_total,
+                 line_item_cost, original_item_id
+             ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(return_item_id(&id, &item.id))
+        .bind(&id)
+        .bind(&item.sku)
+        .bind(&item.plu_code)
+        .bind(&item.barcode)
+        .bind(&item.product_name)
+        .bind(item.price)
+        .bind(-line.quantity)
+        .bind(-item.price * line.quantity)
+        .bind(item.cost)
+        .bind(&item.id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| e.to_string())?;
+        if line.restock.unwrap_or(true) {
+            inventory::record_movement(
+                &mut tx,
+                &item.sku,
+                MovementKind::Return,
+                line.quantity,
+                Some(&id),
+                None,
+                Some(&cashier),
+            )
+            .await?;
+        }
+    }
+
+    if let Some(approver) = &approver {
+        let details = returned
+            .iter()
+            .map(|(item, line)| format!("{} x{}", item.product_name, line.quantity))
+            .collect::<Vec<_>>()
+            .join("; ");
+        approvals::record(
+            &mut tx,
+            Permission::Refund,
+            Some(&id),
+            &cashier,
+            approver,
+            Some(total),
+            &details,
+        )
+        .await?;
+    }
+    audit::record(
+        &mut tx,
+        Some(&cashier),
+        "create_return",
+        "ticket",
+        Some(&id),
+        None,
+        Some(json!({
+            "original_ticket_id": original.id,
+            "total": total,
+            "exchange_rate": exchange_rate,
+            "refund": {
+                "pesos": tenders.pesos,
+                "dollars": tenders.dollars,
+                "cards": tenders.cards,
+                "others": tenders.others,
+            },
+            "items": returned
+                .iter()
+                .map(|(item, line)| json!({
+                    "sku": item.sku,
+                    "quantity": line.quantity,
+                    "restock": line.restock.unwrap_or(true),
+                }))
+                .collect::<Vec<_>>(),
+        })),
+    )
+    .await?;
+    tx.commit().await.map_err(|e| e.to_string())?;
+    Ok(id)
+}
+
+/// A return ticket with its items, for its receipt.
+pub async fn load_return(pool: &Pool<Sqlite>, id: &str) -> Result<ReturnReceipt, String> {
+    let mut receipt = sqlx::query_as::<_, ReturnReceipt>(
+        "SELECT id, original_ticket_id, datetime(created_at, 'localtime') AS created_at,
+                cashier_name, CAST(IFNULL(exchange_rate, 0) AS REAL) AS exchange_rate,
+                CAST(-total_due AS REAL) AS total,
+                CAST(-IFNULL(pesos_paid, 0) AS REAL) AS pesos,
+                CAST(-IFNULL(dollars_paid, 0) AS REAL) AS dollars,
+                CAST(-IFNULL(cards_paid, 0) AS REAL) AS cards,
+                CAST(-IFNULL(others_paid, 0) AS REAL) AS others
+         FROM tickets
+         WHERE id = ? AND original_ticket_id IS NOT NULL",
+    )
+    .bind(id)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| e.to_string())?
+    .ok_or_else(|| format!("Unknown return {}", id))?;
+    receipt.items = sqlx::query_as::<_, ReturnedItem>(
+        "SELECT line_item_product_name AS product_name,
+                CAST(-line_item_quantity AS REAL) AS quantity,
+                CAST(line_item_price AS REAL) AS price,
+                CAST(-line_item_total AS REAL) AS total
+         FROM ticket_items
+         WHERE ticket_id = ?
+         ORDER BY rowid",
+    )
+    .bind(id)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+    Ok(receipt)
+}
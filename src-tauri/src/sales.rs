@@ -0,0 +1,376 @@
+use crate::approvals::{self, Approval};
+use crate::cash_sessions;
+use crate::db;
+use crate::inventory::{self, MovementKind};
+use crate::recipes;
+use crate::settings;
+use crate::users::{self, Permission};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tauri::AppHandle;
+
+/// Terminal id stored on every ticket.
+pub const POS_ID: &str = "POS1";
+
+#[derive(Debug, Deserialize)]
+pub struct SaleItem {
+    pub sku: String,
+    pub plu_code: Option<String>,
+    pub barcode: Option<String>,
+    pub product_name: String,
+    pub price: f64,
+    pub quantity: f64,
+}
+
+/// A paid ticket as built by the sales bar.
+#[derive(Debug, Deserialize)]
+pub struct NewSale {
+    pub id: String,
+    pub subtotal: f64,
+    pub taxes: f64,
+    pub total_due: f64,
+    pub dollars_paid: f64,
+    pub pesos_paid: f64,
+    pub cards_paid: f64,
+    pub others_paid: f64,
+    pub total_paid: f64,
+    pub change: f64,
+    pub items: Vec<SaleItem>,
+}
+
+/// Same id the webview used to generate: sha256 of ticket id, sku, quantity and price.
+fn ticket_item_id(ticket_id: &str, item: &SaleItem) -> String {
+    let data = format!("{}{}{}{}", ticket_id, item.sku, item.quantity, item.price);
+    hex::encode(Sha256::digest(data.as_bytes()))
+}
+
+/// Checks that the amounts of a ticket are sane and add up: the total is
+/// the subtotal plus taxes, the tenders (dollars at `exchange_rate`) cover
+/// it and the change is what they paid over it.
+fn check_amounts(sale: &NewSale, exchange_rate: f64) -> Result<(), String> {
+    let amounts = [
+        sale.subtotal,
+        sale.taxes,
+        sale.total_due,
+        sale.dollars_paid,
+        sale.pesos_paid,
+        sale.cards_paid,
+        sale.others_paid,
+        sale.total_paid,
+        sale.change,
+    ];
+    if amounts
+        .iter()
+        .any(|amount| !amount.is_finite() || *amount < 0.0)
+    {
+        return Err("Ticket amounts must be positive numbers".to_string());
+    }
+    if (sale.subtotal + sale.taxes - sale.total_due).abs() >= 0.01 {
+        return Err(format!(
+            "The ticket total {:.2} is not its subtotal plus taxes ({:.2})",
+            sale.total_due,
+            sale.subtotal + sale.taxes
+        ));
+    }
+    let paid =
+        sale.pesos_paid + sale.dollars_paid * exchange_rate + sale.cards_paid + sale.others_paid;
+    if (paid - sale.total_paid).abs() >= 0.01 {
+        return Err(format!(
+            "The amount paid {:.2} does not match the tenders ({:.2})",
+            sale.total_paid, paid
+        ));
+    }
+    if sale.total_paid < sale.total_due - 0.01 {
+        return Err(format!(
+            "The amount paid {:.2} does not cover the total {:.2}",
+            sale.total_paid, sale.total_due
+        ));
+    }
+    if (sale.change - (sale.total_paid - sale.total_due)).abs() >= 0.01 {
+        return Err(format!(
+            "The change {:.2} should be {:.2}",
+            sale.change,
+            sale.total_paid - sale.total_due
+        ));
+    }
+    Ok(())
+}
+
+/// Records a paid ticket and its items in one transaction, as the signed in
+/// cashier in their open cash session, and takes the items out of stock for products that track it
+/// (or their ingredients, for recipes sold fresh). Selling off the catalog price (other than at one of the
+/// product's price tiers, or within the role's discount limit) takes the
+/// `discount` permission or a manager's `approval`, which is stored with
+/// the ticket. The payment must add up, see `check_amounts`. Warns when the
+/// drawer goes over its limit.
+#[tauri::command]
+pub async fn record_sale(
+    app_handle: AppHandle,
+    sale: NewSale,
+    approval: Option<Approval>,
+) -> Result<String, String> {
+    let cashier = users::require(&app_handle, Permission::Sell)?;
+    if sale.items.is_empty() {
+        return Err("The ticket has no items".to_string());
+    }
+    if sale
+        .items
+        .iter()
+        .any(|item| item.quantity <= 0.0 || item.price < 0.0)
+    {
+        return Err("Ticket items need a positive quantity and price".to_string());
+    }
+    let items_total: f64 = sale
+        .items
+        .iter()
+        .map(|item| item.price * item.quantity)
+        .sum();
+    if (items_total - sale.subtotal).abs() >= 0.01 {
+        return Err(format!(
+            "The ticket subtotal {:.2} does not match its items ({:.2})",
+            sale.subtotal, items_total
+        ));
+    }
+
+    let pool = db::pool(&app_handle).await?;
+    let exchange_rate = settings::load(&pool).await?.exchange_rate_usd_to_mxn;
+    check_amounts(&sale, exchange_rate)?;
+    let cash_session_id = {
+        let mut conn = pool.acquire().await.map_err(|e| e.to_string())?;
+        cash_sessions::current_id(&mut conn, &cashier).await?
+    };
+
+    // Items sold off their catalog price: (item, catalog price)
+    let mut overrides = Vec::new();
+    for item in &sale.items {
+        let catalog: Option<(f64, bool)> = sqlx::query_as(
+            "SELECT CAST(p.price AS REAL),
+                    EXISTS (
+                        SELECT 1 FROM product_prices pp
+                        WHERE pp.sku = p.sku AND ABS(CAST(pp.price AS REAL) - ?2) < 0.005
+                    )
+             FROM products p WHERE p.sku = ?1",
+        )
+        .bind(&item.sku)
+        .bind(item.price)
+        .fetch_optional(&pool)
+        .await
+        .map_err(|e| e.to_string())?;
+        // Unknown SKUs have no price to check the sold one against
+        let Some((catalog_price, at_tier)) = catalog else {
+            return Err(format!(
+                "{} ({}) is not in the catalog",
+                item.product_name, item.sku
+            ));
+        };
+        if at_tier || (item.price - catalog_price).abs() < 0.005 {
+            continue;
+        }
+        let within_limit = item.price < catalog_price
+            && (catalog_price - item.price) / catalog_price * 100.0
+                <= cashier.max_discount_percent + 0.005;
+        if !within_limit {
+            overrides.push((item, catalog_price));
+        }
+    }
+
+    let approver = match overrides.first() {
+        Some((item, catalog_price)) => {
+            let message = format!(
+                "{} needs approval to sell {} at {:.2} (price {:.2})",
+                cashier.display_name, item.product_name, item.price, catalog_price
+            );
+            approvals::authorize(
+                &pool,
+                &cashier,
+                Permission::Discount,
+                &message,
+                approval.as_ref(),
+            )
+            .await?
+        }
+        None => None,
+    };
+
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+
+    sqlx::query(
+        "INSERT INTO tickets (
+             id, subtotal, taxes, total_due, dollars_paid, pesos_paid, cards_paid, others_paid,
+             total_paid, change, cashier_id, cashier_name, pos_id, exchange_rate,
+             cash_session_id
+         ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+    )
+    .bind(&sale.id)
+    .bind(sale.subtotal)
+    .bind(sale.taxes)
+    .bind(sale.total_due)
+    .bind(sale.dollars_paid)
+    .bind(sale.pesos_paid)
+    .bind(sale.cards_paid)
+    .bind(sale.others_paid)
+    .bind(sale.total_paid)
+    .bind(sale.change)
+    .bind(cashier.id)
+    .bind(&cashier.display_name)
+    .bind(POS_ID)
+    .bind(exchange_rate)
+    .bind(cash_session_id)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    for item in &sale.items {
+        let recipe_cost = recipes::consume_ingredients(
+            &mut tx,
+            &item.sku,
+            item.quantity,
+            MovementKind::Consumption,
+            &sale.id,
+            &cashier,
+        )
+        .await?;
+        sqlx::query(
+            "INSERT INTO ticket_items (
+                 id, ticket_id, line_item_sku, line_item_plu_code, line_item_barcode,
+                 line_item_product_name, line_item_price, line_item_quantity, line_item_total,
+                 line_item_cost
+             ) VALUES (
+                 ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9,
+                 IFNULL(?10, (SELECT CAST(IFNULL(average_cost, cost) AS REAL) FROM products WHERE sku = ?3))
+             )",
+        )
+        .bind(ticket_item_id(&sale.id, item))
+        .bind(&sale.id)
+        .bind(&item.sku)
+        .bind(&item.plu_code)
+        .bind(&item.barcode)
+        .bind(&item.product_name)
+        .bind(item.price)
+        .bind(item.quantity)
+        .bind(item.price * item.quantity)
+        .bind(recipe_cost)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| e.to_string())?;
+        inventory::record_movement(
+            &mut tx,
+            &item.sku,
+            MovementKind::Sale,
+            -item.quantity,
+            Some(&sale.id),
+            None,
+            Some(&cashier),
+        )
+        .await?;
+    }
+
+    if let Some(approver) = &approver {
+        // Discount given; negative when sold above the catalog price
+        let amount: f64 = overrides
+            .iter()
+            .map(|(item, catalog_price)| (catalog_price - item.price) * item.quantity)
+            .sum();
+        let details = overrides
+            .iter()
+            .map(|(item, catalog_price)| {
+                format!(
+                    "{} {:.2} -> {:.2}",
+                    item.product_name, catalog_price, item.price
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("; ");
+        approvals::record(
+            &mut tx,
+            Permission::Discount,
+            Some(&sale.id),
+            &cashier,
+            approver,
+            Some(amount),
+            &details,
+        )
+        .await?;
+    }
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+    cash_sessions::notify_drawer_limit(&app_handle, &pool, cash_session_id).await;
+    Ok(sale.id)
+}
+
+/// A ticket as listed on the sales screen.
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct SaleSummary {
+    pub id: String,
+    pub created_at: String,
+    pub subtotal: f64,
+    pub taxes: f64,
+    pub total_due: f64,
+    pub dollars_paid: f64,
+    pub pesos_paid: f64,
+    pub cards_paid: f64,
+    pub others_paid: f64,
+    pub total_paid: f64,
+    pub change: f64,
+    pub cashier_name: Option<String>,
+    pub original_ticket_id: Option<String>,
+    pub voided_at: Option<String>,
+    pub void_reason: Option<String>,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct SaleLine {
+    pub line_item_sku: String,
+    pub line_item_product_name: String,
+    pub line_item_price: f64,
+    pub line_item_quantity: f64,
+    pub line_item_total: f64,
+}
+
+/// Every ticket, newest first, returns and voided ones included.
+#[tauri::command]
+pub async fn get_sales(app_handle: AppHandle) -> Result<Vec<SaleSummary>, String> {
+    users::require(&app_handle, Permission::Sell)?;
+    let pool = db::pool(&app_handle).await?;
+    sqlx::query_as::<_, SaleSummary>(
+        "SELECT id, created_at,
+                CAST(IFNULL(subtotal, 0) AS REAL) AS subtotal,
+                CAST(IFNULL(taxes, 0) AS REAL) AS taxes,
+                CAST(total_due AS REAL) AS total_due,
+                CAST(IFNULL(dollars_paid, 0) AS REAL) AS dollars_paid,
+                CAST(IFNULL(pesos_paid, 0) AS REAL) AS pesos_paid,
+                CAST(IFNULL(cards_paid, 0) AS REAL) AS cards_paid,
+                CAST(IFNULL(others_paid, 0) AS REAL) AS others_paid,
+                CAST(IFNULL(total_paid, 0) AS REAL) AS total_paid,
+                CAST(IFNULL(change, 0) AS REAL) AS change,
+                cashier_name, original_ticket_id, voided_at, void_reason
+         FROM tickets
+         ORDER BY created_at DESC",
+    )
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| e.to_string())
+}
+
+/// Items of a ticket, as sold.
+#[tauri::command]
+pub async fn get_sale_items(
+    app_handle: AppHandle,
+    ticket_id: String,
+) -> Result<Vec<SaleLine>, String> {
+    users::require(&app_handle, Permission::Sell)?;
+    let pool = db::pool(&app_handle).await?;
+    sqlx::query_as::<_, SaleLine>(
+        "SELECT line_item_sku, line_item_product_name,
+                CAST(line_item_price AS REAL) AS line_item_price,
+                CAST(line_item_quantity AS REAL) AS line_item_quantity,
+                CAST(line_item_total AS REAL) AS line_item_total
+         FROM ticket_items
+         WHERE ticket_id = ?
+         ORDER BY rowid",
+    )
+    .bind(ticket_id)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| e.to_string())
+}
@@ -0,0 +1,100 @@
+use crate::barcodes::PRODUCT_COLUMNS;
+use crate::db;
+use crate::types::product;
+use tauri::AppHandle;
+
+const DEFAULT_SEARCH_LIMIT: u32 = 50;
+
+/// Turns what the cashier typed into an FTS5 MATCH expression.
+/// Every word becomes a quoted prefix term, so "tortilla mai" becomes
+/// `"tortilla"* "mai"*` and matches "Tortilla Maíz" (terms are ANDed).
+/// Returns None when the query has nothing searchable in it.
+fn to_match_expression(query: &str) -> Option<String> {
+    let terms: Vec<String> = query
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|term| !term.is_empty())
+        .map(|term| format!("\"{}\"*", term))
+        .collect();
+
+    if terms.is_empty() {
+        None
+    } else {
+        Some(terms.join(" "))
+    }
+}
+
+/// Searches the catalog by name, SKU, PLU code or barcode.
+/// A product whose SKU, PLU code or barcode is exactly the query comes
+/// first, even when the name matches rank it out of the top hits; the
+/// rest is ordered by FTS rank.
+#[tauri::command]
+pub async fn search_products(
+    app_handle: AppHandle,
+    query: String,
+    limit: Option<u32>,
+) -> Result<Vec<product>, String> {
+    let code = query.trim();
+    if code.is_empty() {
+        return Ok(Vec::new());
+    }
+    let limit = limit.unwrap_or(DEFAULT_SEARCH_LIMIT);
+    let pool = db::pool(&app_handle).await?;
+
+    let mut products = sqlx::query_as::<_, product>(&format!(
+        "SELECT {PRODUCT_COLUMNS}
+         FROM products p
+         WHERE p.sku = ?1 OR p.plu_code = ?1
+            OR EXISTS (SELECT 1 FROM product_barcodes b WHERE b.sku = p.sku AND b.barcode = ?1)
+         ORDER BY p.sku = ?1 DESC, p.sku
+         LIMIT ?2"
+    ))
+    .bind(code)
+    .bind(limit)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let Some(match_expression) = to_match_expression(&query) else {
+        return Ok(products);
+    };
+    // Rank inside the FTS table first and only join the top hits,
+    // which keeps broad queries like "t" cheap on large catalogs.
+    let hits = sqlx::query_as::<_, product>(&format!(
+        "SELECT {PRODUCT_COLUMNS}
+         FROM (
+             SELECT rowid, rank FROM products_fts
+             WHERE products_fts MATCH ?1
+             ORDER BY rank
+             LIMIT ?2
+         ) AS hits
+         JOIN products p ON p.rowid = hits.rowid
+         ORDER BY hits.rank"
+    ))
+    .bind(match_expression)
+    .bind(limit)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    for hit in hits {
+        if products.len() >= limit as usize {
+            break;
+        }
+        if !products.iter().any(|product| product.sku == hit.sku) {
+            products.push(hit);
+        }
+    }
+    Ok(products)
+}
+
+/// The whole catalog, for the product grid when nothing is searched.
+#[tauri::command]
+pub async fn get_products(app_handle: AppHandle) -> Result<Vec<product>, String> {
+    let pool = db::pool(&app_handle).await?;
+    sqlx::query_as::<_, product>(&format!(
+        "SELECT {PRODUCT_COLUMNS} FROM products p ORDER BY p.rowid"
+    ))
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| e.to_string())
+}
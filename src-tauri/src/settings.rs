@@ -0,0 +1,337 @@
+use crate::audit;
+use crate::db;
+use crate::users::{self, Permission};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sqlx::{Pool, Sqlite, SqliteConnection};
+use std::collections::HashMap;
+use tauri::{AppHandle, Emitter};
+
+/// Version of the layout of the `settings` rows. Bump it and extend
+/// `upgrade` when a setting is renamed or changes its format.
+const SETTINGS_VERSION: i64 = 1;
+
+const VERSION_KEY: &str = "settings_version";
+const THERMAL_PRINTER_KEY: &str = "thermal_printer";
+const EXCHANGE_RATE_KEY: &str = "exchange_rate_usd_to_mxn";
+const REFUND_RATE_KEY: &str = "refund_rate";
+const DRAWER_LIMIT_PESOS_KEY: &str = "drawer_limit_pesos";
+const DRAWER_LIMIT_DOLLARS_KEY: &str = "drawer_limit_dollars";
+
+const DEFAULT_EXCHANGE_RATE: f64 = 20.0;
+/// Pesos per dollar accepted for the exchange rate; anything outside is a typo.
+const EXCHANGE_RATE_RANGE: std::ops::RangeInclusive<f64> = 10.0..=40.0;
+
+/// Printer as listed by `get_printers`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PrinterSetting {
+    pub vid: u16,
+    pub pid: u16,
+    #[serde(default)]
+    pub manufacturer: String,
+    #[serde(default)]
+    pub product: String,
+}
+
+/// Exchange rate dollars are refunded at.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RefundRate {
+    /// The rate of the returned sale, so the customer gets back the
+    /// dollars they paid. Sales recorded before the rate was stored use
+    /// today's.
+    #[default]
+    Original,
+    /// Today's rate.
+    Current,
+}
+
+/// Station settings shared by every window.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Settings {
+    pub thermal_printer: Option<PrinterSetting>,
+    pub exchange_rate_usd_to_mxn: f64,
+    #[serde(default)]
+    pub refund_rate: RefundRate,
+    /// Cash in the drawer, per currency, above which a safe drop is
+    /// suggested. None for no limit.
+    #[serde(default)]
+    pub drawer_limit_pesos: Option<f64>,
+    #[serde(default)]
+    pub drawer_limit_dollars: Option<f64>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            thermal_printer: None,
+            exchange_rate_usd_to_mxn: DEFAULT_EXCHANGE_RATE,
+            refund_rate: RefundRate::default(),
+            drawer_limit_pesos: None,
+            drawer_limit_dollars: None,
+        }
+    }
+}
+
+impl Settings {
+    pub fn validate(&self) -> Result<(), String> {
+        let rate = self.exchange_rate_usd_to_mxn;
+        if !rate.is_finite() || !EXCHANGE_RATE_RANGE.contains(&rate) {
+            return Err(format!(
+                "Exchange rate {} is outside {}-{} MXN per USD",
+                rate,
+                EXCHANGE_RATE_RANGE.start(),
+                EXCHANGE_RATE_RANGE.end()
+            ));
+        }
+        if [self.drawer_limit_pesos, self.drawer_limit_dollars]
+            .iter()
+            .flatten()
+            .any(|limit| !limit.is_finite() || *limit <= 0.0)
+        {
+            return Err("Drawer limits must be positive".to_string());
+        }
+        Ok(())
+    }
+
+    /// Builds the settings from the stored rows. Missing or invalid values
+    /// fall back to their default and are logged, never guessed.
+    fn from_rows(rows: &HashMap<String, String>) -> Self {
+        let mut settings = Settings::default();
+
+        if let Some(value) = rows.get(THERMAL_PRINTER_KEY) {
+            match serde_json::from_str::<Option<PrinterSetting>>(value) {
+                Ok(printer) => settings.thermal_printer = printer,
+                Err(e) => eprintln!(
+                    "Ignoring invalid {} setting {}: {}",
+                    THERMAL_PRINTER_KEY, value, e
+                ),
+            }
+        }
+
+        if let Some(value) = rows.get(EXCHANGE_RATE_KEY) {
+            // Older versions stored the rate as a quoted string, e.g. "20.00"
+            let rate = serde_json::from_str::<f64>(value)
+                .ok()
+                .or_else(|| {
+                    serde_json::from_str::<String>(value)
+                        .ok()?
+                        .trim()
+                        .parse()
+                        .ok()
+                })
+                .filter(|rate| EXCHANGE_RATE_RANGE.contains(rate));
+            match rate {
+                Some(rate) => settings.exchange_rate_usd_to_mxn = rate,
+                None => eprintln!("Ignoring invalid {} setting {}", EXCHANGE_RATE_KEY, value),
+            }
+        }
+
+        if let Some(value) = rows.get(REFUND_RATE_KEY) {
+            match serde_json::from_str::<RefundRate>(value) {
+                Ok(refund_rate) => settings.refund_rate = refund_rate,
+                Err(e) => eprintln!(
+                    "Ignoring invalid {} setting {}: {}",
+                    REFUND_RATE_KEY, value, e
+                ),
+            }
+        }
+
+        for (key, limit) in [
+            (DRAWER_LIMIT_PESOS_KEY, &mut settings.drawer_limit_pesos),
+            (DRAWER_LIMIT_DOLLARS_KEY, &mut settings.drawer_limit_dollars),
+        ] {
+            if let Some(value) = rows.get(key) {
+                match serde_json::from_str::<Option<f64>>(value) {
+                    Ok(None) => *limit = None,
+                    Ok(Some(amount)) if amount.is_finite() && amount > 0.0 => *limit = Some(amount),
+                    _ => eprintln!("Ignoring invalid {} setting {}", key, value),
+                }
+            }
+        }
+
+        settings
+    }
+}
+
+async fn read_rows(pool: &Pool<Sqlite>) -> Result<HashMap<String, String>, String> {
+    Ok(
+        sqlx::query_as::<_, (String, String)>("SELECT key, CAST(value AS TEXT) FROM settings")
+            .fetch_all(pool)
+            .await
+            .map_err(|e| e.to_string())?
+            .into_iter()
+            .collect(),
+    )
+}
+
+fn stored_version(rows: &HashMap<String, String>) -> i64 {
+    rows.get(VERSION_KEY)
+        .and_then(|value| value.trim().parse().ok())
+        .unwrap_or(0)
+}
+
+/// Writes every setting. Call it in a transaction, with the audit entries
+/// of the change when there are any.
+async fn save(conn: &mut SqliteConnection, settings: &Settings) -> Result<(), String> {
+    let values = [
+        (
+            THERMAL_PRINTER_KEY,
+            serde_json::to_string(&settings.thermal_printer).map_err(|e| e.to_string())?,
+        ),
+        (
+            EXCHANGE_RATE_KEY,
+            serde_json::to_string(&settings.exchange_rate_usd_to_mxn).map_err(|e| e.to_string())?,
+        ),
+        (
+            REFUND_RATE_KEY,
+            serde_json::to_string(&settings.refund_rate).map_err(|e| e.to_string())?,
+        ),
+        (
+            DRAWER_LIMIT_PESOS_KEY,
+            serde_json::to_string(&settings.drawer_limit_pesos).map_err(|e| e.to_string())?,
+        ),
+        (
+            DRAWER_LIMIT_DOLLARS_KEY,
+            serde_json::to_string(&settings.drawer_limit_dollars).map_err(|e| e.to_string())?,
+        ),
+        (VERSION_KEY, SETTINGS_VERSION.to_string()),
+    ];
+
+    for (key, value) in values {
+        sqlx::query(
+            "INSERT INTO settings (key, value, updated_at) VALUES (?, ?, CURRENT_TIMESTAMP)
+             ON CONFLICT(key) DO UPDATE SET value = excluded.value, updated_at = CURRENT_TIMESTAMP",
+        )
+        .bind(key)
+        .bind(value)
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// Rewrites settings stored by an older version in the current format.
+async fn upgrade(pool: &Pool<Sqlite>, rows: &HashMap<String, String>) -> Result<(), String> {
+    // Version 0 (ad-hoc values written by the webview) only needs its values
+    // normalized, which `from_rows` already does.
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    save(&mut tx, &Settings::from_rows(rows)).await?;
+    tx.commit().await.map_err(|e| e.to_string())
+}
+
+/// Loads the settings, upgrading rows written by older versions.
+pub async fn load(pool: &Pool<Sqlite>) -> Result<Settings, String> {
+    let mut rows = read_rows(pool).await?;
+    if stored_version(&rows) < SETTINGS_VERSION {
+        upgrade(pool, &rows).await?;
+        rows = read_rows(pool).await?;
+    }
+    Ok(Settings::from_rows(&rows))
+}
+
+#[tauri::command]
+pub async fn get_settings(app_handle: AppHandle) -> Result<Settings, String> {
+    let pool = db::pool(&app_handle).await?;
+    load(&pool).await
+}
+
+/// Validates and stores the settings, then notifies every window with a
+/// `settings-changed` event carrying the new values. Changing the exchange
+/// rate or the refund rate takes the `change_rate` permission, the printer
+/// or the drawer limits `manage_settings`. Changes are written to the
+/// audit log in the same transaction.
+#[tauri::command]
+pub async fn update_settings(
+    app_handle: AppHandle,
+    settings: Settings,
+) -> Result<Settings, String> {
+    settings.validate()?;
+    let pool = db::pool(&app_handle).await?;
+
+    let current = load(&pool).await?;
+    let rate_changed =
+        (current.exchange_rate_usd_to_mxn - settings.exchange_rate_usd_to_mxn).abs() >= 0.005;
+    let refund_rate_changed = current.refund_rate != settings.refund_rate;
+    let drawer_limit_changed = current.drawer_limit_pesos != settings.drawer_limit_pesos
+        || current.drawer_limit_dollars != settings.drawer_limit_dollars;
+    let printer_changed = current.thermal_printer != settings.thermal_printer;
+    if rate_changed || refund_rate_changed {
+        users::require(&app_handle, Permission::ChangeRate)?;
+    }
+    if drawer_limit_changed || printer_changed {
+        users::require(&app_handle, Permission::ManageSettings)?;
+    }
+
+    let version = stored_version(&read_rows(&pool).await?);
+    if version > SETTINGS_VERSION {
+        return Err(format!(
+            "Settings were saved by a newer version of BorderPOS (settings version {})",
+            version
+        ));
+    }
+
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    save(&mut tx, &settings).await?;
+    let user = users::current_user(&app_handle);
+    if rate_changed {
+        audit::record(
+            &mut tx,
+            user.as_ref(),
+            "change_exchange_rate",
+            "settings",
+            Some(EXCHANGE_RATE_KEY),
+            Some(json!(current.exchange_rate_usd_to_mxn)),
+            Some(json!(settings.exchange_rate_usd_to_mxn)),
+        )
+        .await?;
+    }
+    if refund_rate_changed {
+        audit::record(
+            &mut tx,
+            user.as_ref(),
+            "change_refund_rate",
+            "settings",
+            Some(REFUND_RATE_KEY),
+            Some(json!(current.refund_rate)),
+            Some(json!(settings.refund_rate)),
+        )
+        .await?;
+    }
+    if drawer_limit_changed {
+        audit::record(
+            &mut tx,
+            user.as_ref(),
+            "change_drawer_limit",
+            "settings",
+            None,
+            Some(json!({
+                "pesos": current.drawer_limit_pesos,
+                "dollars": current.drawer_limit_dollars,
+            })),
+            Some(json!({
+                "pesos": settings.drawer_limit_pesos,
+                "dollars": settings.drawer_limit_dollars,
+            })),
+        )
+        .await?;
+    }
+    if printer_changed {
+        audit::record(
+            &mut tx,
+            user.as_ref(),
+            "change_printer",
+            "settings",
+            Some(THERMAL_PRINTER_KEY),
+            Some(json!(current.thermal_printer)),
+            Some(json!(settings.thermal_printer)),
+        )
+        .await?;
+    }
+    tx.commit().await.map_err(|e| e.to_string())?;
+    app_handle
+        .emit("settings-changed", &settings)
+        .map_err(|e| e.to_string())?;
+    Ok(settings)
+}
@@ -0,0 +1,401 @@
+use crate::audit;
+use crate::db;
+use crate::inventory::{self, MovementKind};
+use crate::users::{self, Permission};
+use serde::Serialize;
+use serde_json::json;
+use sqlx::{Pool, Sqlite, SqliteConnection};
+use tauri::AppHandle;
+
+/// Variances below this are rounding noise, not stock to adjust.
+const QUANTITY_EPSILON: f64 = 0.0005;
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct StockCount {
+    pub id: i64,
+    /// None for a full count.
+    pub category_id: Option<i64>,
+    pub category_name: Option<String>,
+    pub status: String,
+    pub notes: Option<String>,
+    pub created_by_name: Option<String>,
+    pub created_at: String,
+    pub posted_by_name: Option<String>,
+    pub posted_at: Option<String>,
+    pub lines: i64,
+    pub counted: i64,
+    pub recounts: i64,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct StockCountLine {
+    pub id: i64,
+    pub sku: String,
+    pub product_name: String,
+    /// Stock when the count started.
+    pub expected_quantity: f64,
+    pub counted_quantity: Option<f64>,
+    /// Count replaced by the last recount.
+    pub previous_quantity: Option<f64>,
+    pub recount: bool,
+    pub counted_by_name: Option<String>,
+    pub counted_at: Option<String>,
+    /// Counted minus expected; None until counted.
+    pub variance: Option<f64>,
+    pub unit_cost: Option<f64>,
+    pub variance_cost: Option<f64>,
+    /// Quantity posted to stock once approved.
+    pub adjustment: Option<f64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct StockCountDetail {
+    pub count: StockCount,
+    pub lines: Vec<StockCountLine>,
+}
+
+const COUNT_QUERY: &str = "SELECT c.id, c.category_id, c.category_name, c.status, c.notes,
+        cu.display_name AS created_by_name,
+        datetime(c.created_at, 'localtime') AS created_at,
+        pu.display_name AS posted_by_name,
+        datetime(c.posted_at, 'localtime') AS posted_at,
+        (SELECT COUNT(*) FROM stock_count_lines l WHERE l.count_id = c.id) AS lines,
+        (SELECT COUNT(*) FROM stock_count_lines l
+         WHERE l.count_id = c.id AND l.counted_quantity IS NOT NULL) AS counted,
+        (SELECT COUNT(*) FROM stock_count_lines l
+         WHERE l.count_id = c.id AND l.recount) AS recounts
+    FROM stock_counts c
+    LEFT JOIN users cu ON cu.id = c.created_by
+    LEFT JOIN users pu ON pu.id = c.posted_by";
+
+/// Reference of the adjustments posted by a count, e.g. `CONTEO-3`.
+fn reference(id: i64) -> String {
+    format!("CONTEO-{}", id)
+}
+
+async fn count_lines(conn: &mut SqliteConnection, id: i64) -> Result<Vec<StockCountLine>, String> {
+    sqlx::query_as::<_, StockCountLine>(
+        "SELECT l.id, l.sku, l.product_name,
+                CAST(l.expected_quantity AS REAL) AS expected_quantity,
+                CAST(l.counted_quantity AS REAL) AS counted_quantity,
+                CAST(l.previous_quantity AS REAL) AS previous_quantity,
+                l.recount, u.display_name AS counted_by_name,
+                datetime(l.counted_at, 'localtime') AS counted_at,
+                CAST(l.counted_quantity - l.expected_quantity AS REAL) AS variance,
+                CAST(IFNULL(p.average_cost, p.cost) AS REAL) AS unit_cost,
+                CAST((l.counted_quantity - l.expected_quantity) * IFNULL(p.average_cost, p.cost)
+                     AS REAL) AS variance_cost,
+                CAST(l.adjustment AS REAL) AS adjustment
+         FROM stock_count_lines l
+         LEFT JOIN products p ON p.sku = l.sku
+         LEFT JOIN users u ON u.id = l.counted_by
+         WHERE l.count_id = ?
+         ORDER BY l.product_name",
+    )
+    .bind(id)
+    .fetch_all(conn)
+    .await
+    .map_err(|e| e.to_string())
+}
+
+/// A count with its lines, for the screen, the count sheet and the
+/// variance report.
+pub async fn load_count(pool: &Pool<Sqlite>, id: i64) -> Result<StockCountDetail, String> {
+    let count = sqlx::query_as::<_, StockCount>(&format!("{COUNT_QUERY} WHERE c.id = ?"))
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Unknown stock count {}", id))?;
+    let mut conn = pool.acquire().await.map_err(|e| e.to_string())?;
+    let lines = count_lines(&mut conn, id).await?;
+    Ok(StockCountDetail { count, lines })
+}
+
+async fn ensure_open(conn: &mut SqliteConnection, id: i64) -> Result<(), String> {
+    let status: String = sqlx::query_scalar("SELECT status FROM stock_counts WHERE id = ?")
+        .bind(id)
+        .fetch_optional(conn)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Unknown stock count {}", id))?;
+    if status != "open" {
+        return Err(format!("Stock count {} is already {}", id, status));
+    }
+    Ok(())
+}
+
+/// Stock counts, newest first.
+#[tauri::command]
+pub async fn get_stock_counts(app_handle: AppHandle) -> Result<Vec<StockCount>, String> {
+    users::require(&app_handle, Permission::ManageInventory)?;
+    let pool = db::pool(&app_handle).await?;
+    sqlx::query_as::<_, StockCount>(&format!("{COUNT_QUERY} ORDER BY c.id DESC"))
+        .fetch_all(&pool)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_stock_count(app_handle: AppHandle, id: i64) -> Result<StockCountDetail, String> {
+    users::require(&app_handle, Permission::ManageInventory)?;
+    let pool = db::pool(&app_handle).await?;
+    load_count(&pool, id).await
+}
+
+/// Starts a count of the tracked products of a category and its
+/// subcategories, or of every tracked product when no category is given,
+/// taking a snapshot of their stock. Returns the id of the count.
+#[tauri::command]
+pub async fn create_stock_count(
+    app_handle: AppHandle,
+    category_id: Option<i64>,
+    notes: Option<String>,
+) -> Result<i64, String> {
+    let user = users::require(&app_handle, Permission::ManageInventory)?;
+    let pool = db::pool(&app_handle).await?;
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+
+    let category_name = match category_id {
+        Some(category_id) => Some(
+            sqlx::query_scalar::<_, String>("SELECT name FROM categories WHERE id = ?")
+                .bind(category_id)
+                .fetch_optional(&mut *tx)
+                .await
+                .map_err(|e| e.to_string())?
+                .ok_or_else(|| format!("Unknown category {}", category_id))?,
+        ),
+        None => None,
+    };
+    let notes = notes
+        .map(|notes| notes.trim().to_string())
+        .filter(|notes| !notes.is_empty());
+
+    let id = sqlx::query(
+        "INSERT INTO stock_counts (category_id, category_name, notes, created_by)
+         VALUES (?, ?, ?, ?)",
+    )
+    .bind(category_id)
+    .bind(&category_name)
+    .bind(&notes)
+    .bind(user.id)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| e.to_string())?
+    .last_insert_rowid();
+
+    let lines = sqlx::query(
+        "WITH RECURSIVE tree(id) AS (
+             SELECT id FROM categories WHERE id = ?2
+             UNION
+             SELECT c.id FROM categories c JOIN tree ON c.parent_id = tree.id
+         )
+         INSERT INTO stock_count_lines (count_id, sku, product_name, expected_quantity)
+         SELECT ?1, p.sku, p.product_name, IFNULL(s.quantity, 0)
+         FROM products p
+         LEFT JOIN stock_levels s ON s.sku = p.sku
+         WHERE p.track_stock AND (?2 IS NULL OR p.category_id IN (SELECT id FROM tree))",
+    )
+    .bind(id)
+    .bind(category_id)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| e.to_string())?
+    .rows_affected();
+    if lines == 0 {
+        return Err("There are no tracked products to count".to_string());
+    }
+
+    audit::record(
+        &mut tx,
+        Some(&user),
+        "create_stock_count",
+        "stock_count",
+        Some(&id.to_string()),
+        None,
+        Some(json!({
+            "category_id": category_id,
+            "category_name": category_name,
+            "lines": lines,
+        })),
+    )
+    .await?;
+    tx.commit().await.map_err(|e| e.to_string())?;
+    Ok(id)
+}
+
+/// Records the counted quantity of a product. Scans `add` to what was
+/// already counted; typed quantities replace it. Counting a product
+/// flagged for recount clears the flag. Returns the counted quantity.
+#[tauri::command]
+pub async fn record_count(
+    app_handle: AppHandle,
+    id: i64,
+    sku: String,
+    quantity: f64,
+    add: Option<bool>,
+) -> Result<f64, String> {
+    let user = users::require(&app_handle, Permission::ManageInventory)?;
+    if quantity < 0.0 {
+        return Err("Counted quantities cannot be negative".to_string());
+    }
+    let pool = db::pool(&app_handle).await?;
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    ensure_open(&mut tx, id).await?;
+    let counted: f64 = sqlx::query_scalar(
+        "UPDATE stock_count_lines SET
+             counted_quantity = CASE WHEN ?1 THEN IFNULL(counted_quantity, 0) + ?2 ELSE ?2 END,
+             recount = 0,
+             counted_by = ?3,
+             counted_at = CURRENT_TIMESTAMP
+         WHERE count_id = ?4 AND sku = ?5
+         RETURNING CAST(counted_quantity AS REAL)",
+    )
+    .bind(add.unwrap_or(false))
+    .bind(quantity)
+    .bind(user.id)
+    .bind(id)
+    .bind(&sku)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|e| e.to_string())?
+    .ok_or_else(|| format!("Product {} is not part of stock count {}", sku, id))?;
+    tx.commit().await.map_err(|e| e.to_string())?;
+    Ok(counted)
+}
+
+/// Flags products to be counted again. Their count is kept as the
+/// previous quantity and cleared, so they cannot be posted until
+/// recounted.
+#[tauri::command]
+pub async fn request_recount(
+    app_handle: AppHandle,
+    id: i64,
+    skus: Vec<String>,
+) -> Result<(), String> {
+    let user = users::require(&app_handle, Permission::ManageInventory)?;
+    let pool = db::pool(&app_handle).await?;
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    ensure_open(&mut tx, id).await?;
+    for sku in &skus {
+        sqlx::query(
+            "UPDATE stock_count_lines SET
+                 previous_quantity = IFNULL(counted_quantity, previous_quantity),
+                 counted_quantity = NULL,
+                 recount = 1
+             WHERE count_id = ? AND sku = ?",
+        )
+        .bind(id)
+        .bind(sku)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| e.to_string())?;
+    }
+    audit::record(
+        &mut tx,
+        Some(&user),
+        "request_recount",
+        "stock_count",
+        Some(&id.to_string()),
+        None,
+        Some(json!({ "skus": skus })),
+    )
+    .await?;
+    tx.commit().await.map_err(|e| e.to_string())
+}
+
+/// Closes a count, posting the variance of the approved products as
+/// adjustments. Adjustments apply to the stock as it is now, so sales
+/// made since the snapshot are kept. Products not approved or not
+/// counted are left as they are. Returns the number of adjustments.
+#[tauri::command]
+pub async fn post_stock_count(
+    app_handle: AppHandle,
+    id: i64,
+    approved: Vec<String>,
+) -> Result<usize, String> {
+    let user = users::require(&app_handle, Permission::ManageInventory)?;
+    let pool = db::pool(&app_handle).await?;
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    ensure_open(&mut tx, id).await?;
+    let lines = count_lines(&mut tx, id).await?;
+
+    let reference = reference(id);
+    let mut adjusted = Vec::new();
+    for sku in &approved {
+        let line = lines
+            .iter()
+            .find(|line| &line.sku == sku)
+            .ok_or_else(|| format!("Product {} is not part of stock count {}", sku, id))?;
+        let Some(variance) = line.variance else {
+            return Err(format!("{} has not been counted", line.product_name));
+        };
+        if variance.abs() < QUANTITY_EPSILON {
+            continue;
+        }
+        inventory::record_movement(
+            &mut tx,
+            sku,
+            MovementKind::Adjustment,
+            variance,
+            Some(&reference),
+            Some("Conteo físico"),
+            Some(&user),
+        )
+        .await?;
+        sqlx::query("UPDATE stock_count_lines SET adjustment = ? WHERE id = ?")
+            .bind(variance)
+            .bind(line.id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| e.to_string())?;
+        adjusted.push(json!({ "sku": sku, "quantity": variance }));
+    }
+
+    sqlx::query(
+        "UPDATE stock_counts SET status = 'posted', posted_by = ?, posted_at = CURRENT_TIMESTAMP
+         WHERE id = ?",
+    )
+    .bind(user.id)
+    .bind(id)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| e.to_string())?;
+    audit::record(
+        &mut tx,
+        Some(&user),
+        "post_stock_count",
+        "stock_count",
+        Some(&id.to_string()),
+        Some(json!({ "status": "open" })),
+        Some(json!({ "status": "posted", "adjustments": adjusted })),
+    )
+    .await?;
+    tx.commit().await.map_err(|e| e.to_string())?;
+    Ok(adjusted.len())
+}
+
+/// Discards an open count without touching the stock.
+#[tauri::command]
+pub async fn cancel_stock_count(app_handle: AppHandle, id: i64) -> Result<(), String> {
+    let user = users::require(&app_handle, Permission::ManageInventory)?;
+    let pool = db::pool(&app_handle).await?;
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    ensure_open(&mut tx, id).await?;
+    sqlx::query("UPDATE stock_counts SET status = 'cancelled' WHERE id = ?")
+        .bind(id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| e.to_string())?;
+    audit::record(
+        &mut tx,
+        Some(&user),
+        "cancel_stock_count",
+        "stock_count",
+        Some(&id.to_string()),
+        Some(json!({ "status": "open" })),
+        Some(json!({ "status": "cancelled" })),
+    )
+    .await?;
+    tx.commit().await.map_err(|e| e.to_string())
+}
@@ -0,0 +1,133 @@
+use crate::audit;
+use crate::db;
+use crate::users::{self, Permission};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tauri::AppHandle;
+
+#[derive(Debug, Serialize, Deserialize, sqlx::FromRow)]
+pub struct Supplier {
+    #[serde(default)]
+    pub id: i64,
+    pub name: String,
+    pub contact_name: Option<String>,
+    pub phone: Option<String>,
+    pub email: Option<String>,
+    pub notes: Option<String>,
+    /// Currency the supplier invoices in, `MXN` or `USD`.
+    #[serde(default = "default_currency")]
+    pub currency: String,
+    #[serde(default = "default_active")]
+    pub is_active: bool,
+}
+
+fn default_currency() -> String {
+    "MXN".to_string()
+}
+
+fn default_active() -> bool {
+    true
+}
+
+fn validate(supplier: &Supplier) -> Result<(), String> {
+    if supplier.name.trim().is_empty() {
+        return Err("The supplier needs a name".to_string());
+    }
+    if !matches!(supplier.currency.as_str(), "MXN" | "USD") {
+        return Err(format!("Unknown currency {}", supplier.currency));
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_suppliers(app_handle: AppHandle) -> Result<Vec<Supplier>, String> {
+    let pool = db::pool(&app_handle).await?;
+    sqlx::query_as::<_, Supplier>(
+        "SELECT id, name, contact_name, phone, email, notes, currency, is_active
+         FROM suppliers
+         ORDER BY is_active DESC, name",
+    )
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| e.to_string())
+}
+
+/// Creates a supplier and returns its id.
+#[tauri::command]
+pub async fn create_supplier(app_handle: AppHandle, supplier: Supplier) -> Result<i64, String> {
+    users::require(&app_handle, Permission::ManageInventory)?;
+    validate(&supplier)?;
+    let pool = db::pool(&app_handle).await?;
+    let result = sqlx::query(
+        "INSERT INTO suppliers (name, contact_name, phone, email, notes, currency)
+         VALUES (?, ?, ?, ?, ?, ?)",
+    )
+    .bind(supplier.name.trim())
+    .bind(&supplier.contact_name)
+    .bind(&supplier.phone)
+    .bind(&supplier.email)
+    .bind(&supplier.notes)
+    .bind(&supplier.currency)
+    .execute(&pool)
+    .await
+    .map_err(|e| e.to_string())?;
+    Ok(result.last_insert_rowid())
+}
+
+#[tauri::command]
+pub async fn update_supplier(app_handle: AppHandle, supplier: Supplier) -> Result<(), String> {
+    users::require(&app_handle, Permission::ManageInventory)?;
+    validate(&supplier)?;
+    let pool = db::pool(&app_handle).await?;
+    sqlx::query(
+        "UPDATE suppliers
+         SET name = ?, contact_name = ?, phone = ?, email = ?, notes = ?, currency = ?,
+             is_active = ?,
+             updated_at = CURRENT_TIMESTAMP
+         WHERE id = ?",
+    )
+    .bind(supplier.name.trim())
+    .bind(&supplier.contact_name)
+    .bind(&supplier.phone)
+    .bind(&supplier.email)
+    .bind(&supplier.notes)
+    .bind(&supplier.currency)
+    .bind(supplier.is_active)
+    .bind(supplier.id)
+    .execute(&pool)
+    .await
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Sets (or clears, with None) who a product is bought from.
+#[tauri::command]
+pub async fn set_product_supplier(
+    app_handle: AppHandle,
+    sku: String,
+    supplier_id: Option<i64>,
+) -> Result<(), String> {
+    let user = users::require(&app_handle, Permission::ManageInventory)?;
+    let pool = db::pool(&app_handle).await?;
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    let updated = sqlx::query("UPDATE products SET supplier_id = ? WHERE sku = ?")
+        .bind(supplier_id)
+        .bind(&sku)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| e.to_string())?;
+    if updated.rows_affected() == 0 {
+        return Err(format!("Unknown product {}", sku));
+    }
+    audit::record(
+        &mut tx,
+        Some(&user),
+        "set_product_supplier",
+        "product",
+        Some(&sku),
+        None,
+        Some(json!({ "supplier_id": supplier_id })),
+    )
+    .await?;
+    tx.commit().await.map_err(|e| e.to_string())
+}
@@ -1,8 +1,22 @@
+use crate::cash_counts::{self, CashCountDetail};
+use crate::cash_movements::{self, CashMovement};
+use crate::cash_sessions::CashSessionReport;
+use crate::purchases::PurchaseOrderDetail;
+use crate::returns::ReturnReceipt;
+use crate::settings;
+use crate::stock_counts::StockCountDetail;
 use crate::types::ticket;
+use crate::voids::VoidSlip;
+use crate::waste::{self, WasteReport};
 use escpos::driver::*;
 use escpos::errors::PrinterError;
 use escpos::printer::Printer;
+use escpos::printer_options::PrinterOptions;
 use escpos::utils::*;
+use sqlx::{Pool, Sqlite};
+
+/// Characters per line of the thermal printer slips.
+const LINE_WIDTH: usize = 42;
 
 /// Formats a sale item for a 42-character line based on the new receipt format.
 /// - name: Item name (truncated to 30 chars with ".." if longer than 32).
@@ -17,8 +31,6 @@ fn format_sale_item(
     total: f32,
 ) -> (String, String, String) {
     const NAME_WIDTH: usize = 44; // Max 32 chars for name, including ".." if truncated
-    const TOTAL_WIDTH: usize = 12; // e.g., "$630.00", right-aligned
-    const LINE_WIDTH: usize = 44; // Total line width
 
     // Truncate name to 30 chars and append ".." if longer than 32, or use full name if shorter
     let name_truncated = if name.len() > NAME_WIDTH {
@@ -224,3 +236,603 @@ pub fn print_ticket<D: Driver>(driver: D, ticket_data: &ticket) -> String {
         }
     }
 }
+
+#[cfg(not(windows))]
+pub type ThermalDriver = UsbDriver;
+#[cfg(windows)]
+pub type ThermalDriver = crate::windows_printing::WindowsDriver;
+
+/// Opens the thermal printer configured in the settings and prints on it
+/// with `print`. Text is sent in code page PC858, which has the accented
+/// letters, ñ and ¿¡ of the Spanish slips.
+pub async fn with_thermal_printer<F>(pool: &Pool<Sqlite>, print: F) -> Result<(), String>
+where
+    F: FnOnce(&mut Printer<ThermalDriver>) -> Result<(), PrinterError>,
+{
+    let printer = settings::load(pool)
+        .await?
+        .thermal_printer
+        .ok_or("No thermal printer is configured")?;
+    #[cfg(not(windows))]
+    let driver = UsbDriver::open(printer.vid, printer.pid, None).map_err(|e| e.to_string())?;
+    #[cfg(windows)]
+    let driver = {
+        use crate::windows_printing::{WindowsDriver, WindowsPrinter};
+        let windows_printer =
+            WindowsPrinter::from_str(printer.product.as_str()).map_err(|e| e.to_string())?;
+        WindowsDriver::open(&windows_printer).map_err(|e| e.to_string())?
+    };
+    let options = PrinterOptions::new(Some(PageCode::PC858), None, LINE_WIDTH as u8);
+    let mut printer = Printer::new(driver, Protocol::default(), Some(options));
+    print(&mut printer).map_err(|e| e.to_string())
+}
+
+/// Sends the pulse that opens the cash drawer wired to the printer.
+pub fn open_cash_drawer<D: Driver>(printer: &mut Printer<D>) -> Result<(), PrinterError> {
+    printer.init()?.cash_drawer(CashDrawer::Pin2)?.print()?;
+    Ok(())
+}
+
+/// Prints a purchase order to hand to the supplier or the driver: one
+/// line per product with the ordered quantity, cost and amount.
+pub fn print_purchase_order<D: Driver>(
+    printer: &mut Printer<D>,
+    detail: &PurchaseOrderDetail,
+) -> Result<(), PrinterError> {
+    let order = &detail.order;
+    printer
+        .init()?
+        .justify(JustifyMode::CENTER)?
+        .bold(true)?
+        .writeln("ORDEN DE COMPRA")?
+        .bold(false)?
+        .writeln("")?
+        .justify(JustifyMode::LEFT)?
+        .writeln(&format!("Folio: OC-{}", order.id))?
+        .writeln(&format!("Fecha: {}", order.created_at))?
+        .writeln(&format!("Proveedor: {}", order.supplier_name))?
+        .writeln(&format!("Moneda: {}", order.currency))?
+        .writeln("------------------------------------------")?;
+
+    for line in &detail.lines {
+        let name: String = line.product_name.chars().take(LINE_WIDTH).collect();
+        let quantity = format!("{} x ${:.2}", line.quantity_ordered, line.unit_cost);
+        let amount = format!("${:.2}", line.quantity_ordered * line.unit_cost);
+        printer.writeln(&name)?;
+        printer.writeln(&format!(
+            "  {:<width$}{:>12}",
+            quantity,
+            amount,
+            width = LINE_WIDTH - 14
+        ))?;
+    }
+
+    printer
+        .writeln("------------------------------------------")?
+        .bold(true)?
+        .writeln(&format!(
+            "{:>width$}",
+            format!("TOTAL {} ${:.2}", order.currency, order.total),
+            width = LINE_WIDTH
+        ))?
+        .bold(false)?;
+    if let Some(notes) = order.notes.as_deref().filter(|notes| !notes.is_empty()) {
+        printer.writeln("")?.writeln(notes)?;
+    }
+    if let Some(created_by) = &order.created_by_name {
+        printer
+            .writeln("")?
+            .writeln(&format!("Elaboró: {}", created_by))?;
+    }
+    printer.feeds(3)?.print_cut()?;
+    Ok(())
+}
+
+/// Prints the waste of a day for the owner's end-of-day review: each
+/// record with its quantity, reason and cost, then the totals per reason.
+pub fn print_waste_report<D: Driver>(
+    printer: &mut Printer<D>,
+    report: &WasteReport,
+) -> Result<(), PrinterError> {
+    printer
+        .init()?
+        .justify(JustifyMode::CENTER)?
+        .bold(true)?
+        .writeln("REPORTE DE MERMAS")?
+        .bold(false)?
+        .writeln(&report.date)?
+        .writeln("")?
+        .justify(JustifyMode::LEFT)?
+        .writeln("------------------------------------------")?;
+
+    for entry in &report.entries {
+        let name: String = entry.product_name.chars().take(LINE_WIDTH).collect();
+        let detail = format!("{} {}", entry.quantity, waste::reason_label(&entry.reason));
+        let cost = entry
+            .cost
+            .map(|cost| format!("${:.2}", cost))
+            .unwrap_or_else(|| "sin costo".to_string());
+        printer.writeln(&name)?;
+        printer.writeln(&format!(
+            "  {:<width$}{:>12}",
+            detail,
+            cost,
+            width = LINE_WIDTH - 14
+        ))?;
+        if let Some(notes) = entry.notes.as_deref() {
+            let notes: String = notes.chars().take(LINE_WIDTH - 2).collect();
+            printer.writeln(&format!("  {}", notes))?;
+        }
+    }
+    if report.entries.is_empty() {
+        printer.writeln("Sin mermas registradas")?;
+    }
+
+    printer.writeln("------------------------------------------")?;
+    for total in &report.by_reason {
+        let label = format!("{} ({})", waste::reason_label(&total.reason), total.entries);
+        printer.writeln(&format!(
+            "{:<width$}{:>12}",
+            label,
+            format!("${:.2}", total.cost),
+            width = LINE_WIDTH - 12
+        ))?;
+    }
+    printer
+        .bold(true)?
+        .writeln(&format!(
+            "{:>width$}",
+            format!("TOTAL ${:.2}", report.total_cost),
+            width = LINE_WIDTH
+        ))?
+        .bold(false)?
+        .feeds(3)?
+        .print_cut()?;
+    Ok(())
+}
+
+fn count_title(detail: &StockCountDetail) -> String {
+    format!(
+        "Conteo {} - {}",
+        detail.count.id,
+        detail.count.category_name.as_deref().unwrap_or("Completo")
+    )
+}
+
+/// Prints the sheet counters fill in by hand: every product of the count
+/// with a blank to write the quantity. Expected stock is left out so the
+/// count is blind.
+pub fn print_count_sheet<D: Driver>(
+    printer: &mut Printer<D>,
+    detail: &StockCountDetail,
+) -> Result<(), PrinterError> {
+    printer
+        .init()?
+        .justify(JustifyMode::CENTER)?
+        .bold(true)?
+        .writeln("HOJA DE CONTEO")?
+        .bold(false)?
+        .writeln(&count_title(detail))?
+        .writeln(&detail.count.created_at)?
+        .writeln("")?
+        .justify(JustifyMode::LEFT)?
+        .writeln("------------------------------------------")?;
+
+    for line in &detail.lines {
+        let name: String = line.product_name.chars().take(LINE_WIDTH).collect();
+        printer.writeln(&name)?;
+        printer.writeln(&format!(
+            "  {:<width$}{:>12}",
+            line.sku,
+            "__________",
+            width = LINE_WIDTH - 14
+        ))?;
+    }
+
+    printer
+        .writeln("------------------------------------------")?
+        .writeln("")?
+        .writeln("Contó: ____________________")?
+        .feeds(3)?
+        .print_cut()?;
+    Ok(())
+}
+
+/// Prints the products whose count differs from the expected stock, with
+/// the variance in units and at cost, and the products not yet counted.
+pub fn print_count_variances<D: Driver>(
+    printer: &mut Printer<D>,
+    detail: &StockCountDetail,
+) -> Result<(), PrinterError> {
+    printer
+        .init()?
+        .justify(JustifyMode::CENTER)?
+        .bold(true)?
+        .writeln("DIFERENCIAS DE CONTEO")?
+        .bold(false)?
+        .writeln(&count_title(detail))?
+        .writeln(&detail.count.created_at)?
+        .writeln("")?
+        .justify(JustifyMode::LEFT)?
+        .writeln("------------------------------------------")?;
+
+    let mut total_cost = 0.0;
+    let mut uncounted = 0;
+    for line in &detail.lines {
+        let Some(variance) = line.variance else {
+            uncounted += 1;
+            continue;
+        };
+        if variance == 0.0 {
+            continue;
+        }
+        total_cost += line.variance_cost.unwrap_or(0.0);
+        let name: String = line.product_name.chars().take(LINE_WIDTH).collect();
+        let quantities = format!(
+            "{} -> {} ({:+})",
+            line.expected_quantity,
+            line.counted_quantity.unwrap_or(0.0),
+            variance
+        );
+        let cost = line
+            .variance_cost
+            .map(|cost| format!("${:.2}", cost))
+            .unwrap_or_else(|| "sin costo".to_string());
+        printer.writeln(&name)?;
+        printer.writeln(&format!(
+            "  {:<width$}{:>12}",
+            quantities,
+            cost,
+            width = LINE_WIDTH - 14
+        ))?;
+    }
+
+    printer
+        .writeln("------------------------------------------")?
+        .bold(true)?
+        .writeln(&format!(
+            "{:>width$}",
+            format!("TOTAL ${:.2}", total_cost),
+            width = LINE_WIDTH
+        ))?
+        .bold(false)?;
+    if uncounted > 0 {
+        printer
+            .writeln("")?
+            .writeln(&format!("Sin contar: {} productos", uncounted))?;
+    }
+    printer.feeds(3)?.print_cut()?;
+    Ok(())
+}
+
+/// Folio printed on tickets: the start of the ticket id.
+fn folio(id: &str) -> &str {
+    &id[..id.len().min(7)]
+}
+
+/// Prints the receipt of a return: the returned items, the original folio
+/// and how the refund was paid.
+pub fn print_return<D: Driver>(
+    printer: &mut Printer<D>,
+    receipt: &ReturnReceipt,
+) -> Result<(), PrinterError> {
+    printer
+        .init()?
+        .justify(JustifyMode::CENTER)?
+        .bold(true)?
+        .writeln("DEVOLUCIÓN")?
+        .bold(false)?
+        .writeln("")?
+        .justify(JustifyMode::LEFT)?
+        .writeln(&format!("Folio: {}", folio(&receipt.id)))?
+        .writeln(&format!(
+            "Ticket original: {}",
+            folio(&receipt.original_ticket_id)
+        ))?
+        .writeln(&format!("Fecha: {}", receipt.created_at))?;
+    if let Some(cashier_name) = &receipt.cashier_name {
+        printer.writeln(&format!("Cajero: {}", cashier_name))?;
+    }
+    printer.writeln("------------------------------------------")?;
+
+    for item in &receipt.items {
+        let name: String = item.product_name.chars().take(LINE_WIDTH).collect();
+        let quantity = format!("{} x ${:.2}", item.quantity, item.price);
+        printer.writeln(&name)?;
+        printer.writeln(&format!(
+            "  {:<width$}{:>12}",
+            quantity,
+            format!("-${:.2}", item.total),
+            width = LINE_WIDTH - 14
+        ))?;
+    }
+
+    printer
+        .writeln("------------------------------------------")?
+        .bold(true)?
+        .writeln(&format!(
+            "{:>width$}",
+            format!("TOTAL DEVUELTO ${:.2}", receipt.total),
+            width = LINE_WIDTH
+        ))?
+        .bold(false)?;
+    let tenders = [
+        ("EFECTIVO MXN", receipt.pesos),
+        ("EFECTIVO USD", receipt.dollars),
+        ("TARJETA", receipt.cards),
+        ("OTROS", receipt.others),
+    ];
+    for (label, amount) in tenders.iter().filter(|(_, amount)| *amount > 0.0) {
+        printer.writeln(&format!(
+            "{:>width$}",
+            format!("{}: ${:.2}", label, amount),
+            width = LINE_WIDTH
+        ))?;
+    }
+    if receipt.dollars > 0.0 {
+        printer.writeln(&format!(
+            "{:>width$}",
+            format!("T.C. ${:.2}", receipt.exchange_rate),
+            width = LINE_WIDTH
+        ))?;
+    }
+    printer
+        .writeln("")?
+        .writeln("")?
+        .writeln("Firma del cliente: ____________________")?
+        .feeds(3)?
+        .print_cut()?;
+    Ok(())
+}
+
+/// Prints the slip of a voided ticket: the cancelled items, who voided
+/// it and why, to be signed and kept with the day's paperwork.
+pub fn print_void_slip<D: Driver>(
+    printer: &mut Printer<D>,
+    slip: &VoidSlip,
+) -> Result<(), PrinterError> {
+    printer
+        .init()?
+        .justify(JustifyMode::CENTER)?
+        .bold(true)?
+        .writeln("CANCELACIÓN")?
+        .bold(false)?
+        .writeln("")?
+        .justify(JustifyMode::LEFT)?
+        .writeln(&format!("Folio: {}", folio(&slip.id)))?;
+    if let Some(original_ticket_id) = &slip.original_ticket_id {
+        printer.writeln(&format!("Devolución de: {}", folio(original_ticket_id)))?;
+    }
+    printer
+        .writeln(&format!("Fecha: {}", slip.created_at))?
+        .writeln(&format!("Cancelado: {}", slip.voided_at))?;
+    if let Some(cashier_name) = &slip.cashier_name {
+        printer.writeln(&format!("Cajero: {}", cashier_name))?;
+    }
+    if let Some(voided_by_name) = &slip.voided_by_name {
+        printer.writeln(&format!("Canceló: {}", voided_by_name))?;
+    }
+    printer
+        .writeln(&format!("Motivo: {}", slip.void_reason))?
+        .writeln("------------------------------------------")?;
+
+    for item in &slip.items {
+        let name: String = item.product_name.chars().take(LINE_WIDTH).collect();
+        let quantity = format!("{} x ${:.2}", item.quantity, item.price);
+        printer.writeln(&name)?;
+        printer.writeln(&format!(
+            "  {:<width$}{:>12}",
+            quantity,
+            format!("${:.2}", item.total),
+            width = LINE_WIDTH - 14
+        ))?;
+    }
+
+    printer
+        .writeln("------------------------------------------")?
+        .bold(true)?
+        .writeln(&format!(
+            "{:>width$}",
+            format!("TOTAL CANCELADO ${:.2}", slip.total_due),
+            width = LINE_WIDTH
+        ))?
+        .bold(false)?
+        .writeln("")?
+        .writeln("")?
+        .writeln("Firma: ____________________")?
+        .feeds(3)?
+        .print_cut()?;
+    Ok(())
+}
+
+/// A `label ... $amount` row of a report.
+fn amount_line(label: &str, amount: f64) -> String {
+    format!(
+        "{:<width$}{:>12}",
+        label,
+        format!("${:.2}", amount),
+        width = LINE_WIDTH - 12
+    )
+}
+
+/// Prints the close report of a cash session: the float, the cash taken
+/// and the expected drawer against the count, per currency.
+pub fn print_cash_session_report<D: Driver>(
+    printer: &mut Printer<D>,
+    report: &CashSessionReport,
+) -> Result<(), PrinterError> {
+    let session = &report.session;
+    let totals = &report.totals;
+    let movements = &report.movements;
+    printer
+        .init()?
+        .justify(JustifyMode::CENTER)?
+        .bold(true)?
+        .writeln("CORTE DE CAJA")?
+        .bold(false)?
+        .writeln("")?
+        .justify(JustifyMode::LEFT)?
+        .writeln(&format!("Sesión: {}  Caja: {}", session.id, session.pos_id))?
+        .writeln(&format!("Cajero: {}", session.cashier_name))?
+        .writeln(&format!("Apertura: {}", session.opened_at))?;
+    if let Some(closed_at) = &session.closed_at {
+        printer.writeln(&format!("Cierre: {}", closed_at))?;
+    }
+    if let Some(closed_by_name) = &session.closed_by_name {
+        printer.writeln(&format!("Cerró: {}", closed_by_name))?;
+    }
+    printer
+        .writeln("------------------------------------------")?
+        .writeln(&format!("Tickets: {}", totals.tickets))?
+        .writeln(&format!("Devoluciones: {}", totals.returns))?
+        .writeln(&amount_line("Total vendido", totals.total_due))?
+        .writeln(&amount_line("Tarjeta", totals.cards_paid))?
+        .writeln(&amount_line("Otros", totals.others_paid))?
+        .writeln("------------------------------------------")?
+        .bold(true)?
+        .writeln("PESOS")?
+        .bold(false)?
+        .writeln(&amount_line("Fondo inicial", session.opening_pesos))?
+        .writeln(&amount_line("Efectivo recibido", totals.pesos_paid))?
+        .writeln(&amount_line("Cambio entregado", -totals.change))?
+        .writeln(&amount_line("Entradas", movements.paid_in_pesos))?
+        .writeln(&amount_line("Salidas", -movements.paid_out_pesos))?
+        .writeln(&amount_line("Retiros", -movements.safe_drops_pesos))?
+        .writeln(&amount_line("Esperado", report.expected_pesos))?;
+    if let (Some(counted), Some(over_short)) = (session.counted_pesos, report.over_short_pesos) {
+        printer
+            .writeln(&amount_line("Contado", counted))?
+            .writeln(&amount_line("Diferencia", over_short))?;
+    }
+    printer
+        .bold(true)?
+        .writeln("DÓLARES")?
+        .bold(false)?
+        .writeln(&amount_line("Fondo inicial", session.opening_dollars))?
+        .writeln(&amount_line("Efectivo recibido", totals.dollars_paid))?
+        .writeln(&amount_line("Entradas", movements.paid_in_dollars))?
+        .writeln(&amount_line("Salidas", -movements.paid_out_dollars))?
+        .writeln(&amount_line("Retiros", -movements.safe_drops_dollars))?
+        .writeln(&amount_line("Esperado", report.expected_dollars))?;
+    if let (Some(counted), Some(over_short)) = (session.counted_dollars, report.over_short_dollars)
+    {
+        printer
+            .writeln(&amount_line("Contado", counted))?
+            .writeln(&amount_line("Diferencia", over_short))?;
+    }
+    if let Some(notes) = &session.notes {
+        printer
+            .writeln("------------------------------------------")?
+            .writeln(notes)?;
+    }
+    printer
+        .writeln("")?
+        .writeln("")?
+        .writeln("Firma del cajero: ____________________")?
+        .feeds(3)?
+        .print_cut()?;
+    Ok(())
+}
+
+/// Prints the slip of a cash paid in, paid out or dropped to the safe,
+/// to be signed and kept with the cash or the receipt.
+pub fn print_cash_movement<D: Driver>(
+    printer: &mut Printer<D>,
+    movement: &CashMovement,
+) -> Result<(), PrinterError> {
+    printer
+        .init()?
+        .justify(JustifyMode::CENTER)?
+        .bold(true)?
+        .writeln(&cash_movements::kind_label(&movement.kind).to_uppercase())?
+        .bold(false)?
+        .writeln("")?
+        .justify(JustifyMode::LEFT)?
+        .writeln(&format!("Folio: {}", movement.id))?
+        .writeln(&format!("Sesión de caja: {}", movement.cash_session_id))?
+        .writeln(&format!("Fecha: {}", movement.created_at))?
+        .writeln(&format!("Cajero: {}", movement.created_by_name))?
+        .writeln(&format!("Autorizó: {}", movement.authorized_by_name))?
+        .writeln(&format!("Motivo: {}", movement.reason))?
+        .writeln("------------------------------------------")?
+        .bold(true)?
+        .writeln(&amount_line(&movement.currency, movement.amount))?
+        .bold(false)?
+        .writeln("")?
+        .writeln("")?
+        .writeln("Entregó: ____________________")?
+        .writeln("")?
+        .writeln("Recibió: ____________________")?
+        .feeds(3)?
+        .print_cut()?;
+    Ok(())
+}
+
+/// Prints the slip of a count by denomination (arqueo), listing every
+/// denomination of each currency, counted or not, and the totals.
+pub fn print_cash_count<D: Driver>(
+    printer: &mut Printer<D>,
+    detail: &CashCountDetail,
+) -> Result<(), PrinterError> {
+    let count = &detail.count;
+    printer
+        .init()?
+        .justify(JustifyMode::CENTER)?
+        .bold(true)?
+        .writeln("ARQUEO DE CAJA")?
+        .bold(false)?
+        .writeln(cash_counts::purpose_label(&count.purpose))?
+        .writeln("")?
+        .justify(JustifyMode::LEFT)?
+        .writeln(&format!("Folio: {}", count.id))?
+        .writeln(&format!("Sesión de caja: {}", count.cash_session_id))?;
+    if let Some(cash_movement_id) = count.cash_movement_id {
+        printer.writeln(&format!("Movimiento: {}", cash_movement_id))?;
+    }
+    printer
+        .writeln(&format!("Fecha: {}", count.created_at))?
+        .writeln(&format!("Contó: {}", count.counted_by_name))?;
+
+    for (currency, title, total) in [
+        ("MXN", "PESOS", count.total_pesos),
+        ("USD", "DÓLARES", count.total_dollars),
+    ] {
+        let lines: Vec<_> = detail
+            .lines
+            .iter()
+            .filter(|line| line.currency == currency)
+            .collect();
+        if lines.is_empty() {
+            continue;
+        }
+        printer
+            .writeln("------------------------------------------")?
+            .bold(true)?
+            .writeln(title)?
+            .bold(false)?;
+        for line in lines {
+            let kind = if line.kind == "coin" {
+                "moneda"
+            } else {
+                "billete"
+            };
+            printer.writeln(&amount_line(
+                &format!("{:>4} x ${:.2} {}", line.quantity, line.value, kind),
+                line.amount,
+            ))?;
+        }
+        printer
+            .bold(true)?
+            .writeln(&amount_line(&format!("Total {}", currency), total))?
+            .bold(false)?;
+    }
+    printer
+        .writeln("")?
+        .writeln("")?
+        .writeln("Contó: ____________________")?
+        .writeln("")?
+        .writeln("Verificó: ____________________")?
+        .feeds(3)?
+        .print_cut()?;
+    Ok(())
+}
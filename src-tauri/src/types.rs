@@ -22,3 +22,14 @@ pub struct ticket {
     pub change: f32,
     pub ticket_items: Vec<ticket_item>,
 }
+
+#[derive(Debug, Serialize, Deserialize, sqlx::FromRow)]
+#[allow(non_camel_case_types)]
+pub struct product {
+    pub sku: String,
+    pub plu_code: Option<String>,
+    pub barcode: Option<String>,
+    pub product_name: String,
+    pub price: f64,
+    pub category_id: Option<i64>,
+}
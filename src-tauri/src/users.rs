@@ -0,0 +1,525 @@
+use crate::admin::{self, LOCKOUT_MINUTES, MAX_FAILED_ATTEMPTS};
+use crate::audit;
+use crate::db;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sqlx::{Pool, Sqlite};
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager};
+
+const MIN_SECRET_LENGTH: usize = 4;
+
+/// Actions a role can be granted. Commands check them with `require`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Permission {
+    Sell,
+    Void,
+    Refund,
+    Discount,
+    Import,
+    ChangeRate,
+    OpenDrawer,
+    ViewReports,
+    ManageUsers,
+    ManageInventory,
+    ManageSettings,
+}
+
+impl Permission {
+    pub const ALL: [Permission; 11] = [
+        Permission::Sell,
+        Permission::Void,
+        Permission::Refund,
+        Permission::Discount,
+        Permission::Import,
+        Permission::ChangeRate,
+        Permission::OpenDrawer,
+        Permission::ViewReports,
+        Permission::ManageUsers,
+        Permission::ManageInventory,
+        Permission::ManageSettings,
+    ];
+
+    /// Name stored in `role_permissions.permission`.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Permission::Sell => "sell",
+            Permission::Void => "void",
+            Permission::Refund => "refund",
+            Permission::Discount => "discount",
+            Permission::Import => "import",
+            Permission::ChangeRate => "change_rate",
+            Permission::OpenDrawer => "open_drawer",
+            Permission::ViewReports => "view_reports",
+            Permission::ManageUsers => "manage_users",
+            Permission::ManageInventory => "manage_inventory",
+            Permission::ManageSettings => "manage_settings",
+        }
+    }
+
+    fn from_str(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|permission| permission.as_str() == name)
+    }
+}
+
+/// The signed in user, as kept in the session.
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionUser {
+    pub id: i64,
+    pub username: String,
+    pub display_name: String,
+    pub role_id: i64,
+    pub role_name: String,
+    pub permissions: Vec<Permission>,
+    /// Discount (percent) the user may give without the `discount` permission.
+    pub max_discount_percent: f64,
+}
+
+impl SessionUser {
+    pub fn can(&self, permission: Permission) -> bool {
+        self.permissions.contains(&permission)
+    }
+}
+
+/// Who is signed in on this station. Held in Rust state rather than the
+/// webview, so reloading the window neither logs out nor grants anything.
+#[derive(Default)]
+pub struct Session(Mutex<Option<SessionUser>>);
+
+/// Returns the signed in user if they hold `permission`.
+pub fn require(app_handle: &AppHandle, permission: Permission) -> Result<SessionUser, String> {
+    let session = app_handle.state::<Session>();
+    let user = session
+        .0
+        .lock()
+        .map_err(|e| e.to_string())?
+        .clone()
+        .ok_or("Nobody is signed in")?;
+    if !user.can(permission) {
+        return Err(format!(
+            "{} ({}) is not allowed to {}",
+            user.display_name,
+            user.role_name,
+            permission.as_str()
+        ));
+    }
+    Ok(user)
+}
+
+pub fn current_user(app_handle: &AppHandle) -> Option<SessionUser> {
+    app_handle.state::<Session>().0.lock().ok()?.clone()
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct User {
+    pub id: i64,
+    pub username: String,
+    pub display_name: String,
+    pub role_id: i64,
+    pub role_name: String,
+    pub is_active: bool,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct Role {
+    pub id: i64,
+    pub name: String,
+    pub max_discount_percent: f64,
+    #[sqlx(skip)]
+    pub permissions: Vec<Permission>,
+}
+
+#[derive(sqlx::FromRow)]
+struct Credential {
+    id: i64,
+    secret_hash: String,
+    locked_until: Option<String>,
+}
+
+async fn role_permissions(pool: &Pool<Sqlite>, role_id: i64) -> Result<Vec<Permission>, String> {
+    Ok(
+        sqlx::query_scalar::<_, String>(
+            "SELECT permission FROM role_permissions WHERE role_id = ?",
+        )
+        .bind(role_id)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| e.to_string())?
+        .iter()
+        .filter_map(|name| Permission::from_str(name))
+        .collect(),
+    )
+}
+
+async fn load_session_user(pool: &Pool<Sqlite>, user_id: i64) -> Result<SessionUser, String> {
+    let max_discount_percent: f64 = sqlx::query_scalar(
+        "SELECT CAST(r.max_discount_percent AS REAL)
+         FROM users u JOIN roles r ON r.id = u.role_id WHERE u.id = ?",
+    )
+    .bind(user_id)
+    .fetch_one(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+    let user = sqlx::query_as::<_, User>(
+        "SELECT u.id, u.username, u.display_name, u.role_id, r.name AS role_name, u.is_active
+         FROM users u JOIN roles r ON r.id = u.role_id
+         WHERE u.id = ?",
+    )
+    .bind(user_id)
+    .fetch_one(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+    Ok(SessionUser {
+        permissions: role_permissions(pool, user.role_id).await?,
+        id: user.id,
+        username: user.username,
+        display_name: user.display_name,
+        role_id: user.role_id,
+        role_name: user.role_name,
+        max_discount_percent,
+    })
+}
+
+fn validate_secret(secret: &str) -> Result<(), String> {
+    if secret.chars().count() < MIN_SECRET_LENGTH {
+        return Err(format!(
+            "The PIN or password must have at least {} characters",
+            MIN_SECRET_LENGTH
+        ));
+    }
+    Ok(())
+}
+
+async fn has_users(pool: &Pool<Sqlite>) -> Result<bool, String> {
+    sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM users)")
+        .fetch_one(pool)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Checks a user's PIN or password, with the same attempt limits and
+/// lockout as the admin password.
+async fn check_secret(pool: &Pool<Sqlite>, username: &str, secret: &str) -> Result<i64, String> {
+    let credential = sqlx::query_as::<_, Credential>(
+        "SELECT id, secret_hash,
+                CASE WHEN locked_until > CURRENT_TIMESTAMP THEN locked_until END AS locked_until
+         FROM users WHERE username = ? AND is_active",
+    )
+    .bind(username.trim())
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| e.to_string())?
+    .ok_or("Unknown or inactive user")?;
+    if let Some(locked_until) = credential.locked_until {
+        return Err(format!(
+            "Too many failed attempts; try again after {} UTC",
+            locked_until
+        ));
+    }
+
+    if admin::password_matches(&credential.secret_hash, secret)? {
+        sqlx::query("UPDATE users SET failed_attempts = 0, locked_until = NULL WHERE id = ?")
+            .bind(credential.id)
+            .execute(pool)
+            .await
+            .map_err(|e| e.to_string())?;
+        return Ok(credential.id);
+    }
+
+    // Counted in one statement, as for the admin password, so concurrent
+    // attempts cannot overwrite each other's count
+    let (failed_attempts, locked): (i64, bool) = sqlx::query_as(
+        "UPDATE users
+         SET failed_attempts = CASE WHEN failed_attempts + 1 >= ?1 THEN 0
+                                    ELSE failed_attempts + 1 END,
+             locked_until = CASE WHEN failed_attempts + 1 >= ?1 THEN datetime('now', ?2)
+                                 ELSE locked_until END
+         WHERE id = ?3
+         RETURNING failed_attempts, IFNULL(locked_until > CURRENT_TIMESTAMP, 0)",
+    )
+    .bind(MAX_FAILED_ATTEMPTS)
+    .bind(format!("+{} minutes", LOCKOUT_MINUTES))
+    .bind(credential.id)
+    .fetch_one(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+    if locked {
+        return Err(format!(
+            "Incorrect PIN. Too many failed attempts; locked for {} minutes",
+            LOCKOUT_MINUTES
+        ));
+    }
+    Err(format!(
+        "Incorrect PIN ({} attempts left)",
+        MAX_FAILED_ATTEMPTS - failed_attempts
+    ))
+}
+
+/// Checks a user's PIN or password without signing them in, e.g. for a
+/// manager approving an action on a cashier's terminal.
+pub async fn authenticate(
+    pool: &Pool<Sqlite>,
+    username: &str,
+    secret: &str,
+) -> Result<SessionUser, String> {
+    let user_id = check_secret(pool, username, secret).await?;
+    load_session_user(pool, user_id).await
+}
+
+/// Active users for the sign-in screen.
+#[tauri::command]
+pub async fn get_login_users(app_handle: AppHandle) -> Result<Vec<User>, String> {
+    let pool = db::pool(&app_handle).await?;
+    sqlx::query_as::<_, User>(
+        "SELECT u.id, u.username, u.display_name, u.role_id, r.name AS role_name, u.is_active
+         FROM users u JOIN roles r ON r.id = u.role_id
+         WHERE u.is_active
+         ORDER BY u.display_name",
+    )
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn get_session(app_handle: AppHandle) -> Option<SessionUser> {
+    current_user(&app_handle)
+}
+
+#[tauri::command]
+pub async fn login(
+    app_handle: AppHandle,
+    username: String,
+    secret: String,
+) -> Result<SessionUser, String> {
+    let pool = db::pool(&app_handle).await?;
+    let user = authenticate(&pool, &username, &secret).await?;
+
+    *app_handle
+        .state::<Session>()
+        .0
+        .lock()
+        .map_err(|e| e.to_string())? = Some(user.clone());
+    let _ = app_handle.emit("session-changed", Some(&user));
+    Ok(user)
+}
+
+#[tauri::command]
+pub fn logout(app_handle: AppHandle) -> Result<(), String> {
+    *app_handle
+        .state::<Session>()
+        .0
+        .lock()
+        .map_err(|e| e.to_string())? = None;
+    let _ = app_handle.emit("session-changed", None::<SessionUser>);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_users(app_handle: AppHandle) -> Result<Vec<User>, String> {
+    require(&app_handle, Permission::ManageUsers)?;
+    let pool = db::pool(&app_handle).await?;
+    sqlx::query_as::<_, User>(
+        "SELECT u.id, u.username, u.display_name, u.role_id, r.name AS role_name, u.is_active
+         FROM users u JOIN roles r ON r.id = u.role_id
+         ORDER BY u.is_active DESC, u.display_name",
+    )
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_roles(app_handle: AppHandle) -> Result<Vec<Role>, String> {
+    let pool = db::pool(&app_handle).await?;
+    let mut roles = sqlx::query_as::<_, Role>(
+        "SELECT id, name, CAST(max_discount_percent AS REAL) AS max_discount_percent
+         FROM roles ORDER BY id",
+    )
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| e.to_string())?;
+    for role in &mut roles {
+        role.permissions = role_permissions(&pool, role.id).await?;
+    }
+    Ok(roles)
+}
+
+/// Replaces the permissions and discount limit of a role. Users signed in
+/// with that role get the new permissions on their next sign-in.
+#[tauri::command]
+pub async fn set_role_permissions(
+    app_handle: AppHandle,
+    role_id: i64,
+    permissions: Vec<Permission>,
+    max_discount_percent: f64,
+) -> Result<(), String> {
+    let current = require(&app_handle, Permission::ManageUsers)?;
+    if !(0.0..=100.0).contains(&max_discount_percent) {
+        return Err("The discount limit must be between 0 and 100%".to_string());
+    }
+    let pool = db::pool(&app_handle).await?;
+    let before = json!({
+        "permissions": role_permissions(&pool, role_id).await?,
+        "max_discount_percent": sqlx::query_scalar::<_, f64>(
+            "SELECT CAST(max_discount_percent AS REAL) FROM roles WHERE id = ?",
+        )
+        .bind(role_id)
+        .fetch_optional(&pool)
+        .await
+        .map_err(|e| e.to_string())?,
+    });
+    let after = json!({
+        "permissions": permissions,
+        "max_discount_percent": max_discount_percent,
+    });
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    sqlx::query("UPDATE roles SET max_discount_percent = ? WHERE id = ?")
+        .bind(max_discount_percent)
+        .bind(role_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| e.to_string())?;
+    sqlx::query("DELETE FROM role_permissions WHERE role_id = ?")
+        .bind(role_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| e.to_string())?;
+    for permission in permissions {
+        sqlx::query("INSERT OR IGNORE INTO role_permissions (role_id, permission) VALUES (?, ?)")
+            .bind(role_id)
+            .bind(permission.as_str())
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+    audit::record(
+        &mut tx,
+        Some(&current),
+        "set_role_permissions",
+        "role",
+        Some(&role_id.to_string()),
+        Some(before),
+        Some(after),
+    )
+    .await?;
+    tx.commit().await.map_err(|e| e.to_string())
+}
+
+/// Creates a user. The very first user can be created without signing in,
+/// so a new station can be set up; after that it takes `manage_users`.
+#[tauri::command]
+pub async fn create_user(
+    app_handle: AppHandle,
+    username: String,
+    display_name: String,
+    role_id: i64,
+    secret: String,
+) -> Result<i64, String> {
+    let pool = db::pool(&app_handle).await?;
+    let current = if has_users(&pool).await? {
+        Some(require(&app_handle, Permission::ManageUsers)?)
+    } else {
+        None
+    };
+    let username = username.trim();
+    let display_name = display_name.trim();
+    if username.is_empty() || display_name.is_empty() {
+        return Err("Username and name are required".to_string());
+    }
+    validate_secret(&secret)?;
+
+    let result = sqlx::query(
+        "INSERT INTO users (username, display_name, role_id, secret_hash) VALUES (?, ?, ?, ?)",
+    )
+    .bind(username)
+    .bind(display_name)
+    .bind(role_id)
+    .bind(admin::hash_password(&secret)?)
+    .execute(&pool)
+    .await
+    .map_err(|e| e.to_string())?;
+    let id = result.last_insert_rowid();
+    audit::log(
+        &pool,
+        current.as_ref(),
+        "create_user",
+        "user",
+        Some(&id.to_string()),
+        None,
+        Some(json!({ "username": username, "display_name": display_name, "role_id": role_id })),
+    )
+    .await?;
+    Ok(id)
+}
+
+/// Updates a user; `secret` resets their PIN or password when given.
+#[tauri::command]
+pub async fn update_user(
+    app_handle: AppHandle,
+    id: i64,
+    display_name: String,
+    role_id: i64,
+    is_active: bool,
+    secret: Option<String>,
+) -> Result<(), String> {
+    let current = require(&app_handle, Permission::ManageUsers)?;
+    if current.id == id && !is_active {
+        return Err("You cannot deactivate your own user".to_string());
+    }
+    let pool = db::pool(&app_handle).await?;
+    let before = sqlx::query_as::<_, (String, i64, bool)>(
+        "SELECT display_name, role_id, is_active FROM users WHERE id = ?",
+    )
+    .bind(id)
+    .fetch_optional(&pool)
+    .await
+    .map_err(|e| e.to_string())?
+    .ok_or("Unknown user")?;
+    let secret_hash = match secret.filter(|secret| !secret.is_empty()) {
+        Some(secret) => {
+            validate_secret(&secret)?;
+            Some(admin::hash_password(&secret)?)
+        }
+        None => None,
+    };
+
+    sqlx::query(
+        "UPDATE users
+         SET display_name = ?, role_id = ?, is_active = ?,
+             secret_hash = IFNULL(?, secret_hash),
+             failed_attempts = CASE WHEN ? IS NULL THEN failed_attempts ELSE 0 END,
+             locked_until = CASE WHEN ? IS NULL THEN locked_until END,
+             updated_at = CURRENT_TIMESTAMP
+         WHERE id = ?",
+    )
+    .bind(display_name.trim())
+    .bind(role_id)
+    .bind(is_active)
+    .bind(&secret_hash)
+    .bind(&secret_hash)
+    .bind(&secret_hash)
+    .bind(id)
+    .execute(&pool)
+    .await
+    .map_err(|e| e.to_string())?;
+    audit::log(
+        &pool,
+        Some(&current),
+        "update_user",
+        "user",
+        Some(&id.to_string()),
+        Some(json!({ "display_name": before.0, "role_id": before.1, "is_active": before.2 })),
+        Some(json!({
+            "display_name": display_name.trim(),
+            "role_id": role_id,
+            "is_active": is_active,
+            "secret_reset": secret_hash.is_some(),
+        })),
+    )
+    .await?;
+    Ok(())
+}
@@ -0,0 +1,231 @@
+use crate::approvals::{self, Approval};
+use crate::audit;
+use crate::db;
+use crate::inventory::{self, MovementKind};
+use crate::users::{self, Permission};
+use serde::Serialize;
+use serde_json::json;
+use sqlx::{Pool, Sqlite, SqliteConnection};
+use tauri::AppHandle;
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct VoidedItem {
+    pub product_name: String,
+    pub quantity: f64,
+    pub price: f64,
+    pub total: f64,
+}
+
+/// A voided ticket as printed on its void slip.
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct VoidSlip {
+    pub id: String,
+    pub original_ticket_id: Option<String>,
+    pub created_at: String,
+    pub voided_at: String,
+    pub cashier_name: Option<String>,
+    pub voided_by_name: Option<String>,
+    pub void_reason: String,
+    pub total_due: f64,
+    #[sqlx(skip)]
+    pub items: Vec<VoidedItem>,
+}
+
+#[derive(sqlx::FromRow)]
+struct TicketToVoid {
+    total_due: f64,
+    original_ticket_id: Option<String>,
+    voided_at: Option<String>,
+    returns: i64,
+    session_closed: bool,
+}
+
+#[derive(sqlx::FromRow)]
+struct TicketMovement {
+    sku: String,
+    kind: String,
+    quantity: f64,
+}
+
+/// Voids a ticket: it and its items are kept but marked void, with who
+/// voided them and why, and left out of sales reports and of its cash
+/// session's drawer. Its stock movements (items sold or returned,
+/// ingredients used) are reversed. Sales with returns against them cannot
+/// be voided until the returns are, nor tickets of closed sessions.
+/// Takes the `void` permission or a manager's `approval`.
+#[tauri::command]
+pub async fn void_ticket(
+    app_handle: AppHandle,
+    ticket_id: String,
+    reason: String,
+    approval: Option<Approval>,
+) -> Result<(), String> {
+    let cashier = users::require(&app_handle, Permission::Sell)?;
+    let reason = reason.trim();
+    if reason.is_empty() {
+        return Err("Voiding a ticket needs a reason".to_string());
+    }
+    let pool = db::pool(&app_handle).await?;
+    let ticket = {
+        let mut conn = pool.acquire().await.map_err(|e| e.to_string())?;
+        ticket_to_void(&mut conn, &ticket_id).await?
+    };
+
+    let message = format!(
+        "{} needs approval to void ticket {} ({:.2})",
+        cashier.display_name,
+        &ticket_id[..7.min(ticket_id.len())],
+        ticket.total_due
+    );
+    let approver = approvals::authorize(
+        &pool,
+        &cashier,
+        Permission::Void,
+        &message,
+        approval.as_ref(),
+    )
+    .await?;
+
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    // Checked again in the transaction, as the ticket may have been voided
+    // or returned while the approval was asked for
+    let ticket = ticket_to_void(&mut tx, &ticket_id).await?;
+    let voided = sqlx::query(
+        "UPDATE tickets SET voided_at = CURRENT_TIMESTAMP, voided_by = ?, void_reason = ?
+         WHERE id = ? AND voided_at IS NULL",
+    )
+    .bind(cashier.id)
+    .bind(reason)
+    .bind(&ticket_id)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| e.to_string())?;
+    if voided.rows_affected() != 1 {
+        return Err(format!("Ticket {} is already void", ticket_id));
+    }
+    sqlx::query("UPDATE ticket_items SET voided = 1 WHERE ticket_id = ?")
+        .bind(&ticket_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let movements = sqlx::query_as::<_, TicketMovement>(
+        "SELECT sku, kind, CAST(quantity AS REAL) AS quantity
+         FROM stock_movements
+         WHERE reference = ?
+         ORDER BY id",
+    )
+    .bind(&ticket_id)
+    .fetch_all(&mut *tx)
+    .await
+    .map_err(|e| e.to_string())?;
+    for movement in &movements {
+        inventory::record_movement(
+            &mut tx,
+            &movement.sku,
+            movement.kind.parse::<MovementKind>()?,
+            -movement.quantity,
+            Some(&ticket_id),
+            Some("Cancelación"),
+            Some(&cashier),
+        )
+        .await?;
+    }
+
+    if let Some(approver) = &approver {
+        approvals::record(
+            &mut tx,
+            Permission::Void,
+            Some(&ticket_id),
+            &cashier,
+            approver,
+            Some(ticket.total_due),
+            reason,
+        )
+        .await?;
+    }
+    audit::record(
+        &mut tx,
+        Some(&cashier),
+        "void_ticket",
+        "ticket",
+        Some(&ticket_id),
+        None,
+        Some(json!({
+            "reason": reason,
+            "total_due": ticket.total_due,
+            "original_ticket_id": ticket.original_ticket_id,
+            "reversed_movements": movements.len(),
+        })),
+    )
+    .await?;
+    tx.commit().await.map_err(|e| e.to_string())
+}
+
+/// A ticket that can be voided: not void yet, without returns against it
+/// and in a cash session still open.
+async fn ticket_to_void(
+    conn: &mut SqliteConnection,
+    ticket_id: &str,
+) -> Result<TicketToVoid, String> {
+    let ticket = sqlx::query_as::<_, TicketToVoid>(
+        "SELECT CAST(t.total_due AS REAL) AS total_due, t.original_ticket_id, t.voided_at,
+                (SELECT COUNT(*) FROM tickets r
+                 WHERE r.original_ticket_id = t.id AND r.voided_at IS NULL) AS returns,
+                EXISTS (SELECT 1 FROM cash_sessions s
+                        WHERE s.id = t.cash_session_id AND s.closed_at IS NOT NULL)
+                    AS session_closed
+         FROM tickets t
+         WHERE t.id = ?",
+    )
+    .bind(ticket_id)
+    .fetch_optional(&mut *conn)
+    .await
+    .map_err(|e| e.to_string())?
+    .ok_or_else(|| format!("Unknown ticket {}", ticket_id))?;
+    if ticket.voided_at.is_some() {
+        return Err(format!("Ticket {} is already void", ticket_id));
+    }
+    if ticket.returns > 0 {
+        return Err(format!("Ticket {} has returns; void them first", ticket_id));
+    }
+    if ticket.session_closed {
+        return Err(format!(
+            "Ticket {} belongs to a closed cash session; return its items instead",
+            ticket_id
+        ));
+    }
+    Ok(ticket)
+}
+
+/// A voided ticket with its items, for its void slip.
+pub async fn load_void_slip(pool: &Pool<Sqlite>, id: &str) -> Result<VoidSlip, String> {
+    let mut slip = sqlx::query_as::<_, VoidSlip>(
+        "SELECT t.id, t.original_ticket_id, datetime(t.created_at, 'localtime') AS created_at,
+                datetime(t.voided_at, 'localtime') AS voided_at, t.cashier_name,
+                u.display_name AS voided_by_name, t.void_reason,
+                CAST(t.total_due AS REAL) AS total_due
+         FROM tickets t
+         LEFT JOIN users u ON u.id = t.voided_by
+         WHERE t.id = ? AND t.voided_at IS NOT NULL",
+    )
+    .bind(id)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| e.to_string())?
+    .ok_or_else(|| format!("Ticket {} is not void", id))?;
+    slip.items = sqlx::query_as::<_, VoidedItem>(
+        "SELECT line_item_product_name AS product_name,
+                CAST(line_item_quantity AS REAL) AS quantity,
+                CAST(line_item_price AS REAL) AS price,
+                CAST(line_item_total AS REAL) AS total
+         FROM ticket_items
+         WHERE ticket_id = ?
+         ORDER BY rowid",
+    )
+    .bind(id)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+    Ok(slip)
+}
@@ -0,0 +1,217 @@
+use crate::audit;
+use crate::db;
+use crate::inventory::{self, MovementKind};
+use crate::recipes;
+use crate::users::{self, Permission};
+use serde::Serialize;
+use serde_json::json;
+use sqlx::{Pool, Sqlite};
+use tauri::AppHandle;
+
+/// Reason codes of `waste_records.reason`.
+const REASONS: [&str; 5] = ["unsold", "damaged", "expired", "sample", "other"];
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct WasteEntry {
+    pub id: i64,
+    pub sku: String,
+    pub product_name: String,
+    pub quantity: f64,
+    pub reason: String,
+    pub notes: Option<String>,
+    pub unit_cost: Option<f64>,
+    /// Quantity times unit cost; None when the product had no cost.
+    pub cost: Option<f64>,
+    pub recorded_by: Option<String>,
+    pub created_at: String,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct WasteTotal {
+    pub reason: String,
+    pub entries: i64,
+    pub cost: f64,
+}
+
+/// Waste of one day, for the owner's end-of-day review.
+#[derive(Debug, Serialize)]
+pub struct WasteReport {
+    pub date: String,
+    pub entries: Vec<WasteEntry>,
+    pub by_reason: Vec<WasteTotal>,
+    /// Entries without a cost count as zero.
+    pub total_cost: f64,
+}
+
+#[derive(sqlx::FromRow)]
+struct WastedProduct {
+    product_name: String,
+    cost: Option<f64>,
+}
+
+/// Reference of the stock movements of a waste record, e.g. `MERMA-7`.
+fn reference(id: i64) -> String {
+    format!("MERMA-{}", id)
+}
+
+/// Spanish label of a reason code, as printed and shown on screen.
+pub fn reason_label(reason: &str) -> &str {
+    match reason {
+        "unsold" => "No vendido",
+        "damaged" => "Dañado",
+        "expired" => "Caducado",
+        "sample" => "Muestra",
+        "other" => "Otro",
+        other => other,
+    }
+}
+
+/// Records goods thrown away or given out without a sale. The product
+/// leaves stock as a waste movement; products sold fresh from a recipe
+/// take their ingredients out instead. The record is valued at the
+/// average cost of the product, or at its recipe cost.
+#[tauri::command]
+pub async fn record_waste(
+    app_handle: AppHandle,
+    sku: String,
+    quantity: f64,
+    reason: String,
+    notes: Option<String>,
+) -> Result<i64, String> {
+    let user = users::require(&app_handle, Permission::ManageInventory)?;
+    if quantity <= 0.0 {
+        return Err("The waste quantity must be positive".to_string());
+    }
+    if !REASONS.contains(&reason.as_str()) {
+        return Err(format!("Unknown waste reason {}", reason));
+    }
+    let notes = notes
+        .map(|notes| notes.trim().to_string())
+        .filter(|notes| !notes.is_empty());
+    if reason == "other" && notes.is_none() {
+        return Err("Waste for other reasons needs a note".to_string());
+    }
+    let pool = db::pool(&app_handle).await?;
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+
+    let product = sqlx::query_as::<_, WastedProduct>(
+        "SELECT product_name, CAST(IFNULL(average_cost, cost) AS REAL) AS cost
+         FROM products
+         WHERE sku = ?",
+    )
+    .bind(&sku)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|e| e.to_string())?
+    .ok_or_else(|| format!("Unknown product {}", sku))?;
+
+    let id = sqlx::query(
+        "INSERT INTO waste_records (sku, product_name, quantity, reason, notes, user_id)
+         VALUES (?, ?, ?, ?, ?, ?)",
+    )
+    .bind(&sku)
+    .bind(&product.product_name)
+    .bind(quantity)
+    .bind(&reason)
+    .bind(&notes)
+    .bind(user.id)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| e.to_string())?
+    .last_insert_rowid();
+
+    let recipe_cost = recipes::consume_ingredients(
+        &mut tx,
+        &sku,
+        quantity,
+        MovementKind::Waste,
+        &reference(id),
+        &user,
+    )
+    .await?;
+    if recipe_cost.is_none() {
+        inventory::record_movement(
+            &mut tx,
+            &sku,
+            MovementKind::Waste,
+            -quantity,
+            Some(&reference(id)),
+            Some(&reason),
+            Some(&user),
+        )
+        .await?;
+    }
+    let unit_cost = recipe_cost.or(product.cost);
+    sqlx::query("UPDATE waste_records SET unit_cost = ? WHERE id = ?")
+        .bind(unit_cost)
+        .bind(id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    audit::record(
+        &mut tx,
+        Some(&user),
+        "record_waste",
+        "product",
+        Some(&sku),
+        None,
+        Some(json!({
+            "waste_id": id,
+            "quantity": quantity,
+            "reason": reason,
+            "notes": notes,
+            "unit_cost": unit_cost,
+        })),
+    )
+    .await?;
+    tx.commit().await.map_err(|e| e.to_string())?;
+    Ok(id)
+}
+
+/// Waste recorded on `date` (YYYY-MM-DD, local time).
+pub async fn waste_report(pool: &Pool<Sqlite>, date: &str) -> Result<WasteReport, String> {
+    let entries = sqlx::query_as::<_, WasteEntry>(
+        "SELECT w.id, w.sku, w.product_name, CAST(w.quantity AS REAL) AS quantity, w.reason,
+                w.notes, CAST(w.unit_cost AS REAL) AS unit_cost,
+                CAST(w.quantity * w.unit_cost AS REAL) AS cost,
+                u.display_name AS recorded_by,
+                datetime(w.created_at, 'localtime') AS created_at
+         FROM waste_records w
+         LEFT JOIN users u ON u.id = w.user_id
+         WHERE date(w.created_at, 'localtime') = ?
+         ORDER BY w.id",
+    )
+    .bind(date)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let by_reason = sqlx::query_as::<_, WasteTotal>(
+        "SELECT reason, COUNT(*) AS entries,
+                CAST(IFNULL(SUM(quantity * unit_cost), 0) AS REAL) AS cost
+         FROM waste_records
+         WHERE date(created_at, 'localtime') = ?
+         GROUP BY reason
+         ORDER BY cost DESC",
+    )
+    .bind(date)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let total_cost = by_reason.iter().map(|total| total.cost).sum();
+    Ok(WasteReport {
+        date: date.to_string(),
+        entries,
+        by_reason,
+        total_cost,
+    })
+}
+
+#[tauri::command]
+pub async fn get_waste_report(app_handle: AppHandle, date: String) -> Result<WasteReport, String> {
+    users::require(&app_handle, Permission::ViewReports)?;
+    let pool = db::pool(&app_handle).await?;
+    waste_report(&pool, &date).await
+}
@@ -1,7 +1,8 @@
 import React, { useEffect, useState } from "react";
 import "@/assets/global.css";
-import { useDatabase } from "@/services/db";
 import { listen } from "@tauri-apps/api/event";
+import { invoke } from "@tauri-apps/api/core";
+import { message } from "@tauri-apps/plugin-dialog";
 import { Route, Switch, Redirect, Link } from "wouter";
 import Drawer from "react-modern-drawer";
 import "react-modern-drawer/dist/index.css";
@@ -9,6 +10,20 @@ import "react-modern-drawer/dist/index.css";
 import MainScreen from "@/screens/main-screen/main-screen";
 import SalesScreen from "@/screens/sales-screen";
 import ConfigScreen from "@/screens/config-screen";
+import LoginScreen from "@/screens/login-screen";
+import UsersScreen from "@/screens/users-screen";
+import ZReportScreen from "@/screens/z-report-screen";
+import AuditScreen from "@/screens/audit-screen";
+import PricesScreen from "@/screens/prices-screen";
+import InventoryScreen from "@/screens/inventory-screen";
+import PurchasesScreen from "@/screens/purchases-screen";
+import MarginsScreen from "@/screens/margins-screen";
+import RecipesScreen from "@/screens/recipes-screen";
+import WasteScreen from "@/screens/waste-screen";
+import CountsScreen from "@/screens/counts-screen";
+import CashScreen from "@/screens/cash-screen";
+import useGlobalStore from "@/hooks/useGlobalStore";
+import ImportDialog from "@/components/import-dialog";
 
 // Wrapper component for the application,
 // which renders the MainScreen component and may include other global components
@@ -20,8 +35,13 @@ import ConfigScreen from "@/screens/config-screen";
 export default function App() {
   const [importError, setImportError] = useState(null);
   const [refreshKey, setRefreshKey] = useState(0);
-  const db = useDatabase();
+  const [importPath, setImportPath] = useState(null);
+  const [lowStock, setLowStock] = useState([]);
   const [isDrawerOpen, setIsDrawerOpen] = React.useState(false);
+  const currentUser = useGlobalStore((state) => state.currentUser);
+  const isSessionLoading = useGlobalStore((state) => state.isSessionLoading);
+  const hasPermission = useGlobalStore((state) => state.hasPermission);
+  const logout = useGlobalStore((state) => state.logout);
   const toggleDrawer = () => {
     setIsDrawerOpen((prevState) => !prevState);
   };
@@ -29,77 +49,80 @@ export default function App() {
 
   useEffect(() => {
     const unlisten = listen("import-csv-selected", (event) => {
-      const csvContent = event.payload;
-      handleCSVImport(csvContent);
+      handleCSVImport(event.payload);
     });
 
     const unlistenError = listen("import-csv-error", (event) => {
       setImportError(event.payload);
     });
 
+    // Scheduled price changes were applied; reload the product list
+    const unlistenPrices = listen("prices-changed", () => {
+      setRefreshKey((prev) => prev + 1);
+    });
+
+    // Products at or below their minimum stock
+    const unlistenLowStock = listen("low-stock", (event) => {
+      setLowStock(event.payload);
+    });
+
+    // A price or cost change left prices below cost
+    const unlistenBelowCost = listen("below-cost", (event) => {
+      const lines = event.payload.map(
+        (item) =>
+          `${item.product_name} (${item.tier ?? "menudeo"}): ` +
+          `$${item.price.toFixed(2)} < costo $${item.cost.toFixed(2)}`
+      );
+      message(lines.join("\n"), {
+        title: "Precios debajo del costo",
+        kind: "warning",
+      });
+    });
+
+    // The cash drawer holds more than its limit; suggest a safe drop
+    const unlistenDrawerLimit = listen("drawer-limit", (event) => {
+      const lines = event.payload.map(
+        (alert) =>
+          `Efectivo ${alert.currency} sobre el límite de ` +
+          `$${alert.limit.toFixed(2)}`
+      );
+      message(`${lines.join("\n")}\nHaga un retiro a caja fuerte.`, {
+        title: "Límite de efectivo en caja",
+        kind: "warning",
+      });
+    });
+
     return () => {
       unlisten.then((f) => f());
       unlistenError.then((f) => f());
+      unlistenPrices.then((f) => f());
+      unlistenLowStock.then((f) => f());
+      unlistenBelowCost.then((f) => f());
+      unlistenDrawerLimit.then((f) => f());
     };
   }, []);
 
-  const handleCSVImport = async (csvContent) => {
-    try {
-      const sql = await db.getConnection();
-
-      const lines = csvContent.trim().split("\n");
-      // Skip the first line (header) and process the rest
-      const dataLines = lines.slice(1);
-
-      const products = dataLines.map((line) => {
-        const columns = line.split(",").map((v) => v.trim());
-        // Ensure each line has exactly 4 columns
-        if (columns.length !== 4) {
-          throw new Error(
-            `Invalid row format: "${line}" does not have exactly 4 columns`
-          );
-        }
-        const [category, code, product, price] = columns;
-        return {
-          sku: code,
-          product_name: product,
-          price: parseFloat(price),
-          plu_code: code,
-          barcode: null,
-        };
-      });
+  // The Rust side parses the file (quoting, encodings, delimiters, sheets)
+  // and the dialog shows the dry run before anything is written
+  const handleCSVImport = (path) => {
+    setImportPath(path);
+  };
 
-      for (const product of products) {
-        // Validate price is a valid number
-        if (isNaN(product.price)) {
-          throw new Error(
-            `Invalid price for product "${product.product_name}": "${product.price}"`
-          );
-        }
-        await sql.execute(
-          `
-          INSERT OR REPLACE INTO products (sku, product_name, price, plu_code, barcode)
-          VALUES (?, ?, ?, ?, ?)
-        `,
-          [
-            product.sku,
-            product.product_name,
-            product.price,
-            product.plu_code,
-            product.barcode,
-          ]
-        );
-      }
-
-      console.log("Products imported successfully:", products);
-      setRefreshKey((prev) => prev + 1);
-      setImportError(null);
-    } catch (error) {
-      console.error("Error importing CSV:", error);
-      setImportError(`Failed to import CSV file: ${error.message}`);
-    }
+  const handleImported = (summary) => {
+    console.log("Products imported successfully:", summary);
+    setImportPath(null);
+    setRefreshKey((prev) => prev + 1);
+    setImportError(null);
   };
 
+  if (isSessionLoading) {
+    return null;
+  }
+
+  if (!currentUser) {
+    return <LoginScreen />;
+  }
+
   return (
     <>
       <Switch>
@@ -113,9 +136,42 @@ export default function App() {
         <Route path="/sales">
           <SalesScreen toggleDrawer={toggleDrawer} />
         </Route>
+        <Route path="/cash">
+          <CashScreen toggleDrawer={toggleDrawer} />
+        </Route>
         <Route path="/config">
           <ConfigScreen toggleDrawer={toggleDrawer} />
         </Route>
+        <Route path="/users">
+          <UsersScreen toggleDrawer={toggleDrawer} />
+        </Route>
+        <Route path="/z-report">
+          <ZReportScreen toggleDrawer={toggleDrawer} />
+        </Route>
+        <Route path="/prices">
+          <PricesScreen toggleDrawer={toggleDrawer} />
+        </Route>
+        <Route path="/inventory">
+          <InventoryScreen toggleDrawer={toggleDrawer} />
+        </Route>
+        <Route path="/purchases">
+          <PurchasesScreen toggleDrawer={toggleDrawer} />
+        </Route>
+        <Route path="/recipes">
+          <RecipesScreen toggleDrawer={toggleDrawer} />
+        </Route>
+        <Route path="/waste">
+          <WasteScreen toggleDrawer={toggleDrawer} />
+        </Route>
+        <Route path="/counts">
+          <CountsScreen toggleDrawer={toggleDrawer} />
+        </Route>
+        <Route path="/margins">
+          <MarginsScreen toggleDrawer={toggleDrawer} />
+        </Route>
+        <Route path="/audit">
+          <AuditScreen toggleDrawer={toggleDrawer} />
+        </Route>
         <Route>
           <Redirect to="/" />
         </Route>
@@ -143,6 +199,15 @@ export default function App() {
           >
             Listado de Ventas
           </Link>
+          {hasPermission("sell") && (
+            <Link
+              href="/cash"
+              onClick={toggleDrawer}
+              className="w-full block py-2 text-gray-900 hover:bg-gray-200 pl-2"
+            >
+              Caja
+            </Link>
+          )}
           <Link
             href="/config"
             onClick={toggleDrawer}
@@ -150,8 +215,139 @@ export default function App() {
           >
             Configuracion
           </Link>
+          {hasPermission("import") && (
+            <Link
+              href="/prices"
+              onClick={toggleDrawer}
+              className="w-full block py-2 text-gray-900 hover:bg-gray-200 pl-2"
+            >
+              Precios
+            </Link>
+          )}
+          {hasPermission("manage_inventory") && (
+            <Link
+              href="/inventory"
+              onClick={toggleDrawer}
+              className="w-full block py-2 text-gray-900 hover:bg-gray-200 pl-2"
+            >
+              Inventario
+              {lowStock.length > 0 && (
+                <span
+                  className="ml-2 px-2 rounded-full bg-red-600 text-white text-xs"
+                  title={lowStock.map((item) => item.product_name).join(", ")}
+                >
+                  {lowStock.length}
+                </span>
+              )}
+            </Link>
+          )}
+          {hasPermission("manage_inventory") && (
+            <Link
+              href="/purchases"
+              onClick={toggleDrawer}
+              className="w-full block py-2 text-gray-900 hover:bg-gray-200 pl-2"
+            >
+              Compras
+            </Link>
+          )}
+          {hasPermission("manage_inventory") && (
+            <Link
+              href="/recipes"
+              onClick={toggleDrawer}
+              className="w-full block py-2 text-gray-900 hover:bg-gray-200 pl-2"
+            >
+              Recetas
+            </Link>
+          )}
+          {hasPermission("manage_inventory") && (
+            <Link
+              href="/waste"
+              onClick={toggleDrawer}
+              className="w-full block py-2 text-gray-900 hover:bg-gray-200 pl-2"
+            >
+              Mermas
+            </Link>
+          )}
+          {hasPermission("manage_inventory") && (
+            <Link
+              href="/counts"
+              onClick={toggleDrawer}
+              className="w-full block py-2 text-gray-900 hover:bg-gray-200 pl-2"
+            >
+              Conteos
+            </Link>
+          )}
+          {hasPermission("view_reports") && (
+            <Link
+              href="/z-report"
+              onClick={toggleDrawer}
+              className="w-full block py-2 text-gray-900 hover:bg-gray-200 pl-2"
+            >
+              Corte Z
+            </Link>
+          )}
+          {hasPermission("view_reports") && (
+            <Link
+              href="/margins"
+              onClick={toggleDrawer}
+              className="w-full block py-2 text-gray-900 hover:bg-gray-200 pl-2"
+            >
+              Márgenes
+            </Link>
+          )}
+          {hasPermission("manage_users") && (
+            <Link
+              href="/users"
+              onClick={toggleDrawer}
+              className="w-full block py-2 text-gray-900 hover:bg-gray-200 pl-2"
+            >
+              Usuarios
+            </Link>
+          )}
+          {hasPermission("manage_users") && (
+            <Link
+              href="/audit"
+              onClick={toggleDrawer}
+              className="w-full block py-2 text-gray-900 hover:bg-gray-200 pl-2"
+            >
+              Bitácora
+            </Link>
+          )}
+          {hasPermission("open_drawer") && (
+            <button
+              onClick={() => {
+                toggleDrawer();
+                invoke("open_cash_drawer").catch((err) =>
+                  message(`${err}`, {
+                    title: "No se pudo abrir el cajón",
+                    kind: "error",
+                  })
+                );
+              }}
+              className="w-full text-left py-2 text-gray-900 hover:bg-gray-200 pl-2 cursor-pointer"
+            >
+              Abrir cajón
+            </button>
+          )}
+          <div className="border-t mt-2 pt-2 pl-2 text-sm text-gray-600">
+            {currentUser.display_name} ({currentUser.role_name})
+          </div>
+          <button
+            onClick={() => {
+              toggleDrawer();
+              logout();
+            }}
+            className="w-full text-left py-2 text-gray-900 hover:bg-gray-200 pl-2 cursor-pointer"
+          >
+            Cerrar sesión
+          </button>
         </div>
       </Drawer>
+      <ImportDialog
+        path={importPath}
+        handleClose={() => setImportPath(null)}
+        handleImported={handleImported}
+      />
     </>
   );
 }
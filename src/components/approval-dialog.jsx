@@ -0,0 +1,109 @@
+import React, { useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import Modal from "@/components/modal";
+import { Button } from "@/components/ui/button";
+import { Input } from "@/components/ui/input";
+
+const NEEDS_APPROVAL = "needs_approval:";
+
+// Parses a `needs_approval:<permission>:<message>` error (see approvals.rs),
+// or returns null for any other error.
+export function parseApprovalError(err) {
+  const text = `${err}`;
+  if (!text.startsWith(NEEDS_APPROVAL)) return null;
+  const rest = text.slice(NEEDS_APPROVAL.length);
+  const separator = rest.indexOf(":");
+  return {
+    permission: rest.slice(0, separator),
+    message: rest.slice(separator + 1),
+  };
+}
+
+// A manager approves a restricted action on the cashier's terminal by
+// entering their PIN and a reason. `handleApprove` retries the action
+// with the approval and throws if it is still refused.
+export default function ApprovalDialog({ request, handleClose, handleApprove }) {
+  const [managers, setManagers] = useState([]);
+  const [managerUsername, setManagerUsername] = useState("");
+  const [managerSecret, setManagerSecret] = useState("");
+  const [reason, setReason] = useState("");
+  const [error, setError] = useState(null);
+  const [isSubmitting, setIsSubmitting] = useState(false);
+
+  useEffect(() => {
+    if (!request) return;
+    setManagerSecret("");
+    setReason("");
+    setError(null);
+    invoke("get_login_users")
+      .then(setManagers)
+      .catch((err) => setError(`${err}`));
+  }, [request]);
+
+  const handleSubmit = async (event) => {
+    event.preventDefault();
+    setIsSubmitting(true);
+    try {
+      await handleApprove({
+        manager_username: managerUsername,
+        manager_secret: managerSecret,
+        reason,
+      });
+    } catch (err) {
+      setManagerSecret("");
+      setError(`${err}`);
+    } finally {
+      setIsSubmitting(false);
+    }
+  };
+
+  return (
+    <Modal isOpen={!!request} handleClose={handleClose} type="small">
+      <form className="flex flex-col gap-4 p-6" onSubmit={handleSubmit}>
+        <h2 className="text-2xl font-bold">Autorización requerida</h2>
+        <div className="text-gray-700">{request?.message}</div>
+
+        <select
+          className="border rounded-md h-9 px-2"
+          value={managerUsername}
+          onChange={(e) => setManagerUsername(e.target.value)}
+        >
+          <option value="">Selecciona quién autoriza</option>
+          {managers.map((manager) => (
+            <option key={manager.id} value={manager.username}>
+              {manager.display_name} ({manager.role_name})
+            </option>
+          ))}
+        </select>
+        <Input
+          type="password"
+          inputMode="numeric"
+          placeholder="PIN"
+          value={managerSecret}
+          onChange={(e) => setManagerSecret(e.target.value)}
+        />
+        <Input
+          placeholder="Motivo"
+          value={reason}
+          onChange={(e) => setReason(e.target.value)}
+        />
+
+        {error && <div className="text-red-600 text-sm">{error}</div>}
+
+        <div className="flex gap-2 justify-end">
+          <Button type="button" variant="outline" onClick={handleClose}>
+            Cancelar
+          </Button>
+          <Button
+            type="submit"
+            disabled={
+              !managerUsername || !managerSecret || !reason.trim() || isSubmitting
+            }
+          >
+            Autorizar
+          </Button>
+        </div>
+      </form>
+    </Modal>
+  );
+}
@@ -0,0 +1,153 @@
+import React, { useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { message } from "@tauri-apps/plugin-dialog";
+import ApprovalDialog, { parseApprovalError } from "@/components/approval-dialog";
+import { Button } from "@/components/ui/button";
+import { Input } from "@/components/ui/input";
+import DenominationCount, {
+  printCashCount,
+} from "@/components/denomination-count";
+
+export const movementKinds = {
+  paid_in: "Entrada",
+  paid_out: "Salida",
+  safe_drop: "Retiro a caja fuerte",
+};
+
+// Cash paid into or out of the drawer, or dropped to the safe, during the
+// cashier's open session. Takes the `open_drawer` permission or a
+// manager's approval; the slip is printed. Safe drops can be counted by
+// denomination, which also prints the arqueo slip.
+export default function CashMovementForm({ cashSessionId, handleRecorded }) {
+  const [kind, setKind] = useState("safe_drop");
+  const [currency, setCurrency] = useState("MXN");
+  const [amount, setAmount] = useState("");
+  const [reason, setReason] = useState("");
+  const [byDenomination, setByDenomination] = useState(false);
+  const [count, setCount] = useState(null);
+  const [pendingApproval, setPendingApproval] = useState(null);
+  const [error, setError] = useState(null);
+
+  const counted = kind === "safe_drop" && byDenomination;
+  const parsedAmount = counted
+    ? count?.totals[currency] ?? 0
+    : parseFloat(amount.replace(",", "."));
+
+  const recordMovement = async (approval = null) => {
+    const id = await invoke("record_cash_movement", {
+      kind,
+      currency,
+      amount: parsedAmount,
+      reason,
+      approval,
+      count: counted ? count?.lines ?? [] : null,
+    });
+    try {
+      await invoke("print_cash_movement", { id });
+    } catch (err) {
+      await message(`${err}`, {
+        title: "No se pudo imprimir el comprobante",
+        kind: "warning",
+      });
+    }
+    if (counted) {
+      await printCashCount(
+        cashSessionId,
+        (cashCount) => cashCount.cash_movement_id === id
+      );
+    }
+    setAmount("");
+    setReason("");
+    setByDenomination(false);
+    setError(null);
+    handleRecorded(id);
+  };
+
+  const handleSubmit = async (event) => {
+    event.preventDefault();
+    try {
+      await recordMovement();
+    } catch (err) {
+      const approvalRequest = parseApprovalError(err);
+      if (approvalRequest) {
+        setPendingApproval(approvalRequest);
+        return;
+      }
+      setError(`${err}`);
+    }
+  };
+
+  const handleApprove = async (approval) => {
+    await recordMovement(approval);
+    setPendingApproval(null);
+  };
+
+  return (
+    <>
+      <form className="flex flex-col gap-2" onSubmit={handleSubmit}>
+        <h2 className="text-lg font-bold">Movimiento de efectivo</h2>
+        <div className="flex gap-2">
+          <select
+            className="border rounded-md h-9 px-2 flex-1"
+            value={kind}
+            onChange={(e) => setKind(e.target.value)}
+          >
+            {Object.entries(movementKinds).map(([code, label]) => (
+              <option key={code} value={code}>
+                {label}
+              </option>
+            ))}
+          </select>
+          <select
+            className="border rounded-md h-9 px-2"
+            value={currency}
+            onChange={(e) => setCurrency(e.target.value)}
+          >
+            <option value="MXN">MXN</option>
+            <option value="USD">USD</option>
+          </select>
+        </div>
+        {kind === "safe_drop" && (
+          <label className="flex items-center gap-2">
+            <input
+              type="checkbox"
+              checked={byDenomination}
+              onChange={(e) => setByDenomination(e.target.checked)}
+            />
+            Por denominación
+          </label>
+        )}
+        {counted ? (
+          <>
+            <DenominationCount currencies={[currency]} onChange={setCount} />
+            <div className="text-right font-bold">
+              {`$${parsedAmount.toFixed(2)} ${currency}`}
+            </div>
+          </>
+        ) : (
+          <Input
+            className="text-right"
+            inputMode="decimal"
+            placeholder="Importe"
+            value={amount}
+            onChange={(e) => setAmount(e.target.value)}
+          />
+        )}
+        <Input
+          placeholder="Motivo"
+          value={reason}
+          onChange={(e) => setReason(e.target.value)}
+        />
+        {error && <div className="text-red-600 text-sm">{error}</div>}
+        <Button type="submit" disabled={!(parsedAmount > 0) || !reason.trim()}>
+          Registrar
+        </Button>
+      </form>
+      <ApprovalDialog
+        request={pendingApproval}
+        handleClose={() => setPendingApproval(null)}
+        handleApprove={handleApprove}
+      />
+    </>
+  );
+}
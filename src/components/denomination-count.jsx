@@ -0,0 +1,90 @@
+import React, { useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { message } from "@tauri-apps/plugin-dialog";
+import { Input } from "@/components/ui/input";
+
+const kindLabels = { coin: "Moneda", bill: "Billete" };
+
+// Prints the arqueo slip of the session's count matching `isCount`.
+export const printCashCount = async (cashSessionId, isCount) => {
+  try {
+    const counts = await invoke("get_cash_counts", { cashSessionId });
+    const count = counts.filter(isCount).pop();
+    if (count) await invoke("print_cash_count", { id: count.id });
+  } catch (err) {
+    await message(`${err}`, {
+      title: "No se pudo imprimir el arqueo",
+      kind: "warning",
+    });
+  }
+};
+
+// Pieces counted per denomination of the given currencies. Reports the
+// lines to send as the `count` and the totals per currency through
+// `onChange`.
+export default function DenominationCount({
+  currencies = ["MXN", "USD"],
+  onChange,
+}) {
+  const [denominations, setDenominations] = useState([]);
+  const [quantities, setQuantities] = useState({});
+  const [error, setError] = useState(null);
+
+  useEffect(() => {
+    invoke("get_denominations")
+      .then(setDenominations)
+      .catch((err) => setError(`${err}`));
+  }, []);
+
+  const shown = denominations.filter((denomination) =>
+    currencies.includes(denomination.currency)
+  );
+
+  useEffect(() => {
+    const lines = [];
+    const totals = { MXN: 0, USD: 0 };
+    for (const denomination of shown) {
+      const quantity = parseInt(quantities[denomination.id] || "0", 10);
+      if (quantity > 0) {
+        lines.push({ denomination_id: denomination.id, quantity });
+        totals[denomination.currency] += quantity * denomination.value;
+      }
+    }
+    onChange({ lines, totals });
+  }, [quantities, denominations, currencies.join()]);
+
+  const setQuantity = (id, value) => {
+    if (!/^\d*$/.test(value)) return;
+    setQuantities((current) => ({ ...current, [id]: value }));
+  };
+
+  if (error) return <div className="text-red-600 text-sm">{error}</div>;
+
+  return (
+    <div className="flex flex-col gap-1">
+      {currencies.map((currency) => (
+        <div key={currency} className="flex flex-col gap-1">
+          <div className="font-semibold">{currency}</div>
+          {shown
+            .filter((denomination) => denomination.currency === currency)
+            .map((denomination) => (
+              <label
+                key={denomination.id}
+                className="flex items-center gap-2 text-sm"
+              >
+                <span className="w-32">
+                  {`${kindLabels[denomination.kind]} $${denomination.value}`}
+                </span>
+                <Input
+                  className="text-right h-8"
+                  inputMode="numeric"
+                  value={quantities[denomination.id] ?? ""}
+                  onChange={(e) => setQuantity(denomination.id, e.target.value)}
+                />
+              </label>
+            ))}
+        </div>
+      ))}
+    </div>
+  );
+}
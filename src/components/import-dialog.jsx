@@ -0,0 +1,137 @@
+import React, { useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import Modal from "@/components/modal";
+import { Button } from "@/components/ui/button";
+
+// Dry run of a product import (CSV, Excel or ODS). Nothing is written
+// until the user presses "Importar"; workbooks with several sheets
+// let the user pick the one holding the catalog, and catalogs exported
+// from eleventa, SICAR or MyBusiness POS are read with their preset.
+export default function ImportDialog({ path, handleClose, handleImported }) {
+  const [sheets, setSheets] = useState([]);
+  const [sheet, setSheet] = useState(null);
+  const [presets, setPresets] = useState([]);
+  const [preset, setPreset] = useState(null);
+  const [preview, setPreview] = useState(null);
+  const [error, setError] = useState(null);
+  const [isImporting, setIsImporting] = useState(false);
+
+  useEffect(() => {
+    invoke("get_import_presets")
+      .then(setPresets)
+      .catch((err) => console.error("Error getting import presets:", err));
+  }, []);
+
+  useEffect(() => {
+    if (!path) return;
+    setPreview(null);
+    setError(null);
+    invoke("get_import_sheets", { path })
+      .then((names) => {
+        setSheets(names);
+        setSheet(names.length > 0 ? names[0] : null);
+      })
+      .catch((err) => setError(`${err}`));
+  }, [path]);
+
+  useEffect(() => {
+    if (!path) return;
+    setPreview(null);
+    invoke("preview_product_import", { path, sheet, preset })
+      .then((result) => {
+        setPreview(result);
+        setError(null);
+      })
+      .catch((err) => setError(`${err}`));
+  }, [path, sheet, preset]);
+
+  const handleImport = async () => {
+    setIsImporting(true);
+    try {
+      const summary = await invoke("commit_product_import", {
+        previewId: preview.id,
+      });
+      handleImported(summary);
+    } catch (err) {
+      setError(`${err}`);
+    } finally {
+      setIsImporting(false);
+    }
+  };
+
+  return (
+    <Modal isOpen={!!path} handleClose={handleClose}>
+      <div className="flex flex-col h-full p-6 gap-4">
+        <h2 className="text-2xl font-bold">Importar productos</h2>
+        <div className="text-sm text-gray-600 break-all">{path}</div>
+
+        <label className="flex items-center gap-2">
+          Formato:
+          <select
+            className="border rounded px-2 py-1"
+            value={preset ?? ""}
+            onChange={(e) => setPreset(e.target.value || null)}
+          >
+            <option value="">Detectar columnas</option>
+            {presets.map((option) => (
+              <option key={option.id} value={option.id}>
+                {option.name} — {option.description}
+              </option>
+            ))}
+          </select>
+        </label>
+
+        {sheets.length > 1 && (
+          <label className="flex items-center gap-2">
+            Hoja:
+            <select
+              className="border rounded px-2 py-1"
+              value={sheet ?? ""}
+              onChange={(e) => setSheet(e.target.value)}
+            >
+              {sheets.map((name) => (
+                <option key={name} value={name}>
+                  {name}
+                </option>
+              ))}
+            </select>
+          </label>
+        )}
+
+        {error && <div className="text-red-600">{error}</div>}
+
+        {preview && (
+          <div className="flex flex-col gap-1 overflow-auto flex-1">
+            <div className="text-sm text-gray-600">Origen: {preview.source}</div>
+            <div>Productos nuevos: {preview.new_products.length}</div>
+            <div>Productos modificados: {preview.changed_products.length}</div>
+            <div>Sin cambios: {preview.unchanged_count}</div>
+            <div>Líneas con errores (se omiten): {preview.errors.length}</div>
+            {preview.warnings.slice(0, 20).map((warning) => (
+              <div key={`w${warning.line}`} className="text-yellow-700 text-sm">
+                Línea {warning.line}: {warning.message}
+              </div>
+            ))}
+            {preview.errors.slice(0, 20).map((lineError) => (
+              <div key={lineError.line} className="text-red-600 text-sm">
+                Línea {lineError.line}: {lineError.message}
+              </div>
+            ))}
+          </div>
+        )}
+
+        <div className="flex justify-end gap-2">
+          <Button variant="outline" onClick={handleClose}>
+            Cancelar
+          </Button>
+          <Button
+            onClick={handleImport}
+            disabled={!preview || isImporting}
+          >
+            Importar
+          </Button>
+        </div>
+      </div>
+    </Modal>
+  );
+}
@@ -0,0 +1,238 @@
+import React, { useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { message } from "@tauri-apps/plugin-dialog";
+import Modal from "@/components/modal";
+import ApprovalDialog, { parseApprovalError } from "@/components/approval-dialog";
+import { Button } from "@/components/ui/button";
+import { Input } from "@/components/ui/input";
+
+const parseNumber = (value) => {
+  const number = parseFloat(`${value}`.replace(",", "."));
+  return isNaN(number) ? 0 : number;
+};
+
+const money = (amount) => `$${(amount ?? 0).toFixed(2)}`;
+
+const emptyTenders = { pesos: "", dollars: "", cards: "", others: "" };
+
+const tenderLabels = {
+  pesos: "Efectivo MXN",
+  dollars: "Efectivo USD",
+  cards: "Tarjeta",
+  others: "Otros",
+};
+
+// Returns items of a sale (`ticketId`: id or printed folio) and refunds
+// them in any tender. Dollars are refunded at the rate set in the
+// configuration (the sale's or today's). Refunds take the `refund`
+// permission or a manager's approval; the return receipt is printed.
+export default function ReturnDialog({ ticketId, handleClose, handleReturned }) {
+  const [ticket, setTicket] = useState(null);
+  const [quantities, setQuantities] = useState({});
+  const [noRestock, setNoRestock] = useState([]);
+  const [tenders, setTenders] = useState(emptyTenders);
+  const [pendingApproval, setPendingApproval] = useState(null);
+  const [error, setError] = useState(null);
+
+  useEffect(() => {
+    setTicket(null);
+    setQuantities({});
+    setNoRestock([]);
+    setTenders(emptyTenders);
+    setError(null);
+    if (!ticketId) return;
+    invoke("get_returnable_ticket", { folio: ticketId })
+      .then(setTicket)
+      .catch((err) => setError(`${err}`));
+  }, [ticketId]);
+
+  const subtotal = (ticket?.items ?? []).reduce(
+    (sum, item) => sum + item.price * parseNumber(quantities[item.id] ?? ""),
+    0
+  );
+  const taxes =
+    ticket && ticket.subtotal > 0
+      ? (ticket.taxes * subtotal) / ticket.subtotal
+      : 0;
+  const total = Math.round((subtotal + taxes) * 100) / 100;
+  const refunded =
+    parseNumber(tenders.pesos) +
+    parseNumber(tenders.dollars) * (ticket?.refund_rate ?? 0) +
+    parseNumber(tenders.cards) +
+    parseNumber(tenders.others);
+  const pending = total - refunded;
+
+  const createReturn = async (approval = null) => {
+    const id = await invoke("create_return", {
+      originalTicketId: ticket.id,
+      lines: ticket.items
+        .filter((item) => parseNumber(quantities[item.id] ?? "") > 0)
+        .map((item) => ({
+          item_id: item.id,
+          quantity: parseNumber(quantities[item.id]),
+          restock: !noRestock.includes(item.id),
+        })),
+      refundTenders: {
+        pesos: parseNumber(tenders.pesos),
+        dollars: parseNumber(tenders.dollars),
+        cards: parseNumber(tenders.cards),
+        others: parseNumber(tenders.others),
+      },
+      approval,
+    });
+    try {
+      await invoke("print_return", { id });
+    } catch (err) {
+      await message(`${err}`, {
+        title: "No se pudo imprimir la devolución",
+        kind: "warning",
+      });
+    }
+    handleReturned(id);
+  };
+
+  const handleSubmit = async (event) => {
+    event.preventDefault();
+    try {
+      await createReturn();
+    } catch (err) {
+      const approvalRequest = parseApprovalError(err);
+      if (approvalRequest) {
+        setPendingApproval(approvalRequest);
+        return;
+      }
+      setError(`${err}`);
+    }
+  };
+
+  const handleApprove = async (approval) => {
+    await createReturn(approval);
+    setPendingApproval(null);
+  };
+
+  return (
+    <>
+      <Modal isOpen={!!ticketId} handleClose={handleClose}>
+        <form className="flex flex-col gap-4 p-6" onSubmit={handleSubmit}>
+          <h2 className="text-2xl font-bold">
+            Devolución del ticket {ticketId?.slice(0, 7)}
+          </h2>
+          {ticket && (
+            <div className="text-gray-700">
+              {`${ticket.created_at} · Total ${money(ticket.total_due)}`}
+            </div>
+          )}
+
+          {ticket && (
+            <table className="bg-white text-sm">
+              <thead>
+                <tr className="text-left border-b">
+                  <th className="p-2">Producto</th>
+                  <th className="p-2 text-right">Precio</th>
+                  <th className="p-2 text-right">Vendido</th>
+                  <th className="p-2 text-right">Devuelto</th>
+                  <th className="p-2 text-right">Devolver</th>
+                  <th className="p-2">Reingresar</th>
+                </tr>
+              </thead>
+              <tbody>
+                {ticket.items.map((item) => (
+                  <tr key={item.id} className="border-b">
+                    <td className="p-2">{item.product_name}</td>
+                    <td className="p-2 text-right">{money(item.price)}</td>
+                    <td className="p-2 text-right">{item.quantity}</td>
+                    <td className="p-2 text-right">{item.returned}</td>
+                    <td className="p-2">
+                      <Input
+                        className="w-20 ml-auto text-right"
+                        inputMode="decimal"
+                        disabled={item.returned >= item.quantity}
+                        value={quantities[item.id] ?? ""}
+                        onChange={(e) =>
+                          setQuantities({
+                            ...quantities,
+                            [item.id]: e.target.value,
+                          })
+                        }
+                      />
+                    </td>
+                    <td className="p-2 text-center">
+                      <input
+                        type="checkbox"
+                        checked={!noRestock.includes(item.id)}
+                        onChange={(e) =>
+                          setNoRestock(
+                            e.target.checked
+                              ? noRestock.filter((id) => id !== item.id)
+                              : [...noRestock, item.id]
+                          )
+                        }
+                      />
+                    </td>
+                  </tr>
+                ))}
+              </tbody>
+            </table>
+          )}
+
+          {ticket && (
+            <div className="flex gap-6">
+              <div className="flex flex-col gap-2 w-64">
+                {Object.entries(tenderLabels).map(([tender, label]) => (
+                  <label key={tender} className="flex items-center gap-2">
+                    <span className="w-28">{label}</span>
+                    <Input
+                      className="text-right"
+                      inputMode="decimal"
+                      value={tenders[tender]}
+                      onChange={(e) =>
+                        setTenders({ ...tenders, [tender]: e.target.value })
+                      }
+                    />
+                  </label>
+                ))}
+              </div>
+              <div className="flex flex-col gap-1 text-right ml-auto">
+                <div>Devolver: {money(total)}</div>
+                <div className="text-sm text-gray-600">
+                  T.C. {ticket.refund_rate.toFixed(2)}
+                </div>
+                <div className={pending > 0.005 ? "text-red-600" : ""}>
+                  Pendiente: {money(pending)}
+                </div>
+                <Button
+                  type="button"
+                  variant="outline"
+                  onClick={() =>
+                    setTenders({ ...emptyTenders, pesos: total.toFixed(2) })
+                  }
+                >
+                  Todo en pesos
+                </Button>
+              </div>
+            </div>
+          )}
+
+          {error && <div className="text-red-600 text-sm">{error}</div>}
+
+          <div className="flex gap-2 justify-end">
+            <Button type="button" variant="outline" onClick={handleClose}>
+              Cancelar
+            </Button>
+            <Button
+              type="submit"
+              disabled={!ticket || total <= 0 || Math.abs(pending) >= 0.01}
+            >
+              Devolver
+            </Button>
+          </div>
+        </form>
+      </Modal>
+      <ApprovalDialog
+        request={pendingApproval}
+        handleClose={() => setPendingApproval(null)}
+        handleApprove={handleApprove}
+      />
+    </>
+  );
+}
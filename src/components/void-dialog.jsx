@@ -0,0 +1,92 @@
+import React, { useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { message } from "@tauri-apps/plugin-dialog";
+import Modal from "@/components/modal";
+import ApprovalDialog, { parseApprovalError } from "@/components/approval-dialog";
+import { Button } from "@/components/ui/button";
+import { Input } from "@/components/ui/input";
+
+// Voids a ticket (`ticket`: a row of the tickets table) with a reason.
+// Voiding takes the `void` permission or a manager's approval; the void
+// slip is printed.
+export default function VoidDialog({ ticket, handleClose, handleVoided }) {
+  const [reason, setReason] = useState("");
+  const [pendingApproval, setPendingApproval] = useState(null);
+  const [error, setError] = useState(null);
+
+  useEffect(() => {
+    setReason("");
+    setError(null);
+  }, [ticket]);
+
+  const voidTicket = async (approval = null) => {
+    await invoke("void_ticket", { ticketId: ticket.id, reason, approval });
+    try {
+      await invoke("print_void_slip", { id: ticket.id });
+    } catch (err) {
+      await message(`${err}`, {
+        title: "No se pudo imprimir la cancelación",
+        kind: "warning",
+      });
+    }
+    handleVoided(ticket.id);
+  };
+
+  const handleSubmit = async (event) => {
+    event.preventDefault();
+    try {
+      await voidTicket();
+    } catch (err) {
+      const approvalRequest = parseApprovalError(err);
+      if (approvalRequest) {
+        setPendingApproval(approvalRequest);
+        return;
+      }
+      setError(`${err}`);
+    }
+  };
+
+  const handleApprove = async (approval) => {
+    await voidTicket(approval);
+    setPendingApproval(null);
+  };
+
+  return (
+    <>
+      <Modal isOpen={!!ticket} handleClose={handleClose}>
+        <form className="flex flex-col gap-4 p-6" onSubmit={handleSubmit}>
+          <h2 className="text-2xl font-bold">
+            Cancelar ticket {ticket?.id.slice(0, 7)}
+          </h2>
+          {ticket && (
+            <div className="text-gray-700">
+              {`${ticket.created_at} · Total $${ticket.total_due}`}
+            </div>
+          )}
+          <Input
+            autoFocus
+            placeholder="Motivo de la cancelación"
+            value={reason}
+            onChange={(e) => setReason(e.target.value)}
+          />
+
+          {error && <div className="text-red-600 text-sm">{error}</div>}
+
+          <div className="flex gap-2 justify-end">
+            <Button type="button" variant="outline" onClick={handleClose}>
+              Volver
+            </Button>
+            <Button type="submit" disabled={!reason.trim()}>
+              Cancelar ticket
+            </Button>
+          </div>
+        </form>
+      </Modal>
+      <ApprovalDialog
+        request={pendingApproval}
+        handleClose={() => setPendingApproval(null)}
+        handleApprove={handleApprove}
+      />
+    </>
+  );
+}
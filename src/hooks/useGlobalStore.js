@@ -1,6 +1,6 @@
 import { create } from "zustand";
-import { DB } from "@/services/db";
 import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
 
 const defaultState = {
   cartItems: [],
@@ -16,9 +16,17 @@ const defaultState = {
   */
   printers: [],
   exchange_rate_usd_to_mxn: 20,
+  // Rate dollars are refunded at: "original" (the sale's) or "current"
+  refund_rate: "original",
+  // Cash per currency above which a safe drop is suggested; null for none
+  drawer_limit_pesos: null,
+  drawer_limit_dollars: null,
   isSettingsLoading: true,
   settingsError: null,
   renderTick: null,
+  // Signed in user as returned by the Rust session, see users.rs
+  currentUser: null,
+  isSessionLoading: true,
 };
 
 const useGlobalStore = create((set, get) => ({
@@ -28,19 +36,21 @@ const useGlobalStore = create((set, get) => ({
       set({ isSettingsLoading: true, settingsError: null });
     }
     try {
-      const [printer, usbPrinters, savedRate] = await Promise.all([
-        DB.select_thermal_printer(),
+      const [settings, usbPrinters] = await Promise.all([
+        invoke("get_settings"),
         invoke("get_printers").catch((err) => {
           console.error("Error getting printers:", err);
           return []; // Return a default/fallback value
         }),
-        DB.get_exchange_rate(),
       ]);
       set({
-        currentPrinter: printer || null,
+        currentPrinter: settings.thermal_printer,
         printers: Array.isArray(usbPrinters) ? usbPrinters : [],
         isSettingsLoading: false,
-        exchange_rate_usd_to_mxn: parseFloat(savedRate),
+        exchange_rate_usd_to_mxn: settings.exchange_rate_usd_to_mxn,
+        refund_rate: settings.refund_rate,
+        drawer_limit_pesos: settings.drawer_limit_pesos,
+        drawer_limit_dollars: settings.drawer_limit_dollars,
         settingsError: null,
       });
     } catch (err) {
@@ -53,14 +63,53 @@ const useGlobalStore = create((set, get) => ({
       });
     }
   },
+  // Settings are validated and stored by Rust, which broadcasts
+  // "settings-changed" so every window picks up the new values
+  _updateSettings: (changes) =>
+    invoke("update_settings", {
+      settings: {
+        thermal_printer: get().currentPrinter,
+        exchange_rate_usd_to_mxn: get().exchange_rate_usd_to_mxn,
+        refund_rate: get().refund_rate,
+        drawer_limit_pesos: get().drawer_limit_pesos,
+        drawer_limit_dollars: get().drawer_limit_dollars,
+        ...changes,
+      },
+    }),
+  _applySettings: (settings) =>
+    set({
+      currentPrinter: settings.thermal_printer,
+      exchange_rate_usd_to_mxn: settings.exchange_rate_usd_to_mxn,
+      refund_rate: settings.refund_rate,
+      drawer_limit_pesos: settings.drawer_limit_pesos,
+      drawer_limit_dollars: settings.drawer_limit_dollars,
+      renderTick: Date.now(),
+    }),
+  _initializeSession: async () => {
+    try {
+      const user = await invoke("get_session");
+      set({ currentUser: user, isSessionLoading: false });
+    } catch (err) {
+      console.error("Error getting session:", err);
+      set({ currentUser: null, isSessionLoading: false });
+    }
+  },
+  setCurrentUser: (user) => set({ currentUser: user }),
+  logout: async () => {
+    await invoke("logout");
+    set({ currentUser: null, cartItems: [] });
+  },
+  // Only hides what the user cannot do; the commands enforce permissions
+  hasPermission: (permission) =>
+    !!get().currentUser && get().currentUser.permissions.includes(permission),
   setCurrentPrinter: async (printer) => {
     const previousPrinter = get().currentPrinter;
     set({ currentPrinter: printer });
     try {
-      await DB.update_thermal_printer(printer);
+      await get()._updateSettings({ thermal_printer: printer });
     } catch (err) {
       console.error("Error saving printer:", err);
-      set({ currentPrinter: previousPrinter });
+      set({ currentPrinter: previousPrinter, settingsError: `${err}` });
     }
   },
   addItem: (item, quantityToAdd = 1) =>
@@ -118,22 +167,58 @@ const useGlobalStore = create((set, get) => ({
         };
       }
     }),
+  // Price override for one cart line; selling off the catalog price may
+  // need a manager's approval when the sale is recorded (see sales.rs)
+  updateItemPrice: (sku, price) =>
+    set((state) => {
+      const parsedPrice = parseFloat(price);
+      if (isNaN(parsedPrice) || parsedPrice < 0) return {};
+      return {
+        cartItems: state.cartItems.map((item) =>
+          item.sku === sku ? { ...item, price: parsedPrice } : item
+        ),
+      };
+    }),
+  setRefundRate: async (refundRate) => {
+    try {
+      await get()._updateSettings({ refund_rate: refundRate });
+      set({ settingsError: null });
+    } catch (err) {
+      console.error("Error saving refund rate:", err);
+      set({ settingsError: `${err}` });
+    }
+  },
+  // `limits`: { drawer_limit_pesos, drawer_limit_dollars }, null for none
+  setDrawerLimits: async (limits) => {
+    try {
+      await get()._updateSettings(limits);
+      set({ settingsError: null });
+    } catch (err) {
+      console.error("Error saving drawer limits:", err);
+      set({ renderTick: Date.now(), settingsError: `${err}` });
+    }
+  },
   clearCart: () => set({ cartItems: [] }),
   clearSettingsError: () => set({ settingsError: null }),
   set_usd_to_mxn_exchange_rate: async (rate) => {
     const newRate = parseFloat(rate);
     console.log({ rate, newRate });
     if (!isNaN(newRate) && String(newRate) === String(rate).trim()) {
-      set({
-        exchange_rate_usd_to_mxn: parseFloat(newRate.toFixed(2)),
-        renderTick: Date.now(),
-      });
-      console.log("New rate:", newRate);
+      const oldRate = get().exchange_rate_usd_to_mxn;
       try {
-        await DB.update_exchange_rate(newRate);
+        await get()._updateSettings({
+          exchange_rate_usd_to_mxn: parseFloat(newRate.toFixed(2)),
+        });
+        console.log("New rate:", newRate);
+        set({ settingsError: null });
       } catch (err) {
+        // Out of range rates are rejected by Rust; keep the old rate
         console.error("Error saving exchange rate:", err);
-        // Optionally revert to old rate or handle error
+        set({
+          exchange_rate_usd_to_mxn: oldRate,
+          renderTick: Date.now(),
+          settingsError: `${err}`,
+        });
       }
     } else {
       // Keep the old rate
@@ -148,5 +233,14 @@ const useGlobalStore = create((set, get) => ({
 }));
 
 useGlobalStore.getState()._initializeSettings();
+useGlobalStore.getState()._initializeSession();
+
+listen("settings-changed", (event) => {
+  useGlobalStore.getState()._applySettings(event.payload);
+});
+
+listen("session-changed", (event) => {
+  useGlobalStore.getState().setCurrentUser(event.payload);
+});
 
 export default useGlobalStore;
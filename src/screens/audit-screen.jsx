@@ -0,0 +1,158 @@
+import React, { useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { Button } from "@/components/ui/button";
+import { Input } from "@/components/ui/input";
+
+const emptyFilter = {
+  from_date: "",
+  to_date: "",
+  action: "",
+  entity_id: "",
+};
+
+// Audit log viewer: who changed prices, imported catalogs, changed the
+// exchange rate or opened the drawer. Requires the manage_users permission.
+export default function AuditScreen({ toggleDrawer }) {
+  const [filter, setFilter] = useState(emptyFilter);
+  const [entries, setEntries] = useState([]);
+  const [verification, setVerification] = useState(null);
+  const [error, setError] = useState(null);
+
+  const loadEntries = async () => {
+    try {
+      // Empty fields are not filtered on
+      const activeFilter = Object.fromEntries(
+        Object.entries(filter).map(([key, value]) => [key, value || null])
+      );
+      setEntries(await invoke("get_audit_log", { filter: activeFilter }));
+      setError(null);
+    } catch (err) {
+      setError(`${err}`);
+    }
+  };
+
+  useEffect(() => {
+    loadEntries();
+  }, []);
+
+  const handleVerify = async () => {
+    try {
+      setVerification(await invoke("verify_audit_log"));
+    } catch (err) {
+      setError(`${err}`);
+    }
+  };
+
+  return (
+    <div className="flex flex-col h-screen w-full bg-background">
+      <div className="flex border-b p-2 h-16">
+        <div
+          className="flex items-center justify-center w-10 h-10 bg-background rounded-full cursor-pointer pl-2 mr-2 pt-[6.5px]"
+          onClick={toggleDrawer}
+        >
+          <svg
+            xmlns="http://www.w3.org/2000/svg"
+            className="h-9 w-9"
+            fill="none"
+            viewBox="0 0 24 24"
+            stroke="rgb(60,60,60)"
+          >
+            <path
+              strokeLinecap="round"
+              strokeLinejoin="round"
+              strokeWidth="2"
+              d="M4 6h16M4 12h16M4 18h16"
+            />
+          </svg>
+        </div>
+        <div className="flex items-center justify-center text-xl text-center">
+          Bitácora
+        </div>
+      </div>
+
+      <form
+        className="flex gap-2 p-4 items-center"
+        onSubmit={(event) => {
+          event.preventDefault();
+          loadEntries();
+        }}
+      >
+        <Input
+          type="date"
+          className="w-44"
+          value={filter.from_date}
+          onChange={(e) => setFilter({ ...filter, from_date: e.target.value })}
+        />
+        <Input
+          type="date"
+          className="w-44"
+          value={filter.to_date}
+          onChange={(e) => setFilter({ ...filter, to_date: e.target.value })}
+        />
+        <Input
+          placeholder="Acción, p. ej. change_price"
+          className="w-56"
+          value={filter.action}
+          onChange={(e) => setFilter({ ...filter, action: e.target.value })}
+        />
+        <Input
+          placeholder="SKU / id"
+          className="w-40"
+          value={filter.entity_id}
+          onChange={(e) => setFilter({ ...filter, entity_id: e.target.value })}
+        />
+        <Button type="submit">Filtrar</Button>
+        <Button type="button" variant="outline" onClick={handleVerify}>
+          Verificar integridad
+        </Button>
+      </form>
+
+      {verification && (
+        <div
+          className={`mx-4 mb-2 text-sm ${
+            verification.is_valid ? "text-green-700" : "text-red-600"
+          }`}
+        >
+          {verification.is_valid
+            ? `Bitácora íntegra (${verification.entries} registros)`
+            : `Bitácora alterada en el registro ${verification.broken_at}: ${verification.problem}`}
+        </div>
+      )}
+      {error && <div className="mx-4 mb-2 text-red-600 text-sm">{error}</div>}
+
+      <div className="flex-1 px-4 pb-4 overflow-auto">
+        <table className="w-full bg-white text-sm">
+          <thead>
+            <tr className="text-left border-b">
+              <th className="p-2">#</th>
+              <th className="p-2">Fecha (UTC)</th>
+              <th className="p-2">Usuario</th>
+              <th className="p-2">Acción</th>
+              <th className="p-2">Objeto</th>
+              <th className="p-2">Antes</th>
+              <th className="p-2">Después</th>
+            </tr>
+          </thead>
+          <tbody>
+            {entries.map((entry) => (
+              <tr key={entry.id} className="border-b align-top">
+                <td className="p-2">{entry.id}</td>
+                <td className="p-2 whitespace-nowrap">
+                  {entry.created_at.slice(0, 19)}
+                </td>
+                <td className="p-2">{entry.user_name ?? "Admin"}</td>
+                <td className="p-2">{entry.action}</td>
+                <td className="p-2">
+                  {entry.entity}
+                  {entry.entity_id ? ` ${entry.entity_id}` : ""}
+                </td>
+                <td className="p-2 break-all">{entry.before_value}</td>
+                <td className="p-2 break-all">{entry.after_value}</td>
+              </tr>
+            ))}
+          </tbody>
+        </table>
+      </div>
+    </div>
+  );
+}
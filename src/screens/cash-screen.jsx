@@ -0,0 +1,480 @@
+import React, { useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { message } from "@tauri-apps/plugin-dialog";
+import useGlobalStore from "@/hooks/useGlobalStore";
+import { Button } from "@/components/ui/button";
+import { Input } from "@/components/ui/input";
+import CashMovementForm, {
+  movementKinds,
+} from "@/components/cash-movement-form";
+import DenominationCount, {
+  printCashCount,
+} from "@/components/denomination-count";
+
+const today = () => {
+  const now = new Date();
+  now.setMinutes(now.getMinutes() - now.getTimezoneOffset());
+  return now.toISOString().slice(0, 10);
+};
+
+const parseNumber = (value) => parseFloat(`${value}`.replace(",", "."));
+
+const money = (amount) => `$${(amount ?? 0).toFixed(2)}`;
+
+const printReport = async (id) => {
+  try {
+    await invoke("print_cash_session_report", { id });
+  } catch (err) {
+    await message(`${err}`, {
+      title: "No se pudo imprimir el corte",
+      kind: "warning",
+    });
+  }
+};
+
+// Close report of a session: expected drawer against the count, per
+// currency.
+const SessionReport = ({ report }) => {
+  const { session, totals, movements } = report;
+  const rows = [
+    ["Tickets", totals.tickets],
+    ["Devoluciones", totals.returns],
+    ["Total vendido", money(totals.total_due)],
+    ["Tarjeta", money(totals.cards_paid)],
+    ["Otros", money(totals.others_paid)],
+  ];
+  const currencies = [
+    {
+      label: "Pesos",
+      rows: [
+        ["Fondo inicial", session.opening_pesos],
+        ["Efectivo recibido", totals.pesos_paid],
+        ["Cambio entregado", -totals.change],
+        ["Entradas", movements.paid_in_pesos],
+        ["Salidas", -movements.paid_out_pesos],
+        ["Retiros", -movements.safe_drops_pesos],
+        ["Esperado", report.expected_pesos],
+      ],
+      counted: session.counted_pesos,
+      overShort: report.over_short_pesos,
+    },
+    {
+      label: "Dólares",
+      rows: [
+        ["Fondo inicial", session.opening_dollars],
+        ["Efectivo recibido", totals.dollars_paid],
+        ["Entradas", movements.paid_in_dollars],
+        ["Salidas", -movements.paid_out_dollars],
+        ["Retiros", -movements.safe_drops_dollars],
+        ["Esperado", report.expected_dollars],
+      ],
+      counted: session.counted_dollars,
+      overShort: report.over_short_dollars,
+    },
+  ];
+
+  return (
+    <div className="flex flex-col gap-3">
+      <h2 className="text-lg font-bold">
+        {`Sesión ${session.id} · ${session.cashier_name} · ` +
+          session.opened_at}
+        {session.closed_at && ` a ${session.closed_at}`}
+      </h2>
+      <div className="flex gap-6">
+        <table className="self-start bg-white">
+          <tbody>
+            {rows.map(([label, value]) => (
+              <tr key={label} className="border-b">
+                <td className="p-2">{label}</td>
+                <td className="p-2 text-right">{value}</td>
+              </tr>
+            ))}
+          </tbody>
+        </table>
+        {currencies.map((currency) => (
+          <table key={currency.label} className="self-start bg-white">
+            <thead>
+              <tr className="text-left border-b">
+                <th className="p-2" colSpan={2}>
+                  {currency.label}
+                </th>
+              </tr>
+            </thead>
+            <tbody>
+              {currency.rows.map(([label, amount]) => (
+                <tr key={label} className="border-b">
+                  <td className="p-2">{label}</td>
+                  <td className="p-2 text-right">{money(amount)}</td>
+                </tr>
+              ))}
+              {currency.counted !== null && (
+                <>
+                  <tr className="border-b">
+                    <td className="p-2">Contado</td>
+                    <td className="p-2 text-right">
+                      {money(currency.counted)}
+                    </td>
+                  </tr>
+                  <tr className="border-b font-bold">
+                    <td className="p-2">Diferencia</td>
+                    <td
+                      className={`p-2 text-right ${
+                        currency.overShort < -0.005 ? "text-red-600" : ""
+                      }`}
+                    >
+                      {money(currency.overShort)}
+                    </td>
+                  </tr>
+                </>
+              )}
+            </tbody>
+          </table>
+        ))}
+      </div>
+      {session.notes && <div className="text-gray-700">{session.notes}</div>}
+      <div>
+        <Button variant="outline" onClick={() => printReport(session.id)}>
+          Imprimir corte
+        </Button>
+      </div>
+    </div>
+  );
+};
+
+// Cash register session of this terminal: opened with the float counted
+// in pesos and dollars, cash paid in, paid out or dropped to the safe
+// while open, closed with a blind count. Either count can be taken by
+// denomination, which prints its arqueo slip. Sales need an open session.
+// Users with view_reports also see the day's sessions.
+export default function CashScreen({ toggleDrawer }) {
+  const currentUser = useGlobalStore((state) => state.currentUser);
+  const hasPermission = useGlobalStore((state) => state.hasPermission);
+  const [current, setCurrent] = useState(null);
+  const [pesos, setPesos] = useState("");
+  const [dollars, setDollars] = useState("");
+  const [notes, setNotes] = useState("");
+  const [byDenomination, setByDenomination] = useState(false);
+  const [count, setCount] = useState(null);
+  const [report, setReport] = useState(null);
+  const [date, setDate] = useState(today());
+  const [sessions, setSessions] = useState([]);
+  const [movements, setMovements] = useState([]);
+  const [error, setError] = useState(null);
+
+  const canViewReports = hasPermission("view_reports");
+
+  const loadCurrent = async () => {
+    try {
+      setCurrent(await invoke("get_current_cash_session"));
+    } catch (err) {
+      setError(`${err}`);
+    }
+  };
+
+  const loadSessions = async () => {
+    if (!canViewReports) return;
+    try {
+      setSessions(await invoke("get_cash_sessions", { date }));
+    } catch (err) {
+      setError(`${err}`);
+    }
+  };
+
+  const loadMovements = async () => {
+    if (!current) {
+      setMovements([]);
+      return;
+    }
+    try {
+      setMovements(
+        await invoke("get_cash_movements", { cashSessionId: current.id })
+      );
+    } catch (err) {
+      setError(`${err}`);
+    }
+  };
+
+  useEffect(() => {
+    loadCurrent();
+  }, []);
+
+  useEffect(() => {
+    loadMovements();
+  }, [current]);
+
+  useEffect(() => {
+    loadSessions();
+  }, [date]);
+
+  const resetForm = () => {
+    setPesos("");
+    setDollars("");
+    setNotes("");
+    setByDenomination(false);
+    setError(null);
+  };
+
+  const amounts = () =>
+    byDenomination
+      ? {
+          pesos: count?.totals.MXN ?? 0,
+          dollars: count?.totals.USD ?? 0,
+          count: count?.lines ?? [],
+        }
+      : {
+          pesos: parseNumber(pesos || "0"),
+          dollars: parseNumber(dollars || "0"),
+          count: null,
+        };
+
+  const handleOpen = async (event) => {
+    event.preventDefault();
+    try {
+      const opening = amounts();
+      const id = await invoke("open_cash_session", {
+        openingPesos: opening.pesos,
+        openingDollars: opening.dollars,
+        count: opening.count,
+      });
+      resetForm();
+      if (opening.count) {
+        await printCashCount(
+          id,
+          (cashCount) => cashCount.purpose === "opening"
+        );
+      }
+      setReport(null);
+      await Promise.all([loadCurrent(), loadSessions()]);
+    } catch (err) {
+      setError(`${err}`);
+    }
+  };
+
+  const handleClose = async (event) => {
+    event.preventDefault();
+    try {
+      const counted = amounts();
+      const closed = await invoke("close_cash_session", {
+        id: current.id,
+        countedPesos: counted.pesos,
+        countedDollars: counted.dollars,
+        notes: notes || null,
+        count: counted.count,
+      });
+      resetForm();
+      setReport(closed);
+      if (counted.count) {
+        await printCashCount(
+          current.id,
+          (cashCount) => cashCount.purpose === "closing"
+        );
+      }
+      await printReport(closed.session.id);
+      await Promise.all([loadCurrent(), loadSessions()]);
+    } catch (err) {
+      setError(`${err}`);
+    }
+  };
+
+  const showReport = async (id) => {
+    try {
+      setReport(await invoke("get_cash_session_report", { id }));
+    } catch (err) {
+      setError(`${err}`);
+    }
+  };
+
+  const isMine = current?.cashier_id === currentUser.id;
+  const canClose = current && (isMine || canViewReports);
+  const validAmounts =
+    byDenomination ||
+    [pesos, dollars].every((amount) => parseNumber(amount || "0") >= 0);
+
+  // Amount inputs of the open and close forms, or the count by
+  // denomination with its totals.
+  const amountFields = (label) => (
+    <>
+      <label className="flex items-center gap-2">
+        <input
+          type="checkbox"
+          checked={byDenomination}
+          onChange={(e) => setByDenomination(e.target.checked)}
+        />
+        Por denominación
+      </label>
+      {byDenomination ? (
+        <>
+          <DenominationCount onChange={setCount} />
+          <div className="text-right font-bold">
+            {`${money(count?.totals.MXN)} MXN · ` +
+              `${money(count?.totals.USD)} USD`}
+          </div>
+        </>
+      ) : (
+        <>
+          <label className="flex items-center gap-2">
+            <span className="w-32">{`${label} MXN`}</span>
+            <Input
+              className="text-right"
+              inputMode="decimal"
+              value={pesos}
+              onChange={(e) => setPesos(e.target.value)}
+            />
+          </label>
+          <label className="flex items-center gap-2">
+            <span className="w-32">{`${label} USD`}</span>
+            <Input
+              className="text-right"
+              inputMode="decimal"
+              value={dollars}
+              onChange={(e) => setDollars(e.target.value)}
+            />
+          </label>
+        </>
+      )}
+    </>
+  );
+
+  return (
+    <div className="flex flex-col h-screen w-full bg-background">
+      <div className="flex border-b p-2 h-16">
+        <div
+          className="flex items-center justify-center w-10 h-10 bg-background rounded-full cursor-pointer pl-2 mr-2 pt-[6.5px]"
+          onClick={toggleDrawer}
+        >
+          <svg
+            xmlns="http://www.w3.org/2000/svg"
+            className="h-9 w-9"
+            fill="none"
+            viewBox="0 0 24 24"
+            stroke="rgb(60,60,60)"
+          >
+            <path
+              strokeLinecap="round"
+              strokeLinejoin="round"
+              strokeWidth="2"
+              d="M4 6h16M4 12h16M4 18h16"
+            />
+          </svg>
+        </div>
+        <div className="flex items-center justify-center text-xl text-center">
+          Caja
+        </div>
+        {canViewReports && (
+          <Input
+            type="date"
+            className="w-44 ml-auto self-center"
+            value={date}
+            onChange={(e) => setDate(e.target.value)}
+          />
+        )}
+      </div>
+
+      <div className="flex flex-1 gap-6 p-4 bg-gray-50 overflow-auto">
+        <div className="flex flex-col gap-4 w-80">
+          {!current && (
+            <form className="flex flex-col gap-2" onSubmit={handleOpen}>
+              <h2 className="text-lg font-bold">Abrir caja</h2>
+              {amountFields("Fondo")}
+              <Button type="submit" disabled={!validAmounts}>
+                Abrir caja
+              </Button>
+            </form>
+          )}
+
+          {current && (
+            <div className="text-gray-700">
+              {`Caja abierta por ${current.cashier_name} desde ` +
+                current.opened_at}
+            </div>
+          )}
+
+          {isMine && (
+            <CashMovementForm
+              cashSessionId={current.id}
+              handleRecorded={loadMovements}
+            />
+          )}
+
+          {canClose && (
+            <form className="flex flex-col gap-2" onSubmit={handleClose}>
+              <h2 className="text-lg font-bold">Cerrar caja</h2>
+              {amountFields("Contado")}
+              <Input
+                placeholder="Notas"
+                value={notes}
+                onChange={(e) => setNotes(e.target.value)}
+              />
+              <Button type="submit" disabled={!validAmounts}>
+                Cerrar caja
+              </Button>
+            </form>
+          )}
+
+          {error && <div className="text-red-600 text-sm">{error}</div>}
+
+          {canViewReports && (
+            <table className="bg-white text-sm">
+              <thead>
+                <tr className="text-left border-b">
+                  <th className="p-2">Sesión</th>
+                  <th className="p-2">Cajero</th>
+                  <th className="p-2">Apertura</th>
+                  <th className="p-2">Estado</th>
+                </tr>
+              </thead>
+              <tbody>
+                {sessions.map((session) => (
+                  <tr
+                    key={session.id}
+                    className={`border-b cursor-pointer hover:bg-gray-100 ${
+                      report?.session.id === session.id ? "bg-gray-100" : ""
+                    }`}
+                    onClick={() => showReport(session.id)}
+                  >
+                    <td className="p-2">{session.id}</td>
+                    <td className="p-2">{session.cashier_name}</td>
+                    <td className="p-2">{session.opened_at.slice(11, 16)}</td>
+                    <td className="p-2">
+                      {session.closed_at ? "Cerrada" : "Abierta"}
+                    </td>
+                  </tr>
+                ))}
+              </tbody>
+            </table>
+          )}
+        </div>
+
+        <div className="flex flex-col flex-1 gap-6">
+          {current && movements.length > 0 && (
+            <table className="self-start bg-white text-sm">
+              <thead>
+                <tr className="text-left border-b">
+                  <th className="p-2">Hora</th>
+                  <th className="p-2">Movimiento</th>
+                  <th className="p-2 text-right">Importe</th>
+                  <th className="p-2">Motivo</th>
+                  <th className="p-2">Autorizó</th>
+                </tr>
+              </thead>
+              <tbody>
+                {movements.map((movement) => (
+                  <tr key={movement.id} className="border-b">
+                    <td className="p-2">{movement.created_at.slice(11, 16)}</td>
+                    <td className="p-2">{movementKinds[movement.kind]}</td>
+                    <td className="p-2 text-right">
+                      {`${money(movement.amount)} ${movement.currency}`}
+                    </td>
+                    <td className="p-2">{movement.reason}</td>
+                    <td className="p-2">{movement.authorized_by_name}</td>
+                  </tr>
+                ))}
+              </tbody>
+            </table>
+          )}
+          {report && <SessionReport report={report} />}
+        </div>
+      </div>
+    </div>
+  );
+}
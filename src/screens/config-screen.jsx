@@ -12,13 +12,45 @@ export default function ConfigScreen({ toggleDrawer }) {
   const exchange_rate_usd_to_mxn = useGlobalStore(
     (state) => state.exchange_rate_usd_to_mxn
   );
+  const refundRate = useGlobalStore((state) => state.refund_rate);
+  const setRefundRate = useGlobalStore((state) => state.setRefundRate);
+  const drawerLimitPesos = useGlobalStore((state) => state.drawer_limit_pesos);
+  const drawerLimitDollars = useGlobalStore(
+    (state) => state.drawer_limit_dollars
+  );
+  const setDrawerLimits = useGlobalStore((state) => state.setDrawerLimits);
   const renderTick = useGlobalStore((state) => state.renderTick);
+  const settingsError = useGlobalStore((state) => state.settingsError);
 
   const [printers, setPrinters] = React.useState([]);
   const [exchangeRate, setExchangeRate] = React.useState(
     `${exchange_rate_usd_to_mxn}`
   );
 
+  const [drawerLimits, setDrawerLimitInputs] = React.useState({
+    drawer_limit_pesos: "",
+    drawer_limit_dollars: "",
+  });
+
+  useEffect(() => {
+    setDrawerLimitInputs({
+      drawer_limit_pesos: `${drawerLimitPesos ?? ""}`,
+      drawer_limit_dollars: `${drawerLimitDollars ?? ""}`,
+    });
+  }, [drawerLimitPesos, drawerLimitDollars, renderTick]);
+
+  // Blank clears the limit
+  const handleDrawerLimitBlur = () => {
+    const parse = (value) =>
+      value.trim() === "" ? null : parseFloat(value.replace(",", "."));
+    const limits = {
+      drawer_limit_pesos: parse(drawerLimits.drawer_limit_pesos),
+      drawer_limit_dollars: parse(drawerLimits.drawer_limit_dollars),
+    };
+    if (Object.values(limits).some(Number.isNaN)) return;
+    setDrawerLimits(limits);
+  };
+
   useEffect(() => {
     console.log("Setting exchange rate to:", exchange_rate_usd_to_mxn);
     setExchangeRate(exchange_rate_usd_to_mxn.toString());
@@ -148,6 +180,50 @@ export default function ConfigScreen({ toggleDrawer }) {
               />
             </div>
           </div>
+          {/* Refund Rate Row */}
+          <div className="flex items-center">
+            <div className="text-xl mr-4 w-60 shrink-0">
+              T.C. en devoluciones
+            </div>
+            <div className="text-lg flex-1">
+              <select
+                className="bg-gray-50 border h-10 border-gray-300 text-gray-900 text-md rounded-lg block w-full"
+                value={refundRate}
+                onChange={(event) => setRefundRate(event.target.value)}
+              >
+                <option value="original">Del ticket original</option>
+                <option value="current">Del día</option>
+              </select>
+            </div>
+          </div>
+          {/* Drawer Limit Rows */}
+          {[
+            ["drawer_limit_pesos", "Límite de efectivo MXN"],
+            ["drawer_limit_dollars", "Límite de efectivo USD"],
+          ].map(([key, label]) => (
+            <div key={key} className="flex items-center">
+              <div className="text-xl mr-4 w-60 shrink-0">{label}</div>
+              <div className="text-lg flex-1">
+                <input
+                  className="bg-gray-50 h-10 border border-gray-300 text-gray-900 text-md rounded-lg block w-full p-2.5"
+                  type="text"
+                  inputMode="decimal"
+                  placeholder="Sin límite"
+                  value={drawerLimits[key]}
+                  onChange={(event) =>
+                    setDrawerLimitInputs({
+                      ...drawerLimits,
+                      [key]: event.target.value,
+                    })
+                  }
+                  onBlur={handleDrawerLimitBlur}
+                />
+              </div>
+            </div>
+          ))}
+          {settingsError && (
+            <div className="text-red-600 text-md">{settingsError}</div>
+          )}
         </div>
       </div>
     </>
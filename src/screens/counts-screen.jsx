@@ -0,0 +1,403 @@
+import React, { useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { Button } from "@/components/ui/button";
+import { Input } from "@/components/ui/input";
+
+const parseNumber = (value) => parseFloat(`${value}`.replace(",", "."));
+
+const money = (amount) => `$${(amount ?? 0).toFixed(2)}`;
+
+const statusLabels = {
+  open: "Abierto",
+  posted: "Aplicado",
+  cancelled: "Cancelado",
+};
+
+// Physical inventory counts, full or by category. Counters scan or type
+// quantities; the variances against the stock when the count started are
+// reviewed, recounted if needed and posted as adjustments.
+export default function CountsScreen({ toggleDrawer }) {
+  const [counts, setCounts] = useState([]);
+  const [categories, setCategories] = useState([]);
+  const [categoryId, setCategoryId] = useState("");
+  const [detail, setDetail] = useState(null);
+  const [code, setCode] = useState("");
+  const [typed, setTyped] = useState({});
+  const [selected, setSelected] = useState([]);
+  const [showVariances, setShowVariances] = useState(false);
+  const [error, setError] = useState(null);
+
+  const loadCounts = async () => {
+    try {
+      setCounts(await invoke("get_stock_counts"));
+    } catch (err) {
+      setError(`${err}`);
+    }
+  };
+
+  const loadDetail = async (id) => {
+    try {
+      setDetail(await invoke("get_stock_count", { id }));
+    } catch (err) {
+      setError(`${err}`);
+    }
+  };
+
+  useEffect(() => {
+    loadCounts();
+    invoke("get_categories")
+      .then(setCategories)
+      .catch((err) => setError(`${err}`));
+  }, []);
+
+  const selectCount = async (id) => {
+    setTyped({});
+    setSelected([]);
+    setShowVariances(false);
+    setError(null);
+    await loadDetail(id);
+  };
+
+  const refresh = async () => {
+    setError(null);
+    await Promise.all([loadDetail(detail.count.id), loadCounts()]);
+  };
+
+  const run = async (action) => {
+    try {
+      await action();
+      await refresh();
+    } catch (err) {
+      setError(`${err}`);
+    }
+  };
+
+  const handleCreate = async () => {
+    try {
+      const id = await invoke("create_stock_count", {
+        categoryId: categoryId === "" ? null : Number(categoryId),
+        notes: null,
+      });
+      await loadCounts();
+      await selectCount(id);
+    } catch (err) {
+      setError(`${err}`);
+    }
+  };
+
+  const handleScan = async (event) => {
+    event.preventDefault();
+    try {
+      const resolved = await invoke("resolve_product_code", { code });
+      if (!resolved) {
+        setError(`Código desconocido: ${code}`);
+        return;
+      }
+      await invoke("record_count", {
+        id: detail.count.id,
+        sku: resolved.product.sku,
+        quantity: resolved.quantity,
+        add: true,
+      });
+      setCode("");
+      await refresh();
+    } catch (err) {
+      setError(`${err}`);
+    }
+  };
+
+  const handleTyped = (sku) => {
+    const quantity = parseNumber(typed[sku] ?? "");
+    if (!(quantity >= 0)) return;
+    run(async () => {
+      await invoke("record_count", { id: detail.count.id, sku, quantity });
+      setTyped({ ...typed, [sku]: undefined });
+    });
+  };
+
+  const toggleSelected = (sku) =>
+    setSelected(
+      selected.includes(sku)
+        ? selected.filter((other) => other !== sku)
+        : [...selected, sku]
+    );
+
+  const isOpen = detail?.count.status === "open";
+  const counted = detail?.lines.filter((line) => line.variance !== null) ?? [];
+
+  return (
+    <div className="flex flex-col h-screen w-full bg-background">
+      <div className="flex border-b p-2 h-16">
+        <div
+          className="flex items-center justify-center w-10 h-10 bg-background rounded-full cursor-pointer pl-2 mr-2 pt-[6.5px]"
+          onClick={toggleDrawer}
+        >
+          <svg
+            xmlns="http://www.w3.org/2000/svg"
+            className="h-9 w-9"
+            fill="none"
+            viewBox="0 0 24 24"
+            stroke="rgb(60,60,60)"
+          >
+            <path
+              strokeLinecap="round"
+              strokeLinejoin="round"
+              strokeWidth="2"
+              d="M4 6h16M4 12h16M4 18h16"
+            />
+          </svg>
+        </div>
+        <div className="flex items-center justify-center text-xl text-center">
+          Conteos de inventario
+        </div>
+        <div className="flex gap-2 ml-auto items-center">
+          <select
+            className="border rounded-md h-9 px-2"
+            value={categoryId}
+            onChange={(e) => setCategoryId(e.target.value)}
+          >
+            <option value="">Conteo completo</option>
+            {categories.map((category) => (
+              <option key={category.id} value={category.id}>
+                {category.name}
+              </option>
+            ))}
+          </select>
+          <Button onClick={handleCreate}>Iniciar conteo</Button>
+        </div>
+      </div>
+
+      {error && <div className="mx-4 mt-2 text-red-600 text-sm">{error}</div>}
+
+      <div className="flex flex-1 gap-6 p-4 bg-gray-50 overflow-auto">
+        <table className="self-start bg-white text-sm w-96">
+          <thead>
+            <tr className="text-left border-b">
+              <th className="p-2">Folio</th>
+              <th className="p-2">Alcance</th>
+              <th className="p-2">Estado</th>
+              <th className="p-2 text-right">Contados</th>
+            </tr>
+          </thead>
+          <tbody>
+            {counts.map((count) => (
+              <tr
+                key={count.id}
+                className={`border-b cursor-pointer hover:bg-gray-100 ${
+                  detail?.count.id === count.id ? "bg-gray-100" : ""
+                }`}
+                onClick={() => selectCount(count.id)}
+              >
+                <td className="p-2">{count.id}</td>
+                <td className="p-2">{count.category_name ?? "Completo"}</td>
+                <td className="p-2">{statusLabels[count.status]}</td>
+                <td className="p-2 text-right">
+                  {count.counted}/{count.lines}
+                </td>
+              </tr>
+            ))}
+          </tbody>
+        </table>
+
+        {detail && (
+          <div className="flex flex-col flex-1 gap-3">
+            <div className="flex items-center gap-2">
+              <h2 className="text-lg font-bold mr-auto">
+                {`Conteo ${detail.count.id} · ${
+                  detail.count.category_name ?? "Completo"
+                } · ${detail.count.created_at}`}
+              </h2>
+              <Button
+                variant="outline"
+                onClick={() =>
+                  run(() =>
+                    invoke("print_count_sheet", { id: detail.count.id })
+                  )
+                }
+              >
+                Imprimir hoja
+              </Button>
+              <Button
+                variant="outline"
+                onClick={() =>
+                  run(() =>
+                    invoke("print_count_variances", { id: detail.count.id })
+                  )
+                }
+              >
+                Imprimir diferencias
+              </Button>
+            </div>
+
+            {isOpen && (
+              <form className="flex gap-2" onSubmit={handleScan}>
+                <Input
+                  autoFocus
+                  placeholder="Escanear código o SKU"
+                  value={code}
+                  onChange={(e) => setCode(e.target.value)}
+                />
+                <Button type="submit" disabled={!code.trim()}>
+                  Contar
+                </Button>
+                <label className="flex items-center gap-1 text-sm ml-4">
+                  <input
+                    type="checkbox"
+                    checked={showVariances}
+                    onChange={(e) => setShowVariances(e.target.checked)}
+                  />
+                  Mostrar diferencias
+                </label>
+              </form>
+            )}
+
+            <table className="bg-white text-sm">
+              <thead>
+                <tr className="text-left border-b">
+                  <th className="p-2"></th>
+                  <th className="p-2">Producto</th>
+                  {(showVariances || !isOpen) && (
+                    <th className="p-2 text-right">Esperado</th>
+                  )}
+                  <th className="p-2 text-right">Contado</th>
+                  {(showVariances || !isOpen) && (
+                    <>
+                      <th className="p-2 text-right">Diferencia</th>
+                      <th className="p-2 text-right">Costo</th>
+                    </>
+                  )}
+                  <th className="p-2">Contó</th>
+                </tr>
+              </thead>
+              <tbody>
+                {detail.lines.map((line) => (
+                  <tr
+                    key={line.sku}
+                    className={`border-b ${line.recount ? "bg-yellow-50" : ""}`}
+                  >
+                    <td className="p-2">
+                      {isOpen && (
+                        <input
+                          type="checkbox"
+                          checked={selected.includes(line.sku)}
+                          onChange={() => toggleSelected(line.sku)}
+                        />
+                      )}
+                    </td>
+                    <td className="p-2">
+                      {line.product_name}
+                      {line.recount && (
+                        <span className="ml-2 text-xs text-yellow-700">
+                          Recontar
+                          {line.previous_quantity !== null &&
+                            ` (antes ${line.previous_quantity})`}
+                        </span>
+                      )}
+                    </td>
+                    {(showVariances || !isOpen) && (
+                      <td className="p-2 text-right">
+                        {line.expected_quantity}
+                      </td>
+                    )}
+                    <td className="p-2 text-right">
+                      {isOpen ? (
+                        <Input
+                          className="w-24 ml-auto text-right"
+                          inputMode="decimal"
+                          placeholder="—"
+                          value={
+                            typed[line.sku] ?? line.counted_quantity ?? ""
+                          }
+                          onChange={(e) =>
+                            setTyped({ ...typed, [line.sku]: e.target.value })
+                          }
+                          onBlur={() =>
+                            typed[line.sku] !== undefined &&
+                            handleTyped(line.sku)
+                          }
+                          onKeyDown={(e) =>
+                            e.key === "Enter" && handleTyped(line.sku)
+                          }
+                        />
+                      ) : (
+                        line.counted_quantity ?? "—"
+                      )}
+                    </td>
+                    {(showVariances || !isOpen) && (
+                      <>
+                        <td
+                          className={`p-2 text-right ${
+                            line.variance < 0 ? "text-red-600" : ""
+                          }`}
+                        >
+                          {line.variance ?? "—"}
+                        </td>
+                        <td className="p-2 text-right">
+                          {line.variance_cost === null
+                            ? "—"
+                            : money(line.variance_cost)}
+                        </td>
+                      </>
+                    )}
+                    <td className="p-2">{line.counted_by_name}</td>
+                  </tr>
+                ))}
+              </tbody>
+            </table>
+
+            {isOpen && (
+              <div className="flex gap-2">
+                <Button
+                  variant="outline"
+                  onClick={() => setSelected(counted.map((line) => line.sku))}
+                >
+                  Seleccionar contados
+                </Button>
+                <Button
+                  variant="outline"
+                  disabled={selected.length === 0}
+                  onClick={() =>
+                    run(async () => {
+                      await invoke("request_recount", {
+                        id: detail.count.id,
+                        skus: selected,
+                      });
+                      setSelected([]);
+                    })
+                  }
+                >
+                  Recontar
+                </Button>
+                <Button
+                  disabled={selected.length === 0}
+                  onClick={() =>
+                    run(async () => {
+                      await invoke("post_stock_count", {
+                        id: detail.count.id,
+                        approved: selected,
+                      });
+                      setSelected([]);
+                    })
+                  }
+                >
+                  Aplicar ajustes
+                </Button>
+                <Button
+                  variant="outline"
+                  className="ml-auto"
+                  onClick={() =>
+                    run(() =>
+                      invoke("cancel_stock_count", { id: detail.count.id })
+                    )
+                  }
+                >
+                  Cancelar conteo
+                </Button>
+              </div>
+            )}
+          </div>
+        )}
+      </div>
+    </div>
+  );
+}
@@ -0,0 +1,411 @@
+import React, { useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { Button } from "@/components/ui/button";
+import { Input } from "@/components/ui/input";
+
+const movementLabels = {
+  sale: "Venta",
+  return: "Devolución",
+  receipt: "Recepción",
+  adjustment: "Ajuste",
+  waste: "Merma",
+  transfer: "Traspaso",
+  production: "Producción",
+  consumption: "Consumo",
+};
+
+// Stock of the products that track it (packaged goods; fresh tortillas
+// are not counted), their movements and manual adjustments, stock
+// thresholds and the reorder suggestions built from them.
+export default function InventoryScreen({ toggleDrawer }) {
+  const [levels, setLevels] = useState([]);
+  const [selected, setSelected] = useState(null);
+  const [movements, setMovements] = useState([]);
+  const [trackSku, setTrackSku] = useState("");
+  const [adjustment, setAdjustment] = useState({ quantity: "", reason: "" });
+  const [thresholds, setThresholds] = useState({ min: "", max: "" });
+  const [suppliers, setSuppliers] = useState([]);
+  const [newSupplier, setNewSupplier] = useState("");
+  const [suggestions, setSuggestions] = useState(null);
+  const [error, setError] = useState(null);
+
+  const loadLevels = async () => {
+    try {
+      setLevels(await invoke("get_stock_levels"));
+    } catch (err) {
+      setError(`${err}`);
+    }
+  };
+
+  const loadMovements = async (sku) => {
+    try {
+      setMovements(await invoke("get_stock_movements", { sku }));
+    } catch (err) {
+      setError(`${err}`);
+    }
+  };
+
+  const loadSuppliers = async () => {
+    try {
+      setSuppliers(await invoke("get_suppliers"));
+    } catch (err) {
+      setError(`${err}`);
+    }
+  };
+
+  useEffect(() => {
+    loadLevels();
+    loadSuppliers();
+  }, []);
+
+  const selectProduct = (level) => {
+    setSelected(level);
+    setAdjustment({ quantity: "", reason: "" });
+    setThresholds({ min: level.min_stock ?? "", max: level.max_stock ?? "" });
+    loadMovements(level.sku);
+  };
+
+  const parseThreshold = (value) =>
+    `${value}`.trim() === "" ? null : parseFloat(`${value}`.replace(",", "."));
+
+  const handleThresholds = async (event) => {
+    event.preventDefault();
+    try {
+      await invoke("set_stock_thresholds", {
+        sku: selected.sku,
+        minStock: parseThreshold(thresholds.min),
+        maxStock: parseThreshold(thresholds.max),
+      });
+      setError(null);
+      await loadLevels();
+    } catch (err) {
+      setError(`${err}`);
+    }
+  };
+
+  const handleSupplier = async (supplierId) => {
+    try {
+      await invoke("set_product_supplier", {
+        sku: selected.sku,
+        supplierId,
+      });
+      setSelected({ ...selected, supplier_id: supplierId });
+      setError(null);
+      await loadLevels();
+    } catch (err) {
+      setError(`${err}`);
+    }
+  };
+
+  const handleCreateSupplier = async () => {
+    try {
+      const supplierId = await invoke("create_supplier", {
+        supplier: { name: newSupplier },
+      });
+      setNewSupplier("");
+      await loadSuppliers();
+      await handleSupplier(supplierId);
+    } catch (err) {
+      setError(`${err}`);
+    }
+  };
+
+  const loadSuggestions = async () => {
+    try {
+      setSuggestions(await invoke("get_reorder_suggestions"));
+      setSelected(null);
+    } catch (err) {
+      setError(`${err}`);
+    }
+  };
+
+  const handleExportPurchaseList = async () => {
+    try {
+      await invoke("export_purchase_list");
+    } catch (err) {
+      setError(`${err}`);
+    }
+  };
+
+  const setTracking = async (sku, trackStock) => {
+    try {
+      await invoke("set_stock_tracking", { sku, trackStock });
+      setTrackSku("");
+      setError(null);
+      if (!trackStock) setSelected(null);
+      await loadLevels();
+    } catch (err) {
+      setError(`${err}`);
+    }
+  };
+
+  const handleAdjust = async (event) => {
+    event.preventDefault();
+    try {
+      await invoke("adjust_stock", {
+        sku: selected.sku,
+        quantity: parseFloat(adjustment.quantity.replace(",", ".")),
+        reason: adjustment.reason,
+      });
+      setAdjustment({ quantity: "", reason: "" });
+      setError(null);
+      await Promise.all([loadLevels(), loadMovements(selected.sku)]);
+    } catch (err) {
+      setError(`${err}`);
+    }
+  };
+
+  return (
+    <div className="flex flex-col h-screen w-full bg-background">
+      <div className="flex border-b p-2 h-16">
+        <div
+          className="flex items-center justify-center w-10 h-10 bg-background rounded-full cursor-pointer pl-2 mr-2 pt-[6.5px]"
+          onClick={toggleDrawer}
+        >
+          <svg
+            xmlns="http://www.w3.org/2000/svg"
+            className="h-9 w-9"
+            fill="none"
+            viewBox="0 0 24 24"
+            stroke="rgb(60,60,60)"
+          >
+            <path
+              strokeLinecap="round"
+              strokeLinejoin="round"
+              strokeWidth="2"
+              d="M4 6h16M4 12h16M4 18h16"
+            />
+          </svg>
+        </div>
+        <div className="flex items-center justify-center text-xl text-center">
+          Inventario
+        </div>
+        <form
+          className="flex gap-2 ml-auto items-center"
+          onSubmit={(event) => {
+            event.preventDefault();
+            setTracking(trackSku.trim(), true);
+          }}
+        >
+          <Input
+            placeholder="SKU a inventariar"
+            className="w-44"
+            value={trackSku}
+            onChange={(e) => setTrackSku(e.target.value)}
+          />
+          <Button type="submit" disabled={!trackSku.trim()}>
+            Agregar
+          </Button>
+          <Button type="button" variant="outline" onClick={loadSuggestions}>
+            Sugerencias de pedido
+          </Button>
+        </form>
+      </div>
+
+      {error && <div className="mx-4 mt-2 text-red-600 text-sm">{error}</div>}
+
+      <div className="flex flex-1 gap-6 p-4 bg-gray-50 overflow-auto">
+        <table className="flex-1 self-start bg-white">
+          <thead>
+            <tr className="text-left border-b">
+              <th className="p-2">SKU</th>
+              <th className="p-2">Producto</th>
+              <th className="p-2 text-right">Existencia</th>
+            </tr>
+          </thead>
+          <tbody>
+            {levels.map((level) => (
+              <tr
+                key={level.sku}
+                className={`border-b cursor-pointer hover:bg-gray-100 ${
+                  selected?.sku === level.sku ? "bg-gray-100" : ""
+                }`}
+                onClick={() => {
+                  setSuggestions(null);
+                  selectProduct(level);
+                }}
+              >
+                <td className="p-2">{level.sku}</td>
+                <td className="p-2">{level.product_name}</td>
+                <td
+                  className={`p-2 text-right ${
+                    level.quantity < 0 ||
+                    (level.min_stock !== null &&
+                      level.quantity <= level.min_stock)
+                      ? "text-red-600"
+                      : ""
+                  }`}
+                >
+                  {level.quantity}
+                </td>
+              </tr>
+            ))}
+          </tbody>
+        </table>
+
+        {selected && (
+          <div className="flex flex-col gap-3 w-[28rem]">
+            <h2 className="text-lg font-bold">{selected.product_name}</h2>
+            <form className="flex gap-2" onSubmit={handleAdjust}>
+              <Input
+                placeholder="+/- cantidad"
+                className="w-28"
+                inputMode="decimal"
+                value={adjustment.quantity}
+                onChange={(e) =>
+                  setAdjustment({ ...adjustment, quantity: e.target.value })
+                }
+              />
+              <Input
+                placeholder="Motivo"
+                value={adjustment.reason}
+                onChange={(e) =>
+                  setAdjustment({ ...adjustment, reason: e.target.value })
+                }
+              />
+              <Button
+                type="submit"
+                disabled={!adjustment.quantity || !adjustment.reason.trim()}
+              >
+                Ajustar
+              </Button>
+            </form>
+            <form className="flex gap-2" onSubmit={handleThresholds}>
+              <Input
+                placeholder="Mínimo"
+                className="w-28"
+                inputMode="decimal"
+                value={thresholds.min}
+                onChange={(e) =>
+                  setThresholds({ ...thresholds, min: e.target.value })
+                }
+              />
+              <Input
+                placeholder="Máximo"
+                className="w-28"
+                inputMode="decimal"
+                value={thresholds.max}
+                onChange={(e) =>
+                  setThresholds({ ...thresholds, max: e.target.value })
+                }
+              />
+              <Button type="submit" variant="outline">
+                Guardar límites
+              </Button>
+            </form>
+            <div className="flex gap-2">
+              <select
+                className="border rounded-md h-9 px-2 flex-1"
+                value={selected.supplier_id ?? ""}
+                onChange={(e) =>
+                  handleSupplier(e.target.value ? Number(e.target.value) : null)
+                }
+              >
+                <option value="">Sin proveedor</option>
+                {suppliers.map((supplier) => (
+                  <option key={supplier.id} value={supplier.id}>
+                    {supplier.name}
+                  </option>
+                ))}
+              </select>
+              <Input
+                placeholder="Nuevo proveedor"
+                className="w-40"
+                value={newSupplier}
+                onChange={(e) => setNewSupplier(e.target.value)}
+              />
+              <Button
+                variant="outline"
+                disabled={!newSupplier.trim()}
+                onClick={handleCreateSupplier}
+              >
+                Crear
+              </Button>
+            </div>
+            <Button
+              variant="outline"
+              className="self-start"
+              onClick={() => setTracking(selected.sku, false)}
+            >
+              Dejar de inventariar
+            </Button>
+
+            <table className="bg-white text-sm">
+              <thead>
+                <tr className="text-left border-b">
+                  <th className="p-2">Fecha</th>
+                  <th className="p-2">Tipo</th>
+                  <th className="p-2 text-right">Cant.</th>
+                  <th className="p-2 text-right">Saldo</th>
+                  <th className="p-2">Detalle</th>
+                </tr>
+              </thead>
+              <tbody>
+                {movements.map((movement) => (
+                  <tr key={movement.id} className="border-b">
+                    <td className="p-2 whitespace-nowrap">
+                      {movement.created_at}
+                    </td>
+                    <td className="p-2">
+                      {movementLabels[movement.kind] ?? movement.kind}
+                    </td>
+                    <td className="p-2 text-right">{movement.quantity}</td>
+                    <td className="p-2 text-right">{movement.balance}</td>
+                    <td className="p-2">
+                      {movement.reason ?? movement.reference?.slice(0, 7)}
+                      {movement.user_name ? ` · ${movement.user_name}` : ""}
+                    </td>
+                  </tr>
+                ))}
+              </tbody>
+            </table>
+          </div>
+        )}
+
+        {suggestions && (
+          <div className="flex flex-col gap-3 w-[32rem]">
+            <div className="flex items-center gap-4">
+              <h2 className="text-lg font-bold">Sugerencias de pedido</h2>
+              <Button
+                variant="outline"
+                size="sm"
+                disabled={suggestions.length === 0}
+                onClick={handleExportPurchaseList}
+              >
+                Exportar por proveedor
+              </Button>
+            </div>
+            <table className="bg-white text-sm">
+              <thead>
+                <tr className="text-left border-b">
+                  <th className="p-2">Proveedor</th>
+                  <th className="p-2">Producto</th>
+                  <th className="p-2 text-right">Existencia</th>
+                  <th className="p-2 text-right">Venta diaria</th>
+                  <th className="p-2 text-right">Pedir</th>
+                </tr>
+              </thead>
+              <tbody>
+                {suggestions.map((suggestion) => (
+                  <tr key={suggestion.sku} className="border-b">
+                    <td className="p-2">
+                      {suggestion.supplier_name ?? "Sin proveedor"}
+                    </td>
+                    <td className="p-2">{suggestion.product_name}</td>
+                    <td className="p-2 text-right">{suggestion.quantity}</td>
+                    <td className="p-2 text-right">
+                      {suggestion.avg_daily_sales.toFixed(2)}
+                    </td>
+                    <td className="p-2 text-right font-bold">
+                      {suggestion.suggested_quantity}
+                    </td>
+                  </tr>
+                ))}
+              </tbody>
+            </table>
+          </div>
+        )}
+      </div>
+    </div>
+  );
+}
@@ -0,0 +1,120 @@
+import React, { useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { Button } from "@/components/ui/button";
+import { Input } from "@/components/ui/input";
+import useGlobalStore from "@/hooks/useGlobalStore";
+
+// Administrador, see 7_users.sql
+const ADMIN_ROLE_ID = 1;
+
+// Cashier sign-in. The session lives in Rust; on a new station with no
+// users yet, the first (administrator) account is created here.
+export default function LoginScreen() {
+  const setCurrentUser = useGlobalStore((state) => state.setCurrentUser);
+  const [users, setUsers] = useState(null);
+  const [username, setUsername] = useState("");
+  const [displayName, setDisplayName] = useState("");
+  const [secret, setSecret] = useState("");
+  const [error, setError] = useState(null);
+
+  const loadUsers = () =>
+    invoke("get_login_users")
+      .then(setUsers)
+      .catch((err) => setError(`${err}`));
+
+  useEffect(() => {
+    loadUsers();
+  }, []);
+
+  const handleLogin = async (event) => {
+    event.preventDefault();
+    try {
+      const user = await invoke("login", { username, secret });
+      setSecret("");
+      setError(null);
+      setCurrentUser(user);
+    } catch (err) {
+      setSecret("");
+      setError(`${err}`);
+    }
+  };
+
+  const handleCreateAdmin = async (event) => {
+    event.preventDefault();
+    try {
+      await invoke("create_user", {
+        username,
+        displayName,
+        roleId: ADMIN_ROLE_ID,
+        secret,
+      });
+      const user = await invoke("login", { username, secret });
+      setSecret("");
+      setCurrentUser(user);
+    } catch (err) {
+      setError(`${err}`);
+    }
+  };
+
+  if (users === null) {
+    return null;
+  }
+
+  const isFirstUser = users.length === 0;
+
+  return (
+    <div className="flex h-screen w-full items-center justify-center bg-gray-50">
+      <form
+        className="flex flex-col gap-4 w-96 p-6 bg-white rounded-lg shadow"
+        onSubmit={isFirstUser ? handleCreateAdmin : handleLogin}
+      >
+        <h1 className="text-2xl font-bold">
+          {isFirstUser ? "Crear administrador" : "Iniciar sesión"}
+        </h1>
+
+        {isFirstUser ? (
+          <>
+            <Input
+              placeholder="Usuario"
+              value={username}
+              onChange={(e) => setUsername(e.target.value)}
+            />
+            <Input
+              placeholder="Nombre"
+              value={displayName}
+              onChange={(e) => setDisplayName(e.target.value)}
+            />
+          </>
+        ) : (
+          <select
+            className="border rounded-md h-9 px-2"
+            value={username}
+            onChange={(e) => setUsername(e.target.value)}
+          >
+            <option value="">Selecciona usuario</option>
+            {users.map((user) => (
+              <option key={user.id} value={user.username}>
+                {user.display_name} ({user.role_name})
+              </option>
+            ))}
+          </select>
+        )}
+
+        <Input
+          type="password"
+          inputMode="numeric"
+          placeholder="PIN o contraseña"
+          value={secret}
+          onChange={(e) => setSecret(e.target.value)}
+          autoFocus
+        />
+
+        {error && <div className="text-red-600 text-sm">{error}</div>}
+
+        <Button type="submit" disabled={!username || !secret}>
+          {isFirstUser ? "Crear y entrar" : "Entrar"}
+        </Button>
+      </form>
+    </div>
+  );
+}
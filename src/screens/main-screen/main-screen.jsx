@@ -1,69 +1,92 @@
-import React, { useState, useEffect, useMemo, useCallback } from "react";
+import React, { useState, useEffect, useCallback } from "react";
 import { SearchBar } from "./sections/search-bar";
 import { Products } from "./sections/products";
 import { SalesBar } from "./sections/sales-bar";
-import { useDatabase } from "@/services/db";
 import useGlobalStore from "@/hooks/useGlobalStore";
+import { invoke } from "@tauri-apps/api/core";
+
+// Term searched for: the part before '*' when a multiplier is typed
+const searchQuery = (term) => {
+  const parts = term.split("*").map((p) => p.trim());
+  return parts.length >= 2 && parts[0] ? parts[0] : term.trim();
+};
+
+const searchProducts = (term) =>
+  invoke("search_products", { query: searchQuery(term) });
 
 function MainScreen({ refreshKey, importError, toggleDrawer }) {
   const [searchTerm, setSearchTerm] = useState("");
+  const [searchResults, setSearchResults] = useState([]);
   const [allItems, setAllItems] = useState([]);
+  const [categories, setCategories] = useState([]);
+  const [activeCategoryId, setActiveCategoryId] = useState(null);
+  const [quickKeys, setQuickKeys] = useState(null); // null shows every product
 
   const addItem = useGlobalStore((state) => state.addItem);
 
-  const db = useDatabase();
-
   const loadProducts = async () => {
     try {
-      const sql = await db.getConnection();
-      const rows = await sql.select("SELECT * FROM products");
+      const rows = await invoke("get_products");
       setAllItems(rows);
     } catch (err) {
-      console.error("Error querying products:", err.message);
+      console.error("Error querying products:", err);
       setAllItems([]); // Set to empty array on error
     }
   };
 
-  useEffect(() => {
-    loadProducts();
-  }, [refreshKey]); // Reload products when refreshKey changes
-
-  const filteredItems = useMemo(() => {
-    if (!searchTerm) {
-      return allItems;
+  const loadCategories = async () => {
+    try {
+      const rows = await invoke("get_categories");
+      setCategories(rows.filter((category) => category.parent_id === null));
+    } catch (err) {
+      console.error("Error querying categories:", err);
+      setCategories([]);
     }
+  };
 
-    let effectiveSearchTerm = searchTerm.toLowerCase().trim();
+  // Quick-key grid: show only the products of the selected category
+  const selectCategory = async (categoryId) => {
+    if (categoryId === activeCategoryId) {
+      setActiveCategoryId(null);
+      setQuickKeys(null);
+      return;
+    }
+    try {
+      const rows = await invoke("get_quick_keys", { categoryId });
+      setActiveCategoryId(categoryId);
+      setQuickKeys(rows);
+    } catch (err) {
+      console.error("Error querying quick keys:", err);
+    }
+  };
 
-    // If multiplier pattern detected, use term before '*'
-    if (effectiveSearchTerm.includes("*")) {
-      const parts = effectiveSearchTerm.split("*").map((p) => p.trim());
+  useEffect(() => {
+    loadProducts();
+    loadCategories();
+    setActiveCategoryId(null);
+    setQuickKeys(null);
+  }, [refreshKey]); // Reload products when refreshKey changes
 
-      // Only use the part before '*' if it exists
-      if (parts.length >= 2 && parts[0]) {
-        effectiveSearchTerm = parts[0];
-      }
-      // Otherwise fall back to regular search with original term
+  useEffect(() => {
+    if (!searchTerm.trim()) {
+      setSearchResults([]);
+      return;
     }
-
-    // Single filter implementation used for both cases
-    return allItems.filter((item) => {
-      const nameMatch = item.product_name
-        .toLowerCase()
-        .includes(effectiveSearchTerm);
-      const pluMatch =
-        item.plu_code &&
-        item.plu_code.toLowerCase().includes(effectiveSearchTerm);
-      const barcodeMatch =
-        item.barcode &&
-        item.barcode.toLowerCase().includes(effectiveSearchTerm);
-
-      return nameMatch || pluMatch || barcodeMatch;
-    });
-  }, [searchTerm, allItems]);
+    let cancelled = false;
+    searchProducts(searchTerm)
+      .then((rows) => {
+        if (!cancelled) setSearchResults(rows);
+      })
+      .catch((err) => console.error("Error searching products:", err));
+    return () => {
+      cancelled = true;
+    };
+  }, [searchTerm, refreshKey]);
+
+  const filteredItems = searchTerm ? searchResults : quickKeys ?? allItems;
 
   const handleSearchSubmit = useCallback(
-    (currentSearchTerm) => {
+    async (currentSearchTerm) => {
       const lowerCaseSearchTerm = currentSearchTerm.toLowerCase().trim();
       let quantity = 1; // Default quantity
 
@@ -90,9 +113,34 @@ function MainScreen({ refreshKey, importError, toggleDrawer }) {
         }
       }
 
+      // A scanned barcode, SKU or PLU resolves exactly; pack barcodes
+      // (e.g. a case of 12) multiply the quantity
+      const code = currentSearchTerm.split("*")[0].trim();
+      const resolved = await invoke("resolve_product_code", { code }).catch(
+        (err) => {
+          console.error("Error resolving product code:", err);
+          return null;
+        }
+      );
+      let itemToAdd = resolved
+        ? { ...resolved.product, barcode: resolved.barcode }
+        : null;
+      if (!itemToAdd) {
+        // Searched again so a fast scanner never adds the hit of a
+        // previous keystroke
+        const hits = await searchProducts(currentSearchTerm).catch((err) => {
+          console.error("Error searching products:", err);
+          return [];
+        });
+        itemToAdd = hits[0];
+      }
+      if (resolved) {
+        quantity = quantity * resolved.quantity;
+      }
+
       // Try to add item with determined quantity
-      if (filteredItems.length > 0) {
-        addItem(filteredItems[0], quantity);
+      if (itemToAdd) {
+        addItem(itemToAdd, quantity);
         setSearchTerm(""); // Clear search bar
         /*
         A input text will be added to the DOM looking like this:
@@ -103,14 +151,14 @@ function MainScreen({ refreshKey, importError, toggleDrawer }) {
         // Todo: select that input text and focus it
         setTimeout(() => {
           let inputElement = document.getElementById(
-            `quantity-${filteredItems[0].sku}`
+            `quantity-${itemToAdd.sku}`
           );
           if (inputElement) {
             inputElement.focus();
           } else {
             console.warn(
               "Could not find input element with id quantity-item.sku",
-              filteredItems[0].sku
+              itemToAdd.sku
             );
           }
         }, 100);
@@ -120,7 +168,7 @@ function MainScreen({ refreshKey, importError, toggleDrawer }) {
       // If neither multiplier nor single match logic applied
       return false;
     },
-    [filteredItems, addItem, setSearchTerm]
+    [addItem, setSearchTerm]
   );
 
   return (
@@ -155,6 +203,21 @@ function MainScreen({ refreshKey, importError, toggleDrawer }) {
               onSearchSubmit={handleSearchSubmit}
             />
           </div>
+          {categories.length > 0 && (
+            <div className="flex flex-row gap-2 px-4 pt-2 overflow-x-auto hide-scrollbar">
+              {categories.map((category) => (
+                <button
+                  key={category.id}
+                  onClick={() => selectCategory(category.id)}
+                  className={`px-3 py-1 text-sm border rounded-full whitespace-nowrap hover:bg-accent ${
+                    category.id === activeCategoryId ? "bg-accent" : ""
+                  }`}
+                >
+                  {category.name}
+                </button>
+              ))}
+            </div>
+          )}
           <Products items={filteredItems} />
         </div>
         <div className="w-[45%]">
@@ -16,7 +16,9 @@ import Modal from "@/components/modal";
 import PaymentScreen from "@/screens/payment-screen";
 import PaymentCompletionScreen from "@/screens/payment-completion-screen";
 import { sha256 } from "js-sha256";
-import { useDatabase } from "@/services/db";
+import { invoke } from "@tauri-apps/api/core";
+import { message } from "@tauri-apps/plugin-dialog";
+import ApprovalDialog, { parseApprovalError } from "@/components/approval-dialog";
 
 export function SalesBar() {
   const [selectedTicket, setSelectedTicket] = useState("ticket1");
@@ -25,8 +27,10 @@ export function SalesBar() {
   const [isCompletionModalOpen, setIsCompletionModalOpen] = useState(false);
   const [paymentDetails, setPaymentDetails] = useState(null);
   const [inputQuantities, setInputQuantities] = useState({});
+  const [inputPrices, setInputPrices] = useState({});
+  // Sale waiting for a manager's approval: { ticketId, details, message }
+  const [pendingApproval, setPendingApproval] = useState(null);
   const tabsListRef = useRef(null);
-  const db = useDatabase();
 
   const dollarToPesosRate = useGlobalStore(
     (state) => state.exchange_rate_usd_to_mxn
@@ -37,6 +41,7 @@ export function SalesBar() {
   const updateItemQuantity = useGlobalStore(
     (state) => state.updateItemQuantity
   );
+  const updateItemPrice = useGlobalStore((state) => state.updateItemPrice);
   const clearCart = useGlobalStore((state) => state.clearCart);
 
   const calculateTotals = () => {
@@ -98,12 +103,32 @@ export function SalesBar() {
     setIsPaymentModalOpen(false);
 
     // Record the sale and clear the cart
-    await recordSale(ticketId, details);
+    try {
+      await recordSale(ticketId, details);
+    } catch (err) {
+      const approvalRequest = parseApprovalError(err);
+      if (approvalRequest) {
+        setPendingApproval({ ticketId, details, ...approvalRequest });
+        return;
+      }
+      await message(`${err}`, {
+        title: "No se pudo registrar la venta",
+        kind: "error",
+      });
+      return;
+    }
 
     // Show completion modal
     setIsCompletionModalOpen(true);
   };
 
+  const handleApproveSale = async (approval) => {
+    const { ticketId, details } = pendingApproval;
+    await recordSale(ticketId, details, approval);
+    setPendingApproval(null);
+    setIsCompletionModalOpen(true);
+  };
+
   const generateTicketId = (paymentMethod, totalAmount) => {
     const timestamp = Date.now();
     const random = Math.random().toString(36).substring(2);
@@ -111,18 +136,13 @@ export function SalesBar() {
     return sha256(data);
   };
 
-  const generateTicketItemId = (ticketId, item) => {
-    const data = `${ticketId}${item.sku}${item.quantity}${item.price}`;
-    return sha256(data);
-  };
-
   /**
-   *
+   * Recorded by Rust as the signed in cashier, see sales.rs
    * @param {paymentDetails} paymentDetails
+   * @param approval Manager approval, when the sale needed one
    */
-  const recordSale = async (ticketId, paymentDetails) => {
+  const recordSale = async (ticketId, paymentDetails, approval = null) => {
     try {
-      const sql = await db.getConnection();
       const {
         totalPaid,
         change,
@@ -132,51 +152,33 @@ export function SalesBar() {
         othersPaid,
       } = paymentDetails;
 
-      const ticketQuery = `
-        INSERT INTO tickets (
-          id, subtotal, taxes, total_due, dollars_paid, pesos_paid, cards_paid, others_paid, total_paid, change, cashier_name, pos_id
-        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
-      `;
-      await sql.execute(ticketQuery, [
-        ticketId,
-        subtotal,
-        taxes,
-        total,
-        dollarsPaid,
-        pesosPaid,
-        cardsPaid,
-        othersPaid,
-        totalPaid,
-        change,
-        "Cashier",
-        "POS1",
-      ]);
-
-      for (const item of cartItems) {
-        const itemId = generateTicketItemId(ticketId, item);
-        const itemTotal = item.price * (item.quantity || 1);
-        const itemQuery = `
-          INSERT INTO ticket_items (
-            id, ticket_id, line_item_sku, line_item_plu_code, line_item_barcode,
-            line_item_product_name, line_item_price, line_item_quantity, line_item_total
-          ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
-        `;
-        await sql.execute(itemQuery, [
-          itemId,
-          ticketId,
-          item.sku,
-          item.plu_code || null,
-          item.barcode || null,
-          item.product_name,
-          item.price,
-          item.quantity || 1,
-          itemTotal,
-        ]);
-      }
+      await invoke("record_sale", {
+        sale: {
+          id: ticketId,
+          subtotal,
+          taxes,
+          total_due: total,
+          dollars_paid: dollarsPaid,
+          pesos_paid: pesosPaid,
+          cards_paid: cardsPaid,
+          others_paid: othersPaid,
+          total_paid: totalPaid,
+          change,
+          items: cartItems.map((item) => ({
+            sku: item.sku,
+            plu_code: item.plu_code || null,
+            barcode: item.barcode || null,
+            product_name: item.product_name,
+            price: item.price,
+            quantity: item.quantity || 1,
+          })),
+        },
+        approval,
+      });
 
       clearCart();
     } catch (err) {
-      console.error("Error recording sale:", err.message);
+      console.error("Error recording sale:", err);
       throw err;
     }
   };
@@ -360,6 +362,54 @@ export function SalesBar() {
                   </tr>
                   {selectedItem === i && (
                     <>
+                      <tr>
+                        <td
+                          colSpan={4}
+                          className="px-2 pl-2.5 pb-2 text-sm text-gray-600"
+                        >
+                          <label className="flex items-center gap-2">
+                            Precio
+                            <input
+                              id={`price-${item.sku}`}
+                              type="text"
+                              inputMode="decimal"
+                              value={
+                                inputPrices[item.sku] !== undefined
+                                  ? inputPrices[item.sku]
+                                  : item.price
+                              }
+                              onClick={(e) => e.stopPropagation()}
+                              onChange={(e) => {
+                                const value = e.target.value;
+                                setInputPrices((prev) => ({
+                                  ...prev,
+                                  [item.sku]: value,
+                                }));
+                              }}
+                              onBlur={() => {
+                                const value = inputPrices[item.sku];
+                                if (value !== undefined) {
+                                  updateItemPrice(
+                                    item.sku,
+                                    value.trim().replace(",", ".")
+                                  );
+                                }
+                                setInputPrices((prev) => {
+                                  const newState = { ...prev };
+                                  delete newState[item.sku];
+                                  return newState;
+                                });
+                              }}
+                              onKeyDown={(e) => {
+                                if (e.key === "Enter") {
+                                  e.target.blur();
+                                }
+                              }}
+                              className="w-24 text-right border-1 h-7"
+                            />
+                          </label>
+                        </td>
+                      </tr>
                       {/*
                     <tr className="bg-accent/60">
                       <td className="px-2 pl-2.5 py-2 overflow-hidden">
@@ -470,6 +520,12 @@ export function SalesBar() {
           paymentDetails={paymentDetails}
         />
       </Modal>
+
+      <ApprovalDialog
+        request={pendingApproval}
+        handleClose={() => setPendingApproval(null)}
+        handleApprove={handleApproveSale}
+      />
     </>
   );
 }
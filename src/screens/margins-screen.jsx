@@ -0,0 +1,161 @@
+import React, { useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { Input } from "@/components/ui/input";
+
+const today = () => {
+  const now = new Date();
+  now.setMinutes(now.getMinutes() - now.getTimezoneOffset());
+  return now.toISOString().slice(0, 10);
+};
+
+const money = (amount) => `$${(amount ?? 0).toFixed(2)}`;
+
+const groupLabels = {
+  product: "Producto",
+  category: "Categoría",
+  day: "Día",
+};
+
+// Gross margin per product, category or day, measured against the average
+// cost each item had when it was sold. Requires the view_reports permission.
+export default function MarginsScreen({ toggleDrawer }) {
+  const [fromDate, setFromDate] = useState(today());
+  const [toDate, setToDate] = useState(today());
+  const [groupBy, setGroupBy] = useState("product");
+  const [rows, setRows] = useState([]);
+  const [error, setError] = useState(null);
+
+  useEffect(() => {
+    invoke("get_margin_report", { fromDate, toDate, groupBy })
+      .then((result) => {
+        setRows(result);
+        setError(null);
+      })
+      .catch((err) => {
+        setRows([]);
+        setError(`${err}`);
+      });
+  }, [fromDate, toDate, groupBy]);
+
+  const totals = rows.reduce(
+    (sum, row) => ({
+      revenue: sum.revenue + row.revenue,
+      cost: sum.cost + row.cost,
+      margin: sum.margin + row.margin,
+      uncosted: sum.uncosted + row.uncosted_revenue,
+    }),
+    { revenue: 0, cost: 0, margin: 0, uncosted: 0 }
+  );
+  const costedRevenue = totals.revenue - totals.uncosted;
+
+  return (
+    <div className="flex flex-col h-screen w-full bg-background">
+      <div className="flex border-b p-2 h-16">
+        <div
+          className="flex items-center justify-center w-10 h-10 bg-background rounded-full cursor-pointer pl-2 mr-2 pt-[6.5px]"
+          onClick={toggleDrawer}
+        >
+          <svg
+            xmlns="http://www.w3.org/2000/svg"
+            className="h-9 w-9"
+            fill="none"
+            viewBox="0 0 24 24"
+            stroke="rgb(60,60,60)"
+          >
+            <path
+              strokeLinecap="round"
+              strokeLinejoin="round"
+              strokeWidth="2"
+              d="M4 6h16M4 12h16M4 18h16"
+            />
+          </svg>
+        </div>
+        <div className="flex items-center justify-center text-xl text-center">
+          Márgenes
+        </div>
+        <div className="flex gap-2 ml-auto items-center">
+          <select
+            className="border rounded-md h-9 px-2"
+            value={groupBy}
+            onChange={(e) => setGroupBy(e.target.value)}
+          >
+            {Object.entries(groupLabels).map(([value, label]) => (
+              <option key={value} value={value}>
+                {label}
+              </option>
+            ))}
+          </select>
+          <Input
+            type="date"
+            className="w-44"
+            value={fromDate}
+            onChange={(e) => setFromDate(e.target.value)}
+          />
+          <Input
+            type="date"
+            className="w-44"
+            value={toDate}
+            onChange={(e) => setToDate(e.target.value)}
+          />
+        </div>
+      </div>
+
+      <div className="flex flex-col flex-1 gap-6 p-4 bg-gray-50 overflow-auto">
+        {error && <div className="text-red-600 text-sm">{error}</div>}
+        <table className="bg-white">
+          <thead>
+            <tr className="text-left border-b">
+              <th className="p-2">{groupLabels[groupBy]}</th>
+              <th className="p-2 text-right">Cantidad</th>
+              <th className="p-2 text-right">Venta</th>
+              <th className="p-2 text-right">Costo</th>
+              <th className="p-2 text-right">Margen</th>
+              <th className="p-2 text-right">%</th>
+              <th className="p-2 text-right">Sin costo</th>
+            </tr>
+          </thead>
+          <tbody>
+            {rows.map((row) => (
+              <tr key={row.key ?? ""} className="border-b">
+                <td className="p-2">{row.label}</td>
+                <td className="p-2 text-right">{row.quantity}</td>
+                <td className="p-2 text-right">{money(row.revenue)}</td>
+                <td className="p-2 text-right">{money(row.cost)}</td>
+                <td
+                  className={`p-2 text-right ${
+                    row.margin < 0 ? "text-red-600" : ""
+                  }`}
+                >
+                  {money(row.margin)}
+                </td>
+                <td className="p-2 text-right">
+                  {row.margin_percent?.toFixed(1) ?? "—"}
+                </td>
+                <td className="p-2 text-right text-gray-500">
+                  {row.uncosted_revenue > 0 ? money(row.uncosted_revenue) : ""}
+                </td>
+              </tr>
+            ))}
+          </tbody>
+          <tfoot>
+            <tr className="font-bold">
+              <td className="p-2">Total</td>
+              <td className="p-2"></td>
+              <td className="p-2 text-right">{money(totals.revenue)}</td>
+              <td className="p-2 text-right">{money(totals.cost)}</td>
+              <td className="p-2 text-right">{money(totals.margin)}</td>
+              <td className="p-2 text-right">
+                {costedRevenue > 0
+                  ? ((totals.margin / costedRevenue) * 100).toFixed(1)
+                  : "—"}
+              </td>
+              <td className="p-2 text-right text-gray-500">
+                {totals.uncosted > 0 ? money(totals.uncosted) : ""}
+              </td>
+            </tr>
+          </tfoot>
+        </table>
+      </div>
+    </div>
+  );
+}
@@ -0,0 +1,310 @@
+import React, { useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
+import { Button } from "@/components/ui/button";
+import { Input } from "@/components/ui/input";
+
+const emptyForm = { sku: "", tier: "", price: "", effectiveAt: "" };
+const emptyCostForm = { sku: "", cost: "" };
+
+// Price changes: right away or scheduled (e.g. the monthly flour increase),
+// and the products whose shelf label needs reprinting. Scheduled changes
+// are applied by a background task in Rust, which emits `prices-changed`.
+// Also sets product costs by hand and lists the prices below cost.
+export default function PricesScreen({ toggleDrawer }) {
+  const [form, setForm] = useState(emptyForm);
+  const [scheduled, setScheduled] = useState([]);
+  const [labels, setLabels] = useState([]);
+  const [belowCost, setBelowCost] = useState([]);
+  const [costForm, setCostForm] = useState(emptyCostForm);
+  const [error, setError] = useState(null);
+
+  const loadPrices = async () => {
+    try {
+      const [scheduledRows, labelRows, belowCostRows] = await Promise.all([
+        invoke("get_scheduled_price_changes"),
+        invoke("get_label_queue"),
+        invoke("get_below_cost_products"),
+      ]);
+      setScheduled(scheduledRows);
+      setLabels(labelRows);
+      setBelowCost(belowCostRows);
+    } catch (err) {
+      setError(`${err}`);
+    }
+  };
+
+  useEffect(() => {
+    loadPrices();
+    const unlisten = listen("prices-changed", loadPrices);
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
+  const submit = async (command, args) => {
+    try {
+      await invoke(command, {
+        sku: form.sku.trim(),
+        tier: form.tier || null,
+        ...args,
+      });
+      setForm(emptyForm);
+      setError(null);
+      await loadPrices();
+    } catch (err) {
+      setError(`${err}`);
+    }
+  };
+
+  const handleCost = async (event) => {
+    event.preventDefault();
+    try {
+      await invoke("set_product_cost", {
+        sku: costForm.sku.trim(),
+        cost: parseFloat(costForm.cost.replace(",", ".")),
+      });
+      setCostForm(emptyCostForm);
+      setError(null);
+      await loadPrices();
+    } catch (err) {
+      setError(`${err}`);
+    }
+  };
+
+  const handleCancel = async (id) => {
+    try {
+      await invoke("cancel_scheduled_price_change", { id });
+      await loadPrices();
+    } catch (err) {
+      setError(`${err}`);
+    }
+  };
+
+  const handleLabelsPrinted = async () => {
+    try {
+      await invoke("clear_label_flags", {
+        skus: labels.map((label) => label.sku),
+      });
+      await loadPrices();
+    } catch (err) {
+      setError(`${err}`);
+    }
+  };
+
+  const price = parseFloat(form.price);
+  const isValid = form.sku.trim() && !isNaN(price) && price >= 0;
+
+  return (
+    <div className="flex flex-col h-screen w-full bg-background">
+      <div className="flex border-b p-2 h-16">
+        <div
+          className="flex items-center justify-center w-10 h-10 bg-background rounded-full cursor-pointer pl-2 mr-2 pt-[6.5px]"
+          onClick={toggleDrawer}
+        >
+          <svg
+            xmlns="http://www.w3.org/2000/svg"
+            className="h-9 w-9"
+            fill="none"
+            viewBox="0 0 24 24"
+            stroke="rgb(60,60,60)"
+          >
+            <path
+              strokeLinecap="round"
+              strokeLinejoin="round"
+              strokeWidth="2"
+              d="M4 6h16M4 12h16M4 18h16"
+            />
+          </svg>
+        </div>
+        <div className="flex items-center justify-center text-xl text-center">
+          Precios
+        </div>
+      </div>
+
+      <div className="flex flex-1 gap-6 p-4 bg-gray-50 overflow-auto">
+        <div className="flex flex-col flex-1 gap-4">
+          <h2 className="text-lg font-bold">Cambios programados</h2>
+          <table className="bg-white">
+            <thead>
+              <tr className="text-left border-b">
+                <th className="p-2">Producto</th>
+                <th className="p-2">Precio</th>
+                <th className="p-2">Actual</th>
+                <th className="p-2">Nuevo</th>
+                <th className="p-2">A partir de</th>
+                <th className="p-2"></th>
+              </tr>
+            </thead>
+            <tbody>
+              {scheduled.map((change) => (
+                <tr key={change.id} className="border-b">
+                  <td className="p-2">
+                    {change.product_name} ({change.sku})
+                  </td>
+                  <td className="p-2">{change.tier ?? "Menudeo"}</td>
+                  <td className="p-2 text-right">
+                    {change.current_price?.toFixed(2)}
+                  </td>
+                  <td className="p-2 text-right">
+                    {change.new_price.toFixed(2)}
+                  </td>
+                  <td className="p-2">{change.effective_at}</td>
+                  <td className="p-2">
+                    <Button
+                      variant="outline"
+                      size="sm"
+                      onClick={() => handleCancel(change.id)}
+                    >
+                      Cancelar
+                    </Button>
+                  </td>
+                </tr>
+              ))}
+            </tbody>
+          </table>
+
+          <div className="flex items-center gap-4">
+            <h2 className="text-lg font-bold">Etiquetas por reimprimir</h2>
+            {labels.length > 0 && (
+              <Button variant="outline" size="sm" onClick={handleLabelsPrinted}>
+                Marcar impresas
+              </Button>
+            )}
+          </div>
+          <table className="bg-white">
+            <thead>
+              <tr className="text-left border-b">
+                <th className="p-2">SKU</th>
+                <th className="p-2">Producto</th>
+                <th className="p-2">Precio</th>
+                <th className="p-2">Cambió</th>
+              </tr>
+            </thead>
+            <tbody>
+              {labels.map((label) => (
+                <tr key={label.sku} className="border-b">
+                  <td className="p-2">{label.sku}</td>
+                  <td className="p-2">{label.product_name}</td>
+                  <td className="p-2 text-right">${label.price.toFixed(2)}</td>
+                  <td className="p-2">{label.updated_at}</td>
+                </tr>
+              ))}
+            </tbody>
+          </table>
+
+          {belowCost.length > 0 && (
+            <>
+              <h2 className="text-lg font-bold text-red-600">
+                Precios debajo del costo
+              </h2>
+              <table className="bg-white">
+                <thead>
+                  <tr className="text-left border-b">
+                    <th className="p-2">Producto</th>
+                    <th className="p-2">Precio</th>
+                    <th className="p-2 text-right">Actual</th>
+                    <th className="p-2 text-right">Último costo</th>
+                  </tr>
+                </thead>
+                <tbody>
+                  {belowCost.map((item) => (
+                    <tr key={`${item.sku}-${item.tier}`} className="border-b">
+                      <td className="p-2">
+                        {item.product_name} ({item.sku})
+                      </td>
+                      <td className="p-2">{item.tier ?? "Menudeo"}</td>
+                      <td className="p-2 text-right text-red-600">
+                        ${item.price.toFixed(2)}
+                      </td>
+                      <td className="p-2 text-right">
+                        ${item.cost.toFixed(2)}
+                      </td>
+                    </tr>
+                  ))}
+                </tbody>
+              </table>
+            </>
+          )}
+        </div>
+
+        <form
+          className="flex flex-col gap-3 w-80"
+          onSubmit={(event) => event.preventDefault()}
+        >
+          <h2 className="text-lg font-bold">Cambiar precio</h2>
+          <Input
+            placeholder="SKU"
+            value={form.sku}
+            onChange={(e) => setForm({ ...form, sku: e.target.value })}
+          />
+          <select
+            className="border rounded-md h-9 px-2"
+            value={form.tier}
+            onChange={(e) => setForm({ ...form, tier: e.target.value })}
+          >
+            <option value="">Menudeo</option>
+            <option value="mayoreo">Mayoreo</option>
+          </select>
+          <Input
+            placeholder="Nuevo precio"
+            inputMode="decimal"
+            value={form.price}
+            onChange={(e) => setForm({ ...form, price: e.target.value })}
+          />
+          <Input
+            type="datetime-local"
+            value={form.effectiveAt}
+            onChange={(e) => setForm({ ...form, effectiveAt: e.target.value })}
+          />
+          {error && <div className="text-red-600 text-sm">{error}</div>}
+          <div className="flex gap-2">
+            <Button
+              type="button"
+              disabled={!isValid}
+              onClick={() => submit("set_product_price", { price })}
+            >
+              Cambiar ahora
+            </Button>
+            <Button
+              type="button"
+              variant="outline"
+              disabled={!isValid || !form.effectiveAt}
+              onClick={() =>
+                submit("schedule_price_change", {
+                  newPrice: price,
+                  effectiveAt: form.effectiveAt,
+                })
+              }
+            >
+              Programar
+            </Button>
+          </div>
+
+          <h2 className="text-lg font-bold mt-4">Cambiar costo</h2>
+          <Input
+            placeholder="SKU"
+            value={costForm.sku}
+            onChange={(e) => setCostForm({ ...costForm, sku: e.target.value })}
+          />
+          <Input
+            placeholder="Costo por unidad"
+            inputMode="decimal"
+            value={costForm.cost}
+            onChange={(e) =>
+              setCostForm({ ...costForm, cost: e.target.value })
+            }
+          />
+          <Button
+            type="button"
+            className="self-start"
+            disabled={!costForm.sku.trim() || !costForm.cost}
+            onClick={handleCost}
+          >
+            Guardar costo
+          </Button>
+        </form>
+      </div>
+    </div>
+  );
+}
@@ -0,0 +1,523 @@
+import React, { useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { Button } from "@/components/ui/button";
+import { Input } from "@/components/ui/input";
+
+const statusLabels = {
+  open: "Abierta",
+  partial: "Parcial",
+  received: "Recibida",
+  cancelled: "Cancelada",
+};
+
+const emptyDraft = { supplierId: "", notes: "", lines: [] };
+
+const parseNumber = (value) => parseFloat(`${value}`.replace(",", "."));
+
+// Purchase orders to suppliers: creating and printing them, and receiving
+// the goods (in full or in part) by scanning their barcodes.
+export default function PurchasesScreen({ toggleDrawer }) {
+  const [orders, setOrders] = useState([]);
+  const [suppliers, setSuppliers] = useState([]);
+  const [detail, setDetail] = useState(null);
+  const [draft, setDraft] = useState(null);
+  const [code, setCode] = useState("");
+  const [receiving, setReceiving] = useState({});
+  const [invoice, setInvoice] = useState({ folio: "", exchangeRate: "" });
+  const [error, setError] = useState(null);
+
+  const loadOrders = async () => {
+    try {
+      setOrders(await invoke("get_purchase_orders"));
+    } catch (err) {
+      setError(`${err}`);
+    }
+  };
+
+  const openOrder = async (id) => {
+    try {
+      setDetail(await invoke("get_purchase_order", { id }));
+      setDraft(null);
+      setReceiving({});
+      setInvoice({ folio: "", exchangeRate: "" });
+      setError(null);
+    } catch (err) {
+      setError(`${err}`);
+    }
+  };
+
+  useEffect(() => {
+    loadOrders();
+    invoke("get_suppliers")
+      .then((all) => setSuppliers(all.filter((supplier) => supplier.is_active)))
+      .catch((err) => setError(`${err}`));
+  }, []);
+
+  // Scanned or typed codes resolve to a product and its pack quantity
+  const resolveCode = async () => {
+    const resolved = await invoke("resolve_product_code", { code }).catch(
+      (err) => {
+        setError(`${err}`);
+        return null;
+      }
+    );
+    setCode("");
+    if (!resolved) {
+      setError(`Código desconocido: ${code}`);
+    }
+    return resolved;
+  };
+
+  const handleDraftScan = async (event) => {
+    event.preventDefault();
+    const resolved = await resolveCode();
+    if (!resolved) return;
+    const { sku, product_name } = resolved.product;
+    const existing = draft.lines.find((line) => line.sku === sku);
+    const lines = existing
+      ? draft.lines.map((line) =>
+          line.sku === sku
+            ? {
+                ...line,
+                quantity: parseNumber(line.quantity) + resolved.quantity,
+              }
+            : line
+        )
+      : [
+          ...draft.lines,
+          { sku, product_name, quantity: resolved.quantity, unitCost: "" },
+        ];
+    setDraft({ ...draft, lines });
+    setError(null);
+  };
+
+  const updateDraftLine = (sku, changes) => {
+    setDraft({
+      ...draft,
+      lines: draft.lines.map((line) =>
+        line.sku === sku ? { ...line, ...changes } : line
+      ),
+    });
+  };
+
+  const handleCreate = async () => {
+    try {
+      const id = await invoke("create_purchase_order", {
+        order: {
+          supplier_id: Number(draft.supplierId),
+          notes: draft.notes.trim() || null,
+          lines: draft.lines.map((line) => ({
+            sku: line.sku,
+            quantity: parseNumber(line.quantity),
+            unit_cost: parseNumber(line.unitCost),
+          })),
+        },
+      });
+      await loadOrders();
+      await openOrder(id);
+    } catch (err) {
+      setError(`${err}`);
+    }
+  };
+
+  const handleReceiveScan = async (event) => {
+    event.preventDefault();
+    const resolved = await resolveCode();
+    if (!resolved) return;
+    const line = detail.lines.find((line) => line.sku === resolved.product.sku);
+    if (!line) {
+      setError(`${resolved.product.product_name} no está en esta orden`);
+      return;
+    }
+    setReceiving({
+      ...receiving,
+      [line.id]:
+        (parseNumber(receiving[line.id] ?? 0) || 0) + resolved.quantity,
+    });
+    setError(null);
+  };
+
+  const handleReceiveAll = () => {
+    setReceiving(
+      Object.fromEntries(
+        detail.lines.map((line) => [
+          line.id,
+          Math.max(line.quantity_ordered - line.quantity_received, 0),
+        ])
+      )
+    );
+  };
+
+  const handleReceive = async () => {
+    try {
+      await invoke("receive_purchase_order", {
+        id: detail.order.id,
+        receipt: {
+          supplier_invoice: invoice.folio.trim() || null,
+          exchange_rate: invoice.exchangeRate
+            ? parseNumber(invoice.exchangeRate)
+            : null,
+          lines: Object.entries(receiving)
+            .map(([lineId, quantity]) => ({
+              line_id: Number(lineId),
+              quantity: parseNumber(quantity) || 0,
+            }))
+            .filter((line) => line.quantity > 0),
+        },
+      });
+      await Promise.all([loadOrders(), openOrder(detail.order.id)]);
+    } catch (err) {
+      setError(`${err}`);
+    }
+  };
+
+  const handlePrint = async () => {
+    try {
+      await invoke("print_purchase_order", { id: detail.order.id });
+    } catch (err) {
+      setError(`${err}`);
+    }
+  };
+
+  const handleCancel = async () => {
+    try {
+      await invoke("cancel_purchase_order", { id: detail.order.id });
+      await Promise.all([loadOrders(), openOrder(detail.order.id)]);
+    } catch (err) {
+      setError(`${err}`);
+    }
+  };
+
+  const isReceivable =
+    detail && ["open", "partial"].includes(detail.order.status);
+
+  return (
+    <div className="flex flex-col h-screen w-full bg-background">
+      <div className="flex border-b p-2 h-16">
+        <div
+          className="flex items-center justify-center w-10 h-10 bg-background rounded-full cursor-pointer pl-2 mr-2 pt-[6.5px]"
+          onClick={toggleDrawer}
+        >
+          <svg
+            xmlns="http://www.w3.org/2000/svg"
+            className="h-9 w-9"
+            fill="none"
+            viewBox="0 0 24 24"
+            stroke="rgb(60,60,60)"
+          >
+            <path
+              strokeLinecap="round"
+              strokeLinejoin="round"
+              strokeWidth="2"
+              d="M4 6h16M4 12h16M4 18h16"
+            />
+          </svg>
+        </div>
+        <div className="flex items-center justify-center text-xl text-center">
+          Compras
+        </div>
+        <div className="flex gap-2 ml-auto items-center">
+          <Button
+            onClick={() => {
+              setDraft(emptyDraft);
+              setDetail(null);
+            }}
+          >
+            Nueva orden
+          </Button>
+        </div>
+      </div>
+
+      {error && <div className="mx-4 mt-2 text-red-600 text-sm">{error}</div>}
+
+      <div className="flex flex-1 gap-6 p-4 bg-gray-50 overflow-auto">
+        <table className="flex-1 self-start bg-white">
+          <thead>
+            <tr className="text-left border-b">
+              <th className="p-2">Folio</th>
+              <th className="p-2">Fecha</th>
+              <th className="p-2">Proveedor</th>
+              <th className="p-2">Estado</th>
+              <th className="p-2 text-right">Total</th>
+            </tr>
+          </thead>
+          <tbody>
+            {orders.map((order) => (
+              <tr
+                key={order.id}
+                className={`border-b cursor-pointer hover:bg-gray-100 ${
+                  detail?.order.id === order.id ? "bg-gray-100" : ""
+                }`}
+                onClick={() => openOrder(order.id)}
+              >
+                <td className="p-2">OC-{order.id}</td>
+                <td className="p-2 whitespace-nowrap">{order.created_at}</td>
+                <td className="p-2">{order.supplier_name}</td>
+                <td className="p-2">{statusLabels[order.status]}</td>
+                <td className="p-2 text-right">
+                  {order.currency} ${order.total.toFixed(2)}
+                </td>
+              </tr>
+            ))}
+          </tbody>
+        </table>
+
+        {draft && (
+          <div className="flex flex-col gap-3 w-[32rem]">
+            <h2 className="text-lg font-bold">Nueva orden de compra</h2>
+            <select
+              className="border rounded-md h-9 px-2"
+              value={draft.supplierId}
+              onChange={(e) =>
+                setDraft({ ...draft, supplierId: e.target.value })
+              }
+            >
+              <option value="">Proveedor...</option>
+              {suppliers.map((supplier) => (
+                <option key={supplier.id} value={supplier.id}>
+                  {supplier.name} ({supplier.currency})
+                </option>
+              ))}
+            </select>
+            <form className="flex gap-2" onSubmit={handleDraftScan}>
+              <Input
+                placeholder="Escanear código o SKU"
+                value={code}
+                onChange={(e) => setCode(e.target.value)}
+              />
+              <Button type="submit" variant="outline" disabled={!code.trim()}>
+                Agregar
+              </Button>
+            </form>
+            <table className="bg-white text-sm">
+              <thead>
+                <tr className="text-left border-b">
+                  <th className="p-2">Producto</th>
+                  <th className="p-2 text-right">Cantidad</th>
+                  <th className="p-2 text-right">Costo</th>
+                  <th className="p-2"></th>
+                </tr>
+              </thead>
+              <tbody>
+                {draft.lines.map((line) => (
+                  <tr key={line.sku} className="border-b">
+                    <td className="p-2">{line.product_name}</td>
+                    <td className="p-2">
+                      <Input
+                        className="w-20 ml-auto text-right"
+                        inputMode="decimal"
+                        value={line.quantity}
+                        onChange={(e) =>
+                          updateDraftLine(line.sku, {
+                            quantity: e.target.value,
+                          })
+                        }
+                      />
+                    </td>
+                    <td className="p-2">
+                      <Input
+                        className="w-24 ml-auto text-right"
+                        inputMode="decimal"
+                        value={line.unitCost}
+                        onChange={(e) =>
+                          updateDraftLine(line.sku, {
+                            unitCost: e.target.value,
+                          })
+                        }
+                      />
+                    </td>
+                    <td className="p-2">
+                      <Button
+                        variant="outline"
+                        size="sm"
+                        onClick={() =>
+                          setDraft({
+                            ...draft,
+                            lines: draft.lines.filter(
+                              (other) => other.sku !== line.sku
+                            ),
+                          })
+                        }
+                      >
+                        Quitar
+                      </Button>
+                    </td>
+                  </tr>
+                ))}
+              </tbody>
+            </table>
+            <Input
+              placeholder="Notas"
+              value={draft.notes}
+              onChange={(e) => setDraft({ ...draft, notes: e.target.value })}
+            />
+            <Button
+              className="self-start"
+              disabled={
+                !draft.supplierId ||
+                draft.lines.length === 0 ||
+                draft.lines.some((line) => line.unitCost === "")
+              }
+              onClick={handleCreate}
+            >
+              Crear orden
+            </Button>
+          </div>
+        )}
+
+        {detail && (
+          <div className="flex flex-col gap-3 w-[36rem]">
+            <div className="flex items-center gap-4">
+              <h2 className="text-lg font-bold">
+                OC-{detail.order.id} · {detail.order.supplier_name}
+              </h2>
+              <span className="text-sm">
+                {statusLabels[detail.order.status]} · {detail.order.currency}
+              </span>
+            </div>
+            <div className="flex gap-2">
+              <Button variant="outline" size="sm" onClick={handlePrint}>
+                Imprimir
+              </Button>
+              {isReceivable && (
+                <Button variant="outline" size="sm" onClick={handleCancel}>
+                  Cancelar orden
+                </Button>
+              )}
+            </div>
+            {detail.order.notes && (
+              <div className="text-sm">{detail.order.notes}</div>
+            )}
+
+            {isReceivable && (
+              <form className="flex gap-2" onSubmit={handleReceiveScan}>
+                <Input
+                  placeholder="Escanear lo recibido"
+                  value={code}
+                  onChange={(e) => setCode(e.target.value)}
+                />
+                <Button type="submit" variant="outline" disabled={!code.trim()}>
+                  Sumar
+                </Button>
+                <Button
+                  type="button"
+                  variant="outline"
+                  onClick={handleReceiveAll}
+                >
+                  Todo
+                </Button>
+              </form>
+            )}
+
+            <table className="bg-white text-sm">
+              <thead>
+                <tr className="text-left border-b">
+                  <th className="p-2">Producto</th>
+                  <th className="p-2 text-right">Pedido</th>
+                  <th className="p-2 text-right">Recibido</th>
+                  <th className="p-2 text-right">Costo</th>
+                  {isReceivable && <th className="p-2 text-right">Recibir</th>}
+                </tr>
+              </thead>
+              <tbody>
+                {detail.lines.map((line) => (
+                  <tr key={line.id} className="border-b">
+                    <td className="p-2">{line.product_name}</td>
+                    <td className="p-2 text-right">{line.quantity_ordered}</td>
+                    <td
+                      className={`p-2 text-right ${
+                        line.quantity_received < line.quantity_ordered
+                          ? "text-orange-600"
+                          : ""
+                      }`}
+                    >
+                      {line.quantity_received}
+                    </td>
+                    <td className="p-2 text-right">
+                      ${line.unit_cost.toFixed(2)}
+                    </td>
+                    {isReceivable && (
+                      <td className="p-2">
+                        <Input
+                          className="w-20 ml-auto text-right"
+                          inputMode="decimal"
+                          value={receiving[line.id] ?? ""}
+                          onChange={(e) =>
+                            setReceiving({
+                              ...receiving,
+                              [line.id]: e.target.value,
+                            })
+                          }
+                        />
+                      </td>
+                    )}
+                  </tr>
+                ))}
+              </tbody>
+            </table>
+
+            {isReceivable && (
+              <div className="flex gap-2">
+                <Input
+                  placeholder="Folio de factura"
+                  value={invoice.folio}
+                  onChange={(e) =>
+                    setInvoice({ ...invoice, folio: e.target.value })
+                  }
+                />
+                {detail.order.currency === "USD" && (
+                  <Input
+                    placeholder="Tipo de cambio"
+                    className="w-32"
+                    inputMode="decimal"
+                    value={invoice.exchangeRate}
+                    onChange={(e) =>
+                      setInvoice({ ...invoice, exchangeRate: e.target.value })
+                    }
+                  />
+                )}
+                <Button
+                  disabled={
+                    !Object.values(receiving).some((q) => parseNumber(q) > 0)
+                  }
+                  onClick={handleReceive}
+                >
+                  Recibir
+                </Button>
+              </div>
+            )}
+
+            {detail.receipts.length > 0 && (
+              <table className="bg-white text-sm">
+                <thead>
+                  <tr className="text-left border-b">
+                    <th className="p-2">Recepción</th>
+                    <th className="p-2">Factura</th>
+                    <th className="p-2 text-right">Tipo de cambio</th>
+                    <th className="p-2">Recibió</th>
+                  </tr>
+                </thead>
+                <tbody>
+                  {detail.receipts.map((receipt) => (
+                    <tr key={receipt.id} className="border-b">
+                      <td className="p-2 whitespace-nowrap">
+                        {receipt.received_at}
+                      </td>
+                      <td className="p-2">{receipt.supplier_invoice}</td>
+                      <td className="p-2 text-right">
+                        {receipt.currency === "USD"
+                          ? receipt.exchange_rate.toFixed(4)
+                          : "—"}
+                      </td>
+                      <td className="p-2">{receipt.received_by_name}</td>
+                    </tr>
+                  ))}
+                </tbody>
+              </table>
+            )}
+          </div>
+        )}
+      </div>
+    </div>
+  );
+}
@@ -0,0 +1,432 @@
+import React, { useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { Button } from "@/components/ui/button";
+import { Input } from "@/components/ui/input";
+import useGlobalStore from "@/hooks/useGlobalStore";
+
+const today = () => {
+  const now = new Date();
+  now.setMinutes(now.getMinutes() - now.getTimezoneOffset());
+  return now.toISOString().slice(0, 10);
+};
+
+const parseNumber = (value) => parseFloat(`${value}`.replace(",", "."));
+
+const emptyRecipe = {
+  sku: "",
+  yield_quantity: "",
+  notes: "",
+  ingredients: [],
+  isNew: true,
+};
+
+// Recipes (bill of materials) of produced goods, production batches of
+// the ones that track stock, and theoretical versus actual use of raw
+// materials. Recipes of products sold fresh consume their ingredients on
+// each sale.
+export default function RecipesScreen({ toggleDrawer }) {
+  const hasPermission = useGlobalStore((state) => state.hasPermission);
+  const [recipes, setRecipes] = useState([]);
+  const [editing, setEditing] = useState(null);
+  const [ingredientCode, setIngredientCode] = useState("");
+  const [production, setProduction] = useState({ quantity: "", used: {} });
+  const [range, setRange] = useState({ from: today(), to: today() });
+  const [usage, setUsage] = useState([]);
+  const [error, setError] = useState(null);
+
+  const loadRecipes = async () => {
+    try {
+      setRecipes(await invoke("get_recipes"));
+    } catch (err) {
+      setError(`${err}`);
+    }
+  };
+
+  useEffect(() => {
+    loadRecipes();
+  }, []);
+
+  useEffect(() => {
+    if (!hasPermission("view_reports")) return;
+    invoke("get_ingredient_usage_report", {
+      fromDate: range.from,
+      toDate: range.to,
+    })
+      .then(setUsage)
+      .catch((err) => setError(`${err}`));
+  }, [range, recipes]);
+
+  const selectRecipe = (recipe) => {
+    setEditing({ ...recipe, notes: recipe.notes ?? "", isNew: false });
+    setProduction({ quantity: "", used: {} });
+    setError(null);
+  };
+
+  const handleAddIngredient = async (event) => {
+    event.preventDefault();
+    try {
+      const resolved = await invoke("resolve_product_code", {
+        code: ingredientCode,
+      });
+      if (!resolved) {
+        setError(`Código desconocido: ${ingredientCode}`);
+        return;
+      }
+      const { sku, product_name } = resolved.product;
+      if (!editing.ingredients.some((i) => i.ingredient_sku === sku)) {
+        setEditing({
+          ...editing,
+          ingredients: [
+            ...editing.ingredients,
+            { ingredient_sku: sku, product_name, quantity: "" },
+          ],
+        });
+      }
+      setIngredientCode("");
+      setError(null);
+    } catch (err) {
+      setError(`${err}`);
+    }
+  };
+
+  const updateIngredient = (sku, quantity) => {
+    setEditing({
+      ...editing,
+      ingredients: editing.ingredients.map((ingredient) =>
+        ingredient.ingredient_sku === sku
+          ? { ...ingredient, quantity }
+          : ingredient
+      ),
+    });
+  };
+
+  const handleSave = async () => {
+    try {
+      await invoke("save_recipe", {
+        recipe: {
+          sku: editing.sku.trim(),
+          yield_quantity: parseNumber(editing.yield_quantity),
+          notes: editing.notes.trim() || null,
+          ingredients: editing.ingredients.map((ingredient) => ({
+            ingredient_sku: ingredient.ingredient_sku,
+            quantity: parseNumber(ingredient.quantity),
+          })),
+        },
+      });
+      setEditing(null);
+      setError(null);
+      await loadRecipes();
+    } catch (err) {
+      setError(`${err}`);
+    }
+  };
+
+  const handleDelete = async () => {
+    try {
+      await invoke("delete_recipe", { sku: editing.sku });
+      setEditing(null);
+      await loadRecipes();
+    } catch (err) {
+      setError(`${err}`);
+    }
+  };
+
+  const produced = parseNumber(production.quantity);
+  const theoretical = (ingredient) =>
+    produced > 0
+      ? (ingredient.quantity * produced) / editing.yield_quantity
+      : 0;
+
+  const handleProduce = async (event) => {
+    event.preventDefault();
+    try {
+      await invoke("record_production", {
+        sku: editing.sku,
+        quantity: produced,
+        used: Object.entries(production.used)
+          .filter(([, quantity]) => quantity !== "")
+          .map(([sku, quantity]) => ({
+            ingredient_sku: sku,
+            quantity: parseNumber(quantity),
+          })),
+        notes: null,
+      });
+      setProduction({ quantity: "", used: {} });
+      setError(null);
+      await loadRecipes();
+    } catch (err) {
+      setError(`${err}`);
+    }
+  };
+
+  return (
+    <div className="flex flex-col h-screen w-full bg-background">
+      <div className="flex border-b p-2 h-16">
+        <div
+          className="flex items-center justify-center w-10 h-10 bg-background rounded-full cursor-pointer pl-2 mr-2 pt-[6.5px]"
+          onClick={toggleDrawer}
+        >
+          <svg
+            xmlns="http://www.w3.org/2000/svg"
+            className="h-9 w-9"
+            fill="none"
+            viewBox="0 0 24 24"
+            stroke="rgb(60,60,60)"
+          >
+            <path
+              strokeLinecap="round"
+              strokeLinejoin="round"
+              strokeWidth="2"
+              d="M4 6h16M4 12h16M4 18h16"
+            />
+          </svg>
+        </div>
+        <div className="flex items-center justify-center text-xl text-center">
+          Recetas y producción
+        </div>
+        <div className="flex gap-2 ml-auto items-center">
+          <Button onClick={() => setEditing(emptyRecipe)}>Nueva receta</Button>
+        </div>
+      </div>
+
+      {error && <div className="mx-4 mt-2 text-red-600 text-sm">{error}</div>}
+
+      <div className="flex flex-1 gap-6 p-4 bg-gray-50 overflow-auto">
+        <div className="flex flex-col flex-1 gap-4">
+          <table className="self-stretch bg-white">
+            <thead>
+              <tr className="text-left border-b">
+                <th className="p-2">Producto</th>
+                <th className="p-2 text-right">Rinde</th>
+                <th className="p-2">Consumo</th>
+              </tr>
+            </thead>
+            <tbody>
+              {recipes.map((recipe) => (
+                <tr
+                  key={recipe.sku}
+                  className={`border-b cursor-pointer hover:bg-gray-100 ${
+                    editing?.sku === recipe.sku ? "bg-gray-100" : ""
+                  }`}
+                  onClick={() => selectRecipe(recipe)}
+                >
+                  <td className="p-2">{recipe.product_name}</td>
+                  <td className="p-2 text-right">{recipe.yield_quantity}</td>
+                  <td className="p-2">
+                    {recipe.consumed_on_sale ? "Al vender" : "Por lote"}
+                  </td>
+                </tr>
+              ))}
+            </tbody>
+          </table>
+
+          {hasPermission("view_reports") && (
+            <>
+              <div className="flex items-center gap-2">
+                <h2 className="text-lg font-bold mr-auto">
+                  Consumo teórico vs. real
+                </h2>
+                <Input
+                  type="date"
+                  className="w-44"
+                  value={range.from}
+                  onChange={(e) => setRange({ ...range, from: e.target.value })}
+                />
+                <Input
+                  type="date"
+                  className="w-44"
+                  value={range.to}
+                  onChange={(e) => setRange({ ...range, to: e.target.value })}
+                />
+              </div>
+              <table className="bg-white text-sm">
+                <thead>
+                  <tr className="text-left border-b">
+                    <th className="p-2">Materia prima</th>
+                    <th className="p-2 text-right">Teórico</th>
+                    <th className="p-2 text-right">Real</th>
+                    <th className="p-2 text-right">Diferencia</th>
+                    <th className="p-2 text-right">%</th>
+                  </tr>
+                </thead>
+                <tbody>
+                  {usage.map((row) => (
+                    <tr key={row.sku} className="border-b">
+                      <td className="p-2">{row.product_name}</td>
+                      <td className="p-2 text-right">
+                        {row.theoretical.toFixed(3)}
+                      </td>
+                      <td className="p-2 text-right">
+                        {row.actual.toFixed(3)}
+                      </td>
+                      <td
+                        className={`p-2 text-right ${
+                          row.variance > 0 ? "text-red-600" : ""
+                        }`}
+                      >
+                        {row.variance.toFixed(3)}
+                      </td>
+                      <td className="p-2 text-right">
+                        {row.variance_percent?.toFixed(1) ?? "—"}
+                      </td>
+                    </tr>
+                  ))}
+                </tbody>
+              </table>
+            </>
+          )}
+        </div>
+
+        {editing && (
+          <div className="flex flex-col gap-3 w-[30rem]">
+            <h2 className="text-lg font-bold">
+              {editing.isNew ? "Nueva receta" : editing.product_name}
+            </h2>
+            {editing.isNew && (
+              <Input
+                placeholder="SKU del producto terminado"
+                value={editing.sku}
+                onChange={(e) =>
+                  setEditing({ ...editing, sku: e.target.value })
+                }
+              />
+            )}
+            <Input
+              placeholder="Rinde (unidades del producto)"
+              inputMode="decimal"
+              value={editing.yield_quantity}
+              onChange={(e) =>
+                setEditing({ ...editing, yield_quantity: e.target.value })
+              }
+            />
+            <form className="flex gap-2" onSubmit={handleAddIngredient}>
+              <Input
+                placeholder="Materia prima (código o SKU)"
+                value={ingredientCode}
+                onChange={(e) => setIngredientCode(e.target.value)}
+              />
+              <Button
+                type="submit"
+                variant="outline"
+                disabled={!ingredientCode.trim()}
+              >
+                Agregar
+              </Button>
+            </form>
+            <table className="bg-white text-sm">
+              <tbody>
+                {editing.ingredients.map((ingredient) => (
+                  <tr key={ingredient.ingredient_sku} className="border-b">
+                    <td className="p-2">{ingredient.product_name}</td>
+                    <td className="p-2">
+                      <Input
+                        className="w-24 ml-auto text-right"
+                        inputMode="decimal"
+                        value={ingredient.quantity}
+                        onChange={(e) =>
+                          updateIngredient(
+                            ingredient.ingredient_sku,
+                            e.target.value
+                          )
+                        }
+                      />
+                    </td>
+                    <td className="p-2">
+                      <Button
+                        variant="outline"
+                        size="sm"
+                        onClick={() =>
+                          setEditing({
+                            ...editing,
+                            ingredients: editing.ingredients.filter(
+                              (other) =>
+                                other.ingredient_sku !==
+                                ingredient.ingredient_sku
+                            ),
+                          })
+                        }
+                      >
+                        Quitar
+                      </Button>
+                    </td>
+                  </tr>
+                ))}
+              </tbody>
+            </table>
+            <Input
+              placeholder="Notas"
+              value={editing.notes}
+              onChange={(e) =>
+                setEditing({ ...editing, notes: e.target.value })
+              }
+            />
+            <div className="flex gap-2">
+              <Button
+                disabled={
+                  !editing.sku.trim() ||
+                  !editing.yield_quantity ||
+                  editing.ingredients.length === 0
+                }
+                onClick={handleSave}
+              >
+                Guardar receta
+              </Button>
+              {!editing.isNew && (
+                <Button variant="outline" onClick={handleDelete}>
+                  Eliminar
+                </Button>
+              )}
+            </div>
+
+            {!editing.isNew && !editing.consumed_on_sale && (
+              <form
+                className="flex flex-col gap-2 border-t pt-3"
+                onSubmit={handleProduce}
+              >
+                <h3 className="font-bold">Registrar lote de producción</h3>
+                <Input
+                  placeholder="Cantidad producida"
+                  inputMode="decimal"
+                  value={production.quantity}
+                  onChange={(e) =>
+                    setProduction({ ...production, quantity: e.target.value })
+                  }
+                />
+                {editing.ingredients.map((ingredient) => (
+                  <div
+                    key={ingredient.ingredient_sku}
+                    className="flex items-center gap-2 text-sm"
+                  >
+                    <span className="flex-1">{ingredient.product_name}</span>
+                    <Input
+                      className="w-28 text-right"
+                      inputMode="decimal"
+                      placeholder={theoretical(ingredient).toFixed(3)}
+                      value={production.used[ingredient.ingredient_sku] ?? ""}
+                      onChange={(e) =>
+                        setProduction({
+                          ...production,
+                          used: {
+                            ...production.used,
+                            [ingredient.ingredient_sku]: e.target.value,
+                          },
+                        })
+                      }
+                    />
+                  </div>
+                ))}
+                <Button
+                  type="submit"
+                  className="self-start"
+                  disabled={!(produced > 0)}
+                >
+                  Producir
+                </Button>
+              </form>
+            )}
+          </div>
+        )}
+      </div>
+    </div>
+  );
+}
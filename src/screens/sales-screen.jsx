@@ -1,8 +1,9 @@
 import React from "react";
-import { useDatabase } from "@/services/db";
 import useGlobalStore from "@/hooks/useGlobalStore";
 import { invoke } from "@tauri-apps/api/core";
 import { print_ticket } from "@/lib/utils";
+import ReturnDialog from "@/components/return-dialog";
+import VoidDialog from "@/components/void-dialog";
 
 const TicketModal = ({
   currentTicket,
@@ -102,15 +103,14 @@ export default function SalesScreen({ toggleDrawer }) {
   const [currentTicket, setCurrentTicket] = React.useState(null);
   const [currentTicketID, setCurrentTicketID] = React.useState(null);
   const [currentTicketItems, setCurrentTicketItems] = React.useState(null);
-
-  const db = useDatabase();
+  // Sale whose items are being returned, see return-dialog.jsx
+  const [returnTicketID, setReturnTicketID] = React.useState(null);
+  // Ticket being voided, see void-dialog.jsx
+  const [voidTicket, setVoidTicket] = React.useState(null);
 
   const loadSales = async () => {
     try {
-      const sql = await db.getConnection();
-      const rows = await sql.select(
-        "SELECT * FROM tickets ORDER BY created_at DESC"
-      );
+      const rows = await invoke("get_sales");
       console.log(rows);
       setSales(rows);
       /*
@@ -148,23 +148,20 @@ export default function SalesScreen({ toggleDrawer }) {
         ]
   */
     } catch (err) {
-      console.error("Error querying sales:", err.message);
+      console.error("Error querying sales:", err);
       setSales([]);
     }
   };
 
   const loadTicket = async (ticketID) => {
+    if (!ticketID) return;
     try {
-      const sql = await db.getConnection();
-      const rows = await sql.select(
-        "SELECT * FROM ticket_items WHERE ticket_id = ?",
-        [ticketID]
-      );
+      const rows = await invoke("get_sale_items", { ticketId: ticketID });
       console.log(rows);
       setCurrentTicketItems(rows);
       //setTicket(rows[0]);
     } catch (err) {
-      console.error("Error querying ticket:", err.message);
+      console.error("Error querying ticket:", err);
       setCurrentTicketItems(null);
     }
   };
@@ -243,6 +240,16 @@ export default function SalesScreen({ toggleDrawer }) {
                     </td>
                     <td className="px-6 py-4 text-gray-700 border-r">
                       ${sale.total_due}
+                      {sale.original_ticket_id && (
+                        <span className="ml-2 text-sm text-red-600">
+                          Devolución de {sale.original_ticket_id.slice(0, 7)}
+                        </span>
+                      )}
+                      {sale.voided_at && (
+                        <span className="ml-2 text-sm text-red-600">
+                          Cancelado: {sale.void_reason}
+                        </span>
+                      )}
                     </td>
                     <td className="px-6 py-4">
                       <div className="flex items-center space-x-4">
@@ -256,9 +263,22 @@ export default function SalesScreen({ toggleDrawer }) {
                         >
                           Abrir Ticket
                         </button>
-                        <button className="text-sm text-red-600 flex items-center px-3 py-2 text-base font-medium bg-white border rounded-sm hover:shadow-[0px_1.5px_1px_0px_rgba(0,0,0,0.1)] hover:shadow-red-300 cursor-pointer">
-                          Cancelar
-                        </button>
+                        {!sale.original_ticket_id && !sale.voided_at && (
+                          <button
+                            onClick={() => setReturnTicketID(sale.id)}
+                            className="text-sm text-gray-700 flex items-center px-3 py-2 text-base font-medium bg-white border rounded-sm hover:shadow-[0px_1.5px_1px_0px_rgba(0,0,0,0.1)] hover:shadow-gray-300 cursor-pointer"
+                          >
+                            Devolución
+                          </button>
+                        )}
+                        {!sale.voided_at && (
+                          <button
+                            onClick={() => setVoidTicket(sale)}
+                            className="text-sm text-red-600 flex items-center px-3 py-2 text-base font-medium bg-white border rounded-sm hover:shadow-[0px_1.5px_1px_0px_rgba(0,0,0,0.1)] hover:shadow-red-300 cursor-pointer"
+                          >
+                            Cancelar
+                          </button>
+                        )}
                       </div>
                     </td>
                   </tr>
@@ -268,6 +288,22 @@ export default function SalesScreen({ toggleDrawer }) {
           </div>
         </div>
       </div>
+      <ReturnDialog
+        ticketId={returnTicketID}
+        handleClose={() => setReturnTicketID(null)}
+        handleReturned={() => {
+          setReturnTicketID(null);
+          loadSales();
+        }}
+      />
+      <VoidDialog
+        ticket={voidTicket}
+        handleClose={() => setVoidTicket(null)}
+        handleVoided={() => {
+          setVoidTicket(null);
+          loadSales();
+        }}
+      />
       <TicketModal
         isOpen={isModalOpen}
         currentTicket={currentTicket}
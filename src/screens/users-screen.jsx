@@ -0,0 +1,243 @@
+import React, { useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { Button } from "@/components/ui/button";
+import { Input } from "@/components/ui/input";
+
+const emptyForm = {
+  id: null,
+  username: "",
+  displayName: "",
+  roleId: "",
+  isActive: true,
+  secret: "",
+};
+
+// Cashier accounts and their roles. Requires the manage_users permission,
+// which the commands check on their own.
+export default function UsersScreen({ toggleDrawer }) {
+  const [users, setUsers] = useState([]);
+  const [roles, setRoles] = useState([]);
+  const [form, setForm] = useState(emptyForm);
+  const [error, setError] = useState(null);
+
+  const loadUsers = async () => {
+    try {
+      const [userRows, roleRows] = await Promise.all([
+        invoke("get_users"),
+        invoke("get_roles"),
+      ]);
+      setUsers(userRows);
+      setRoles(roleRows);
+      setError(null);
+    } catch (err) {
+      setError(`${err}`);
+    }
+  };
+
+  useEffect(() => {
+    loadUsers();
+  }, []);
+
+  // Discount a role may give without the discount permission; more than
+  // that needs a manager's approval at the register
+  const saveDiscountLimit = async (role, value) => {
+    const maxDiscountPercent = parseFloat(value);
+    if (
+      isNaN(maxDiscountPercent) ||
+      maxDiscountPercent === role.max_discount_percent
+    ) {
+      return;
+    }
+    try {
+      await invoke("set_role_permissions", {
+        roleId: role.id,
+        permissions: role.permissions,
+        maxDiscountPercent,
+      });
+      await loadUsers();
+    } catch (err) {
+      setError(`${err}`);
+    }
+  };
+
+  const editUser = (user) =>
+    setForm({
+      id: user.id,
+      username: user.username,
+      displayName: user.display_name,
+      roleId: user.role_id,
+      isActive: user.is_active,
+      secret: "",
+    });
+
+  const handleSubmit = async (event) => {
+    event.preventDefault();
+    try {
+      if (form.id === null) {
+        await invoke("create_user", {
+          username: form.username,
+          displayName: form.displayName,
+          roleId: Number(form.roleId),
+          secret: form.secret,
+        });
+      } else {
+        await invoke("update_user", {
+          id: form.id,
+          displayName: form.displayName,
+          roleId: Number(form.roleId),
+          isActive: form.isActive,
+          secret: form.secret || null,
+        });
+      }
+      setForm(emptyForm);
+      await loadUsers();
+    } catch (err) {
+      setError(`${err}`);
+    }
+  };
+
+  return (
+    <div className="flex flex-col h-screen w-full bg-background">
+      <div className="flex border-b p-2 h-16">
+        <div
+          className="flex items-center justify-center w-10 h-10 bg-background rounded-full cursor-pointer pl-2 mr-2 pt-[6.5px]"
+          onClick={toggleDrawer}
+        >
+          <svg
+            xmlns="http://www.w3.org/2000/svg"
+            className="h-9 w-9"
+            fill="none"
+            viewBox="0 0 24 24"
+            stroke="rgb(60,60,60)"
+          >
+            <path
+              strokeLinecap="round"
+              strokeLinejoin="round"
+              strokeWidth="2"
+              d="M4 6h16M4 12h16M4 18h16"
+            />
+          </svg>
+        </div>
+        <div className="flex items-center justify-center text-xl text-center">
+          Usuarios
+        </div>
+      </div>
+
+      <div className="flex flex-1 gap-6 p-4 bg-gray-50 overflow-auto">
+        <table className="flex-1 self-start bg-white">
+          <thead>
+            <tr className="text-left border-b">
+              <th className="p-2">Nombre</th>
+              <th className="p-2">Usuario</th>
+              <th className="p-2">Rol</th>
+              <th className="p-2">Activo</th>
+            </tr>
+          </thead>
+          <tbody>
+            {users.map((user) => (
+              <tr
+                key={user.id}
+                className="border-b cursor-pointer hover:bg-gray-100"
+                onClick={() => editUser(user)}
+              >
+                <td className="p-2">{user.display_name}</td>
+                <td className="p-2">{user.username}</td>
+                <td className="p-2">{user.role_name}</td>
+                <td className="p-2">{user.is_active ? "Sí" : "No"}</td>
+              </tr>
+            ))}
+          </tbody>
+        </table>
+
+        <table className="self-start bg-white">
+          <thead>
+            <tr className="text-left border-b">
+              <th className="p-2">Rol</th>
+              <th className="p-2">Descuento máx. (%)</th>
+            </tr>
+          </thead>
+          <tbody>
+            {roles.map((role) => (
+              <tr key={role.id} className="border-b">
+                <td className="p-2">{role.name}</td>
+                <td className="p-2">
+                  <Input
+                    key={role.max_discount_percent}
+                    className="w-24 text-right"
+                    inputMode="decimal"
+                    defaultValue={role.max_discount_percent}
+                    onBlur={(e) => saveDiscountLimit(role, e.target.value)}
+                  />
+                </td>
+              </tr>
+            ))}
+          </tbody>
+        </table>
+
+        <form className="flex flex-col gap-3 w-80" onSubmit={handleSubmit}>
+          <h2 className="text-lg font-bold">
+            {form.id === null ? "Nuevo usuario" : "Editar usuario"}
+          </h2>
+          <Input
+            placeholder="Usuario"
+            value={form.username}
+            disabled={form.id !== null}
+            onChange={(e) => setForm({ ...form, username: e.target.value })}
+          />
+          <Input
+            placeholder="Nombre"
+            value={form.displayName}
+            onChange={(e) => setForm({ ...form, displayName: e.target.value })}
+          />
+          <select
+            className="border rounded-md h-9 px-2"
+            value={form.roleId}
+            onChange={(e) => setForm({ ...form, roleId: e.target.value })}
+          >
+            <option value="">Selecciona rol</option>
+            {roles.map((role) => (
+              <option key={role.id} value={role.id}>
+                {role.name}
+              </option>
+            ))}
+          </select>
+          <Input
+            type="password"
+            placeholder={
+              form.id === null ? "PIN o contraseña" : "Nuevo PIN (opcional)"
+            }
+            value={form.secret}
+            onChange={(e) => setForm({ ...form, secret: e.target.value })}
+          />
+          {form.id !== null && (
+            <label className="flex items-center gap-2">
+              <input
+                type="checkbox"
+                checked={form.isActive}
+                onChange={(e) =>
+                  setForm({ ...form, isActive: e.target.checked })
+                }
+              />
+              Activo
+            </label>
+          )}
+          {error && <div className="text-red-600 text-sm">{error}</div>}
+          <div className="flex gap-2">
+            <Button type="submit" disabled={!form.roleId}>
+              Guardar
+            </Button>
+            {form.id !== null && (
+              <Button
+                type="button"
+                variant="outline"
+                onClick={() => setForm(emptyForm)}
+              >
+                Cancelar
+              </Button>
+            )}
+          </div>
+        </form>
+      </div>
+    </div>
+  );
+}
@@ -0,0 +1,225 @@
+import React, { useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { Button } from "@/components/ui/button";
+import { Input } from "@/components/ui/input";
+import useGlobalStore from "@/hooks/useGlobalStore";
+
+const today = () => {
+  const now = new Date();
+  now.setMinutes(now.getMinutes() - now.getTimezoneOffset());
+  return now.toISOString().slice(0, 10);
+};
+
+const parseNumber = (value) => parseFloat(`${value}`.replace(",", "."));
+
+const money = (amount) => `$${(amount ?? 0).toFixed(2)}`;
+
+const reasonLabels = {
+  unsold: "Sobrante / No vendido",
+  damaged: "Dañado",
+  expired: "Caducado",
+  sample: "Muestra",
+  other: "Otro",
+};
+
+const emptyWaste = { code: "", quantity: "", reason: "unsold", notes: "" };
+
+// Waste (mermas): goods thrown away or given out without a sale. Each
+// record leaves stock and is valued at cost; the daily report can be
+// printed for the end-of-day review.
+export default function WasteScreen({ toggleDrawer }) {
+  const hasPermission = useGlobalStore((state) => state.hasPermission);
+  const [waste, setWaste] = useState(emptyWaste);
+  const [date, setDate] = useState(today());
+  const [report, setReport] = useState(null);
+  const [error, setError] = useState(null);
+
+  const loadReport = async () => {
+    if (!hasPermission("view_reports")) return;
+    try {
+      setReport(await invoke("get_waste_report", { date }));
+    } catch (err) {
+      setError(`${err}`);
+    }
+  };
+
+  useEffect(() => {
+    loadReport();
+  }, [date]);
+
+  const handleSubmit = async (event) => {
+    event.preventDefault();
+    try {
+      const resolved = await invoke("resolve_product_code", {
+        code: waste.code,
+      });
+      if (!resolved) {
+        setError(`Código desconocido: ${waste.code}`);
+        return;
+      }
+      await invoke("record_waste", {
+        sku: resolved.product.sku,
+        quantity: parseNumber(waste.quantity),
+        reason: waste.reason,
+        notes: waste.notes.trim() || null,
+      });
+      setWaste(emptyWaste);
+      setError(null);
+      await loadReport();
+    } catch (err) {
+      setError(`${err}`);
+    }
+  };
+
+  const handlePrint = async () => {
+    try {
+      await invoke("print_waste_report", { date });
+    } catch (err) {
+      setError(`${err}`);
+    }
+  };
+
+  return (
+    <div className="flex flex-col h-screen w-full bg-background">
+      <div className="flex border-b p-2 h-16">
+        <div
+          className="flex items-center justify-center w-10 h-10 bg-background rounded-full cursor-pointer pl-2 mr-2 pt-[6.5px]"
+          onClick={toggleDrawer}
+        >
+          <svg
+            xmlns="http://www.w3.org/2000/svg"
+            className="h-9 w-9"
+            fill="none"
+            viewBox="0 0 24 24"
+            stroke="rgb(60,60,60)"
+          >
+            <path
+              strokeLinecap="round"
+              strokeLinejoin="round"
+              strokeWidth="2"
+              d="M4 6h16M4 12h16M4 18h16"
+            />
+          </svg>
+        </div>
+        <div className="flex items-center justify-center text-xl text-center">
+          Mermas
+        </div>
+        {hasPermission("view_reports") && (
+          <div className="flex gap-2 ml-auto items-center">
+            <Input
+              type="date"
+              className="w-44"
+              value={date}
+              onChange={(e) => setDate(e.target.value)}
+            />
+            <Button variant="outline" onClick={handlePrint}>
+              Imprimir
+            </Button>
+          </div>
+        )}
+      </div>
+
+      {error && <div className="mx-4 mt-2 text-red-600 text-sm">{error}</div>}
+
+      <div className="flex flex-1 gap-6 p-4 bg-gray-50 overflow-auto">
+        <form className="flex flex-col gap-3 w-80" onSubmit={handleSubmit}>
+          <h2 className="text-lg font-bold">Registrar merma</h2>
+          <Input
+            placeholder="Código o SKU"
+            value={waste.code}
+            onChange={(e) => setWaste({ ...waste, code: e.target.value })}
+          />
+          <Input
+            placeholder="Cantidad"
+            inputMode="decimal"
+            value={waste.quantity}
+            onChange={(e) => setWaste({ ...waste, quantity: e.target.value })}
+          />
+          <select
+            className="border rounded-md h-9 px-2"
+            value={waste.reason}
+            onChange={(e) => setWaste({ ...waste, reason: e.target.value })}
+          >
+            {Object.entries(reasonLabels).map(([value, label]) => (
+              <option key={value} value={value}>
+                {label}
+              </option>
+            ))}
+          </select>
+          <Input
+            placeholder={
+              waste.reason === "other" ? "Notas (requeridas)" : "Notas"
+            }
+            value={waste.notes}
+            onChange={(e) => setWaste({ ...waste, notes: e.target.value })}
+          />
+          <Button
+            type="submit"
+            disabled={
+              !waste.code.trim() ||
+              !(parseNumber(waste.quantity) > 0) ||
+              (waste.reason === "other" && !waste.notes.trim())
+            }
+          >
+            Registrar
+          </Button>
+        </form>
+
+        {report && (
+          <div className="flex flex-col flex-1 gap-4">
+            <table className="bg-white text-sm">
+              <thead>
+                <tr className="text-left border-b">
+                  <th className="p-2">Hora</th>
+                  <th className="p-2">Producto</th>
+                  <th className="p-2 text-right">Cantidad</th>
+                  <th className="p-2">Motivo</th>
+                  <th className="p-2 text-right">Costo</th>
+                  <th className="p-2">Registró</th>
+                </tr>
+              </thead>
+              <tbody>
+                {report.entries.map((entry) => (
+                  <tr key={entry.id} className="border-b">
+                    <td className="p-2">{entry.created_at.slice(11, 16)}</td>
+                    <td className="p-2">{entry.product_name}</td>
+                    <td className="p-2 text-right">{entry.quantity}</td>
+                    <td className="p-2" title={entry.notes ?? ""}>
+                      {reasonLabels[entry.reason] ?? entry.reason}
+                    </td>
+                    <td className="p-2 text-right">
+                      {entry.cost === null ? "—" : money(entry.cost)}
+                    </td>
+                    <td className="p-2">{entry.recorded_by}</td>
+                  </tr>
+                ))}
+              </tbody>
+            </table>
+            <table className="bg-white self-end w-80">
+              <tbody>
+                {report.by_reason.map((total) => (
+                  <tr key={total.reason} className="border-b">
+                    <td className="p-2">
+                      {`${reasonLabels[total.reason] ?? total.reason} (${
+                        total.entries
+                      })`}
+                    </td>
+                    <td className="p-2 text-right">{money(total.cost)}</td>
+                  </tr>
+                ))}
+              </tbody>
+              <tfoot>
+                <tr className="font-bold">
+                  <td className="p-2">Total</td>
+                  <td className="p-2 text-right">
+                    {money(report.total_cost)}
+                  </td>
+                </tr>
+              </tfoot>
+            </table>
+          </div>
+        )}
+      </div>
+    </div>
+  );
+}
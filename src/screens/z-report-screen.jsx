@@ -0,0 +1,183 @@
+import React, { useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { Input } from "@/components/ui/input";
+
+const today = () => {
+  const now = new Date();
+  now.setMinutes(now.getMinutes() - now.getTimezoneOffset());
+  return now.toISOString().slice(0, 10);
+};
+
+const money = (amount) => `$${(amount ?? 0).toFixed(2)}`;
+
+// End of day (Z) report: totals, payments, sales per cashier and per
+// category and the manager approvals given that day. Requires the
+// view_reports permission.
+export default function ZReportScreen({ toggleDrawer }) {
+  const [date, setDate] = useState(today());
+  const [report, setReport] = useState(null);
+  const [categories, setCategories] = useState([]);
+  const [error, setError] = useState(null);
+
+  useEffect(() => {
+    Promise.all([
+      invoke("get_z_report", { date }),
+      invoke("get_category_sales_report", { fromDate: date, toDate: date }),
+    ])
+      .then(([result, categorySales]) => {
+        setReport(result);
+        setCategories(categorySales);
+        setError(null);
+      })
+      .catch((err) => {
+        setReport(null);
+        setCategories([]);
+        setError(`${err}`);
+      });
+  }, [date]);
+
+  return (
+    <div className="flex flex-col h-screen w-full bg-background">
+      <div className="flex border-b p-2 h-16">
+        <div
+          className="flex items-center justify-center w-10 h-10 bg-background rounded-full cursor-pointer pl-2 mr-2 pt-[6.5px]"
+          onClick={toggleDrawer}
+        >
+          <svg
+            xmlns="http://www.w3.org/2000/svg"
+            className="h-9 w-9"
+            fill="none"
+            viewBox="0 0 24 24"
+            stroke="rgb(60,60,60)"
+          >
+            <path
+              strokeLinecap="round"
+              strokeLinejoin="round"
+              strokeWidth="2"
+              d="M4 6h16M4 12h16M4 18h16"
+            />
+          </svg>
+        </div>
+        <div className="flex items-center justify-center text-xl text-center">
+          Corte Z
+        </div>
+        <Input
+          type="date"
+          className="w-44 ml-auto self-center"
+          value={date}
+          onChange={(e) => setDate(e.target.value)}
+        />
+      </div>
+
+      <div className="flex flex-col flex-1 gap-6 p-4 bg-gray-50 overflow-auto">
+        {error && <div className="text-red-600 text-sm">{error}</div>}
+        {report && (
+          <>
+            <table className="self-start bg-white">
+              <tbody>
+                {[
+                  ["Tickets", report.totals.tickets],
+                  ["Devoluciones", report.totals.returns],
+                  ["Devuelto", money(report.totals.returns_total)],
+                  ["Cancelados", report.totals.voids],
+                  ["Cancelado", money(report.totals.voids_total)],
+                  ["Subtotal", money(report.totals.subtotal)],
+                  ["Impuestos", money(report.totals.taxes)],
+                  ["Total", money(report.totals.total_due)],
+                  ["Pesos", money(report.totals.pesos_paid)],
+                  ["Dólares", money(report.totals.dollars_paid)],
+                  ["Tarjetas", money(report.totals.cards_paid)],
+                  ["Otros", money(report.totals.others_paid)],
+                  ["Cambio", money(report.totals.change)],
+                ].map(([label, value]) => (
+                  <tr key={label} className="border-b">
+                    <td className="p-2">{label}</td>
+                    <td className="p-2 text-right font-bold">{value}</td>
+                  </tr>
+                ))}
+              </tbody>
+            </table>
+
+            <h2 className="text-lg font-bold">Por cajero</h2>
+            <table className="self-start bg-white">
+              <thead>
+                <tr className="text-left border-b">
+                  <th className="p-2">Cajero</th>
+                  <th className="p-2">Tickets</th>
+                  <th className="p-2">Total</th>
+                </tr>
+              </thead>
+              <tbody>
+                {report.cashiers.map((cashier) => (
+                  <tr key={cashier.cashier_name} className="border-b">
+                    <td className="p-2">{cashier.cashier_name}</td>
+                    <td className="p-2 text-right">{cashier.tickets}</td>
+                    <td className="p-2 text-right">
+                      {money(cashier.total_due)}
+                    </td>
+                  </tr>
+                ))}
+              </tbody>
+            </table>
+
+            <h2 className="text-lg font-bold">Por categoría</h2>
+            <table className="self-start bg-white">
+              <thead>
+                <tr className="text-left border-b">
+                  <th className="p-2">Categoría</th>
+                  <th className="p-2">Tickets</th>
+                  <th className="p-2">Cantidad</th>
+                  <th className="p-2">Total</th>
+                </tr>
+              </thead>
+              <tbody>
+                {categories.map((category) => (
+                  <tr key={category.category_id ?? "none"} className="border-b">
+                    <td className="p-2">{category.category_name}</td>
+                    <td className="p-2 text-right">{category.tickets}</td>
+                    <td className="p-2 text-right">{category.quantity}</td>
+                    <td className="p-2 text-right">{money(category.total)}</td>
+                  </tr>
+                ))}
+              </tbody>
+            </table>
+
+            <h2 className="text-lg font-bold">Autorizaciones</h2>
+            {report.approvals.length === 0 ? (
+              <div className="text-sm text-gray-600">Sin autorizaciones</div>
+            ) : (
+              <table className="bg-white">
+                <thead>
+                  <tr className="text-left border-b">
+                    <th className="p-2">Hora</th>
+                    <th className="p-2">Acción</th>
+                    <th className="p-2">Cajero</th>
+                    <th className="p-2">Autorizó</th>
+                    <th className="p-2">Motivo</th>
+                    <th className="p-2">Monto</th>
+                    <th className="p-2">Detalle</th>
+                  </tr>
+                </thead>
+                <tbody>
+                  {report.approvals.map((approval) => (
+                    <tr key={approval.id} className="border-b">
+                      <td className="p-2">{approval.created_at.slice(11)}</td>
+                      <td className="p-2">{approval.action}</td>
+                      <td className="p-2">{approval.requested_by}</td>
+                      <td className="p-2">{approval.approved_by}</td>
+                      <td className="p-2">{approval.reason}</td>
+                      <td className="p-2 text-right">
+                        {approval.amount === null ? "" : money(approval.amount)}
+                      </td>
+                      <td className="p-2 text-sm">{approval.details}</td>
+                    </tr>
+                  ))}
+                </tbody>
+              </table>
+            )}
+          </>
+        )}
+      </div>
+    </div>
+  );
+}